@@ -117,6 +117,7 @@ impl MarketAdapter for BinanceAdapter {
                 name: format!("{}/{}", base_asset, quote_asset),
                 asset_type: "crypto".to_string(),
                 source: "binance".to_string(),
+                currency: quote_asset.to_string(),
             });
         }
         
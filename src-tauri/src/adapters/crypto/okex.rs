@@ -120,6 +120,7 @@ impl MarketAdapter for OkexAdapter {
                 name: format!("{}/{}", base_ccy, quote_ccy),
                 asset_type: "crypto".to_string(),
                 source: "okex".to_string(),
+                currency: quote_ccy.to_string(),
             });
         }
         
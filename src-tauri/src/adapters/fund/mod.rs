@@ -1,2 +1,135 @@
 pub mod tiantian;
-pub mod sina;
\ No newline at end of file
+pub mod sina;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{CorporateActionEvent, CorporateActionType};
+
+/// 单位净值变动小于该值时视为没有分红或折算（净值一般保留 4 位小数）
+const NAV_JUMP_EPSILON: f64 = 0.0005;
+
+/// 单次分红占前一日单位净值的最大比例，超过时视为份额折算 / 拆分而非分红
+const MAX_DIVIDEND_RATIO: f64 = 0.2;
+
+/// 基金单日净值
+#[derive(Debug, Clone)]
+pub struct NavPoint {
+    pub timestamp: DateTime<Utc>,
+    pub nav: f64,                     // 单位净值 DWJZ
+    pub acc_nav: Option<f64>,         // 累计净值 LJJZ，货币基金等可能没有
+    pub distribution: Option<String>, // 数据源的分红送配说明，如 "每份派现金0.0150元"
+}
+
+fn round4(value: f64) -> f64 {
+    (value * 10000.0).round() / 10000.0
+}
+
+/// 解析分红送配说明，返回事件类型和数值
+///
+/// 支持 "每份派现金0.0150元"、"每份基金份额折算1.0238份"、"每份基金份额分拆1.5000份" 等写法。
+pub fn parse_distribution(text: &str) -> Option<(CorporateActionType, f64)> {
+    let number_after = |keyword: &str| -> Option<f64> {
+        let rest = &text[text.find(keyword)? + keyword.len()..];
+        let number: String = rest
+            .chars()
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        number.parse::<f64>().ok().filter(|v| *v > 0.0)
+    };
+
+    if let Some(value) = number_after("派现金") {
+        return Some((CorporateActionType::Dividend, value));
+    }
+    for keyword in ["折算", "分拆", "拆分"] {
+        if let Some(value) = number_after(keyword) {
+            return Some((CorporateActionType::Split, value));
+        }
+    }
+    None
+}
+
+/// 从净值序列中提取公司行为
+///
+/// 1. 数据源给出的分红送配说明优先（detected_from = "endpoint"）。
+/// 2. 其余日期按单位净值与累计净值推算：不分红时两者的日变动相同，
+///    设 `expected = 前一日单位净值 + 累计净值变动`，`jump = expected - 当日单位净值`：
+///    - 当日净值归一（约等于 1.0000）、`jump` 为负，或 `jump` 超过前一日净值的 20%
+///      （累计净值持平而单位净值大幅下跌同理），视为份额折算 / 拆分，比例为 `expected / 当日净值`；
+///    - 其余 `jump` 为正的情况视为每份分红 `jump`。
+pub fn extract_corporate_actions(symbol: &str, points: &[NavPoint]) -> Vec<CorporateActionEvent> {
+    let mut sorted: Vec<&NavPoint> = points.iter().collect();
+    sorted.sort_by_key(|p| p.timestamp);
+
+    let mut events = Vec::new();
+
+    for (i, point) in sorted.iter().enumerate() {
+        if let Some((action_type, value)) = point
+            .distribution
+            .as_deref()
+            .and_then(parse_distribution)
+        {
+            events.push(CorporateActionEvent {
+                symbol: symbol.to_string(),
+                action_type,
+                ex_date: point.timestamp.timestamp(),
+                value,
+                nav: Some(point.nav),
+                detected_from: "endpoint".to_string(),
+                description: point.distribution.clone().unwrap_or_default(),
+            });
+            continue;
+        }
+
+        if i == 0 {
+            continue;
+        }
+        let prev = sorted[i - 1];
+        let (prev_acc, acc) = match (prev.acc_nav, point.acc_nav) {
+            (Some(prev_acc), Some(acc)) if prev.nav > 0.0 && point.nav > 0.0 => (prev_acc, acc),
+            _ => continue,
+        };
+
+        let expected = prev.nav + (acc - prev_acc);
+        let jump = expected - point.nav;
+        if jump.abs() < NAV_JUMP_EPSILON {
+            continue;
+        }
+
+        let reset_to_par = (point.nav - 1.0).abs() < NAV_JUMP_EPSILON
+            && (prev.nav - 1.0).abs() >= NAV_JUMP_EPSILON;
+        // 分红不会超过净值的一定比例，累计净值持平而单位净值大幅下跌时是份额拆分
+        let acc_flat = (acc - prev_acc).abs() < NAV_JUMP_EPSILON;
+        let large_drop = (prev.nav - point.nav) / prev.nav > MAX_DIVIDEND_RATIO;
+        let implausible_dividend = jump / prev.nav > MAX_DIVIDEND_RATIO;
+        let is_split = jump < 0.0 || reset_to_par || (acc_flat && large_drop) || implausible_dividend;
+
+        let (action_type, value, description) = if is_split {
+            let ratio = round4(expected / point.nav);
+            (
+                CorporateActionType::Split,
+                ratio,
+                format!("每份基金份额折算{:.4}份（净值推算）", ratio),
+            )
+        } else {
+            let dividend = round4(jump);
+            (
+                CorporateActionType::Dividend,
+                dividend,
+                format!("每份派现金{:.4}元（净值推算）", dividend),
+            )
+        };
+
+        events.push(CorporateActionEvent {
+            symbol: symbol.to_string(),
+            action_type,
+            ex_date: point.timestamp.timestamp(),
+            value,
+            nav: Some(point.nav),
+            detected_from: "nav".to_string(),
+            description,
+        });
+    }
+
+    events
+}
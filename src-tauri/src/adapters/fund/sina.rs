@@ -6,6 +6,7 @@
 /// - 支持获取可用基金（产品）列表。
 /// - 获取指定基金代码的最新净值（NAV）。
 /// - 获取指定日期区间内的基金历史日净值数据（蜡烛图）。
+/// - 根据单位净值与累计净值的差异推算分红和份额折算事件。
 ///
 /// # 示例
 /// ```rust
@@ -34,7 +35,8 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::models::{Candle, MarketAdapter, Product, Ticker};
+use super::{extract_corporate_actions, NavPoint};
+use crate::models::{Candle, CorporateActionEvent, MarketAdapter, Product, Ticker};
 
 pub struct SinaFundAdapter {
     client: Client,
@@ -55,6 +57,65 @@ impl SinaFundAdapter {
             .map_err(|e| format!("Failed to parse date: {}", e))
             .map(|date| Utc.from_utc_datetime(&date.and_hms(0, 0, 0)))
     }
+
+    /// 获取区间内的历史净值（含累计净值），按时间升序
+    async fn fetch_nav_history(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<NavPoint>, String> {
+        let start_date = start_time.format("%Y-%m-%d").to_string();
+        let end_date = end_time.format("%Y-%m-%d").to_string();
+
+        let url = format!(
+            "{}/api/fund/history_nav?symbol={}&start_date={}&end_date={}",
+            self.base_url, symbol, start_date, end_date
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch historical data: {}", e))?;
+
+        let json: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+        let data = &json["data"];
+
+        if !data.is_array() {
+            return Err("Invalid response format".to_string());
+        }
+
+        let mut points = Vec::new();
+
+        for item in data.as_array().unwrap() {
+            let date_str = item["date"].as_str().ok_or("Missing date")?;
+            let nav_str = item["nav"].as_str().ok_or("Missing NAV")?;
+
+            let timestamp = self.parse_date(date_str)?;
+            let nav = nav_str
+                .parse::<f64>()
+                .map_err(|e| format!("Invalid NAV: {}", e))?;
+            let acc_nav = item["acc_nav"].as_str().and_then(|v| v.parse::<f64>().ok());
+
+            points.push(NavPoint {
+                timestamp,
+                nav,
+                acc_nav,
+                distribution: None,
+            });
+        }
+
+        // 按时间排序
+        points.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        Ok(points)
+    }
 }
 
 #[async_trait]
@@ -83,12 +144,14 @@ impl MarketAdapter for SinaFundAdapter {
                 name: "华夏成长混合".to_string(),
                 asset_type: "fund".to_string(),
                 source: "sina".to_string(),
+                currency: "CNY".to_string(),
             },
             Product {
                 symbol: "000002".to_string(),
                 name: "华夏优势增长混合".to_string(),
                 asset_type: "fund".to_string(),
                 source: "sina".to_string(),
+                currency: "CNY".to_string(),
             },
         ];
 
@@ -129,51 +192,24 @@ impl MarketAdapter for SinaFundAdapter {
         _interval: &str,
     ) -> Result<Vec<Candle>, String> {
         // 新浪基金只提供日级别的数据，忽略 interval 参数
-        let start_date = start_time.format("%Y-%m-%d").to_string();
-        let end_date = end_time.format("%Y-%m-%d").to_string();
-
-        let url = format!(
-            "{}/api/fund/history_nav?symbol={}&start_date={}&end_date={}",
-            self.base_url, symbol, start_date, end_date
-        );
-
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to fetch historical data: {}", e))?;
-
-        let json: Value = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        let data = &json["data"];
-
-        if !data.is_array() {
-            return Err("Invalid response format".to_string());
-        }
-
-        let mut candles = Vec::new();
-
-        for item in data.as_array().unwrap() {
-            let date_str = item["date"].as_str().ok_or("Missing date")?;
-            let nav_str = item["nav"].as_str().ok_or("Missing NAV")?;
-
-            let timestamp = self.parse_date(date_str)?;
-            let nav = nav_str
-                .parse::<f64>()
-                .map_err(|e| format!("Invalid NAV: {}", e))?;
-
-            // 基金只有净值，没有开高低收，所以我们用相同的值
-            let candle = Candle::new(timestamp, nav, nav, nav, nav, 0.0);
-            candles.push(candle);
-        }
+        let points = self.fetch_nav_history(symbol, start_time, end_time).await?;
 
-        // 按时间排序
-        candles.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        // 基金只有净值，没有开高低收，所以我们用相同的值
+        let candles = points
+            .iter()
+            .map(|p| Candle::new(p.timestamp, p.nav, p.nav, p.nav, p.nav, 0.0))
+            .collect();
 
         Ok(candles)
     }
+
+    async fn get_corporate_actions(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<CorporateActionEvent>, String> {
+        let points = self.fetch_nav_history(symbol, start_time, end_time).await?;
+        Ok(extract_corporate_actions(symbol, &points))
+    }
 }
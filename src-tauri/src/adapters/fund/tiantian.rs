@@ -4,6 +4,7 @@
 /// - 获取基金产品列表
 /// - 获取基金最新净值（Ticker）
 /// - 获取基金历史净值（K线/Candle）
+/// - 获取基金分红和份额折算事件（分红送配字段 FHSP，以及单位净值与累计净值的差异推算）
 /// - 检查与天天基金服务器的连接
 ///
 /// # 字段
@@ -22,6 +23,7 @@
 /// - 天天基金的历史净值数据只支持日线
 /// - 返回的部分数据为字符串类型，需要进行解析
 /// - ticker 接口返回的是 JSONP 格式，需要提取 JSON 部分
+/// - 历史净值接口分页返回，每页最多 100 条，会自动翻页取完整区间
 ///
 /// # 依赖
 /// - `reqwest` 用于 HTTP 请求
@@ -32,7 +34,11 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use async_trait::async_trait;
-use crate::models::{Candle, MarketAdapter, Product, Ticker};
+use super::{extract_corporate_actions, NavPoint};
+use crate::models::{Candle, CorporateActionEvent, MarketAdapter, Product, Ticker};
+
+/// 历史净值接口每页条数
+const HISTORY_PAGE_SIZE: usize = 100;
 
 pub struct TiantianFundAdapter {
     client: Client,
@@ -89,6 +95,81 @@ impl TiantianFundAdapter {
             .map_err(|e| format!("Failed to parse date: {}", e))
             .map(|date| Utc.from_utc_datetime(&date.and_hms(0, 0, 0)))
     }
+
+    /// 获取区间内的历史净值（含累计净值和分红送配说明），按时间升序
+    async fn fetch_nav_history(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<NavPoint>, String> {
+        let start_date = start_time.format("%Y-%m-%d").to_string();
+        let end_date = end_time.format("%Y-%m-%d").to_string();
+
+        let mut points = Vec::new();
+        let mut page_index = 1;
+
+        loop {
+            let url = format!(
+                "http://api.fund.eastmoney.com/f10/lsjz?fundCode={}&pageIndex={}&pageSize={}&startDate={}&endDate={}",
+                symbol, page_index, HISTORY_PAGE_SIZE, start_date, end_date
+            );
+
+            let response = self
+                .client
+                .get(&url)
+                .header("Referer", "http://fundf10.eastmoney.com/")
+                .send()
+                .await
+                .map_err(|e| format!("Failed to fetch historical data: {}", e))?;
+
+            let json: Value = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+            let data = &json["Data"]["LSJZList"];
+
+            if !data.is_array() {
+                return Err("Invalid response format".to_string());
+            }
+
+            let items = data.as_array().unwrap();
+
+            for item in items {
+                let date_str = item["FSRQ"].as_str().ok_or("Missing date")?;
+                let nav_str = item["DWJZ"].as_str().ok_or("Missing NAV")?;
+
+                let timestamp = self.parse_date(date_str)?;
+                let nav = nav_str
+                    .parse::<f64>()
+                    .map_err(|e| format!("Invalid NAV: {}", e))?;
+                let acc_nav = item["LJJZ"].as_str().and_then(|v| v.parse::<f64>().ok());
+                let distribution = item["FHSP"]
+                    .as_str()
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty());
+
+                points.push(NavPoint {
+                    timestamp,
+                    nav,
+                    acc_nav,
+                    distribution,
+                });
+            }
+
+            let total = json["TotalCount"].as_u64().unwrap_or(0) as usize;
+            if items.len() < HISTORY_PAGE_SIZE || points.len() >= total {
+                break;
+            }
+            page_index += 1;
+        }
+
+        // 按时间排序
+        points.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        Ok(points)
+    }
 }
 
 #[async_trait]
@@ -122,12 +203,14 @@ impl MarketAdapter for TiantianFundAdapter {
                 name: "华夏成长混合".to_string(),
                 asset_type: "fund".to_string(),
                 source: "tiantian".to_string(),
+                currency: "CNY".to_string(),
             },
             Product {
                 symbol: "000002".to_string(),
                 name: "华夏优势增长混合".to_string(),
                 asset_type: "fund".to_string(),
                 source: "tiantian".to_string(),
+                currency: "CNY".to_string(),
             },
         ];
 
@@ -175,52 +258,24 @@ impl MarketAdapter for TiantianFundAdapter {
         _interval: &str,
     ) -> Result<Vec<Candle>, String> {
         // 天天基金只提供日级别的数据，忽略 interval 参数
-        let start_date = start_time.format("%Y-%m-%d").to_string();
-        let end_date = end_time.format("%Y-%m-%d").to_string();
+        let points = self.fetch_nav_history(symbol, start_time, end_time).await?;
 
-        let url = format!(
-            "http://api.fund.eastmoney.com/f10/lsjz?fundCode={}&pageIndex=1&pageSize=100&startDate={}&endDate={}",
-            symbol, start_date, end_date
-        );
-
-        let response = self
-            .client
-            .get(&url)
-            .header("Referer", "http://fundf10.eastmoney.com/")
-            .send()
-            .await
-            .map_err(|e| format!("Failed to fetch historical data: {}", e))?;
-
-        let json: Value = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        let data = &json["Data"]["LSJZList"];
-
-        if !data.is_array() {
-            return Err("Invalid response format".to_string());
-        }
-
-        let mut candles = Vec::new();
-
-        for item in data.as_array().unwrap() {
-            let date_str = item["FSRQ"].as_str().ok_or("Missing date")?;
-            let nav_str = item["DWJZ"].as_str().ok_or("Missing NAV")?;
-
-            let timestamp = self.parse_date(date_str)?;
-            let nav = nav_str
-                .parse::<f64>()
-                .map_err(|e| format!("Invalid NAV: {}", e))?;
-
-            // 基金只有净值，没有开高低收，所以我们用相同的值
-            let candle = Candle::new(timestamp, nav, nav, nav, nav, 0.0);
-            candles.push(candle);
-        }
-
-        // 按时间排序
-        candles.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        // 基金只有净值，没有开高低收，所以我们用相同的值
+        let candles = points
+            .iter()
+            .map(|p| Candle::new(p.timestamp, p.nav, p.nav, p.nav, p.nav, 0.0))
+            .collect();
 
         Ok(candles)
     }
+
+    async fn get_corporate_actions(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<CorporateActionEvent>, String> {
+        let points = self.fetch_nav_history(symbol, start_time, end_time).await?;
+        Ok(extract_corporate_actions(symbol, &points))
+    }
 }
@@ -0,0 +1,97 @@
+/// `EastmoneyFxAdapter` 是 `FxAdapter` trait 的实现，用于从东方财富获取外汇汇率。
+///
+/// # 支持币种
+/// - 外币兑人民币：USD、HKD、EUR、GBP、AUD、CAD、SGD、CHF（使用离岸人民币 CNH 报价近似 CNY）
+/// - 反向报价（人民币兑外币）按倒数换算
+///
+/// # 用法示例
+/// ```rust
+/// let adapter = EastmoneyFxAdapter::new();
+/// let usd_cny = adapter.get_rate("USD", "CNY").await?;
+/// let history = adapter.get_rate_history("HKD", "CNY", start, end).await?;
+/// ```
+///
+/// # 注意事项
+/// - 非人民币之间的汇率（如 USD/HKD）不直接支持，由汇率服务经人民币交叉换算。
+/// - 仅支持日线历史数据。
+///
+/// # 依赖
+/// - 复用 `adapters::stock::eastmoney` 中的行情和K线请求函数
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+
+use crate::adapters::stock::eastmoney::{fetch_klines, fetch_ticker, PriceAdjustment};
+use crate::models::FxAdapter;
+
+/// 支持的外币（兑离岸人民币）
+const SUPPORTED_CURRENCIES: [&str; 8] = ["USD", "HKD", "EUR", "GBP", "AUD", "CAD", "SGD", "CHF"];
+
+pub struct EastmoneyFxAdapter {
+    client: Client,
+}
+
+impl EastmoneyFxAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+
+    /// 返回 (secid, 是否需要取倒数)
+    fn pair(&self, base: &str, quote: &str) -> Result<(String, bool), String> {
+        let base = base.to_uppercase();
+        let quote = quote.to_uppercase();
+
+        if quote == "CNY" && SUPPORTED_CURRENCIES.contains(&base.as_str()) {
+            Ok((format!("133.{}CNH", base), false))
+        } else if base == "CNY" && SUPPORTED_CURRENCIES.contains(&quote.as_str()) {
+            Ok((format!("133.{}CNH", quote), true))
+        } else {
+            Err(format!("Unsupported currency pair: {}/{}", base, quote))
+        }
+    }
+}
+
+#[async_trait]
+impl FxAdapter for EastmoneyFxAdapter {
+    fn name(&self) -> &str {
+        "eastmoney"
+    }
+
+    async fn get_rate(&self, base: &str, quote: &str) -> Result<f64, String> {
+        let (secid, inverse) = self.pair(base, quote)?;
+        let ticker = fetch_ticker(&self.client, &secid, &format!("{}{}", base, quote), 1.0).await?;
+
+        if ticker.price <= 0.0 {
+            return Err(format!("Invalid rate for {}/{}", base, quote));
+        }
+        Ok(if inverse { 1.0 / ticker.price } else { ticker.price })
+    }
+
+    async fn get_rate_history(
+        &self,
+        base: &str,
+        quote: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, f64)>, String> {
+        let (secid, inverse) = self.pair(base, quote)?;
+        let candles = fetch_klines(
+            &self.client,
+            &secid,
+            "1d",
+            PriceAdjustment::None,
+            start_time,
+            end_time,
+            1.0,
+        )
+        .await?;
+
+        Ok(candles
+            .into_iter()
+            .filter(|c| c.close > 0.0)
+            .map(|c| (c.timestamp, if inverse { 1.0 / c.close } else { c.close }))
+            .collect())
+    }
+}
@@ -0,0 +1,267 @@
+/// `EastmoneyGoldAdapter` 是 `MarketAdapter` trait 的实现，用于从东方财富获取黄金行情数据。
+///
+/// # 主要功能
+/// - 获取支持的黄金品种列表
+/// - 获取黄金实时行情
+/// - 获取黄金日线/周线/月线及分钟线K线
+/// - 按目标单位和币种统一换算价格（克/金衡盎司，人民币/美元）
+///
+/// # 支持品种
+/// - `AU9999`：上海黄金交易所 Au99.99 现货（原始报价：人民币/克）
+/// - `AUTD`：上海黄金交易所 Au(T+D) 延期（原始报价：人民币/克）
+/// - `PAPER`：纸黄金（银行纸黄金以上金所 Au99.99 为定价基准，使用 Au99.99 行情近似，原始报价：人民币/克）
+/// - `XAUUSD`：伦敦现货黄金（原始报价：美元/金衡盎司）
+///
+/// # 字段
+/// - `client`: Reqwest HTTP 客户端
+/// - `unit`: 输出价格的重量单位（`GoldUnit`）
+/// - `currency`: 输出价格的币种（"CNY" 或 "USD"）
+///
+/// # 用法示例
+/// ```rust
+/// // 以人民币/克计价，便于与国内持仓（克）直接相乘估值
+/// let adapter = EastmoneyGoldAdapter::new(GoldUnit::Gram, "CNY");
+/// let ticker = adapter.get_ticker("XAUUSD").await?;
+/// let candles = adapter.get_candles("AU9999", start, end, "1d").await?;
+/// ```
+///
+/// # 注意事项
+/// - 跨币种换算使用美元兑离岸人民币（USDCNH）汇率，历史数据按日期匹配当日或之前最近的汇率。
+/// - 1 金衡盎司 = 31.1034768 克。
+/// - 周期与 A 股接口相同（1m、5m、15m、30m、1h、1d、1w、1M），不支持的周期返回错误；
+///   分钟线同样按日期匹配当日或之前最近的汇率。
+///
+/// # 依赖
+/// - 复用 `adapters::stock::eastmoney` 中的行情和K线请求函数
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::adapters::stock::eastmoney::{fetch_klines, fetch_ticker, PriceAdjustment};
+use crate::models::{Candle, MarketAdapter, Product, Ticker};
+
+/// 1 金衡盎司对应的克数
+pub const GRAMS_PER_TROY_OUNCE: f64 = 31.1034768;
+
+/// 美元兑人民币汇率的 secid
+const USD_CNY_SECID: &str = "133.USDCNH";
+
+/// 黄金价格的重量单位
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum GoldUnit {
+    Gram,      // 克
+    TroyOunce, // 金衡盎司
+}
+
+impl GoldUnit {
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "oz" | "troy_ounce" => GoldUnit::TroyOunce,
+            _ => GoldUnit::Gram,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            GoldUnit::Gram => "g",
+            GoldUnit::TroyOunce => "oz",
+        }
+    }
+}
+
+/// 品种信息：(代码, 名称, secid, 原始单位, 原始币种)
+const INSTRUMENTS: [(&str, &str, &str, GoldUnit, &str); 4] = [
+    ("AU9999", "黄金Au99.99", "118.AU9999", GoldUnit::Gram, "CNY"),
+    ("AUTD", "黄金延期Au(T+D)", "118.AUTD", GoldUnit::Gram, "CNY"),
+    ("PAPER", "纸黄金", "118.AU9999", GoldUnit::Gram, "CNY"),
+    ("XAUUSD", "伦敦金", "122.XAU", GoldUnit::TroyOunce, "USD"),
+];
+
+/// 换算黄金价格
+///
+/// `usd_cny` 为 1 美元兑换的人民币数量，同币种换算时不会用到。
+pub fn convert_gold_price(
+    price: f64,
+    from_unit: GoldUnit,
+    from_currency: &str,
+    to_unit: GoldUnit,
+    to_currency: &str,
+    usd_cny: f64,
+) -> f64 {
+    let per_unit = match (from_unit, to_unit) {
+        (GoldUnit::Gram, GoldUnit::TroyOunce) => price * GRAMS_PER_TROY_OUNCE,
+        (GoldUnit::TroyOunce, GoldUnit::Gram) => price / GRAMS_PER_TROY_OUNCE,
+        _ => price,
+    };
+
+    match (from_currency, to_currency) {
+        ("USD", "CNY") => per_unit * usd_cny,
+        ("CNY", "USD") => per_unit / usd_cny,
+        _ => per_unit,
+    }
+}
+
+pub struct EastmoneyGoldAdapter {
+    client: Client,
+    unit: GoldUnit,
+    currency: String,
+}
+
+impl EastmoneyGoldAdapter {
+    pub fn new(unit: GoldUnit, currency: &str) -> Self {
+        Self {
+            client: Client::new(),
+            unit,
+            currency: currency.to_uppercase(),
+        }
+    }
+
+    fn instrument(
+        &self,
+        symbol: &str,
+    ) -> Result<(&'static str, GoldUnit, &'static str), String> {
+        let normalized = symbol.trim().to_uppercase().replace(['(', ')', '+', '.', '/'], "");
+        let key = match normalized.as_str() {
+            "AU9999" => "AU9999",
+            "AUTD" => "AUTD",
+            "PAPER" | "PAPERGOLD" => "PAPER",
+            "XAUUSD" | "XAU" => "XAUUSD",
+            _ => return Err(format!("Unsupported gold symbol: {}", symbol)),
+        };
+
+        INSTRUMENTS
+            .iter()
+            .find(|(code, ..)| *code == key)
+            .map(|(_, _, secid, unit, currency)| (*secid, *unit, *currency))
+            .ok_or_else(|| format!("Unsupported gold symbol: {}", symbol))
+    }
+
+    fn needs_fx(&self, currency: &str) -> bool {
+        currency != self.currency
+    }
+}
+
+#[async_trait]
+impl MarketAdapter for EastmoneyGoldAdapter {
+    fn name(&self) -> &str {
+        "eastmoney"
+    }
+
+    fn asset_type(&self) -> &str {
+        "gold"
+    }
+
+    async fn check_connection(&self) -> Result<bool, String> {
+        fetch_ticker(&self.client, "118.AU9999", "AU9999", 1.0)
+            .await
+            .map(|_| true)
+            .map_err(|e| format!("Connection failed: {}", e))
+    }
+
+    async fn get_products(&self) -> Result<Vec<Product>, String> {
+        Ok(INSTRUMENTS
+            .iter()
+            .map(|(code, name, ..)| Product {
+                symbol: code.to_string(),
+                name: name.to_string(),
+                asset_type: "gold".to_string(),
+                source: "eastmoney".to_string(),
+                currency: self.currency.clone(),
+            })
+            .collect())
+    }
+
+    async fn get_ticker(&self, symbol: &str) -> Result<Ticker, String> {
+        let (secid, unit, currency) = self.instrument(symbol)?;
+        let ticker = fetch_ticker(&self.client, secid, symbol, 1.0).await?;
+
+        let usd_cny = if self.needs_fx(currency) {
+            fetch_ticker(&self.client, USD_CNY_SECID, "USDCNH", 1.0)
+                .await?
+                .price
+        } else {
+            1.0
+        };
+
+        let convert = |p: f64| convert_gold_price(p, unit, currency, self.unit, &self.currency, usd_cny);
+
+        Ok(Ticker {
+            symbol: ticker.symbol,
+            price: convert(ticker.price),
+            timestamp: ticker.timestamp,
+            volume: ticker.volume,
+            high_24h: ticker.high_24h.map(convert),
+            low_24h: ticker.low_24h.map(convert),
+        })
+    }
+
+    async fn get_candles(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        interval: &str,
+    ) -> Result<Vec<Candle>, String> {
+        let (secid, unit, currency) = self.instrument(symbol)?;
+        let candles = fetch_klines(
+            &self.client,
+            secid,
+            interval,
+            PriceAdjustment::None,
+            start_time,
+            end_time,
+            1.0,
+        )
+        .await?;
+
+        if !self.needs_fx(currency) && unit == self.unit {
+            return Ok(candles);
+        }
+
+        // 多取一段汇率数据，保证区间开头的日期也能匹配到之前的汇率
+        let fx: BTreeMap<DateTime<Utc>, f64> = if self.needs_fx(currency) {
+            fetch_klines(
+                &self.client,
+                USD_CNY_SECID,
+                "1d",
+                PriceAdjustment::None,
+                start_time - Duration::days(10),
+                end_time,
+                1.0,
+            )
+            .await?
+            .into_iter()
+            .map(|c| (c.timestamp, c.close))
+            .collect()
+        } else {
+            BTreeMap::new()
+        };
+
+        let mut converted = Vec::with_capacity(candles.len());
+        for candle in candles {
+            let usd_cny = if self.needs_fx(currency) {
+                match fx.range(..=candle.timestamp).next_back() {
+                    Some((_, rate)) => *rate,
+                    None => continue, // 没有可用汇率的K线跳过
+                }
+            } else {
+                1.0
+            };
+            let convert =
+                |p: f64| convert_gold_price(p, unit, currency, self.unit, &self.currency, usd_cny);
+
+            converted.push(Candle::new(
+                candle.timestamp,
+                convert(candle.open),
+                convert(candle.high),
+                convert(candle.low),
+                convert(candle.close),
+                candle.volume,
+            ));
+        }
+
+        Ok(converted)
+    }
+}
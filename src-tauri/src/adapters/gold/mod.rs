@@ -1 +1 @@
-// 这里可以添加黄金适配器的实现
\ No newline at end of file
+pub mod eastmoney;
\ No newline at end of file
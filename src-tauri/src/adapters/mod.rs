@@ -2,8 +2,18 @@ pub mod fund;
 pub mod crypto;
 pub mod stock;
 pub mod gold;
+pub mod fx;
 
-use crate::models::MarketAdapter;
+use crate::models::{FxAdapter, MarketAdapter};
+use gold::eastmoney::GoldUnit;
+use stock::eastmoney::PriceAdjustment;
+
+/// 是否为复权行情数据源（`_qfq` 前复权 / `_hfq` 后复权）
+///
+/// 复权价格会随分红送转整体调整，只用于指标计算和图表展示，不能写入按实际成交价估值的 `price_history`。
+pub fn is_adjusted_source(source: &str) -> bool {
+    source.ends_with("_qfq") || source.ends_with("_hfq")
+}
 
 pub fn get_adapter(asset_type: &str, source: &str) -> Result<Box<dyn MarketAdapter>, String> {
     match (asset_type, source) {
@@ -11,6 +21,48 @@ pub fn get_adapter(asset_type: &str, source: &str) -> Result<Box<dyn MarketAdapt
         ("fund", "sina") => Ok(Box::new(fund::sina::SinaFundAdapter::new())),
         ("crypto", "binance") => Ok(Box::new(crypto::binance::BinanceAdapter::new(None, None))),
         ("crypto", "okex") => Ok(Box::new(crypto::okex::OkexAdapter::new(None, None))),
+        ("stock", "eastmoney") | ("stock", "eastmoney_raw") => Ok(Box::new(
+            stock::eastmoney::EastmoneyStockAdapter::new(PriceAdjustment::None),
+        )),
+        ("stock", "eastmoney_qfq") => Ok(Box::new(stock::eastmoney::EastmoneyStockAdapter::new(
+            PriceAdjustment::Forward,
+        ))),
+        ("stock", "eastmoney_hfq") => Ok(Box::new(stock::eastmoney::EastmoneyStockAdapter::new(
+            PriceAdjustment::Backward,
+        ))),
+        ("hk_stock", "eastmoney") => Ok(Box::new(stock::hk::EastmoneyHkStockAdapter::new(
+            PriceAdjustment::None,
+        ))),
+        ("hk_stock", "eastmoney_qfq") => Ok(Box::new(stock::hk::EastmoneyHkStockAdapter::new(
+            PriceAdjustment::Forward,
+        ))),
+        ("hk_stock", "eastmoney_hfq") => Ok(Box::new(stock::hk::EastmoneyHkStockAdapter::new(
+            PriceAdjustment::Backward,
+        ))),
+        ("us_stock", "eastmoney") => Ok(Box::new(stock::us::EastmoneyUsStockAdapter::new(
+            PriceAdjustment::None,
+        ))),
+        ("us_stock", "eastmoney_qfq") => Ok(Box::new(stock::us::EastmoneyUsStockAdapter::new(
+            PriceAdjustment::Forward,
+        ))),
+        ("us_stock", "eastmoney_hfq") => Ok(Box::new(stock::us::EastmoneyUsStockAdapter::new(
+            PriceAdjustment::Backward,
+        ))),
+        ("gold", "eastmoney") => Ok(Box::new(gold::eastmoney::EastmoneyGoldAdapter::new(
+            GoldUnit::Gram,
+            "CNY",
+        ))),
+        ("gold", "eastmoney_usd_oz") => Ok(Box::new(gold::eastmoney::EastmoneyGoldAdapter::new(
+            GoldUnit::TroyOunce,
+            "USD",
+        ))),
         _ => Err(format!("Unsupported asset type: {} or source: {}", asset_type, source)),
     }
-}
\ No newline at end of file
+}
+
+pub fn get_fx_adapter(source: &str) -> Result<Box<dyn FxAdapter>, String> {
+    match source {
+        "eastmoney" => Ok(Box::new(fx::eastmoney::EastmoneyFxAdapter::new())),
+        _ => Err(format!("Unsupported fx source: {}", source)),
+    }
+}
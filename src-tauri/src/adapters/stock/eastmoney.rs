@@ -0,0 +1,413 @@
+/// `EastmoneyStockAdapter` 是 `MarketAdapter` trait 的实现，用于从东方财富获取 A 股（沪深京）行情数据。
+///
+/// # 主要功能
+/// - 获取沪深京 A 股股票列表
+/// - 获取股票实时行情（最新价、成交量、当日最高/最低）
+/// - 获取日线/周线/月线及分钟线K线，支持不复权、前复权、后复权
+/// - 检查与东方财富服务器的连接
+///
+/// # 字段
+/// - `client`: Reqwest HTTP 客户端
+/// - `adjustment`: K线复权方式（`PriceAdjustment`）
+///
+/// # 代码格式
+/// 支持 `600519`、`sh600519`、`SH600519`、`600519.SH`、`1.600519` 等写法，
+/// 统一转换为东方财富的 secid（`市场.代码`，上海为 1，深圳/北京为 0）。
+/// 未带交易所前后缀时按代码首位判断：6/9/5 开头为上海，其余为深圳/北京。
+///
+/// # 用法示例
+/// ```rust
+/// let adapter = EastmoneyStockAdapter::new(PriceAdjustment::None);
+/// let products = adapter.get_products().await?;
+/// let ticker = adapter.get_ticker("600519").await?;
+/// let candles = adapter.get_candles("600519.SH", start, end, "1d").await?;
+/// ```
+///
+/// # 注意事项
+/// - 数据源 `eastmoney` 返回不复权价格，行情同步写入 `price_history` 时只能使用不复权价格，
+///   持仓估值和定投成交都按实际成交价计算。
+/// - 前复权（`eastmoney_qfq`）以最新价格为基准向前调整历史价格，每次分红送转后历史价格都会变化；
+///   后复权（`eastmoney_hfq`）以上市首日为基准向后调整，最新价格会高于实际价格。两者只用于指标和图表。
+/// - 日线及以上周期的时间戳为交易日的 UTC 零点，与 `price_history.date` 一致；
+///   分钟线时间为北京时间，转换为 UTC 存储。
+/// - 分钟线接口只提供最近若干个交易日的数据。
+/// - 接口返回的 A 股成交量单位为“手”，已换算为股。
+///
+/// # 依赖
+/// - `reqwest` 用于 HTTP 请求
+/// - `serde_json` 用于 JSON 解析
+/// - `chrono` 用于时间处理
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::{Candle, MarketAdapter, Product, Ticker};
+
+const QUOTE_URL: &str = "https://push2.eastmoney.com/api/qt/stock/get";
+const KLINE_URL: &str = "https://push2his.eastmoney.com/api/qt/stock/kline/get";
+const LIST_URL: &str = "https://push2.eastmoney.com/api/qt/clist/get";
+
+/// K线复权方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PriceAdjustment {
+    None,     // 不复权
+    Forward,  // 前复权
+    Backward, // 后复权
+}
+
+impl PriceAdjustment {
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "qfq" | "forward" => PriceAdjustment::Forward,
+            "hfq" | "backward" => PriceAdjustment::Backward,
+            _ => PriceAdjustment::None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            PriceAdjustment::None => "none",
+            PriceAdjustment::Forward => "qfq",
+            PriceAdjustment::Backward => "hfq",
+        }
+    }
+
+    /// 东方财富接口的 fqt 参数
+    pub(crate) fn fqt(&self) -> u8 {
+        match self {
+            PriceAdjustment::None => 0,
+            PriceAdjustment::Forward => 1,
+            PriceAdjustment::Backward => 2,
+        }
+    }
+}
+
+/// 将 A 股代码转换为东方财富 secid
+pub fn a_share_secid(symbol: &str) -> Result<String, String> {
+    let symbol = symbol.trim().to_uppercase();
+
+    if let Some((left, right)) = symbol.split_once('.') {
+        // 1.600519 形式
+        if left == "0" || left == "1" {
+            return Ok(format!("{}.{}", left, right));
+        }
+        // 600519.SH 形式
+        return match right {
+            "SH" | "SS" => Ok(format!("1.{}", left)),
+            "SZ" | "BJ" => Ok(format!("0.{}", left)),
+            _ => Err(format!("Unsupported A-share symbol: {}", symbol)),
+        };
+    }
+
+    let (prefix, code) = if symbol.len() > 6 && symbol.is_char_boundary(2) {
+        symbol.split_at(2)
+    } else {
+        ("", symbol.as_str())
+    };
+
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid A-share symbol: {}", symbol));
+    }
+
+    match prefix {
+        "SH" => Ok(format!("1.{}", code)),
+        "SZ" | "BJ" => Ok(format!("0.{}", code)),
+        _ => match code.chars().next() {
+            Some('6') | Some('9') | Some('5') => Ok(format!("1.{}", code)),
+            _ => Ok(format!("0.{}", code)),
+        },
+    }
+}
+
+/// 周期字符串转换为东方财富 klt 参数，不支持的周期返回错误
+pub(crate) fn eastmoney_interval(interval: &str) -> Result<&'static str, String> {
+    match interval {
+        "1m" => Ok("1"),
+        "5m" => Ok("5"),
+        "15m" => Ok("15"),
+        "30m" => Ok("30"),
+        "1h" => Ok("60"),
+        "1d" => Ok("101"),
+        "1w" => Ok("102"),
+        "1M" => Ok("103"),
+        _ => Err(format!(
+            "Unsupported interval: {} (supported: 1m, 5m, 15m, 30m, 1h, 1d, 1w, 1M)",
+            interval
+        )),
+    }
+}
+
+/// 接口字段可能是数字，也可能是字符串（停牌时为 "-"）
+pub(crate) fn value_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+fn beijing() -> FixedOffset {
+    FixedOffset::east_opt(8 * 3600).unwrap()
+}
+
+/// 获取实时行情
+///
+/// `volume_unit` 为成交量换算系数（A 股接口单位为手，传 100.0）
+pub(crate) async fn fetch_ticker(
+    client: &Client,
+    secid: &str,
+    symbol: &str,
+    volume_unit: f64,
+) -> Result<Ticker, String> {
+    let url = format!(
+        "{}?secid={}&fltt=2&invt=2&fields=f43,f44,f45,f46,f47,f57,f58,f86",
+        QUOTE_URL, secid
+    );
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch ticker: {}", e))?;
+
+    let json: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    let data = &json["data"];
+    if data.is_null() {
+        return Err(format!("Symbol not found: {}", symbol));
+    }
+
+    let price = value_f64(&data["f43"]).ok_or("Missing last price")?;
+    let high = value_f64(&data["f44"]).unwrap_or(price);
+    let low = value_f64(&data["f45"]).unwrap_or(price);
+    let volume = value_f64(&data["f47"]).unwrap_or(0.0) * volume_unit;
+    let timestamp = data["f86"]
+        .as_i64()
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+        .unwrap_or_else(Utc::now);
+
+    Ok(Ticker::with_details(
+        symbol.to_string(),
+        price,
+        timestamp,
+        volume,
+        high,
+        low,
+    ))
+}
+
+/// 获取K线数据
+pub(crate) async fn fetch_klines(
+    client: &Client,
+    secid: &str,
+    interval: &str,
+    adjustment: PriceAdjustment,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    volume_unit: f64,
+) -> Result<Vec<Candle>, String> {
+    let start_date = start_time.with_timezone(&beijing()).format("%Y%m%d");
+    let end_date = end_time.with_timezone(&beijing()).format("%Y%m%d");
+
+    let url = format!(
+        "{}?secid={}&fields1=f1,f2,f3,f4,f5,f6&fields2=f51,f52,f53,f54,f55,f56&klt={}&fqt={}&beg={}&end={}&lmt=10000",
+        KLINE_URL,
+        secid,
+        eastmoney_interval(interval)?,
+        adjustment.fqt(),
+        start_date,
+        end_date
+    );
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch candles: {}", e))?;
+
+    let json: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    let klines = &json["data"]["klines"];
+    if json["data"].is_null() {
+        return Err(format!("Symbol not found: {}", secid));
+    }
+    if !klines.is_array() {
+        return Err("Invalid response format".to_string());
+    }
+
+    let mut candles = Vec::new();
+
+    // 每条K线格式: "日期,开盘,收盘,最高,最低,成交量"
+    for line in klines.as_array().unwrap() {
+        let line = line.as_str().ok_or("Invalid kline")?;
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() < 6 {
+            continue;
+        }
+
+        let timestamp = if fields[0].len() > 10 {
+            let local = NaiveDateTime::parse_from_str(fields[0], "%Y-%m-%d %H:%M")
+                .map_err(|e| format!("Invalid timestamp: {}", e))?;
+            beijing()
+                .from_local_datetime(&local)
+                .single()
+                .ok_or("Invalid timestamp")?
+                .with_timezone(&Utc)
+        } else {
+            let date = NaiveDate::parse_from_str(fields[0], "%Y-%m-%d")
+                .map_err(|e| format!("Invalid date: {}", e))?;
+            Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        };
+
+        let parse = |s: &str| s.parse::<f64>().map_err(|e| format!("Parse error: {}", e));
+        let open = parse(fields[1])?;
+        let close = parse(fields[2])?;
+        let high = parse(fields[3])?;
+        let low = parse(fields[4])?;
+        let volume = parse(fields[5])? * volume_unit;
+
+        candles.push(Candle::new(timestamp, open, high, low, close, volume));
+    }
+
+    candles.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    Ok(candles)
+}
+
+/// 分页获取东方财富证券列表
+///
+/// `fs` 为东方财富的市场筛选参数，`symbol_of(代码, 市场编号)` 用于生成产品代码。
+pub(crate) async fn fetch_product_list(
+    client: &Client,
+    fs: &str,
+    asset_type: &str,
+    currency: &str,
+    symbol_of: fn(&str, i64) -> String,
+) -> Result<Vec<Product>, String> {
+    let mut products = Vec::new();
+    let mut page = 1;
+
+    loop {
+        let url = format!(
+            "{}?pn={}&pz=100&po=1&np=1&fltt=2&invt=2&fid=f12&fs={}&fields=f12,f13,f14",
+            LIST_URL, page, fs
+        );
+
+        let response = client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch products: {}", e))?;
+
+        let json: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+        let total = json["data"]["total"].as_u64().unwrap_or(0) as usize;
+        let diff = match json["data"]["diff"].as_array() {
+            Some(diff) if !diff.is_empty() => diff,
+            _ => break,
+        };
+
+        for item in diff {
+            let code = item["f12"].as_str().ok_or("Missing code")?;
+            let name = item["f14"].as_str().ok_or("Missing name")?;
+            let market = item["f13"].as_i64().unwrap_or(0);
+
+            products.push(Product {
+                symbol: symbol_of(code, market),
+                name: name.to_string(),
+                asset_type: asset_type.to_string(),
+                source: "eastmoney".to_string(),
+                currency: currency.to_string(),
+            });
+        }
+
+        if products.len() >= total {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(products)
+}
+
+pub struct EastmoneyStockAdapter {
+    client: Client,
+    adjustment: PriceAdjustment,
+}
+
+impl EastmoneyStockAdapter {
+    pub fn new(adjustment: PriceAdjustment) -> Self {
+        Self {
+            client: Client::new(),
+            adjustment,
+        }
+    }
+}
+
+#[async_trait]
+impl MarketAdapter for EastmoneyStockAdapter {
+    fn name(&self) -> &str {
+        "eastmoney"
+    }
+
+    fn asset_type(&self) -> &str {
+        "stock"
+    }
+
+    async fn check_connection(&self) -> Result<bool, String> {
+        let url = format!("{}?secid=1.000001&fields=f43", QUOTE_URL);
+
+        match self.client.get(&url).send().await {
+            Ok(_) => Ok(true),
+            Err(e) => Err(format!("Connection failed: {}", e)),
+        }
+    }
+
+    async fn get_products(&self) -> Result<Vec<Product>, String> {
+        // 沪深主板、创业板、科创板、北交所
+        fetch_product_list(
+            &self.client,
+            "m:0+t:6,m:0+t:80,m:1+t:2,m:1+t:23,m:0+t:81+s:2048",
+            "stock",
+            "CNY",
+            |code, _| code.to_string(),
+        )
+        .await
+    }
+
+    async fn get_ticker(&self, symbol: &str) -> Result<Ticker, String> {
+        let secid = a_share_secid(symbol)?;
+        fetch_ticker(&self.client, &secid, symbol, 100.0).await
+    }
+
+    async fn get_candles(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        interval: &str,
+    ) -> Result<Vec<Candle>, String> {
+        let secid = a_share_secid(symbol)?;
+        fetch_klines(
+            &self.client,
+            &secid,
+            interval,
+            self.adjustment,
+            start_time,
+            end_time,
+            100.0,
+        )
+        .await
+    }
+}
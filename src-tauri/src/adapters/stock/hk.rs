@@ -0,0 +1,124 @@
+/// `EastmoneyHkStockAdapter` 是 `MarketAdapter` trait 的实现，用于从东方财富获取港股行情数据。
+///
+/// # 主要功能
+/// - 获取港股主板及创业板股票列表（报价币种 HKD）
+/// - 获取港股实时行情
+/// - 获取日线/周线/月线及分钟线K线，支持不复权、前复权、后复权（拆股、派息调整）
+///
+/// # 字段
+/// - `client`: Reqwest HTTP 客户端
+/// - `adjustment`: K线复权方式（`PriceAdjustment`）
+///
+/// # 代码格式
+/// 支持 `00700.HK`、`0700.HK`、`700`、`HK00700`、`116.00700` 等写法，
+/// 统一为 5 位数字代码，产品代码格式为 `00700.HK`。
+///
+/// # 用法示例
+/// ```rust
+/// let adapter = EastmoneyHkStockAdapter::new(PriceAdjustment::None);
+/// let ticker = adapter.get_ticker("00700.HK").await?;
+/// let candles = adapter.get_candles("0700.HK", start, end, "1d").await?;
+/// ```
+///
+/// # 注意事项
+/// - 数据源 `eastmoney` 为不复权价格，用于行情同步和持仓估值；`eastmoney_qfq` / `eastmoney_hfq` 为复权价格，只用于指标和图表。
+/// - 港股免费行情可能存在延时。
+/// - 分钟线时间为北京时间（与香港时间相同），转换为 UTC 存储。
+///
+/// # 依赖
+/// - 复用 `adapters::stock::eastmoney` 中的请求函数
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+
+use super::eastmoney::{fetch_klines, fetch_product_list, fetch_ticker, PriceAdjustment};
+use crate::models::{Candle, MarketAdapter, Product, Ticker};
+
+/// 港股代码统一为 5 位数字
+pub fn normalize_hk_symbol(symbol: &str) -> Result<String, String> {
+    let upper = symbol.trim().to_uppercase();
+    let code = upper
+        .strip_suffix(".HK")
+        .or_else(|| upper.strip_prefix("HK"))
+        .or_else(|| upper.strip_prefix("116."))
+        .unwrap_or(&upper);
+
+    if code.is_empty() || code.len() > 5 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid HK stock symbol: {}", symbol));
+    }
+
+    Ok(format!("{:0>5}", code))
+}
+
+fn hk_secid(symbol: &str) -> Result<String, String> {
+    Ok(format!("116.{}", normalize_hk_symbol(symbol)?))
+}
+
+pub struct EastmoneyHkStockAdapter {
+    client: Client,
+    adjustment: PriceAdjustment,
+}
+
+impl EastmoneyHkStockAdapter {
+    pub fn new(adjustment: PriceAdjustment) -> Self {
+        Self {
+            client: Client::new(),
+            adjustment,
+        }
+    }
+}
+
+#[async_trait]
+impl MarketAdapter for EastmoneyHkStockAdapter {
+    fn name(&self) -> &str {
+        "eastmoney"
+    }
+
+    fn asset_type(&self) -> &str {
+        "hk_stock"
+    }
+
+    async fn check_connection(&self) -> Result<bool, String> {
+        fetch_ticker(&self.client, "116.00700", "00700.HK", 1.0)
+            .await
+            .map(|_| true)
+            .map_err(|e| format!("Connection failed: {}", e))
+    }
+
+    async fn get_products(&self) -> Result<Vec<Product>, String> {
+        // 港股主板、创业板
+        fetch_product_list(
+            &self.client,
+            "m:128+t:3,m:128+t:4",
+            "hk_stock",
+            "HKD",
+            |code, _| format!("{}.HK", code),
+        )
+        .await
+    }
+
+    async fn get_ticker(&self, symbol: &str) -> Result<Ticker, String> {
+        let secid = hk_secid(symbol)?;
+        fetch_ticker(&self.client, &secid, symbol, 1.0).await
+    }
+
+    async fn get_candles(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        interval: &str,
+    ) -> Result<Vec<Candle>, String> {
+        let secid = hk_secid(symbol)?;
+        fetch_klines(
+            &self.client,
+            &secid,
+            interval,
+            self.adjustment,
+            start_time,
+            end_time,
+            1.0,
+        )
+        .await
+    }
+}
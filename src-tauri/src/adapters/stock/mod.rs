@@ -1 +1,3 @@
-// 这里可以添加股票适配器的实现
\ No newline at end of file
+pub mod eastmoney;
+pub mod hk;
+pub mod us;
\ No newline at end of file
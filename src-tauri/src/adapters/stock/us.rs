@@ -0,0 +1,180 @@
+/// `EastmoneyUsStockAdapter` 是 `MarketAdapter` trait 的实现，用于从东方财富获取美股行情数据。
+///
+/// # 主要功能
+/// - 获取纳斯达克、纽交所、美国证券交易所股票列表（报价币种 USD）
+/// - 获取美股实时行情
+/// - 获取日线/周线/月线及分钟线K线，支持不复权、前复权、后复权（拆股、派息调整）
+///
+/// # 字段
+/// - `client`: Reqwest HTTP 客户端
+/// - `adjustment`: K线复权方式（`PriceAdjustment`）
+///
+/// # 代码格式
+/// - 普通代码：`AAPL`、`aapl`、`AAPL.US`、`US.AAPL`
+/// - 指定交易所：`NASDAQ:AAPL`、`NYSE:BABA`、`AMEX:SPY`，或后缀 `AAPL.O`（纳斯达克）、`BABA.N`（纽交所）
+///   （不支持 `.A` 后缀，避免与 `BRK.A` 等 A 类股混淆）
+/// - 多类别股份：`BRK.B`、`BRK-B`、`BRK/B` 统一为 `BRK_B`
+///
+/// 未指定交易所时依次尝试纳斯达克(105)、纽交所(106)、美交所(107)，取第一个有行情的市场，
+/// 探测结果按代码缓存在进程内，之后的请求不再重复探测。
+///
+/// # 用法示例
+/// ```rust
+/// let adapter = EastmoneyUsStockAdapter::new(PriceAdjustment::None);
+/// let ticker = adapter.get_ticker("AAPL").await?;
+/// let candles = adapter.get_candles("BRK.B", start, end, "1d").await?;
+/// ```
+///
+/// # 注意事项
+/// - 数据源 `eastmoney` 为不复权价格，用于行情同步和持仓估值；`eastmoney_qfq` / `eastmoney_hfq` 为复权价格，只用于指标和图表。
+/// - 日线时间戳为美东交易日的 UTC 零点，与 `price_history.date` 一致。
+/// - 美股免费行情可能存在延时。
+///
+/// # 依赖
+/// - 复用 `adapters::stock::eastmoney` 中的请求函数
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use super::eastmoney::{fetch_klines, fetch_product_list, fetch_ticker, PriceAdjustment};
+use crate::models::{Candle, MarketAdapter, Product, Ticker};
+
+/// 东方财富美股市场编号：纳斯达克、纽交所、美交所
+const US_MARKETS: [i64; 3] = [105, 106, 107];
+
+lazy_static! {
+    /// 已探测到的 secid（规范化代码 -> secid）
+    static ref RESOLVED_SECIDS: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+}
+
+/// 规范化美股代码，返回 (代码, 指定的市场编号)
+pub fn normalize_us_symbol(symbol: &str) -> Result<(String, Option<i64>), String> {
+    let mut upper = symbol.trim().to_uppercase();
+    let mut market = None;
+
+    if let Some((exchange, code)) = upper.clone().split_once(':') {
+        market = match exchange {
+            "NASDAQ" => Some(105),
+            "NYSE" => Some(106),
+            "AMEX" | "NYSEAMERICAN" => Some(107),
+            _ => return Err(format!("Unsupported US exchange: {}", exchange)),
+        };
+        upper = code.to_string();
+    }
+
+    if let Some(code) = upper.strip_prefix("US.") {
+        upper = code.to_string();
+    }
+    if let Some(code) = upper.strip_suffix(".US") {
+        upper = code.to_string();
+    }
+
+    for (suffix, id) in [(".O", 105), (".N", 106)] {
+        if let Some(code) = upper.strip_suffix(suffix) {
+            market = Some(id);
+            upper = code.to_string();
+            break;
+        }
+    }
+
+    let code = upper.replace(['.', '-', '/'], "_");
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid US stock symbol: {}", symbol));
+    }
+
+    Ok((code, market))
+}
+
+pub struct EastmoneyUsStockAdapter {
+    client: Client,
+    adjustment: PriceAdjustment,
+}
+
+impl EastmoneyUsStockAdapter {
+    pub fn new(adjustment: PriceAdjustment) -> Self {
+        Self {
+            client: Client::new(),
+            adjustment,
+        }
+    }
+
+    /// 确定 secid，未指定交易所时按市场顺序探测并缓存结果
+    async fn resolve_secid(&self, symbol: &str) -> Result<String, String> {
+        let (code, market) = normalize_us_symbol(symbol)?;
+
+        if let Some(market) = market {
+            return Ok(format!("{}.{}", market, code));
+        }
+
+        if let Some(secid) = RESOLVED_SECIDS.read().unwrap().get(&code) {
+            return Ok(secid.clone());
+        }
+
+        for market in US_MARKETS {
+            let secid = format!("{}.{}", market, code);
+            if fetch_ticker(&self.client, &secid, symbol, 1.0).await.is_ok() {
+                RESOLVED_SECIDS.write().unwrap().insert(code, secid.clone());
+                return Ok(secid);
+            }
+        }
+
+        Err(format!("Symbol not found: {}", symbol))
+    }
+}
+
+#[async_trait]
+impl MarketAdapter for EastmoneyUsStockAdapter {
+    fn name(&self) -> &str {
+        "eastmoney"
+    }
+
+    fn asset_type(&self) -> &str {
+        "us_stock"
+    }
+
+    async fn check_connection(&self) -> Result<bool, String> {
+        fetch_ticker(&self.client, "105.AAPL", "AAPL", 1.0)
+            .await
+            .map(|_| true)
+            .map_err(|e| format!("Connection failed: {}", e))
+    }
+
+    async fn get_products(&self) -> Result<Vec<Product>, String> {
+        fetch_product_list(
+            &self.client,
+            "m:105,m:106,m:107",
+            "us_stock",
+            "USD",
+            |code, _| code.replace('_', "."),
+        )
+        .await
+    }
+
+    async fn get_ticker(&self, symbol: &str) -> Result<Ticker, String> {
+        let secid = self.resolve_secid(symbol).await?;
+        fetch_ticker(&self.client, &secid, symbol, 1.0).await
+    }
+
+    async fn get_candles(
+        &self,
+        symbol: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        interval: &str,
+    ) -> Result<Vec<Candle>, String> {
+        let secid = self.resolve_secid(symbol).await?;
+        fetch_klines(
+            &self.client,
+            &secid,
+            interval,
+            self.adjustment,
+            start_time,
+            end_time,
+            1.0,
+        )
+        .await
+    }
+}
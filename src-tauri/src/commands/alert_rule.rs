@@ -0,0 +1,84 @@
+/**
+ * 提醒规则
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    AlertRule, AlertRuleTrigger, DeleteAlertRuleRequest, GetAlertRulesRequest, MessageResponse,
+    SaveAlertRuleRequest,
+};
+use crate::services::alert_rule::{
+    delete_alert_rule, evaluate_alert_rules, get_alert_rules, save_alert_rule,
+};
+use log::{error, info};
+use tauri::command;
+
+/// 创建或更新提醒规则
+#[command]
+pub async fn alert_save_alert_rule_command(
+    request: SaveAlertRuleRequest,
+) -> Result<AlertRule, ErrorResponse> {
+    info!("Save alert rule request received for user: {}", request.user_id);
+
+    match save_alert_rule(&request) {
+        Ok(rule) => {
+            info!("Alert rule saved successfully: {}", rule.name);
+            Ok(rule)
+        }
+        Err(err) => {
+            error!("Failed to save alert rule: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取用户的提醒规则
+#[command]
+pub async fn alert_get_alert_rules_command(
+    request: GetAlertRulesRequest,
+) -> Result<Vec<AlertRule>, ErrorResponse> {
+    match get_alert_rules(request.user_id, request.asset_id) {
+        Ok(rules) => {
+            info!("Retrieved {} alert rules for user: {}", rules.len(), request.user_id);
+            Ok(rules)
+        }
+        Err(err) => {
+            error!("Failed to get alert rules: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 删除提醒规则
+#[command]
+pub async fn alert_delete_alert_rule_command(
+    request: DeleteAlertRuleRequest,
+) -> Result<MessageResponse, ErrorResponse> {
+    info!("Delete alert rule request received for rule: {}", request.id);
+
+    match delete_alert_rule(request.id, request.user_id) {
+        Ok(_) => Ok(MessageResponse {
+            message: "提醒规则删除成功".to_string(),
+        }),
+        Err(err) => {
+            error!("Failed to delete alert rule: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 按当前价格评估用户的提醒规则
+#[command]
+pub async fn alert_evaluate_alert_rules_command(
+    user_id: i64,
+) -> Result<Vec<AlertRuleTrigger>, ErrorResponse> {
+    match evaluate_alert_rules(Some(user_id), None) {
+        Ok(triggers) => {
+            info!("{} alert rules triggered for user: {}", triggers.len(), user_id);
+            Ok(triggers)
+        }
+        Err(err) => {
+            error!("Failed to evaluate alert rules: {}", err);
+            Err(err.into())
+        }
+    }
+}
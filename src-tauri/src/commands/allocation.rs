@@ -0,0 +1,104 @@
+/**
+ * 目标配置与再平衡
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    DeleteTargetAllocationRequest, DriftReport, GetDriftReportRequest, MessageResponse,
+    PlanRebalanceRequest, RebalancePlan, SaveTargetAllocationRequest, TargetAllocation,
+};
+use crate::services::allocation::{
+    delete_target_allocation, get_drift_report, get_target_allocations, plan_rebalance,
+    save_target_allocation,
+};
+use log::{error, info};
+use tauri::command;
+
+/// 新增或修改目标配置
+#[command]
+pub async fn allocation_save_target_allocation_command(
+    request: SaveTargetAllocationRequest,
+) -> Result<TargetAllocation, ErrorResponse> {
+    info!("Save target allocation request received for user: {}", request.user_id);
+
+    match save_target_allocation(
+        request.id,
+        request.user_id,
+        &request.scope,
+        request.scope_id,
+        request.target_percent,
+        request.tolerance_percent,
+    ) {
+        Ok(allocation) => Ok(allocation),
+        Err(err) => {
+            error!("Failed to save target allocation: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取用户的目标配置
+#[command]
+pub async fn allocation_get_target_allocations_command(
+    user_id: i64,
+) -> Result<Vec<TargetAllocation>, ErrorResponse> {
+    match get_target_allocations(user_id) {
+        Ok(allocations) => Ok(allocations),
+        Err(err) => {
+            error!("Failed to get target allocations: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 删除目标配置
+#[command]
+pub async fn allocation_delete_target_allocation_command(
+    request: DeleteTargetAllocationRequest,
+) -> Result<MessageResponse, ErrorResponse> {
+    match delete_target_allocation(request.id, request.user_id) {
+        Ok(_) => Ok(MessageResponse {
+            message: "目标配置删除成功".to_string(),
+        }),
+        Err(err) => {
+            error!("Failed to delete target allocation: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取目标配置偏离报告
+#[command]
+pub async fn allocation_get_drift_report_command(
+    request: GetDriftReportRequest,
+) -> Result<DriftReport, ErrorResponse> {
+    match get_drift_report(request.user_id, request.scope.as_deref()) {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            error!("Failed to get drift report: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 生成再平衡方案
+#[command]
+pub async fn allocation_plan_rebalance_command(
+    request: PlanRebalanceRequest,
+) -> Result<RebalancePlan, ErrorResponse> {
+    info!("Plan rebalance request received for user: {}", request.user_id);
+
+    match plan_rebalance(
+        request.user_id,
+        &request.scope,
+        request.additional_cash.unwrap_or(0.0),
+        request.min_trade_amount.unwrap_or(0.0),
+        &request.lot_sizes.unwrap_or_default(),
+        request.create_pending.unwrap_or(false),
+    ) {
+        Ok(plan) => Ok(plan),
+        Err(err) => {
+            error!("Failed to plan rebalance: {}", err);
+            Err(err.into())
+        }
+    }
+}
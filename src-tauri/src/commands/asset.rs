@@ -5,12 +5,13 @@ use crate::error::auth::ErrorResponse;
 use crate::models::{
     AssetType, UserGroup, Asset, CreateGroupRequest, UpdateGroupRequest, DeleteGroupRequest,
     CreateAssetRequest, UpdateAssetRequest, DeleteAssetRequest, GetUserAssetsRequest,
-    MessageResponse,
+    MessageResponse, SetDividendMethodRequest,
 };
 use crate::services::asset::{
     get_asset_types, create_user_group, update_user_group, delete_user_group, get_user_groups,
     create_asset, update_asset, delete_asset, get_user_assets,
 };
+use crate::services::corporate_action::set_dividend_method;
 use tauri::command;
 use log::{info, error};
 
@@ -118,6 +119,8 @@ pub async fn asset_create_asset_command(request: CreateAssetRequest) -> Result<A
         &request.code,
         &request.name,
         request.current_price,
+        request.currency.as_deref(),
+        request.lot_size,
     ) {
         Ok(asset) => {
             info!("Asset created successfully: {} ({})", asset.name, asset.code);
@@ -142,6 +145,8 @@ pub async fn asset_update_asset_command(request: UpdateAssetRequest) -> Result<A
         request.current_price,
         request.position_amount,
         request.position_cost,
+        request.currency.as_deref(),
+        request.lot_size,
     ) {
         Ok(asset) => {
             info!("Asset updated successfully: {}", asset.name);
@@ -172,6 +177,24 @@ pub async fn asset_delete_asset_command(request: DeleteAssetRequest) -> Result<M
     }
 }
 
+#[command]
+pub async fn asset_set_dividend_method_command(request: SetDividendMethodRequest) -> Result<MessageResponse, ErrorResponse> {
+    info!("Set dividend method request received for asset: {}", request.asset_id);
+    
+    match set_dividend_method(request.user_id, request.asset_id, &request.dividend_method) {
+        Ok(_) => {
+            info!("Dividend method updated successfully: {}", request.asset_id);
+            Ok(MessageResponse {
+                message: "分红方式设置成功".to_string(),
+            })
+        },
+        Err(err) => {
+            error!("Failed to set dividend method: {}", err);
+            Err(err.into())
+        },
+    }
+}
+
 #[command]
 pub async fn asset_get_user_assets_command(request: GetUserAssetsRequest) -> Result<Vec<Asset>, ErrorResponse> {
     info!("Get user assets request received for user: {}", request.user_id);
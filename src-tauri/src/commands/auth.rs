@@ -90,7 +90,7 @@ pub async fn auth_send_verification_code_command(
         request.email, request.purpose
     );
 
-    match generate_and_send_verification_code(&request.email, &request.purpose) {
+    match generate_and_send_verification_code(&request.email, &request.purpose).await {
         Ok(_) => {
             info!("Verification code sent to: {}", request.email);
             Ok(MessageResponse {
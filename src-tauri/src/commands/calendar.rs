@@ -0,0 +1,31 @@
+/**
+ * 交易日历
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{GetTradingDaysRequest, MessageResponse, TradingDays};
+use crate::services::calendar::{get_trading_days, reload_calendars};
+use log::error;
+use tauri::command;
+
+/// 获取区间内的交易日和休市日
+#[command]
+pub async fn calendar_get_trading_days_command(
+    request: GetTradingDaysRequest,
+) -> Result<TradingDays, ErrorResponse> {
+    match get_trading_days(&request.exchange, &request.start_date, &request.end_date) {
+        Ok(days) => Ok(days),
+        Err(err) => {
+            error!("Failed to get trading days: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 重新加载休市日数据（修改自定义休市日文件后调用）
+#[command]
+pub async fn calendar_reload_command() -> Result<MessageResponse, ErrorResponse> {
+    reload_calendars();
+    Ok(MessageResponse {
+        message: "交易日历已重新加载".to_string(),
+    })
+}
@@ -3,13 +3,20 @@
  */
 use crate::error::auth::ErrorResponse;
 use crate::models::{
-    GetAssetPriceHistoryRequest, MarkAlertReadRequest, MessageResponse, PortfolioSummary,
-    PriceHistory, TradeAlert,
+    AssetSyncStatus, CheckCandleQualityRequest, CorporateAction, CorporateActionSyncReport,
+    DataQualityReport, GetAssetPriceHistoryRequest,
+    MarkAlertReadRequest, MarketSyncReport, MessageResponse, PortfolioSummary, PriceHistory,
+    SyncCorporateActionsRequest, SyncMarketDataRequest, TradeAlert,
+};
+use crate::services::corporate_action::{
+    get_asset_corporate_actions, sync_corporate_actions, DEFAULT_LOOKBACK_DAYS,
 };
 use crate::services::data::{
     create_trade_alert, get_asset_price_history, get_portfolio_summary, get_user_trade_alerts,
     mark_alert_read, update_asset_price, update_asset_price_batch,
 };
+use crate::services::data_quality::check_dataset;
+use crate::services::market_sync::{get_asset_sync_status, sync_assets};
 use serde_json::Value;
 
 #[tauri::command]
@@ -93,3 +100,56 @@ pub async fn data_get_portfolio_summary(user_id: i64) -> Result<PortfolioSummary
         Err(e) => Err(ErrorResponse::from(e)),
     }
 }
+
+#[tauri::command]
+pub async fn data_check_candle_quality(
+    request: CheckCandleQualityRequest,
+) -> Result<DataQualityReport, ErrorResponse> {
+    match check_dataset(&request) {
+        Ok(report) => Ok(report),
+        Err(e) => Err(ErrorResponse::from(e)),
+    }
+}
+
+#[tauri::command]
+pub async fn data_sync_market_data(
+    request: SyncMarketDataRequest,
+) -> Result<MarketSyncReport, ErrorResponse> {
+    let asset_types = request.asset_type.map(|t| vec![t]);
+    match sync_assets(Some(request.user_id), asset_types, true).await {
+        Ok(report) => Ok(report),
+        Err(e) => Err(ErrorResponse::from(e)),
+    }
+}
+
+#[tauri::command]
+pub async fn data_get_asset_sync_status(
+    user_id: i64,
+) -> Result<Vec<AssetSyncStatus>, ErrorResponse> {
+    match get_asset_sync_status(user_id) {
+        Ok(statuses) => Ok(statuses),
+        Err(e) => Err(ErrorResponse::from(e)),
+    }
+}
+
+#[tauri::command]
+pub async fn data_sync_corporate_actions(
+    request: SyncCorporateActionsRequest,
+) -> Result<CorporateActionSyncReport, ErrorResponse> {
+    let lookback_days = request.lookback_days.unwrap_or(DEFAULT_LOOKBACK_DAYS);
+    match sync_corporate_actions(Some(request.user_id), lookback_days).await {
+        Ok(report) => Ok(report),
+        Err(e) => Err(ErrorResponse::from(e)),
+    }
+}
+
+#[tauri::command]
+pub async fn data_get_corporate_actions(
+    user_id: i64,
+    asset_id: i64,
+) -> Result<Vec<CorporateAction>, ErrorResponse> {
+    match get_asset_corporate_actions(user_id, asset_id) {
+        Ok(actions) => Ok(actions),
+        Err(e) => Err(ErrorResponse::from(e)),
+    }
+}
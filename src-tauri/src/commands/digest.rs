@@ -0,0 +1,62 @@
+/**
+ * 摘要报告
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    DigestDeliveryResult, DigestPreview, DigestRequest, DigestSettings, UpdateDigestSettingsRequest,
+};
+use crate::services::digest::{get_digest_settings, preview_digest, send_digest, update_digest_settings};
+use log::{error, info};
+use tauri::command;
+
+/// 获取用户的摘要报告设置
+#[command]
+pub async fn digest_get_settings_command(user_id: i64) -> Result<DigestSettings, ErrorResponse> {
+    match get_digest_settings(user_id) {
+        Ok(settings) => Ok(settings),
+        Err(err) => {
+            error!("Failed to get digest settings: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 更新用户的摘要报告设置
+#[command]
+pub async fn digest_update_settings_command(
+    request: UpdateDigestSettingsRequest,
+) -> Result<DigestSettings, ErrorResponse> {
+    match update_digest_settings(&request) {
+        Ok(settings) => Ok(settings),
+        Err(err) => {
+            error!("Failed to update digest settings: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 生成日报 / 周报预览（Markdown 和 HTML）
+#[command]
+pub async fn digest_preview_command(request: DigestRequest) -> Result<DigestPreview, ErrorResponse> {
+    match preview_digest(request.user_id, &request.period) {
+        Ok(preview) => Ok(preview),
+        Err(err) => {
+            error!("Failed to preview digest: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 立即生成日报 / 周报并按设置投递
+#[command]
+pub async fn digest_send_command(request: DigestRequest) -> Result<DigestDeliveryResult, ErrorResponse> {
+    info!("Send {} digest request received for user: {}", request.period, request.user_id);
+
+    match send_digest(request.user_id, &request.period) {
+        Ok(result) => Ok(result),
+        Err(err) => {
+            error!("Failed to send digest: {}", err);
+            Err(err.into())
+        }
+    }
+}
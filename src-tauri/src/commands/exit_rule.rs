@@ -0,0 +1,84 @@
+/**
+ * 止盈止损
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    DeleteExitRuleRequest, ExitRule, ExitRuleTrigger, GetExitRulesRequest, MessageResponse,
+    SaveExitRuleRequest,
+};
+use crate::services::exit_rule::{
+    delete_exit_rule, evaluate_exit_rules, get_exit_rules, save_exit_rule,
+};
+use log::{error, info};
+use tauri::command;
+
+/// 创建或更新止盈止损规则
+#[command]
+pub async fn exit_save_exit_rule_command(
+    request: SaveExitRuleRequest,
+) -> Result<ExitRule, ErrorResponse> {
+    info!("Save exit rule request received for user: {}", request.user_id);
+
+    match save_exit_rule(&request) {
+        Ok(rule) => {
+            info!("Exit rule saved successfully: {}", rule.id);
+            Ok(rule)
+        }
+        Err(err) => {
+            error!("Failed to save exit rule: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取用户的止盈止损规则
+#[command]
+pub async fn exit_get_exit_rules_command(
+    request: GetExitRulesRequest,
+) -> Result<Vec<ExitRule>, ErrorResponse> {
+    match get_exit_rules(request.user_id, request.asset_id) {
+        Ok(rules) => {
+            info!("Retrieved {} exit rules for user: {}", rules.len(), request.user_id);
+            Ok(rules)
+        }
+        Err(err) => {
+            error!("Failed to get exit rules: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 删除止盈止损规则
+#[command]
+pub async fn exit_delete_exit_rule_command(
+    request: DeleteExitRuleRequest,
+) -> Result<MessageResponse, ErrorResponse> {
+    info!("Delete exit rule request received for rule: {}", request.id);
+
+    match delete_exit_rule(request.id, request.user_id) {
+        Ok(_) => Ok(MessageResponse {
+            message: "止盈止损规则删除成功".to_string(),
+        }),
+        Err(err) => {
+            error!("Failed to delete exit rule: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 按当前价格评估用户的止盈止损规则
+#[command]
+pub async fn exit_evaluate_exit_rules_command(
+    user_id: i64,
+) -> Result<Vec<ExitRuleTrigger>, ErrorResponse> {
+    match evaluate_exit_rules(Some(user_id), None) {
+        Ok(triggers) => {
+            info!("{} exit rules triggered for user: {}", triggers.len(), user_id);
+            Ok(triggers)
+        }
+        Err(err) => {
+            error!("Failed to evaluate exit rules: {}", err);
+            Err(err.into())
+        }
+    }
+}
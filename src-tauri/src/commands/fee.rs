@@ -0,0 +1,99 @@
+/**
+ * 交易费用
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    DeleteFeeScheduleRequest, EstimateFeeRequest, FeeBreakdown, FeeSchedule, MessageResponse,
+    SaveFeeScheduleRequest,
+};
+use crate::services::fee::{
+    delete_fee_schedule, estimate_fee, get_fee_schedules, save_fee_schedule,
+};
+use log::{error, info};
+use tauri::command;
+
+/// 创建或更新费率方案
+#[command]
+pub async fn fee_save_fee_schedule_command(
+    request: SaveFeeScheduleRequest,
+) -> Result<FeeSchedule, ErrorResponse> {
+    info!("Save fee schedule request received for user: {}", request.user_id);
+
+    match save_fee_schedule(
+        request.id,
+        request.user_id,
+        request.asset_type_id,
+        request.asset_id,
+        &request.name,
+        &request.buy_tiers,
+        request.buy_discount.unwrap_or(1.0),
+        &request.redemption_tiers,
+        request.commission_rate.unwrap_or(0.0),
+        request.min_commission.unwrap_or(0.0),
+        request.stamp_duty_rate.unwrap_or(0.0),
+        request.transfer_fee_rate.unwrap_or(0.0),
+    ) {
+        Ok(schedule) => {
+            info!("Fee schedule saved successfully: {}", schedule.name);
+            Ok(schedule)
+        }
+        Err(err) => {
+            error!("Failed to save fee schedule: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取用户的费率方案
+#[command]
+pub async fn fee_get_fee_schedules_command(user_id: i64) -> Result<Vec<FeeSchedule>, ErrorResponse> {
+    match get_fee_schedules(user_id) {
+        Ok(schedules) => {
+            info!("Retrieved {} fee schedules for user: {}", schedules.len(), user_id);
+            Ok(schedules)
+        }
+        Err(err) => {
+            error!("Failed to get fee schedules: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 删除费率方案
+#[command]
+pub async fn fee_delete_fee_schedule_command(
+    request: DeleteFeeScheduleRequest,
+) -> Result<MessageResponse, ErrorResponse> {
+    info!("Delete fee schedule request received for schedule: {}", request.id);
+
+    match delete_fee_schedule(request.id, request.user_id) {
+        Ok(_) => Ok(MessageResponse {
+            message: "费率方案删除成功".to_string(),
+        }),
+        Err(err) => {
+            error!("Failed to delete fee schedule: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 预估交易费用
+#[command]
+pub async fn fee_estimate_fee_command(
+    request: EstimateFeeRequest,
+) -> Result<FeeBreakdown, ErrorResponse> {
+    match estimate_fee(
+        request.user_id,
+        request.asset_id,
+        &request.transaction_type,
+        request.amount,
+        request.price,
+        request.transaction_date,
+    ) {
+        Ok(fee) => Ok(fee),
+        Err(err) => {
+            error!("Failed to estimate fee: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -0,0 +1,85 @@
+/**
+ * 汇率
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    DeleteFxRateRequest, FxRate, FxSyncReport, GetFxRatesRequest, MessageResponse, SaveFxRateRequest,
+    SyncFxRatesRequest,
+};
+use crate::services::fx::{delete_fx_rate, get_fx_rates, save_manual_rate, sync_fx_rates};
+use log::{error, info};
+use tauri::command;
+
+/// 默认回补的汇率天数
+const DEFAULT_FX_LOOKBACK_DAYS: i64 = 365;
+
+/// 手动录入汇率
+#[command]
+pub async fn fx_save_manual_rate_command(request: SaveFxRateRequest) -> Result<FxRate, ErrorResponse> {
+    info!(
+        "Save manual fx rate request received: {}/{}",
+        request.base_currency, request.quote_currency
+    );
+
+    match save_manual_rate(
+        request.user_id,
+        &request.base_currency,
+        &request.quote_currency,
+        request.rate,
+        request.rate_date,
+    ) {
+        Ok(rate) => Ok(rate),
+        Err(err) => {
+            error!("Failed to save manual fx rate: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取汇率历史
+#[command]
+pub async fn fx_get_rates_command(request: GetFxRatesRequest) -> Result<Vec<FxRate>, ErrorResponse> {
+    match get_fx_rates(
+        request.user_id,
+        &request.base_currency,
+        &request.quote_currency,
+        request.start_date,
+        request.end_date,
+    ) {
+        Ok(rates) => Ok(rates),
+        Err(err) => {
+            error!("Failed to get fx rates: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 删除汇率记录
+#[command]
+pub async fn fx_delete_rate_command(request: DeleteFxRateRequest) -> Result<MessageResponse, ErrorResponse> {
+    info!("Delete fx rate request received for rate: {}", request.id);
+
+    match delete_fx_rate(request.id, request.user_id) {
+        Ok(_) => Ok(MessageResponse {
+            message: "汇率删除成功".to_string(),
+        }),
+        Err(err) => {
+            error!("Failed to delete fx rate: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 手动触发汇率同步
+#[command]
+pub async fn fx_sync_rates_command(request: SyncFxRatesRequest) -> Result<FxSyncReport, ErrorResponse> {
+    info!("Fx sync request received");
+
+    match sync_fx_rates(request.lookback_days.unwrap_or(DEFAULT_FX_LOOKBACK_DAYS)).await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            error!("Failed to sync fx rates: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -3,12 +3,18 @@
  */
 use crate::error::auth::ErrorResponse;
 use crate::models::{
-    DeleteInvestmentPlanRequest, InvestmentPlan, MessageResponse, SaveInvestmentPlanRequest,
+    DeleteInvestmentPlanRequest, GetPlanExecutionsRequest, InvestmentPlan, MessageResponse,
+    PlanAmount, PlanBacktestRequest, PlanBacktestResult, PlanExecution, PlanSchedule,
+    PlanSettlementSummary, PlanStopConditions, PreviewPlanAmountRequest,
+    SaveInvestmentPlanRequest,
 };
 use crate::services::investment_plan::{
-    delete_investment_plan, execute_due_investment_plans, get_user_investment_plans,
-    save_investment_plan,get_today_investment_plans
+    delete_investment_plan, get_user_investment_plans,
+    save_investment_plan,get_today_investment_plans, preview_plan_amount,
 };
+use crate::services::plan_backtest::backtest_investment_plan;
+use crate::services::plan_execution::{execute_due_investment_plans, get_plan_executions};
+use crate::services::plan_settlement::settle_pending_plan_executions;
 use log::{error, info};
 use tauri::command;
 use tauri::http::request;
@@ -28,10 +34,23 @@ pub async fn plan_save_investment_plan_command(
         request.user_id,
         request.asset_id,
         &request.name,
-        &request.frequency,
-        request.day_of_week,
-        request.day_of_month,
+        &PlanSchedule {
+            frequency: request.frequency.clone(),
+            day_of_week: request.day_of_week,
+            day_of_month: request.day_of_month,
+            days_of_month: request.days_of_month.clone().unwrap_or_default(),
+            cron_expression: request.cron_expression.clone(),
+            start_date: request.start_date,
+            end_date: request.end_date,
+        },
         request.amount,
+        request.mode.as_deref(),
+        request.mode_config.as_ref(),
+        request.catch_up_policy.as_deref(),
+        &PlanStopConditions {
+            max_total_amount: request.max_total_amount,
+            take_profit_percent: request.take_profit_percent,
+        },
         true,
     ) {
         Ok(plan) => {
@@ -134,4 +153,60 @@ pub async fn plan_get_today_investment_plans_command(
             Err(err.into())
         },
     }
-}
\ No newline at end of file
+}
+
+/// 确认到期的待确认定投（按成交日净值）
+#[command]
+pub async fn plan_settle_pending_executions_command() -> Result<PlanSettlementSummary, ErrorResponse> {
+    info!("Settle pending plan executions request received");
+
+    match settle_pending_plan_executions() {
+        Ok(summary) => Ok(summary),
+        Err(err) => {
+            error!("Failed to settle pending plan executions: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 预览定投计划本期金额
+#[command]
+pub async fn plan_preview_plan_amount_command(
+    request: PreviewPlanAmountRequest,
+) -> Result<PlanAmount, ErrorResponse> {
+    match preview_plan_amount(request.id, request.user_id) {
+        Ok(plan_amount) => Ok(plan_amount),
+        Err(err) => {
+            error!("Failed to preview plan amount: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 回测定投计划
+#[command]
+pub async fn plan_backtest_investment_plan_command(
+    request: PlanBacktestRequest,
+) -> Result<PlanBacktestResult, ErrorResponse> {
+    match backtest_investment_plan(&request) {
+        Ok(result) => Ok(result),
+        Err(err) => {
+            error!("Failed to backtest investment plan: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取定投计划的执行记录
+#[command]
+pub async fn plan_get_plan_executions_command(
+    request: GetPlanExecutionsRequest,
+) -> Result<Vec<PlanExecution>, ErrorResponse> {
+    match get_plan_executions(request.user_id, request.plan_id, request.limit) {
+        Ok(executions) => Ok(executions),
+        Err(err) => {
+            error!("Failed to get plan executions: {}", err);
+            Err(err.into())
+        }
+    }
+}
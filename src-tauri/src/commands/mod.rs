@@ -3,4 +3,17 @@ pub mod asset;
 pub mod transaction;
 pub mod investment_plan;
 pub mod strategy;
-pub mod data;
\ No newline at end of file
+pub mod data;
+pub mod fee;
+pub mod position;
+pub mod settings;
+pub mod performance;
+pub mod snapshot;
+pub mod fx;
+pub mod allocation;
+pub mod calendar;
+pub mod exit_rule;
+pub mod alert_rule;
+pub mod strategy_signal;
+pub mod notification;
+pub mod digest;
@@ -0,0 +1,143 @@
+/**
+ * 通知推送
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    GetNotificationDeliveriesRequest, MessageResponse, NotificationChannel, NotificationChannelRequest,
+    NotificationDelivery, NotificationDispatchReport, NotificationPreferences,
+    SaveNotificationChannelRequest, UpdateNotificationPreferencesRequest,
+};
+use crate::services::notification::{
+    delete_notification_channel, get_notification_channels, get_notification_deliveries,
+    get_notification_preferences, process_notifications, save_notification_channel,
+    send_test_notification, update_notification_preferences,
+};
+use log::{error, info};
+use tauri::command;
+
+/// 创建或更新通知渠道
+#[command]
+pub async fn notify_save_channel_command(
+    request: SaveNotificationChannelRequest,
+) -> Result<NotificationChannel, ErrorResponse> {
+    info!("Save notification channel request received for user: {}", request.user_id);
+
+    match save_notification_channel(&request) {
+        Ok(channel) => {
+            info!("Notification channel saved successfully: {}", channel.name);
+            Ok(channel)
+        }
+        Err(err) => {
+            error!("Failed to save notification channel: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取用户的通知渠道
+#[command]
+pub async fn notify_get_channels_command(
+    user_id: i64,
+) -> Result<Vec<NotificationChannel>, ErrorResponse> {
+    match get_notification_channels(user_id) {
+        Ok(channels) => {
+            info!("Retrieved {} notification channels for user: {}", channels.len(), user_id);
+            Ok(channels)
+        }
+        Err(err) => {
+            error!("Failed to get notification channels: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 删除通知渠道
+#[command]
+pub async fn notify_delete_channel_command(
+    request: NotificationChannelRequest,
+) -> Result<MessageResponse, ErrorResponse> {
+    info!("Delete notification channel request received for channel: {}", request.id);
+
+    match delete_notification_channel(request.id, request.user_id) {
+        Ok(_) => Ok(MessageResponse {
+            message: "通知渠道删除成功".to_string(),
+        }),
+        Err(err) => {
+            error!("Failed to delete notification channel: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 通过渠道发送测试通知
+#[command]
+pub async fn notify_test_channel_command(
+    request: NotificationChannelRequest,
+) -> Result<MessageResponse, ErrorResponse> {
+    match send_test_notification(request.id, request.user_id).await {
+        Ok(_) => Ok(MessageResponse {
+            message: "测试通知已发送".to_string(),
+        }),
+        Err(err) => {
+            error!("Failed to send test notification: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取用户的通知偏好
+#[command]
+pub async fn notify_get_preferences_command(
+    user_id: i64,
+) -> Result<NotificationPreferences, ErrorResponse> {
+    match get_notification_preferences(user_id) {
+        Ok(preferences) => Ok(preferences),
+        Err(err) => {
+            error!("Failed to get notification preferences: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 更新用户的通知偏好
+#[command]
+pub async fn notify_update_preferences_command(
+    request: UpdateNotificationPreferencesRequest,
+) -> Result<NotificationPreferences, ErrorResponse> {
+    match update_notification_preferences(&request) {
+        Ok(preferences) => Ok(preferences),
+        Err(err) => {
+            error!("Failed to update notification preferences: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取用户的通知发送记录
+#[command]
+pub async fn notify_get_deliveries_command(
+    request: GetNotificationDeliveriesRequest,
+) -> Result<Vec<NotificationDelivery>, ErrorResponse> {
+    match get_notification_deliveries(request.user_id, request.status.as_deref(), request.limit) {
+        Ok(deliveries) => {
+            info!("Retrieved {} notification deliveries for user: {}", deliveries.len(), request.user_id);
+            Ok(deliveries)
+        }
+        Err(err) => {
+            error!("Failed to get notification deliveries: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 立即推送新提醒并发送到期的通知
+#[command]
+pub async fn notify_dispatch_command() -> Result<NotificationDispatchReport, ErrorResponse> {
+    match process_notifications().await {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            error!("Failed to dispatch notifications: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -0,0 +1,36 @@
+/**
+ * 收益率
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{GetReturnsRequest, PerformanceReport};
+use crate::services::performance::get_returns;
+use log::{error, info};
+use tauri::command;
+
+/// 获取资产、分组、资产类型、定投计划或整个组合的 XIRR 和 TWR
+#[command]
+pub async fn performance_get_returns_command(
+    request: GetReturnsRequest,
+) -> Result<Vec<PerformanceReport>, ErrorResponse> {
+    match get_returns(
+        request.user_id,
+        &request.scope,
+        request.scope_id,
+        request.start_date,
+        request.end_date,
+    ) {
+        Ok(reports) => {
+            info!(
+                "Calculated {} {} return reports for user: {}",
+                reports.len(),
+                request.scope,
+                request.user_id
+            );
+            Ok(reports)
+        }
+        Err(err) => {
+            error!("Failed to calculate returns: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -0,0 +1,52 @@
+/**
+ * 持仓台账
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{GetPositionLedgerRequest, GetRealizedTradesRequest, PositionLedger, RealizedTrade};
+use crate::services::position::{get_position_ledger, get_realized_trades};
+use log::{error, info};
+use tauri::command;
+
+/// 获取资产的持仓批次、浮动盈亏和卖出明细
+#[command]
+pub async fn position_get_position_ledger_command(
+    request: GetPositionLedgerRequest,
+) -> Result<PositionLedger, ErrorResponse> {
+    match get_position_ledger(request.user_id, request.asset_id, request.method.as_deref()) {
+        Ok(ledger) => {
+            info!(
+                "Retrieved ledger for asset {} with {} lots",
+                request.asset_id,
+                ledger.lots.len()
+            );
+            Ok(ledger)
+        }
+        Err(err) => {
+            error!("Failed to get position ledger: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取已实现盈亏明细
+#[command]
+pub async fn position_get_realized_trades_command(
+    request: GetRealizedTradesRequest,
+) -> Result<Vec<RealizedTrade>, ErrorResponse> {
+    match get_realized_trades(
+        request.user_id,
+        request.asset_id,
+        request.start_date,
+        request.end_date,
+        request.method.as_deref(),
+    ) {
+        Ok(trades) => {
+            info!("Retrieved {} realized trades for user: {}", trades.len(), request.user_id);
+            Ok(trades)
+        }
+        Err(err) => {
+            error!("Failed to get realized trades: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -0,0 +1,40 @@
+/**
+ * 用户设置
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{UpdateUserSettingsRequest, UserSettings};
+use crate::services::settings::{get_user_settings, update_user_settings};
+use log::{error, info};
+use tauri::command;
+
+/// 获取用户设置
+#[command]
+pub async fn settings_get_user_settings_command(user_id: i64) -> Result<UserSettings, ErrorResponse> {
+    match get_user_settings(user_id) {
+        Ok(settings) => Ok(settings),
+        Err(err) => {
+            error!("Failed to get user settings: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 更新用户设置
+#[command]
+pub async fn settings_update_user_settings_command(
+    request: UpdateUserSettingsRequest,
+) -> Result<UserSettings, ErrorResponse> {
+    info!("Update user settings request received for user: {}", request.user_id);
+
+    match update_user_settings(
+        request.user_id,
+        request.cost_basis_method.as_deref(),
+        request.base_currency.as_deref(),
+    ) {
+        Ok(settings) => Ok(settings),
+        Err(err) => {
+            error!("Failed to update user settings: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/**
+ * 组合净值快照
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::{
+    BackfillSnapshotsRequest, DrawdownReport, GetPeriodReturnsRequest, GetSnapshotsRequest,
+    MessageResponse, PeriodReturn, PortfolioSnapshot,
+};
+use crate::services::snapshot::{backfill_snapshots, get_drawdowns, get_period_returns, get_snapshots};
+use log::{error, info};
+use tauri::command;
+
+/// 回填净值快照
+#[command]
+pub async fn snapshot_backfill_snapshots_command(
+    request: BackfillSnapshotsRequest,
+) -> Result<MessageResponse, ErrorResponse> {
+    info!("Backfill snapshots request received for user: {}", request.user_id);
+
+    match backfill_snapshots(request.user_id, request.start_date) {
+        Ok(count) => Ok(MessageResponse {
+            message: format!("已生成 {} 条净值快照", count),
+        }),
+        Err(err) => {
+            error!("Failed to backfill snapshots: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取净值曲线
+#[command]
+pub async fn snapshot_get_net_worth_curve_command(
+    request: GetSnapshotsRequest,
+) -> Result<Vec<PortfolioSnapshot>, ErrorResponse> {
+    match get_snapshots(
+        request.user_id,
+        &request.scope,
+        request.scope_id,
+        request.start_date,
+        request.end_date,
+    ) {
+        Ok(snapshots) => Ok(snapshots),
+        Err(err) => {
+            error!("Failed to get net worth curve: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取回撤统计
+#[command]
+pub async fn snapshot_get_drawdowns_command(
+    request: GetSnapshotsRequest,
+) -> Result<DrawdownReport, ErrorResponse> {
+    match get_drawdowns(
+        request.user_id,
+        &request.scope,
+        request.scope_id,
+        request.start_date,
+        request.end_date,
+    ) {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            error!("Failed to get drawdowns: {}", err);
+            Err(err.into())
+        }
+    }
+}
+
+/// 获取月度 / 年度收益率
+#[command]
+pub async fn snapshot_get_period_returns_command(
+    request: GetPeriodReturnsRequest,
+) -> Result<Vec<PeriodReturn>, ErrorResponse> {
+    match get_period_returns(
+        request.user_id,
+        &request.scope,
+        request.scope_id,
+        &request.period,
+    ) {
+        Ok(returns) => Ok(returns),
+        Err(err) => {
+            error!("Failed to get period returns: {}", err);
+            Err(err.into())
+        }
+    }
+}
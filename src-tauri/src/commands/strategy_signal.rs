@@ -0,0 +1,25 @@
+/**
+ * 策略信号
+ */
+use crate::error::auth::ErrorResponse;
+use crate::models::StrategySignal;
+use crate::services::strategy_signal::evaluate_strategy_applications;
+use log::{error, info};
+use tauri::command;
+
+/// 按最新行情评估用户的策略应用，生成买卖信号提醒
+#[command]
+pub async fn signal_evaluate_strategy_applications_command(
+    user_id: i64,
+) -> Result<Vec<StrategySignal>, ErrorResponse> {
+    match evaluate_strategy_applications(Some(user_id), None) {
+        Ok(signals) => {
+            info!("{} strategy signals generated for user: {}", signals.len(), user_id);
+            Ok(signals)
+        }
+        Err(err) => {
+            error!("Failed to evaluate strategy applications: {}", err);
+            Err(err.into())
+        }
+    }
+}
@@ -4,10 +4,11 @@
 use crate::error::auth::ErrorResponse;
 use crate::models::{
     Transaction, CreateTransactionRequest, UpdateTransactionRequest, DeleteTransactionRequest,
-    GetUserTransactionsRequest, MessageResponse,
+    GetUserTransactionsRequest, ConfirmTransactionRequest, MessageResponse,
 };
 use crate::services::transaction::{
     create_transaction, update_transaction, delete_transaction, get_user_transactions,
+    confirm_transaction,
 };
 use tauri::command;
 use log::{info, error};
@@ -22,6 +23,7 @@ pub async fn create_transaction_command(request: CreateTransactionRequest) -> Re
         &request.transaction_type,
         request.amount,
         request.price,
+        request.fee,
         request.transaction_date,
         request.notes.as_deref(),
     ) {
@@ -46,6 +48,7 @@ pub async fn update_transaction_command(request: UpdateTransactionRequest) -> Re
         &request.transaction_type,
         request.amount,
         request.price,
+        request.fee,
         request.transaction_date,
         request.notes.as_deref(),
     ) {
@@ -82,7 +85,13 @@ pub async fn delete_transaction_command(request: DeleteTransactionRequest) -> Re
 pub async fn get_user_transactions_command(request: GetUserTransactionsRequest) -> Result<Vec<Transaction>, ErrorResponse> {
     info!("Get user transactions request received for user: {}", request.user_id);
     
-    match get_user_transactions(request.user_id, request.asset_id, request.start_date, request.end_date) {
+    match get_user_transactions(
+        request.user_id,
+        request.asset_id,
+        request.start_date,
+        request.end_date,
+        request.status.as_deref(),
+    ) {
         Ok(transactions) => {
             info!("Retrieved {} transactions for user: {}", transactions.len(), request.user_id);
             Ok(transactions)
@@ -92,4 +101,22 @@ pub async fn get_user_transactions_command(request: GetUserTransactionsRequest)
             Err(err.into())
         },
     }
-}
\ No newline at end of file
+}
+
+#[command]
+pub async fn confirm_transaction_command(request: ConfirmTransactionRequest) -> Result<MessageResponse, ErrorResponse> {
+    info!("Confirm transaction request received for transaction: {}", request.id);
+    
+    match confirm_transaction(request.id, request.user_id) {
+        Ok(_) => {
+            info!("Transaction confirmed successfully: {}", request.id);
+            Ok(MessageResponse {
+                message: "交易确认成功".to_string(),
+            })
+        },
+        Err(err) => {
+            error!("Failed to confirm transaction: {}", err);
+            Err(err.into())
+        },
+    }
+}
@@ -1,5 +1,6 @@
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::sync::RwLock;
@@ -24,6 +25,238 @@ pub struct Config {
 
     // 新增交易相关配置
     pub trading: TradingConfig, // 交易系统配置
+
+    // 行情自动同步配置
+    #[serde(default)]
+    pub market_sync: MarketSyncConfig, // 行情同步配置
+
+    // 汇率配置
+    #[serde(default)]
+    pub fx: FxConfig, // 汇率同步配置
+
+    // 交易日历配置
+    #[serde(default)]
+    pub calendar: CalendarConfig, // 交易日历配置
+
+    // 定投成交确认配置
+    #[serde(default)]
+    pub settlement: SettlementConfig, // 定投成交确认配置
+
+    // 通知推送配置
+    #[serde(default)]
+    pub notification: NotificationConfig, // 通知推送配置
+
+    // 邮件发送配置
+    #[serde(default)]
+    pub email: EmailConfig, // SMTP 邮件配置
+
+    // 摘要报告配置
+    #[serde(default)]
+    pub digest: DigestConfig, // 日报 / 周报配置
+}
+
+// ==================== 摘要报告配置 ====================
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DigestConfig {
+    pub enabled: bool,      // 是否由后台任务按用户设置发送日报 / 周报
+    pub send_hour: u32,     // 每日发送时间(北京时间小时，放在收盘同步和净值快照之后)
+    pub output_dir: String, // 报告保存目录(按用户分子目录)
+    pub top_movers: usize,  // 报告中列出的涨跌幅最大资产数
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        DigestConfig {
+            enabled: true,
+            send_hour: 22,
+            output_dir: "data/reports".to_string(),
+            top_movers: 5,
+        }
+    }
+}
+
+// ==================== 邮件发送配置 ====================
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmailConfig {
+    pub enabled: bool,        // 是否发送邮件(关闭时开发模式下只记录到 debug 日志)
+    pub transport: String,    // 发送方式(SMTP / FILE)，FILE 把邮件写入 outbox_dir，用于本地测试
+    pub host: String,         // SMTP 服务器
+    pub port: u16,            // SMTP 端口
+    pub tls_mode: String,     // 加密方式(NONE / STARTTLS / TLS)
+    pub username: String,     // SMTP 用户名(为空时不认证)
+    pub password: String,     // SMTP 密码
+    pub from_email: String,   // 发件人邮箱
+    pub from_name: String,    // 发件人名称
+    pub timeout_seconds: u64, // 发送超时时间(秒)
+    pub outbox_dir: String,   // FILE 方式的邮件目录(.eml 文件)
+    pub language: String,     // 邮件模板语言(zh-CN / en-US)
+    pub app_url: String,      // 邮件中链接的应用地址(如密码重置页面)
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        EmailConfig {
+            enabled: false,
+            transport: "SMTP".to_string(),
+            host: "smtp.example.com".to_string(),
+            port: 587,
+            tls_mode: "STARTTLS".to_string(),
+            username: "".to_string(),
+            password: "".to_string(),
+            from_email: "noreply@example.com".to_string(),
+            from_name: "WolfQuant".to_string(),
+            timeout_seconds: 10,
+            outbox_dir: "data/outbox".to_string(),
+            language: "zh-CN".to_string(),
+            app_url: "http://localhost:1420".to_string(),
+        }
+    }
+}
+
+// ==================== 通知推送配置 ====================
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationConfig {
+    pub enabled: bool,                // 是否由后台任务推送交易提醒
+    pub max_attempts: u32,            // 单条通知最多发送次数(含首次)
+    pub retry_delay_seconds: i64,     // 首次重试间隔(秒)，之后每次翻倍
+    pub request_timeout_seconds: u64, // Webhook 请求超时时间(秒)
+    pub alert_lookback_hours: i64,    // 只推送最近多少小时内的提醒，更早的提醒不再补发
+    pub telegram_api_base: String,    // Telegram Bot API 地址(可指向本地模拟服务)
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        NotificationConfig {
+            enabled: true,
+            max_attempts: 5,
+            retry_delay_seconds: 60,
+            request_timeout_seconds: 10,
+            alert_lookback_hours: 24,
+            telegram_api_base: "https://api.telegram.org".to_string(),
+        }
+    }
+}
+
+// ==================== 定投成交确认配置 ====================
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettlementRule {
+    pub cutoff: Option<String>, // 北京时间截止时间(如: 15:00)，之前下单按当日收盘价/净值成交，之后按下一交易日；为空时按当前价格立即成交
+    pub confirm_days: i64,      // 成交日之后第几个交易日确认(基金 T+1 为 1)
+}
+
+impl Default for SettlementRule {
+    fn default() -> Self {
+        SettlementRule {
+            cutoff: Some("15:00".to_string()),
+            confirm_days: 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettlementConfig {
+    pub rules: HashMap<String, SettlementRule>, // 资产类型 -> 成交确认规则，未配置的类型使用默认规则(15:00, T+1)
+    pub max_pending_days: i64,                  // 超过预计确认日期多少个交易日仍未取得成交日价格时标记为失败
+}
+
+impl Default for SettlementConfig {
+    fn default() -> Self {
+        let rule = |cutoff: Option<&str>, confirm_days: i64| SettlementRule {
+            cutoff: cutoff.map(|cutoff| cutoff.to_string()),
+            confirm_days,
+        };
+
+        let mut rules = HashMap::new();
+        rules.insert("FUND".to_string(), rule(Some("15:00"), 1));
+        rules.insert("STOCK".to_string(), rule(Some("15:00"), 0));
+        rules.insert("GOLD".to_string(), rule(Some("15:00"), 0));
+        rules.insert("HK_STOCK".to_string(), rule(Some("16:00"), 0));
+        rules.insert("US_STOCK".to_string(), rule(Some("23:59"), 0));
+        rules.insert("CRYPTO".to_string(), rule(None, 0));
+
+        SettlementConfig {
+            rules,
+            max_pending_days: 7,
+        }
+    }
+}
+
+// ==================== 交易日历配置 ====================
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CalendarConfig {
+    pub holiday_dir: String,                  // 自定义休市日目录(cn.txt / hk.txt / us.txt，与内置数据合并)
+    pub asset_types: HashMap<String, String>, // 资产类型 -> 交易所(SSE / SZSE / HKEX / NYSE / CRYPTO)
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        let mut asset_types = HashMap::new();
+        asset_types.insert("FUND".to_string(), "SSE".to_string());
+        asset_types.insert("STOCK".to_string(), "SSE".to_string());
+        asset_types.insert("GOLD".to_string(), "SSE".to_string());
+        asset_types.insert("HK_STOCK".to_string(), "HKEX".to_string());
+        asset_types.insert("US_STOCK".to_string(), "NYSE".to_string());
+        asset_types.insert("CRYPTO".to_string(), "CRYPTO".to_string());
+
+        CalendarConfig {
+            holiday_dir: "data/calendars".to_string(),
+            asset_types,
+        }
+    }
+}
+
+// ==================== 汇率配置 ====================
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FxConfig {
+    pub enabled: bool,  // 是否随每日收盘同步自动更新汇率
+    pub source: String, // 汇率数据源(如: eastmoney)
+    // 缺少汇率时是否按 1:1 计算(默认关闭：缺少汇率时组合汇总、收益率、配置偏离和快照返回错误)
+    #[serde(default)]
+    pub allow_missing_rate_fallback: bool,
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        FxConfig {
+            enabled: true,
+            source: "eastmoney".to_string(),
+            allow_missing_rate_fallback: false,
+        }
+    }
+}
+
+// ==================== 行情同步配置 ====================
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarketSyncConfig {
+    pub enabled: bool,                     // 是否启用后台自动同步
+    pub intraday_asset_types: Vec<String>, // 盘中高频刷新的资产类型(如: CRYPTO)
+    pub intraday_interval_minutes: u32,    // 盘中刷新间隔(分钟)
+    pub daily_sync_hour: u32,              // 每日收盘同步时间(北京时间小时，基金净值通常 20-22 点公布)
+    pub history_days: i64,                 // 每次回补的日线天数
+    pub request_timeout_seconds: u64,      // 单个资产请求超时时间(秒)
+    pub sources: HashMap<String, String>,  // 资产类型 -> 数据源(如: FUND -> tiantian)
+}
+
+impl Default for MarketSyncConfig {
+    fn default() -> Self {
+        let mut sources = HashMap::new();
+        sources.insert("FUND".to_string(), "tiantian".to_string());
+        sources.insert("CRYPTO".to_string(), "binance".to_string());
+        sources.insert("STOCK".to_string(), "eastmoney".to_string());
+        sources.insert("GOLD".to_string(), "eastmoney".to_string());
+        sources.insert("HK_STOCK".to_string(), "eastmoney".to_string());
+        sources.insert("US_STOCK".to_string(), "eastmoney".to_string());
+
+        MarketSyncConfig {
+            enabled: true,
+            intraday_asset_types: vec!["CRYPTO".to_string()],
+            intraday_interval_minutes: 15,
+            daily_sync_hour: 22,
+            history_days: 7,
+            request_timeout_seconds: 30,
+            sources,
+        }
+    }
 }
 
 // ==================== 交易系统配置 ====================
@@ -176,7 +409,7 @@ impl Default for Config {
                 backup_interval_days: 7,
                 backup_path: "data/backups".to_string(),
                 max_size: 10,
-                version: 1,
+                version: 2,
                 schema_dir: "data/schemas".to_string(),
             },
             //交易配置
@@ -227,6 +460,20 @@ impl Default for Config {
                     batch_size: 50,
                 },
             },
+            //行情同步
+            market_sync: MarketSyncConfig::default(),
+            //汇率
+            fx: FxConfig::default(),
+            //交易日历
+            calendar: CalendarConfig::default(),
+            //定投成交确认
+            settlement: SettlementConfig::default(),
+            //通知推送
+            notification: NotificationConfig::default(),
+            //邮件发送
+            email: EmailConfig::default(),
+            //摘要报告
+            digest: DigestConfig::default(),
         }
     }
 }
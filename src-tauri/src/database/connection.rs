@@ -1,5 +1,8 @@
 use crate::config::Config;
-use crate::database::migrations::{get_database_version, migrate_database, set_database_version};
+use crate::database::migrations::{
+    get_database_version, get_table_columns, migrate_database, set_database_version,
+    target_database_version,
+};
 use crate::database::schema::load_all_schemas;
 use crate::error::auth::AuthError;
 use crate::utils::crypto::hash_password;
@@ -49,7 +52,7 @@ pub fn get_connection_from_pool(
 
 /// 初始化数据库(带事务、迁移和表结构验证)
 pub fn init_database() -> Result<(), AuthError> {
-    let config = Config::get();
+    let target_version = target_database_version();
     let mut conn = get_connection_from_pool()?;
 
     // 启用外键约束
@@ -78,22 +81,25 @@ pub fn init_database() -> Result<(), AuthError> {
         initialize_data(&tx)?;
 
         // 设置版本号
-        set_database_version(&tx, config.database.version)?;
+        set_database_version(&tx, target_version)?;
 
         tx.commit()?;
         info!("Database initialized successfully");
-    } else if version < config.database.version {
+    } else if version < target_version {
         // 执行迁移
         migrate_database(&mut conn, version)?;
         info!(
             "Database migrated from version {} to {}",
-            version, config.database.version
+            version, target_version
         );
     }
 
     // 验证并更新表结构
     verify_and_update_table_schemas(&mut conn)?;
 
+    // 已有数据库补齐新增的资产类型
+    seed_asset_types(&conn)?;
+
     // 插入一个默认的用户（如果不存在）
     let user_exists: bool = conn
         .query_row(
@@ -139,16 +145,22 @@ fn create_tables(tx: &Transaction) -> Result<(), AuthError> {
 
 /// 初始化数据(批量插入)
 fn initialize_data(tx: &Transaction) -> Result<(), AuthError> {
-    // 批量插入资产类型
+    seed_asset_types(tx)
+}
+
+/// 插入资产类型(已存在的忽略，可重复执行)
+fn seed_asset_types(conn: &Connection) -> Result<(), AuthError> {
     let asset_types = [
         ("FUND", "基金"),
         ("GOLD", "黄金"),
         ("CRYPTO", "数字货币"),
         ("STOCK", "股票"),
+        ("HK_STOCK", "港股"),
+        ("US_STOCK", "美股"),
     ];
 
     let mut stmt =
-        tx.prepare("INSERT OR IGNORE INTO asset_types (name, description) VALUES (?1, ?2)")?;
+        conn.prepare("INSERT OR IGNORE INTO asset_types (name, description) VALUES (?1, ?2)")?;
 
     for (name, description) in asset_types.iter() {
         stmt.execute([name, description])?;
@@ -158,20 +170,37 @@ fn initialize_data(tx: &Transaction) -> Result<(), AuthError> {
 }
 
 /// 验证并更新表结构
+///
+/// 重建表时需要关闭外键约束，否则 DROP TABLE 会触发其他表的 ON DELETE CASCADE 删除数据。
+/// 该设置在事务内无效，因此在事务外切换，完成后恢复。
 fn verify_and_update_table_schemas(conn: &mut Connection) -> Result<(), AuthError> {
+    conn.pragma_update(None, "foreign_keys", &0)?;
+    let result = rebuild_mismatched_tables(conn);
+    conn.pragma_update(None, "foreign_keys", &1)?;
+    result
+}
+
+/// 按期望结构创建缺失的表、重建结构不一致的表，并补建索引
+fn rebuild_mismatched_tables(conn: &mut Connection) -> Result<(), AuthError> {
     let schemas = load_all_schemas()?;
     let tx = conn.transaction()?;
+    let mut rebuilt_tables = Vec::new();
 
     for (table_name, expected_schema) in schemas.iter() {
+        let normalized_expected = normalize_sql(expected_schema);
+        if !normalized_expected.starts_with("create table") {
+            continue;
+        }
+
         match get_current_table_schema(&tx, table_name)? {
             Some(current_schema) => {
                 // 规范化SQL以便比较
                 let normalized_current = normalize_sql(&current_schema);
-                let normalized_expected = normalize_sql(expected_schema);
 
                 if normalized_current != normalized_expected {
                     info!("表 {} 结构不匹配，进行更新", table_name);
-                    update_table_structure(&tx, table_name, &current_schema, expected_schema)?;
+                    update_table_structure(&tx, table_name, expected_schema)?;
+                    rebuilt_tables.push(table_name.clone());
                 }
             }
             None => {
@@ -182,6 +211,21 @@ fn verify_and_update_table_schemas(conn: &mut Connection) -> Result<(), AuthErro
         }
     }
 
+    // 重建表会删除原表上的索引，建表完成后统一补建
+    for (name, schema) in schemas.iter() {
+        if !normalize_sql(schema).starts_with("create table") {
+            tx.execute_batch(schema).map_err(|e| {
+                error!("Failed to create {}: {}", name, e);
+                AuthError::DatabaseError(format!("创建 {} 失败: {}", name, e))
+            })?;
+        }
+    }
+
+    // 重建后的数据必须满足外键约束，否则回滚
+    for table_name in rebuilt_tables.iter() {
+        check_foreign_keys(&tx, table_name)?;
+    }
+
     tx.commit()?;
     Ok(())
 }
@@ -204,8 +248,12 @@ fn get_current_table_schema(
 }
 
 /// 规范化SQL语句以便比较
+///
+/// sqlite_master 中保存的建表语句不含 IF NOT EXISTS，重命名后的表名带引号，比较时一并去掉。
 fn normalize_sql(sql: &str) -> String {
     sql.to_lowercase()
+        .replace("if not exists", "")
+        .replace('"', "")
         .replace("\n", " ")
         .replace("\t", " ")
         .split_whitespace()
@@ -213,80 +261,82 @@ fn normalize_sql(sql: &str) -> String {
         .join(" ")
 }
 
-/// 更新表结构
+/// 更新表结构（按期望结构新建表，复制共同列的数据后替换原表，需在关闭外键约束时调用）
 fn update_table_structure(
     tx: &Transaction,
     table_name: &str,
-    current_schema: &str,
     expected_schema: &str,
 ) -> Result<(), AuthError> {
-    // 获取表中的数据
-    let temp_table_name = format!("{}_temp", table_name);
-
-    // 1. 获取当前表的列
-    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table_name))?;
-
-    let current_columns: Vec<String> = stmt
-        .query_map([], |row| {
-            let name: String = row.get(1)?;
-            Ok(name)
-        })?
-        .filter_map(Result::ok)
-        .collect();
-
-    // 2. 获取期望表的列
-    tx.execute_batch(&format!("DROP TABLE IF EXISTS {}", temp_table_name))?;
-    tx.execute_batch(&expected_schema.replace(
-        &format!("CREATE TABLE {}", table_name),
-        &format!("CREATE TABLE {}", temp_table_name),
-    ))?;
-
-    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", temp_table_name))?;
-
-    let expected_columns: Vec<String> = stmt
-        .query_map([], |row| {
-            let name: String = row.get(1)?;
-            Ok(name)
-        })?
-        .filter_map(Result::ok)
-        .collect();
-
-    // 3. 找出共同的列
+    let new_table_name = format!("{}_new", table_name);
+    let body = expected_schema
+        .find('(')
+        .map(|index| &expected_schema[index..])
+        .ok_or_else(|| AuthError::DatabaseError(format!("表 {} 的结构定义无效", table_name)))?;
+
+    // 1. 按期望结构创建新表
+    tx.execute_batch(&format!("DROP TABLE IF EXISTS {}", new_table_name))?;
+    tx.execute_batch(&format!("CREATE TABLE {} {}", new_table_name, body))?;
+
+    // 2. 找出新旧表共同的列
+    let current_columns = get_table_columns(tx, table_name)?;
+    let expected_columns = get_table_columns(tx, &new_table_name)?;
     let common_columns: Vec<String> = current_columns
-        .iter()
+        .into_iter()
         .filter(|col| expected_columns.contains(col))
-        .cloned()
         .collect();
 
+    if common_columns.is_empty() {
+        return Err(AuthError::DatabaseError(format!(
+            "表 {} 与期望结构没有共同的列，无法迁移数据",
+            table_name
+        )));
+    }
+
     let columns_str = common_columns.join(", ");
 
-    // 4. 创建新表并迁移数据
+    // 3. 迁移数据并替换原表
     tx.execute_batch(&format!(
         "
-        CREATE TABLE {}_new {};
-        INSERT INTO {}_new ({}) 
-        SELECT {} FROM {};
-        DROP TABLE {};
-        ALTER TABLE {}_new RENAME TO {};
+        INSERT INTO {new} ({columns}) SELECT {columns} FROM {table};
+        DROP TABLE {table};
+        ALTER TABLE {new} RENAME TO {table};
     ",
-        table_name,
-        &expected_schema[expected_schema.find("(").unwrap()..],
-        table_name,
-        columns_str,
-        columns_str,
-        table_name,
-        table_name,
-        table_name,
-        table_name
+        new = new_table_name,
+        columns = columns_str,
+        table = table_name,
     ))?;
 
-    // 5. 清理临时表
-    tx.execute_batch(&format!("DROP TABLE IF EXISTS {}", temp_table_name))?;
-
     info!("表 {} 结构已更新", table_name);
     Ok(())
 }
 
+/// 检查表中的外键约束，存在不满足约束的数据时返回错误
+fn check_foreign_keys(conn: &Connection, table_name: &str) -> Result<(), AuthError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA foreign_key_check({})", table_name))?;
+    let violations: Vec<(Option<i64>, String)> = stmt
+        .query_map([], |row| Ok((row.get(1)?, row.get(2)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    if let Some((rowid, parent)) = violations.first() {
+        error!(
+            "Foreign key check failed for table {}: {} violations, first rowid {:?} -> {}",
+            table_name,
+            violations.len(),
+            rowid,
+            parent
+        );
+        return Err(AuthError::DatabaseError(format!(
+            "表 {} 有 {} 条数据不满足外键约束（引用 {}），已取消结构更新",
+            table_name,
+            violations.len(),
+            parent
+        )));
+    }
+
+    Ok(())
+}
+
 /// 执行查询(使用连接池)
 pub fn execute_query(query: &str, params: &[&dyn rusqlite::ToSql]) -> Result<(), AuthError> {
     let conn = get_connection_from_pool()?;
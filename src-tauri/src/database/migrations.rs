@@ -1,7 +1,45 @@
 use crate::config::Config;
 use crate::error::auth::AuthError;
 use rusqlite::Connection;
-use log::error;
+use log::{error, info};
+
+/// 当前代码对应的数据库结构版本（与配置中的 `database.version` 取较大值）
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// 版本2为已有表新增的列（表名、列名、列定义）
+///
+/// `ALTER TABLE ... ADD COLUMN` 只能追加可为空或带默认值的列，外键约束等由之后的表结构校验按新结构重建补齐。
+const VERSION_2_COLUMNS: &[(&str, &str, &str)] = &[
+    ("assets", "dividend_method", "TEXT NOT NULL DEFAULT 'NONE'"),
+    ("transactions", "corporate_action_id", "INTEGER"),
+    ("transactions", "fee", "REAL NOT NULL DEFAULT 0"),
+    ("transactions", "plan_id", "INTEGER"),
+    ("assets", "currency", "TEXT NOT NULL DEFAULT 'CNY'"),
+    ("user_settings", "base_currency", "TEXT NOT NULL DEFAULT 'CNY'"),
+    ("transactions", "status", "TEXT NOT NULL DEFAULT 'CONFIRMED'"),
+    ("investment_plans", "mode", "TEXT NOT NULL DEFAULT 'FIXED'"),
+    ("investment_plans", "mode_config", "TEXT"),
+    ("transactions", "settle_date", "INTEGER"),
+    ("transactions", "confirm_date", "INTEGER"),
+    ("transactions", "failure_reason", "TEXT"),
+    ("investment_plans", "catch_up_policy", "TEXT NOT NULL DEFAULT 'ONCE'"),
+    ("investment_plans", "days_of_month", "TEXT"),
+    ("investment_plans", "cron_expression", "TEXT"),
+    ("investment_plans", "start_date", "INTEGER"),
+    ("investment_plans", "end_date", "INTEGER"),
+    ("investment_plans", "max_total_amount", "REAL"),
+    ("investment_plans", "take_profit_percent", "REAL"),
+    ("investment_plans", "stop_reason", "TEXT"),
+    ("trade_alerts", "rule_id", "INTEGER"),
+    ("strategy_applications", "last_signal", "TEXT"),
+    ("strategy_applications", "last_signal_at", "INTEGER"),
+    ("strategy_applications", "last_evaluated", "INTEGER"),
+    ("trade_alerts", "notified_at", "INTEGER"),
+    ("notification_deliveries", "html_body", "TEXT"),
+    ("trade_alerts", "signal_strategy_id", "INTEGER"),
+    ("fx_rates", "user_id", "INTEGER NOT NULL DEFAULT 0"),
+    ("assets", "lot_size", "REAL"),
+];
 
 /// 获取当前数据库版本
 pub fn get_database_version(conn: &Connection) -> Result<u32, AuthError> {
@@ -16,8 +54,8 @@ pub fn get_database_version(conn: &Connection) -> Result<u32, AuthError> {
         return Ok(0);
     }
 
-    // 获取版本号
-    conn.query_row("SELECT version FROM db_version LIMIT 1", [], |row| {
+    // 获取版本号（每次升级都会插入一行新版本，取最大值）
+    conn.query_row("SELECT COALESCE(MAX(version), 0) FROM db_version", [], |row| {
         row.get(0)
     })
     .map_err(|e| {
@@ -42,9 +80,45 @@ pub fn set_database_version(conn: &Connection, version: u32) -> Result<(), AuthE
     Ok(())
 }
 
+/// 目标数据库版本
+pub fn target_database_version() -> u32 {
+    Config::get().database.version.max(SCHEMA_VERSION)
+}
+
+/// 获取表的现有列名（表不存在时为空）
+pub fn get_table_columns(conn: &Connection, table_name: &str) -> Result<Vec<String>, AuthError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(Result::ok)
+        .collect();
+    Ok(columns)
+}
+
+/// 为已有表追加缺少的列（可重复执行，表不存在时跳过，由建表流程按新结构创建）
+fn add_missing_columns(conn: &Connection, columns: &[(&str, &str, &str)]) -> Result<(), AuthError> {
+    for (table_name, column_name, definition) in columns {
+        let existing = get_table_columns(conn, table_name)?;
+        if existing.is_empty() || existing.iter().any(|name| name == column_name) {
+            continue;
+        }
+
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table_name, column_name, definition
+        ))
+        .map_err(|e| {
+            error!("Failed to add column {}.{}: {}", table_name, column_name, e);
+            AuthError::DatabaseError(format!("添加列 {}.{} 失败: {}", table_name, column_name, e))
+        })?;
+        info!("Added column {}.{}", table_name, column_name);
+    }
+
+    Ok(())
+}
+
 /// 数据库迁移
 pub fn migrate_database(conn: &mut Connection, current_version: u32) -> Result<(), AuthError> {
-    let config = Config::get();
     let tx = conn.transaction()?;
 
     // 版本1的迁移(示例)
@@ -53,8 +127,13 @@ pub fn migrate_database(conn: &mut Connection, current_version: u32) -> Result<(
         // 例如修改表结构或转换数据
     }
 
+    // 版本2：为已有表追加新增的列，保留原有数据
+    if current_version < 2 {
+        add_missing_columns(&tx, VERSION_2_COLUMNS)?;
+    }
+
     // 更新版本号
-    set_database_version(&tx, config.database.version)?;
+    set_database_version(&tx, target_database_version())?;
     tx.commit()?;
 
     Ok(())
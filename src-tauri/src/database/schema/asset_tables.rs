@@ -44,6 +44,9 @@ pub fn get_schemas() -> HashMap<String, String> {
             current_price REAL,
             position_amount REAL DEFAULT 0,
             position_cost REAL DEFAULT 0,
+            dividend_method TEXT NOT NULL DEFAULT 'NONE',
+            currency TEXT NOT NULL DEFAULT 'CNY',
+            lot_size REAL,
             last_updated INTEGER,
             created_at INTEGER NOT NULL,
             updated_at INTEGER NOT NULL,
@@ -54,6 +57,23 @@ pub fn get_schemas() -> HashMap<String, String> {
         )".to_string(),
     );
     
+    // 目标配置表（scope 为 ASSET / GROUP / ASSET_TYPE，scope_id 为对应的资产、分组或资产类型ID）
+    schemas.insert(
+        "target_allocations".to_string(),
+        "CREATE TABLE IF NOT EXISTS target_allocations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            scope TEXT NOT NULL,
+            scope_id INTEGER NOT NULL,
+            target_percent REAL NOT NULL,
+            tolerance_percent REAL NOT NULL DEFAULT 5,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            UNIQUE (user_id, scope, scope_id)
+        )".to_string(),
+    );
+    
     // 价格历史表
     schemas.insert(
         "price_history".to_string(),
@@ -72,5 +92,55 @@ pub fn get_schemas() -> HashMap<String, String> {
         )".to_string(),
     );
     
+    // 汇率表（1 单位 base_currency 兑换的 quote_currency 数量，source 为 MANUAL 表示手动录入，
+    // user_id 为录入手动汇率的用户，0 表示数据源同步的公共汇率）
+    schemas.insert(
+        "fx_rates".to_string(),
+        "CREATE TABLE IF NOT EXISTS fx_rates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            base_currency TEXT NOT NULL,
+            quote_currency TEXT NOT NULL,
+            rate_date INTEGER NOT NULL,
+            rate REAL NOT NULL,
+            source TEXT NOT NULL,
+            user_id INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            UNIQUE (base_currency, quote_currency, rate_date, source, user_id)
+        )".to_string(),
+    );
+    
+    // 资产行情同步状态表
+    schemas.insert(
+        "asset_sync_status".to_string(),
+        "CREATE TABLE IF NOT EXISTS asset_sync_status (
+            asset_id INTEGER PRIMARY KEY,
+            source TEXT NOT NULL,
+            status TEXT NOT NULL,
+            message TEXT,
+            last_attempt INTEGER NOT NULL,
+            last_success INTEGER,
+            FOREIGN KEY (asset_id) REFERENCES assets (id) ON DELETE CASCADE
+        )".to_string(),
+    );
+    
+    // 公司行为表（分红、份额折算），按 (资产类型, 代码) 记录，所有用户共享
+    schemas.insert(
+        "corporate_actions".to_string(),
+        "CREATE TABLE IF NOT EXISTS corporate_actions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            asset_type TEXT NOT NULL,
+            symbol TEXT NOT NULL,
+            action_type TEXT NOT NULL,
+            ex_date INTEGER NOT NULL,
+            value REAL NOT NULL,
+            nav REAL,
+            source TEXT NOT NULL,
+            detected_from TEXT NOT NULL,
+            description TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            UNIQUE (asset_type, symbol, action_type, ex_date)
+        )".to_string(),
+    );
+    
     schemas
 }
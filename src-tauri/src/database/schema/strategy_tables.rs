@@ -89,7 +89,7 @@ pub fn get_schemas() -> HashMap<String, String> {
         )".to_string(),
     );
     
-    // 策略应用表 - 记录策略应用于哪些资产
+    // 策略应用表 - 记录策略应用于哪些资产，last_signal 为最近一次生成的买卖信号（用于去重）
     schemas.insert(
         "strategy_applications".to_string(),
         "CREATE TABLE IF NOT EXISTS strategy_applications (
@@ -98,6 +98,9 @@ pub fn get_schemas() -> HashMap<String, String> {
             strategy_id INTEGER NOT NULL,
             asset_id INTEGER NOT NULL,
             is_active BOOLEAN NOT NULL DEFAULT 1,
+            last_signal TEXT,
+            last_signal_at INTEGER,
+            last_evaluated INTEGER,
             created_at INTEGER NOT NULL,
             updated_at INTEGER NOT NULL,
             FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
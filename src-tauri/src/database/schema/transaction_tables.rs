@@ -15,14 +15,68 @@ pub fn get_schemas() -> HashMap<String, String> {
             amount REAL NOT NULL,
             price REAL NOT NULL,
             total_cost REAL NOT NULL,
+            fee REAL NOT NULL DEFAULT 0,
             transaction_date INTEGER NOT NULL,
             notes TEXT,
+            corporate_action_id INTEGER,
+            plan_id INTEGER,
+            status TEXT NOT NULL DEFAULT 'CONFIRMED',
+            settle_date INTEGER,
+            confirm_date INTEGER,
+            failure_reason TEXT,
             created_at INTEGER NOT NULL,
             FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            FOREIGN KEY (asset_id) REFERENCES assets (id) ON DELETE CASCADE,
+            FOREIGN KEY (corporate_action_id) REFERENCES corporate_actions (id) ON DELETE SET NULL,
+            FOREIGN KEY (plan_id) REFERENCES investment_plans (id) ON DELETE SET NULL
+        )".to_string(),
+    );
+    
+    // 费率方案表（asset_id 不为空时针对单个资产，否则针对资产类型）
+    schemas.insert(
+        "fee_schedules".to_string(),
+        "CREATE TABLE IF NOT EXISTS fee_schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            asset_type_id INTEGER,
+            asset_id INTEGER,
+            name TEXT NOT NULL,
+            buy_tiers TEXT NOT NULL DEFAULT '[]',
+            buy_discount REAL NOT NULL DEFAULT 1,
+            redemption_tiers TEXT NOT NULL DEFAULT '[]',
+            commission_rate REAL NOT NULL DEFAULT 0,
+            min_commission REAL NOT NULL DEFAULT 0,
+            stamp_duty_rate REAL NOT NULL DEFAULT 0,
+            transfer_fee_rate REAL NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            FOREIGN KEY (asset_type_id) REFERENCES asset_types (id) ON DELETE CASCADE,
             FOREIGN KEY (asset_id) REFERENCES assets (id) ON DELETE CASCADE
         )".to_string(),
     );
     
+    // 组合净值快照表（scope_id 为 0 表示整个组合）
+    schemas.insert(
+        "portfolio_snapshots".to_string(),
+        "CREATE TABLE IF NOT EXISTS portfolio_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            snapshot_date INTEGER NOT NULL,
+            scope TEXT NOT NULL,
+            scope_id INTEGER NOT NULL DEFAULT 0,
+            market_value REAL NOT NULL,
+            cost REAL NOT NULL,
+            realized_profit REAL NOT NULL DEFAULT 0,
+            net_contribution REAL NOT NULL,
+            daily_contribution REAL NOT NULL DEFAULT 0,
+            unit_value REAL NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            UNIQUE (user_id, snapshot_date, scope, scope_id)
+        )".to_string(),
+    );
+    
     // 定投计划表
     schemas.insert(
         "investment_plans".to_string(),
@@ -35,7 +89,17 @@ pub fn get_schemas() -> HashMap<String, String> {
             day_of_week INTEGER,
             day_of_month INTEGER,
             amount REAL NOT NULL,
+            mode TEXT NOT NULL DEFAULT 'FIXED',
+            mode_config TEXT,
+            days_of_month TEXT,
+            cron_expression TEXT,
+            start_date INTEGER,
+            end_date INTEGER,
+            catch_up_policy TEXT NOT NULL DEFAULT 'ONCE',
+            max_total_amount REAL,
+            take_profit_percent REAL,
             is_active BOOLEAN NOT NULL DEFAULT 1,
+            stop_reason TEXT,
             last_executed INTEGER,
             next_execution INTEGER,
             created_at INTEGER NOT NULL,
@@ -45,6 +109,32 @@ pub fn get_schemas() -> HashMap<String, String> {
         )".to_string(),
     );
     
+    // 定投执行记录表（每个计划日期一条，status 为 EXECUTED / PAUSED / SKIPPED / FAILED）
+    schemas.insert(
+        "plan_executions".to_string(),
+        "CREATE TABLE IF NOT EXISTS plan_executions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            plan_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            asset_id INTEGER NOT NULL,
+            scheduled_date INTEGER NOT NULL,
+            executed_at INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            catch_up BOOLEAN NOT NULL DEFAULT 0,
+            amount REAL NOT NULL DEFAULT 0,
+            fee REAL NOT NULL DEFAULT 0,
+            price REAL,
+            shares REAL,
+            transaction_id INTEGER,
+            message TEXT,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (plan_id) REFERENCES investment_plans (id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            FOREIGN KEY (asset_id) REFERENCES assets (id) ON DELETE CASCADE,
+            FOREIGN KEY (transaction_id) REFERENCES transactions (id) ON DELETE SET NULL
+        )".to_string(),
+    );
+    
     // 投资策略表
     schemas.insert(
         "investment_strategies".to_string(),
@@ -79,7 +169,58 @@ pub fn get_schemas() -> HashMap<String, String> {
         )".to_string(),
     );
     
-    // 交易提醒表
+    // 止盈止损规则表（plan_id 为空时按资产的全部持仓计算）
+    schemas.insert(
+        "exit_rules".to_string(),
+        "CREATE TABLE IF NOT EXISTS exit_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            asset_id INTEGER NOT NULL,
+            plan_id INTEGER,
+            rule_type TEXT NOT NULL,
+            threshold REAL NOT NULL,
+            sell_percent REAL,
+            pause_plan BOOLEAN NOT NULL DEFAULT 0,
+            is_active BOOLEAN NOT NULL DEFAULT 1,
+            peak_price REAL,
+            last_value REAL,
+            last_evaluated INTEGER,
+            triggered_at INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            FOREIGN KEY (asset_id) REFERENCES assets (id) ON DELETE CASCADE,
+            FOREIGN KEY (plan_id) REFERENCES investment_plans (id) ON DELETE CASCADE
+        )".to_string(),
+    );
+    
+    // 提醒规则表（价格更新后评估，触发后生成交易提醒）
+    schemas.insert(
+        "alert_rules".to_string(),
+        "CREATE TABLE IF NOT EXISTS alert_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            asset_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            rule_type TEXT NOT NULL,
+            direction TEXT NOT NULL DEFAULT 'ABOVE',
+            threshold REAL,
+            period INTEGER,
+            long_period INTEGER,
+            cooldown_minutes INTEGER NOT NULL DEFAULT 1440,
+            is_active BOOLEAN NOT NULL DEFAULT 1,
+            last_value REAL,
+            last_evaluated INTEGER,
+            last_triggered INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            FOREIGN KEY (asset_id) REFERENCES assets (id) ON DELETE CASCADE
+        )".to_string(),
+    );
+    
+    // 交易提醒表（strategy_id 为关联的投资策略，signal_strategy_id 为生成买卖信号的策略，
+    // rule_id 为触发提醒的提醒规则，notified_at 为加入通知队列的时间）
     schemas.insert(
         "trade_alerts".to_string(),
         "CREATE TABLE IF NOT EXISTS trade_alerts (
@@ -87,13 +228,88 @@ pub fn get_schemas() -> HashMap<String, String> {
             user_id INTEGER NOT NULL,
             asset_id INTEGER NOT NULL,
             strategy_id INTEGER,
+            signal_strategy_id INTEGER,
+            rule_id INTEGER,
             alert_type TEXT NOT NULL,
             message TEXT NOT NULL,
             is_read BOOLEAN NOT NULL DEFAULT 0,
+            notified_at INTEGER,
             created_at INTEGER NOT NULL,
             FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
             FOREIGN KEY (asset_id) REFERENCES assets (id) ON DELETE CASCADE,
-            FOREIGN KEY (strategy_id) REFERENCES investment_strategies (id) ON DELETE SET NULL
+            FOREIGN KEY (strategy_id) REFERENCES investment_strategies (id) ON DELETE SET NULL,
+            FOREIGN KEY (signal_strategy_id) REFERENCES strategies (id) ON DELETE SET NULL,
+            FOREIGN KEY (rule_id) REFERENCES alert_rules (id) ON DELETE SET NULL
+        )".to_string(),
+    );
+    
+    // 通知渠道表
+    schemas.insert(
+        "notification_channels".to_string(),
+        "CREATE TABLE IF NOT EXISTS notification_channels (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            channel_type TEXT NOT NULL,
+            target TEXT,
+            secret TEXT,
+            is_enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+        )".to_string(),
+    );
+    
+    // 通知偏好表（alert_types 为 JSON 数组，为空时推送全部提醒）
+    schemas.insert(
+        "notification_preferences".to_string(),
+        "CREATE TABLE IF NOT EXISTS notification_preferences (
+            user_id INTEGER PRIMARY KEY,
+            alert_types TEXT NOT NULL DEFAULT '[]',
+            quiet_hours_start TEXT,
+            quiet_hours_end TEXT,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+        )".to_string(),
+    );
+    
+    // 通知发送记录表（html_body 为邮件渠道使用的 HTML 正文，如摘要报告）
+    schemas.insert(
+        "notification_deliveries".to_string(),
+        "CREATE TABLE IF NOT EXISTS notification_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            channel_id INTEGER NOT NULL,
+            alert_id INTEGER,
+            title TEXT NOT NULL,
+            body TEXT NOT NULL,
+            html_body TEXT,
+            status TEXT NOT NULL DEFAULT 'PENDING',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            next_attempt_at INTEGER,
+            sent_at INTEGER,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+            FOREIGN KEY (channel_id) REFERENCES notification_channels (id) ON DELETE CASCADE,
+            FOREIGN KEY (alert_id) REFERENCES trade_alerts (id) ON DELETE SET NULL
+        )".to_string(),
+    );
+    
+    // 摘要报告设置表（weekly_day 1 为周一，7 为周日）
+    schemas.insert(
+        "digest_settings".to_string(),
+        "CREATE TABLE IF NOT EXISTS digest_settings (
+            user_id INTEGER PRIMARY KEY,
+            daily_enabled BOOLEAN NOT NULL DEFAULT 0,
+            weekly_enabled BOOLEAN NOT NULL DEFAULT 0,
+            weekly_day INTEGER NOT NULL DEFAULT 7,
+            send_to_channels BOOLEAN NOT NULL DEFAULT 1,
+            save_to_disk BOOLEAN NOT NULL DEFAULT 0,
+            last_daily_date INTEGER,
+            last_weekly_date INTEGER,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
         )".to_string(),
     );
     
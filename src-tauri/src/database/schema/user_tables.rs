@@ -18,6 +18,18 @@ pub fn get_schemas() -> HashMap<String, String> {
         )".to_string(),
     );
     
+    // 用户设置表
+    schemas.insert(
+        "user_settings".to_string(),
+        "CREATE TABLE IF NOT EXISTS user_settings (
+            user_id INTEGER PRIMARY KEY,
+            cost_basis_method TEXT NOT NULL DEFAULT 'AVERAGE',
+            base_currency TEXT NOT NULL DEFAULT 'CNY',
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+        )".to_string(),
+    );
+    
     // 会话表
     schemas.insert(
         "sessions".to_string(),
@@ -21,11 +21,16 @@ mod utils;
 
 // 数据
 use commands::data::{
+    data_check_candle_quality,
     data_create_trade_alert,
     data_get_asset_price_history,
+    data_get_asset_sync_status,
+    data_get_corporate_actions,
     data_get_portfolio_summary,
     data_get_user_trade_alerts,
     data_mark_alert_read,
+    data_sync_corporate_actions,
+    data_sync_market_data,
     //
     data_update_asset_price,
     data_update_asset_price_batch,
@@ -40,13 +45,76 @@ use commands::auth::{
 use commands::asset::{
     asset_create_asset_command, asset_create_group_command, asset_delete_asset_command,
     asset_delete_group_command, asset_get_asset_types_command, asset_get_user_assets_command,
-    asset_get_user_groups_command, asset_update_asset_command, asset_update_group_command,
+    asset_get_user_groups_command, asset_set_dividend_method_command, asset_update_asset_command,
+    asset_update_group_command,
 };
 //定投计划
 use commands::investment_plan::{
     plan_delete_investment_plan_command, plan_execute_due_investment_plans_command,
     plan_get_today_investment_plans_command, plan_get_user_investment_plans_command,
-    plan_save_investment_plan_command,
+    plan_preview_plan_amount_command, plan_save_investment_plan_command,
+    plan_backtest_investment_plan_command, plan_settle_pending_executions_command,
+    plan_get_plan_executions_command,
+};
+//交易费用
+use commands::fee::{
+    fee_delete_fee_schedule_command, fee_estimate_fee_command, fee_get_fee_schedules_command,
+    fee_save_fee_schedule_command,
+};
+//提醒规则
+use commands::alert_rule::{
+    alert_delete_alert_rule_command, alert_evaluate_alert_rules_command,
+    alert_get_alert_rules_command, alert_save_alert_rule_command,
+};
+//策略信号
+use commands::strategy_signal::signal_evaluate_strategy_applications_command;
+//通知推送
+use commands::notification::{
+    notify_delete_channel_command, notify_dispatch_command, notify_get_channels_command,
+    notify_get_deliveries_command, notify_get_preferences_command, notify_save_channel_command,
+    notify_test_channel_command, notify_update_preferences_command,
+};
+//摘要报告
+use commands::digest::{
+    digest_get_settings_command, digest_preview_command, digest_send_command,
+    digest_update_settings_command,
+};
+//止盈止损
+use commands::exit_rule::{
+    exit_delete_exit_rule_command, exit_evaluate_exit_rules_command, exit_get_exit_rules_command,
+    exit_save_exit_rule_command,
+};
+//汇率
+use commands::fx::{
+    fx_delete_rate_command, fx_get_rates_command, fx_save_manual_rate_command, fx_sync_rates_command,
+};
+//交易日历
+use commands::calendar::{calendar_get_trading_days_command, calendar_reload_command};
+//持仓台账
+use commands::position::{
+    position_get_position_ledger_command, position_get_realized_trades_command,
+};
+//收益率
+use commands::performance::performance_get_returns_command;
+//净值快照
+use commands::snapshot::{
+    snapshot_backfill_snapshots_command, snapshot_get_drawdowns_command,
+    snapshot_get_net_worth_curve_command, snapshot_get_period_returns_command,
+};
+//目标配置与再平衡
+use commands::allocation::{
+    allocation_delete_target_allocation_command, allocation_get_drift_report_command,
+    allocation_get_target_allocations_command, allocation_plan_rebalance_command,
+    allocation_save_target_allocation_command,
+};
+//用户设置
+use commands::settings::{
+    settings_get_user_settings_command, settings_update_user_settings_command,
+};
+//交易记录
+use commands::transaction::{
+    confirm_transaction_command, create_transaction_command, delete_transaction_command,
+    get_user_transactions_command, update_transaction_command,
 };
 
 fn main() {
@@ -65,7 +133,15 @@ fn main() {
         eprintln!("Failed to initialize database: {}", e);
     }
 
+    // 启动后台定时任务（行情同步等）
+    services::scheduler::start_scheduler();
+
     tauri::Builder::default()
+        .setup(|app| {
+            // 桌面通知通过应用句柄发送事件
+            services::notification_sender::set_app_handle(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             //登陆
             auth_forgot_password_command,
@@ -83,6 +159,11 @@ fn main() {
             data_mark_alert_read,
             data_get_user_trade_alerts,
             data_get_portfolio_summary,
+            data_check_candle_quality,
+            data_sync_market_data,
+            data_get_asset_sync_status,
+            data_sync_corporate_actions,
+            data_get_corporate_actions,
             //资产
             asset_get_asset_types_command,
             asset_create_group_command,
@@ -93,13 +174,81 @@ fn main() {
             asset_update_asset_command,
             asset_delete_asset_command,
             asset_get_user_assets_command,
+            asset_set_dividend_method_command,
             //定投计划
             plan_save_investment_plan_command,
             plan_get_today_investment_plans_command,
             plan_delete_investment_plan_command,
             plan_get_user_investment_plans_command,
             plan_execute_due_investment_plans_command,
-           
+            plan_preview_plan_amount_command,
+            plan_backtest_investment_plan_command,
+            plan_settle_pending_executions_command,
+            plan_get_plan_executions_command,
+            //交易记录
+            create_transaction_command,
+            update_transaction_command,
+            delete_transaction_command,
+            get_user_transactions_command,
+            confirm_transaction_command,
+            //交易费用
+            fee_save_fee_schedule_command,
+            fee_get_fee_schedules_command,
+            fee_delete_fee_schedule_command,
+            fee_estimate_fee_command,
+            //止盈止损
+            exit_save_exit_rule_command,
+            exit_get_exit_rules_command,
+            exit_delete_exit_rule_command,
+            exit_evaluate_exit_rules_command,
+            //提醒规则
+            alert_save_alert_rule_command,
+            alert_get_alert_rules_command,
+            alert_delete_alert_rule_command,
+            alert_evaluate_alert_rules_command,
+            //策略信号
+            signal_evaluate_strategy_applications_command,
+            //通知推送
+            notify_save_channel_command,
+            notify_get_channels_command,
+            notify_delete_channel_command,
+            notify_test_channel_command,
+            notify_get_preferences_command,
+            notify_update_preferences_command,
+            notify_get_deliveries_command,
+            notify_dispatch_command,
+            //摘要报告
+            digest_get_settings_command,
+            digest_update_settings_command,
+            digest_preview_command,
+            digest_send_command,
+            //汇率
+            fx_save_manual_rate_command,
+            fx_get_rates_command,
+            fx_delete_rate_command,
+            fx_sync_rates_command,
+            //交易日历
+            calendar_get_trading_days_command,
+            calendar_reload_command,
+            //持仓台账
+            position_get_position_ledger_command,
+            position_get_realized_trades_command,
+            //收益率
+            performance_get_returns_command,
+            //净值快照
+            snapshot_backfill_snapshots_command,
+            snapshot_get_net_worth_curve_command,
+            snapshot_get_drawdowns_command,
+            snapshot_get_period_returns_command,
+            //目标配置与再平衡
+            allocation_save_target_allocation_command,
+            allocation_get_target_allocations_command,
+            allocation_delete_target_allocation_command,
+            allocation_get_drift_report_command,
+            allocation_plan_rebalance_command,
+            //用户设置
+            settings_get_user_settings_command,
+            settings_update_user_settings_command,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::models::Candle;
+use crate::models::CorporateActionEvent;
 use crate::models::Ticker;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,6 +13,7 @@ pub struct Product {
     pub name: String,
     pub asset_type: String,
     pub source: String,
+    pub currency: String, // 报价币种，如 CNY、HKD、USD、USDT
 }
 
 #[async_trait]
@@ -39,4 +41,32 @@ pub trait MarketAdapter: Send + Sync {
         end_time: DateTime<Utc>,
         interval: &str,
     ) -> Result<Vec<Candle>, String>;
-}
\ No newline at end of file
+
+    // 获取分红、份额折算等公司行为，默认不支持
+    async fn get_corporate_actions(
+        &self,
+        _symbol: &str,
+        _start_time: DateTime<Utc>,
+        _end_time: DateTime<Utc>,
+    ) -> Result<Vec<CorporateActionEvent>, String> {
+        Ok(Vec::new())
+    }
+}
+
+#[async_trait]
+pub trait FxAdapter: Send + Sync {
+    // 获取适配器名称
+    fn name(&self) -> &str;
+
+    // 获取最新汇率（1 单位 base 兑换的 quote 数量）
+    async fn get_rate(&self, base: &str, quote: &str) -> Result<f64, String>;
+
+    // 获取日线汇率历史：(日期, 收盘汇率)
+    async fn get_rate_history(
+        &self,
+        base: &str,
+        quote: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, f64)>, String>;
+}
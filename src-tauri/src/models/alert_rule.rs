@@ -0,0 +1,138 @@
+/// 提醒规则相关结构体。
+///
+/// 字段说明：
+/// - `AlertRule`: 用户为资产设置的提醒条件，价格更新后评估，满足条件时生成交易提醒（`trade_alerts.rule_id` 关联规则）。
+///   - `direction`: ABOVE / BELOW，含义见 `AlertRuleType`。
+///   - `threshold`: 阈值，均线交叉不需要。
+///   - `period`: 涨跌幅的天数、RSI 周期或短期均线天数。
+///   - `long_period`: 长期均线天数，仅均线交叉使用。
+///   - `cooldown_minutes`: 冷却时间，触发后在冷却时间内不重复提醒。
+///   - `last_value`: 最近一次评估的指标值（价格穿越以此判断上一次价格）。
+///   - `last_triggered`: 最近一次触发时间。
+/// - `AlertRuleTrigger`: 一次触发的结果，`alert_id` 为生成的交易提醒。
+use serde::{Deserialize, Serialize};
+
+/// 提醒规则类型
+///
+/// | 类型 | 触发条件 |
+/// |------|----------|
+/// | PRICE_CROSS 价格穿越 | 价格向上（ABOVE）或向下（BELOW）穿越阈值 |
+/// | PRICE_CHANGE 区间涨跌幅 | `period` 天内涨幅 ≥ 阈值（ABOVE）或跌幅 ≥ 阈值（BELOW），阈值为百分比 |
+/// | RSI 相对强弱 | RSI(`period`，默认 14) 高于（ABOVE）或低于（BELOW）阈值 |
+/// | MA_CROSS 均线交叉 | 短期均线上穿（ABOVE，金叉）或下穿（BELOW，死叉）长期均线 |
+/// | NAV_DROP 净值下跌 | 较前一交易日收盘价（净值）跌幅 ≥ 阈值，阈值为百分比 |
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AlertRuleType {
+    PriceCross,
+    PriceChange,
+    Rsi,
+    MaCross,
+    NavDrop,
+}
+
+impl AlertRuleType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "PRICE_CROSS" => Some(AlertRuleType::PriceCross),
+            "PRICE_CHANGE" => Some(AlertRuleType::PriceChange),
+            "RSI" => Some(AlertRuleType::Rsi),
+            "MA_CROSS" => Some(AlertRuleType::MaCross),
+            "NAV_DROP" => Some(AlertRuleType::NavDrop),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            AlertRuleType::PriceCross => "PRICE_CROSS",
+            AlertRuleType::PriceChange => "PRICE_CHANGE",
+            AlertRuleType::Rsi => "RSI",
+            AlertRuleType::MaCross => "MA_CROSS",
+            AlertRuleType::NavDrop => "NAV_DROP",
+        }
+    }
+}
+
+/// 提醒方向
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AlertDirection {
+    Above,
+    Below,
+}
+
+impl AlertDirection {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "ABOVE" => Some(AlertDirection::Above),
+            "BELOW" => Some(AlertDirection::Below),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            AlertDirection::Above => "ABOVE",
+            AlertDirection::Below => "BELOW",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: i64,
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub name: String,
+    pub rule_type: String,
+    pub direction: String,
+    pub threshold: Option<f64>,
+    pub period: Option<i64>,
+    pub long_period: Option<i64>,
+    pub cooldown_minutes: i64,
+    pub is_active: bool,
+    pub last_value: Option<f64>,
+    pub last_evaluated: Option<i64>,
+    pub last_triggered: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveAlertRuleRequest {
+    pub id: Option<i64>,
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub name: Option<String>, // 为空时按规则生成
+    pub rule_type: String,
+    pub direction: Option<String>, // 为空时为 ABOVE，净值下跌固定为 BELOW
+    pub threshold: Option<f64>,
+    pub period: Option<i64>,
+    pub long_period: Option<i64>,
+    pub cooldown_minutes: Option<i64>, // 为空时为 1440（一天）
+    pub is_active: Option<bool>,       // 为空时为 true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAlertRuleRequest {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetAlertRulesRequest {
+    pub user_id: i64,
+    pub asset_id: Option<i64>, // 为空时返回全部
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRuleTrigger {
+    pub rule_id: i64,
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub rule_type: String,
+    pub value: f64,
+    pub price: f64,
+    pub message: String,
+    pub alert_id: i64,
+}
@@ -0,0 +1,140 @@
+/// 目标配置与再平衡相关结构体。
+///
+/// 字段说明：
+/// - `TargetAllocation`: 目标配置，按资产、分组或资产类型设置目标权重和容忍区间。
+///   - `target_percent`: 目标权重（占组合总市值的百分比）
+///   - `tolerance_percent`: 容忍区间（百分点），实际权重偏离目标超过该值时需要再平衡
+/// - `AllocationDrift`: 单个目标配置的当前权重与偏离情况。
+/// - `DriftReport`: 偏离报告，金额均为用户的基准币种；缺少汇率的条目 `converted` 为 false，并在 `warnings` 中提示。
+/// - `RebalanceTrade`: 再平衡建议交易，`amount`、`fee` 为资产报价币种，`amount_base` 为基准币种。
+/// - `RebalancePlan`: 再平衡方案，`pending_transaction_ids` 为已生成的待确认交易。
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 目标配置的范围
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum AllocationScope {
+    Asset,     // 单个资产
+    Group,     // 用户分组
+    AssetType, // 资产类型
+}
+
+impl AllocationScope {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "ASSET" => Some(AllocationScope::Asset),
+            "GROUP" => Some(AllocationScope::Group),
+            "ASSET_TYPE" => Some(AllocationScope::AssetType),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            AllocationScope::Asset => "ASSET",
+            AllocationScope::Group => "GROUP",
+            AllocationScope::AssetType => "ASSET_TYPE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetAllocation {
+    pub id: i64,
+    pub user_id: i64,
+    pub scope: String,
+    pub scope_id: i64,
+    pub scope_name: String,
+    pub target_percent: f64,
+    pub tolerance_percent: f64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationDrift {
+    pub allocation_id: i64,
+    pub scope: String,
+    pub scope_id: i64,
+    pub scope_name: String,
+    pub target_percent: f64,
+    pub tolerance_percent: f64,
+    pub current_value: f64,
+    pub current_percent: f64,
+    pub drift_percent: f64,   // 当前权重 - 目标权重（百分点）
+    pub target_value: f64,    // 按当前总市值计算的目标市值
+    pub deviation_value: f64, // 当前市值 - 目标市值
+    pub out_of_band: bool,
+    pub converted: bool, // 范围内的资产缺少汇率（按 1:1 计算）时为 false
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub base_currency: String,
+    pub total_value: f64,
+    pub needs_rebalance: bool,
+    pub items: Vec<AllocationDrift>,
+    pub warnings: Vec<String>, // 缺少汇率等提示
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalanceTrade {
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub asset_code: String,
+    pub currency: String,
+    pub scope_id: i64,
+    pub transaction_type: String, // BUY / SELL
+    pub shares: f64,
+    pub price: f64,
+    pub amount: f64,
+    pub amount_base: f64,
+    pub fee: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalancePlan {
+    pub base_currency: String,
+    pub scope: String,
+    pub total_value: f64,
+    pub additional_cash: f64,
+    pub trades: Vec<RebalanceTrade>,
+    pub total_buy: f64,  // 基准币种
+    pub total_sell: f64, // 基准币种
+    pub total_fee: f64,  // 基准币种
+    pub cash_remaining: f64,
+    pub warnings: Vec<String>,
+    pub pending_transaction_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveTargetAllocationRequest {
+    pub id: Option<i64>,
+    pub user_id: i64,
+    pub scope: String, // ASSET / GROUP / ASSET_TYPE
+    pub scope_id: i64,
+    pub target_percent: f64,
+    pub tolerance_percent: Option<f64>, // 为空时默认 5 个百分点
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteTargetAllocationRequest {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetDriftReportRequest {
+    pub user_id: i64,
+    pub scope: Option<String>, // 为空时返回所有目标配置
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanRebalanceRequest {
+    pub user_id: i64,
+    pub scope: String,
+    pub additional_cash: Option<f64>,   // 追加投入资金（基准币种）
+    pub min_trade_amount: Option<f64>,  // 低于该金额（基准币种）的交易忽略
+    pub lot_sizes: Option<HashMap<i64, f64>>, // 资产ID -> 最小交易单位，为空时按资产设置或资产类型默认
+    pub create_pending: Option<bool>,   // 是否生成待确认交易
+}
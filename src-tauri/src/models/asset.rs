@@ -11,6 +11,8 @@
 /// - `code`: 资产代码
 /// - `name`: 资产名称
 /// - `current_price`: 当前价格（可选）
+/// - `currency`: 报价币种（如 CNY、HKD、USD、USDT）
+/// - `lot_size`: 最小交易单位（可选，如港股每手股数；为空时按资产类型默认）
 /// - `position_amount`: 持仓数量（可选）
 /// - `position_cost`: 持仓成本（可选）
 /// - `last_updated`: 最后更新时间（可选）
@@ -31,6 +33,8 @@ pub struct Asset {
     pub code: String,
     pub name: String,
     pub current_price: Option<f64>,//当前价格
+    pub currency: String,//报价币种
+    pub lot_size: Option<f64>,//最小交易单位
     pub position_amount: Option<f64>,//持仓数量
     pub position_cost: Option<f64>,//持仓成本
     pub last_updated: Option<i64>,
@@ -50,6 +54,8 @@ pub struct Asset {
 /// - `code`: 资产代码
 /// - `name`: 资产名称
 /// - `current_price`: 当前价格（可选）
+/// - `currency`: 报价币种（可选，为空时按资产类型取默认币种）
+/// - `lot_size`: 最小交易单位（可选，如港股每手股数）
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateAssetRequest {
     pub user_id: i64,
@@ -58,6 +64,8 @@ pub struct CreateAssetRequest {
     pub code: String,
     pub name: String,
     pub current_price: Option<f64>,
+    pub currency: Option<String>,
+    pub lot_size: Option<f64>,
 }
 
 
@@ -71,6 +79,8 @@ pub struct CreateAssetRequest {
 /// - `current_price`: 当前价格（可选）
 /// - `position_amount`: 持仓数量（可选）
 /// - `position_cost`: 持仓成本（可选）
+/// - `currency`: 报价币种（可选，为空时不修改）
+/// - `lot_size`: 最小交易单位（可选，为空时不修改）
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateAssetRequest {
     pub id: i64,
@@ -80,6 +90,8 @@ pub struct UpdateAssetRequest {
     pub current_price: Option<f64>,
     pub position_amount: Option<f64>,
     pub position_cost: Option<f64>,
+    pub currency: Option<String>,
+    pub lot_size: Option<f64>,
 }
 
 /// 删除资产请求结构体。
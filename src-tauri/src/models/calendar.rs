@@ -0,0 +1,86 @@
+/// 交易日历相关结构体。
+///
+/// 字段说明：
+/// - `Exchange`: 交易所（沪深两市共用同一份休市日数据，数字货币全年无休）。
+/// - `Holiday`: 休市日，`date` 为 `YYYY-MM-DD`。
+/// - `TradingDays`: 区间内的交易日和休市日，`covered_years` 为有休市日数据的年份，
+///   超出这些年份时只按周末判断，并在 `warnings` 中提示。
+use serde::{Deserialize, Serialize};
+
+/// 交易所
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Exchange {
+    Sse,    // 上海证券交易所
+    Szse,   // 深圳证券交易所
+    Hkex,   // 香港交易所
+    Nyse,   // 纽约证券交易所
+    Crypto, // 数字货币，全年无休
+}
+
+impl Exchange {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "SSE" => Some(Exchange::Sse),
+            "SZSE" => Some(Exchange::Szse),
+            "HKEX" => Some(Exchange::Hkex),
+            "NYSE" => Some(Exchange::Nyse),
+            "CRYPTO" => Some(Exchange::Crypto),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Exchange::Sse => "SSE",
+            Exchange::Szse => "SZSE",
+            Exchange::Hkex => "HKEX",
+            Exchange::Nyse => "NYSE",
+            Exchange::Crypto => "CRYPTO",
+        }
+    }
+
+    /// 休市日数据文件名（不含扩展名），全年无休的交易所返回 None
+    pub fn holiday_file(&self) -> Option<&'static str> {
+        match self {
+            Exchange::Sse | Exchange::Szse => Some("cn"),
+            Exchange::Hkex => Some("hk"),
+            Exchange::Nyse => Some("us"),
+            Exchange::Crypto => None,
+        }
+    }
+
+    /// 没有休市日数据时使用的每年交易日数
+    pub fn default_trading_days_per_year(&self) -> f64 {
+        match self {
+            Exchange::Sse | Exchange::Szse => 242.0,
+            Exchange::Hkex => 247.0,
+            Exchange::Nyse => 252.0,
+            Exchange::Crypto => 365.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Holiday {
+    pub date: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingDays {
+    pub exchange: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub trading_days: Vec<String>,
+    pub holidays: Vec<Holiday>, // 区间内工作日休市的日期
+    pub covered_years: Vec<i32>,
+    pub trading_days_per_year: f64,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetTradingDaysRequest {
+    pub exchange: String,   // SSE / SZSE / HKEX / NYSE / CRYPTO
+    pub start_date: String, // YYYY-MM-DD
+    pub end_date: String,   // YYYY-MM-DD
+}
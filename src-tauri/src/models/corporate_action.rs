@@ -0,0 +1,112 @@
+/// 公司行为（分红、份额折算/拆分）相关结构体。
+///
+/// 字段说明：
+/// - `CorporateActionEvent`: 适配器从数据源获取或由净值推算出的事件（未入库）。
+/// - `CorporateAction`: `corporate_actions` 表中的事件记录，按 (资产类型, 代码) 共享给所有用户。
+/// - `CorporateActionSyncReport`: 一次同步任务的汇总结果。
+/// - `DividendMethod`: 资产的分红处理方式，决定是否自动生成分红/再投资/折算交易。
+///
+/// `value` 的含义随事件类型变化：分红为每份派现金额，折算为折算比例（新份额/旧份额）。
+/// 所有日期均为 UTC 当日零点的秒级时间戳，与 `price_history.date` 一致。
+use serde::{Deserialize, Serialize};
+
+/// 事件类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CorporateActionType {
+    Dividend, // 现金分红
+    Split,    // 份额折算/拆分
+}
+
+impl CorporateActionType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "DIVIDEND" => Some(CorporateActionType::Dividend),
+            "SPLIT" => Some(CorporateActionType::Split),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            CorporateActionType::Dividend => "DIVIDEND",
+            CorporateActionType::Split => "SPLIT",
+        }
+    }
+}
+
+/// 分红处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DividendMethod {
+    None,     // 不自动生成交易
+    Cash,     // 现金分红，生成 DIVIDEND 交易
+    Reinvest, // 红利再投资，按除息日净值生成 REINVEST 交易
+}
+
+impl DividendMethod {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "NONE" => Some(DividendMethod::None),
+            "CASH" => Some(DividendMethod::Cash),
+            "REINVEST" => Some(DividendMethod::Reinvest),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            DividendMethod::None => "NONE",
+            DividendMethod::Cash => "CASH",
+            DividendMethod::Reinvest => "REINVEST",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorporateActionEvent {
+    pub symbol: String,
+    pub action_type: CorporateActionType,
+    pub ex_date: i64,
+    pub value: f64,
+    pub nav: Option<f64>,          // 除息日/折算日单位净值
+    pub detected_from: String,     // endpoint: 数据源分红送配信息；nav: 由净值与累计净值推算
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorporateAction {
+    pub id: i64,
+    pub asset_type: String,
+    pub symbol: String,
+    pub action_type: String,
+    pub ex_date: i64,
+    pub value: f64,
+    pub nav: Option<f64>,
+    pub source: String,
+    pub detected_from: String,
+    pub description: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorporateActionSyncReport {
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub symbols_checked: usize,
+    pub actions_found: usize,
+    pub actions_saved: usize,
+    pub transactions_created: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncCorporateActionsRequest {
+    pub user_id: i64,
+    pub lookback_days: Option<i64>, // 为空时默认检查最近一年
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetDividendMethodRequest {
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub dividend_method: String, // NONE / CASH / REINVEST
+}
@@ -0,0 +1,138 @@
+/// K线数据质量检查相关结构体。
+///
+/// 字段说明：
+/// - `DataQualityReport`: 单个数据集（symbol + source + interval）的质量报告。
+/// - `CandleGap`: 缺失K线区间，`start`/`end` 为缺口前后两根K线的时间戳。
+/// - `CandleAnomaly`: 异常K线记录，包含异常类型和说明。
+/// - `RepairSummary`: 修复结果统计。
+/// - `RepairMode`: 修复模式（去重、前值填充、剔除异常值）。
+///
+/// 所有时间戳均为秒级 Unix 时间戳。
+use serde::{Deserialize, Serialize};
+
+/// 异常类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AnomalyKind {
+    Duplicate,       // 重复时间戳
+    OutOfOrder,      // 时间戳乱序
+    InvalidPrice,    // 价格为零、负数或非数字
+    HighLowInverted, // 最高价低于最低价，或开收盘价超出高低区间
+    Spike,           // 相对前一根K线的异常跳变
+}
+
+impl AnomalyKind {
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "duplicate" => AnomalyKind::Duplicate,
+            "out_of_order" => AnomalyKind::OutOfOrder,
+            "invalid_price" => AnomalyKind::InvalidPrice,
+            "high_low_inverted" => AnomalyKind::HighLowInverted,
+            _ => AnomalyKind::Spike,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::Duplicate => "duplicate",
+            AnomalyKind::OutOfOrder => "out_of_order",
+            AnomalyKind::InvalidPrice => "invalid_price",
+            AnomalyKind::HighLowInverted => "high_low_inverted",
+            AnomalyKind::Spike => "spike",
+        }
+    }
+}
+
+/// 修复模式
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum RepairMode {
+    Dedupe,       // 去重并按时间排序，同一时间戳保留最后一条
+    ForwardFill,  // 用前一根K线的收盘价填充缺失K线
+    DropOutliers, // 剔除无效价格、高低价倒挂和异常跳变的K线
+}
+
+impl RepairMode {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "dedupe" => Some(RepairMode::Dedupe),
+            "forward_fill" => Some(RepairMode::ForwardFill),
+            "drop_outliers" => Some(RepairMode::DropOutliers),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            RepairMode::Dedupe => "dedupe",
+            RepairMode::ForwardFill => "forward_fill",
+            RepairMode::DropOutliers => "drop_outliers",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleGap {
+    pub start: i64,
+    pub end: i64,
+    pub missing_bars: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleAnomaly {
+    pub timestamp: i64,
+    pub kind: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RepairSummary {
+    pub modes: Vec<String>,
+    pub removed_duplicates: usize,
+    pub filled_bars: usize,
+    pub dropped_outliers: usize,
+    pub candles_after: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataQualityReport {
+    pub symbol: String,
+    pub source: String,
+    pub interval: String,
+    pub checked_at: i64,
+    pub total_candles: usize,
+    pub first_timestamp: Option<i64>,
+    pub last_timestamp: Option<i64>,
+    pub duplicate_count: usize,
+    pub out_of_order_count: usize,
+    pub invalid_price_count: usize,
+    pub high_low_count: usize,
+    pub spike_count: usize,
+    pub missing_bars: i64,
+    pub exchange: Option<String>, // 日线按该交易所的交易日计算缺失K线，为空时按自然日计算
+    pub gaps: Vec<CandleGap>,
+    pub anomalies: Vec<CandleAnomaly>,
+    pub repair: Option<RepairSummary>,
+}
+
+impl DataQualityReport {
+    /// 数据集是否没有任何问题
+    pub fn is_clean(&self) -> bool {
+        self.duplicate_count == 0
+            && self.out_of_order_count == 0
+            && self.invalid_price_count == 0
+            && self.high_low_count == 0
+            && self.spike_count == 0
+            && self.gaps.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckCandleQualityRequest {
+    pub symbol: String,
+    pub source: String,
+    pub interval: String,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub spike_threshold: Option<f64>, // 跳变阈值（比例），默认 0.2
+    pub repair_modes: Option<Vec<String>>, // dedupe / forward_fill / drop_outliers
+    pub exchange: Option<String>,          // SSE / SZSE / HKEX / NYSE / CRYPTO，日线缺口和前值填充跳过休市日
+}
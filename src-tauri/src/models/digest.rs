@@ -0,0 +1,140 @@
+/// 组合摘要报告相关结构体。
+///
+/// 字段说明：
+/// - `DigestSettings`: 用户的摘要订阅设置。
+///   - `weekly_day`: 周报发送日（1 为周一，7 为周日）。
+///   - `send_to_channels`: 是否通过通知渠道推送（邮件渠道发送 HTML，其余渠道发送 Markdown）。
+///   - `save_to_disk`: 是否把报告保存到 `digest.output_dir`（同时保存 .md 和 .html）。
+///   - `last_daily_date` / `last_weekly_date`: 最近一次自动发送的日期（北京时间 0 点），避免重复发送。
+/// - `DigestReport`: 报告内容，统计区间为日报当天、周报最近 7 天（北京时间）。
+///   - `start_value` / `end_value`: 区间前一日和最新的组合市值快照，没有快照时为空。
+///   - `net_contribution`: 区间内的净投入（买入减卖出），`profit` 为市值变化扣除净投入后的收益，缺少期初快照时为空。
+///   - `top_movers`: 持仓资产中区间涨跌幅绝对值最大的资产。
+///   - `plan_executions`: 区间内的定投执行记录（含暂停、跳过和失败）。
+///   - `alerts`: 区间内触发的交易提醒。
+///   - `upcoming_plans`: 今日待执行的定投计划。
+use crate::models::InvestmentPlan;
+use serde::{Deserialize, Serialize};
+
+/// 报告周期
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DigestPeriod {
+    Daily,
+    Weekly,
+}
+
+impl DigestPeriod {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "DAILY" => Some(DigestPeriod::Daily),
+            "WEEKLY" => Some(DigestPeriod::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            DigestPeriod::Daily => "DAILY",
+            DigestPeriod::Weekly => "WEEKLY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSettings {
+    pub user_id: i64,
+    pub daily_enabled: bool,
+    pub weekly_enabled: bool,
+    pub weekly_day: i64,
+    pub send_to_channels: bool,
+    pub save_to_disk: bool,
+    pub last_daily_date: Option<i64>,
+    pub last_weekly_date: Option<i64>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateDigestSettingsRequest {
+    pub user_id: i64,
+    pub daily_enabled: bool,
+    pub weekly_enabled: bool,
+    pub weekly_day: Option<i64>, // 为空时为 7（周日）
+    pub send_to_channels: bool,
+    pub save_to_disk: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DigestRequest {
+    pub user_id: i64,
+    pub period: String, // DAILY / WEEKLY
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestMover {
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub asset_code: String,
+    pub currency: String,
+    pub start_price: f64,
+    pub end_price: f64,
+    pub change_percent: f64,
+    pub market_value: f64, // 资产币种
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestPlanExecution {
+    pub plan_id: i64,
+    pub plan_name: String,
+    pub asset_name: String,
+    pub scheduled_date: i64,
+    pub status: String,
+    pub amount: f64,
+    pub fee: f64,
+    pub price: Option<f64>,
+    pub shares: Option<f64>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestAlert {
+    pub alert_id: i64,
+    pub asset_name: String,
+    pub alert_type: String,
+    pub message: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestReport {
+    pub user_id: i64,
+    pub period: String,
+    pub start_date: i64,
+    pub end_date: i64,
+    pub base_currency: String,
+    pub start_value: Option<f64>,
+    pub end_value: Option<f64>,
+    pub value_change: Option<f64>,
+    pub value_change_percent: Option<f64>,
+    pub net_contribution: Option<f64>,
+    pub profit: Option<f64>,
+    pub top_movers: Vec<DigestMover>,
+    pub plan_executions: Vec<DigestPlanExecution>,
+    pub alerts: Vec<DigestAlert>,
+    pub upcoming_plans: Vec<InvestmentPlan>,
+    pub generated_at: i64,
+}
+
+/// 报告及其 Markdown / HTML 渲染结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestPreview {
+    pub report: DigestReport,
+    pub markdown: String,
+    pub html: String,
+}
+
+/// 报告的投递结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestDeliveryResult {
+    pub queued: usize,      // 加入通知队列的条数
+    pub files: Vec<String>, // 保存的文件路径
+}
@@ -0,0 +1,119 @@
+/// 止盈止损规则相关结构体。
+///
+/// 字段说明：
+/// - `ExitRule`: 持仓的退出规则，`plan_id` 为空时按资产的全部持仓计算，否则只按该定投计划买入的份额计算。
+///   - `threshold`: 阈值（百分比），含义见 `ExitRuleType`。
+///   - `sell_percent`: 触发时生成待确认卖出交易的份额比例（1-100），为空时只提醒。
+///   - `pause_plan`: 触发时暂停相关定投计划（计划规则暂停该计划，资产规则暂停该资产的所有计划）。
+///   - `peak_price`: 移动止损的最高价，从规则保存时的价格开始跟踪。
+///   - `last_value`: 最近一次评估的指标值。
+///   - `triggered_at`: 触发时间，触发后规则停用，重新保存后恢复。
+/// - `ExitRuleTrigger`: 一次触发的结果，`alert_id` 为生成的交易提醒，`transaction_id` 为待确认卖出交易。
+use serde::{Deserialize, Serialize};
+
+/// 退出规则类型
+///
+/// | 类型 | 触发条件 |
+/// |------|----------|
+/// | TARGET_PROFIT 目标止盈 | 持仓收益率 ≥ 阈值 |
+/// | TRAILING_STOP 移动止损 | 价格从最高价回撤 ≥ 阈值 |
+/// | MAX_LOSS 最大亏损 | 持仓收益率 ≤ -阈值 |
+/// | ANNUALIZED_RETURN 年化止盈 | 持仓年化收益率（XIRR）≥ 阈值，持有不足 30 天时不计算 |
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ExitRuleType {
+    TargetProfit,
+    TrailingStop,
+    MaxLoss,
+    AnnualizedReturn,
+}
+
+impl ExitRuleType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "TARGET_PROFIT" => Some(ExitRuleType::TargetProfit),
+            "TRAILING_STOP" => Some(ExitRuleType::TrailingStop),
+            "MAX_LOSS" => Some(ExitRuleType::MaxLoss),
+            "ANNUALIZED_RETURN" => Some(ExitRuleType::AnnualizedReturn),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ExitRuleType::TargetProfit => "TARGET_PROFIT",
+            ExitRuleType::TrailingStop => "TRAILING_STOP",
+            ExitRuleType::MaxLoss => "MAX_LOSS",
+            ExitRuleType::AnnualizedReturn => "ANNUALIZED_RETURN",
+        }
+    }
+
+    /// 触发时生成的交易提醒类型
+    pub fn alert_type(&self) -> &'static str {
+        match self {
+            ExitRuleType::TargetProfit | ExitRuleType::AnnualizedReturn => "TAKE_PROFIT",
+            ExitRuleType::TrailingStop | ExitRuleType::MaxLoss => "STOP_LOSS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitRule {
+    pub id: i64,
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub plan_id: Option<i64>,
+    pub plan_name: Option<String>,
+    pub rule_type: String,
+    pub threshold: f64,
+    pub sell_percent: Option<f64>,
+    pub pause_plan: bool,
+    pub is_active: bool,
+    pub peak_price: Option<f64>,
+    pub last_value: Option<f64>,
+    pub last_evaluated: Option<i64>,
+    pub triggered_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveExitRuleRequest {
+    pub id: Option<i64>,
+    pub user_id: i64,
+    pub asset_id: Option<i64>, // 与 plan_id 二选一，指定计划时资产为计划的资产
+    pub plan_id: Option<i64>,
+    pub rule_type: String,
+    pub threshold: f64,
+    pub sell_percent: Option<f64>,
+    pub pause_plan: Option<bool>, // 为空时为 false
+    pub is_active: Option<bool>,  // 为空时为 true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteExitRuleRequest {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetExitRulesRequest {
+    pub user_id: i64,
+    pub asset_id: Option<i64>, // 为空时返回全部
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitRuleTrigger {
+    pub rule_id: i64,
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub plan_id: Option<i64>,
+    pub rule_type: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub price: f64,
+    pub message: String,
+    pub alert_id: i64,
+    pub transaction_id: Option<i64>,
+    pub paused_plans: usize,
+}
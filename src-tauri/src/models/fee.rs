@@ -0,0 +1,120 @@
+/// 交易费用相关结构体。
+///
+/// 字段说明：
+/// - `FeeSchedule`: 费率方案，可设置在资产类型（如所有基金）或单个资产上，单个资产优先。
+///   - 基金：`buy_tiers` 分档申购费率（按申购金额），`buy_discount` 申购费折扣（1.0 为不打折，0.1 为一折），
+///     `redemption_tiers` 按持有天数分档的赎回费率（按先进先出计算每笔份额的持有天数）。
+///   - 股票：`commission_rate` 佣金费率（买卖双向）及 `min_commission` 单笔最低佣金，
+///     `stamp_duty_rate` 印花税（仅卖出），`transfer_fee_rate` 过户费（买卖双向）。
+/// - `FeeTier`: 申购费档位，申购金额不低于 `min_amount` 时适用；`fixed_fee` 不为空时按笔收取固定费用（不打折）。
+/// - `HoldingPeriodFee`: 赎回费档位，持有天数不少于 `min_days` 时适用 `rate`。
+/// - `FeeBreakdown`: 单笔交易的费用明细。
+///
+/// 费率均为小数（0.015 表示 1.5%）。
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub min_amount: f64,
+    pub rate: f64,
+    pub fixed_fee: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingPeriodFee {
+    pub min_days: i64,
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub id: i64,
+    pub user_id: i64,
+    pub asset_type_id: Option<i64>,
+    pub asset_id: Option<i64>,
+    pub name: String,
+    pub buy_tiers: Vec<FeeTier>,
+    pub buy_discount: f64,
+    pub redemption_tiers: Vec<HoldingPeriodFee>,
+    pub commission_rate: f64,
+    pub min_commission: f64,
+    pub stamp_duty_rate: f64,
+    pub transfer_fee_rate: f64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl FeeSchedule {
+    /// 按申购总金额（净申购金额 + 费用）确定申购费档位（取满足条件的最高档）
+    pub fn buy_tier(&self, gross_amount: f64) -> Option<&FeeTier> {
+        self.buy_tiers
+            .iter()
+            .filter(|tier| gross_amount >= tier.min_amount)
+            .max_by(|a, b| a.min_amount.total_cmp(&b.min_amount))
+    }
+
+    /// 按持有天数确定赎回费率
+    pub fn redemption_rate(&self, holding_days: i64) -> f64 {
+        self.redemption_tiers
+            .iter()
+            .filter(|tier| holding_days >= tier.min_days)
+            .max_by_key(|tier| tier.min_days)
+            .map(|tier| tier.rate)
+            .unwrap_or(0.0)
+    }
+
+    /// 佣金（不足最低佣金时按最低佣金收取）
+    pub fn commission(&self, trade_amount: f64) -> f64 {
+        if self.commission_rate <= 0.0 && self.min_commission <= 0.0 {
+            return 0.0;
+        }
+        (trade_amount * self.commission_rate).max(self.min_commission)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeeBreakdown {
+    pub subscription_fee: f64, // 申购费
+    pub redemption_fee: f64,   // 赎回费
+    pub commission: f64,       // 佣金
+    pub stamp_duty: f64,       // 印花税
+    pub transfer_fee: f64,     // 过户费
+}
+
+impl FeeBreakdown {
+    pub fn total(&self) -> f64 {
+        self.subscription_fee + self.redemption_fee + self.commission + self.stamp_duty + self.transfer_fee
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveFeeScheduleRequest {
+    pub id: Option<i64>,
+    pub user_id: i64,
+    pub asset_type_id: Option<i64>,
+    pub asset_id: Option<i64>,
+    pub name: String,
+    pub buy_tiers: Vec<FeeTier>,
+    pub buy_discount: Option<f64>,
+    pub redemption_tiers: Vec<HoldingPeriodFee>,
+    pub commission_rate: Option<f64>,
+    pub min_commission: Option<f64>,
+    pub stamp_duty_rate: Option<f64>,
+    pub transfer_fee_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteFeeScheduleRequest {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateFeeRequest {
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub transaction_type: String,
+    pub amount: f64,
+    pub price: f64,
+    pub transaction_date: i64,
+}
@@ -0,0 +1,59 @@
+/// 汇率相关结构体。
+///
+/// 字段说明：
+/// - `FxRate`: 汇率记录，表示 1 单位 `base_currency` 兑换的 `quote_currency` 数量。
+///   - `rate_date`: 汇率日期（UTC 当日零点）
+///   - `source`: 数据源名称，手动录入为 `MANUAL`（同一天手动汇率优先）
+///   - `user_id`: 录入手动汇率的用户，只对该用户的换算生效；0 为数据源同步的公共汇率
+/// - `FxSyncReport`: 汇率同步结果。
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxRate {
+    pub id: i64,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate_date: i64,
+    pub rate: f64,
+    pub source: String,
+    pub user_id: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxSyncReport {
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub pairs_checked: usize,
+    pub rates_saved: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveFxRateRequest {
+    pub user_id: i64,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: f64,
+    pub rate_date: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetFxRatesRequest {
+    pub user_id: i64,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub start_date: Option<i64>,
+    pub end_date: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteFxRateRequest {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncFxRatesRequest {
+    pub lookback_days: Option<i64>,
+}
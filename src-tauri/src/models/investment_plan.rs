@@ -1,6 +1,232 @@
 /// 定投计划
+///
+/// 字段说明：
+/// - `mode`: 定投模式（见 `PlanMode`），`FIXED` 为固定金额
+/// - `mode_config`: 智能定投规则（见 `SmartDcaConfig`），固定金额模式为空
+/// - `amount`: 基准定投金额，智能定投按规则在此基础上调整
+/// - `frequency`: 执行频率（见 `PlanSchedule`），`days_of_month` 为每月多个执行日（如 1 号和 15 号），
+///   `cron_expression` 为 CRON 频率的表达式
+/// - `start_date` / `end_date`: 计划的开始和结束日期，结束日期之后计划自动停止
+/// - `max_total_amount` / `take_profit_percent`: 停止条件，累计投入达到上限或计划收益率达到止盈目标时自动停止，
+///   `stop_reason` 为自动停止的原因（见 `PlanStopReason`）
+/// - `catch_up_policy`: 错过执行日（应用未运行）后的补执行策略（见 `CatchUpPolicy`）
+/// - `PlanExecution`: 定投计划每一期的执行记录，`transaction_status` 为对应交易的当前状态（待确认 / 已确认 / 失败）
+/// - `PlanSettlementSummary`: 一次成交确认的结果，`pending` 为仍在等待净值的定投笔数
 use serde::{Deserialize, Serialize};
 
+/// 定投模式
+///
+/// | 模式 | 指标 | 金额 |
+/// |------|------|------|
+/// | FIXED 固定金额 | - | 基准金额 |
+/// | MA_DEVIATION 均线偏离 | 现价相对 N 日均线的偏离（%） | 基准金额 × 档位倍数 |
+/// | DRAWDOWN 回撤 | 现价相对回看期内最高价的回撤（%） | 基准金额 × 档位倍数 |
+/// | PE_PERCENTILE 估值百分位 | 现价在回看期内的历史百分位（%），以价格（净值）百分位作为估值近似 | 基准金额 × 档位倍数 |
+/// | VALUE_AVERAGING 价值平均 | 计划持仓市值与目标市值路径的差额 | 目标市值 - 当前市值 |
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PlanMode {
+    Fixed,
+    MaDeviation,
+    Drawdown,
+    PePercentile,
+    ValueAveraging,
+}
+
+impl PlanMode {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "FIXED" => Some(PlanMode::Fixed),
+            "MA_DEVIATION" => Some(PlanMode::MaDeviation),
+            "DRAWDOWN" => Some(PlanMode::Drawdown),
+            "PE_PERCENTILE" => Some(PlanMode::PePercentile),
+            "VALUE_AVERAGING" => Some(PlanMode::ValueAveraging),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            PlanMode::Fixed => "FIXED",
+            PlanMode::MaDeviation => "MA_DEVIATION",
+            PlanMode::Drawdown => "DRAWDOWN",
+            PlanMode::PePercentile => "PE_PERCENTILE",
+            PlanMode::ValueAveraging => "VALUE_AVERAGING",
+        }
+    }
+
+    /// 模式名称（用于交易备注）
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlanMode::Fixed => "固定金额",
+            PlanMode::MaDeviation => "均线偏离",
+            PlanMode::Drawdown => "回撤",
+            PlanMode::PePercentile => "估值百分位",
+            PlanMode::ValueAveraging => "价值平均",
+        }
+    }
+}
+
+/// 错过执行日后的补执行策略
+///
+/// | 策略 | 说明 |
+/// |------|------|
+/// | ALL 全部补执行 | 每个错过的期数按当期的历史价格补执行 |
+/// | ONCE 补执行一次 | 按当前价格执行一次，其余错过的期数记为跳过 |
+/// | SKIP 跳过 | 错过的期数全部记为跳过，从下一期开始正常执行 |
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CatchUpPolicy {
+    All,
+    Once,
+    Skip,
+}
+
+impl CatchUpPolicy {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "ALL" => Some(CatchUpPolicy::All),
+            "ONCE" => Some(CatchUpPolicy::Once),
+            "SKIP" => Some(CatchUpPolicy::Skip),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            CatchUpPolicy::All => "ALL",
+            CatchUpPolicy::Once => "ONCE",
+            CatchUpPolicy::Skip => "SKIP",
+        }
+    }
+}
+
+/// 单期执行结果
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PlanExecutionStatus {
+    Executed, // 已下单（生成了交易）
+    Paused,   // 智能定投本期金额为 0，不买入
+    Skipped,  // 错过的期数按补执行策略跳过
+    Failed,   // 没有价格等原因无法执行
+}
+
+impl PlanExecutionStatus {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "EXECUTED" => Some(PlanExecutionStatus::Executed),
+            "PAUSED" => Some(PlanExecutionStatus::Paused),
+            "SKIPPED" => Some(PlanExecutionStatus::Skipped),
+            "FAILED" => Some(PlanExecutionStatus::Failed),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            PlanExecutionStatus::Executed => "EXECUTED",
+            PlanExecutionStatus::Paused => "PAUSED",
+            PlanExecutionStatus::Skipped => "SKIPPED",
+            PlanExecutionStatus::Failed => "FAILED",
+        }
+    }
+}
+
+/// 定投执行日程
+///
+/// | 频率 | 参数 |
+/// |------|------|
+/// | DAILY 每个交易日 | - |
+/// | WEEKLY 每周 / BIWEEKLY 每两周 | `day_of_week`（1-7） |
+/// | MONTHLY 每月 | `day_of_month` 或 `days_of_month`（1-31，如 `[1, 15]`），超过当月天数时取月末 |
+/// | QUARTERLY 每季度 | 同每月，在每季度第一个月（1、4、7、10 月）执行 |
+/// | LAST_TRADING_DAY 每月最后一个交易日 | - |
+/// | CRON 自定义 | `cron_expression`：分 时 日 月 星期（0-7，0 和 7 为周日），按北京时间 |
+///
+/// 执行日遇资产所在交易所休市时顺延到下一个交易日。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanSchedule {
+    pub frequency: String,
+    pub day_of_week: Option<i64>,
+    pub day_of_month: Option<i64>,
+    #[serde(default)]
+    pub days_of_month: Vec<i64>,
+    pub cron_expression: Option<String>,
+    pub start_date: Option<i64>, // 首次执行不早于该日期
+    pub end_date: Option<i64>,   // 最后一次执行不晚于该日期
+}
+
+/// 计划的自动停止条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanStopConditions {
+    pub max_total_amount: Option<f64>,    // 累计投入上限（含费用），最后一期按剩余额度投入
+    pub take_profit_percent: Option<f64>, // 止盈目标：计划持仓收益率达到该百分比时停止
+}
+
+/// 计划自动停止的原因
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PlanStopReason {
+    EndDate,    // 已过结束日期
+    AmountCap,  // 累计投入达到上限
+    TakeProfit, // 达到止盈目标
+    ExitRule,   // 止盈止损规则触发后暂停
+}
+
+impl PlanStopReason {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "END_DATE" => Some(PlanStopReason::EndDate),
+            "AMOUNT_CAP" => Some(PlanStopReason::AmountCap),
+            "TAKE_PROFIT" => Some(PlanStopReason::TakeProfit),
+            "EXIT_RULE" => Some(PlanStopReason::ExitRule),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            PlanStopReason::EndDate => "END_DATE",
+            PlanStopReason::AmountCap => "AMOUNT_CAP",
+            PlanStopReason::TakeProfit => "TAKE_PROFIT",
+            PlanStopReason::ExitRule => "EXIT_RULE",
+        }
+    }
+}
+
+/// 金额档位：指标值不低于 `min_value` 时适用 `multiplier`（取满足条件的最高档），
+/// 指标值低于所有档位时倍数为 1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmountTier {
+    pub min_value: f64,
+    pub multiplier: f64,
+}
+
+/// 智能定投规则
+///
+/// - `tiers`: 金额档位，为空时按模式使用默认档位
+/// - `ma_period`: 均线周期（交易日），默认 250
+/// - `lookback_days`: 回撤和百分位的回看天数，默认 1825（约 5 年）
+/// - `growth_percent`: 价值平均每期目标增长率（%），默认 0
+/// - `min_amount` / `max_amount`: 单期金额下限 / 上限，默认 0 / 基准金额 × 3；计算结果为 0 时本期跳过
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmartDcaConfig {
+    #[serde(default)]
+    pub tiers: Vec<AmountTier>,
+    pub ma_period: Option<usize>,
+    pub lookback_days: Option<i64>,
+    pub growth_percent: Option<f64>,
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+}
+
+/// 智能定投的计算结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanAmount {
+    pub mode: String,
+    pub base_amount: f64,
+    pub amount: f64,
+    pub indicator: Option<f64>,
+    pub multiplier: Option<f64>,
+    pub reason: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InvestmentPlan {
     pub id: i64,
@@ -12,8 +238,18 @@ pub struct InvestmentPlan {
     pub frequency: String,
     pub day_of_week: Option<i64>,
     pub day_of_month: Option<i64>,
+    pub days_of_month: Vec<i64>,
+    pub cron_expression: Option<String>,
+    pub start_date: Option<i64>,
+    pub end_date: Option<i64>,
     pub amount: f64,
+    pub mode: String,
+    pub mode_config: Option<SmartDcaConfig>,
+    pub catch_up_policy: String,
+    pub max_total_amount: Option<f64>,
+    pub take_profit_percent: Option<f64>,
     pub is_active: bool,
+    pub stop_reason: Option<String>,
     pub last_executed: Option<i64>,
     pub next_execution: Option<i64>,
     pub created_at: i64,
@@ -29,7 +265,16 @@ pub struct SaveInvestmentPlanRequest {
     pub frequency: String,
     pub day_of_week: Option<i64>,
     pub day_of_month: Option<i64>,
+    pub days_of_month: Option<Vec<i64>>,
+    pub cron_expression: Option<String>,
+    pub start_date: Option<i64>,
+    pub end_date: Option<i64>,
     pub amount: f64,
+    pub mode: Option<String>, // 为空时为 FIXED
+    pub mode_config: Option<SmartDcaConfig>,
+    pub catch_up_policy: Option<String>, // ALL / ONCE / SKIP，为空时为 ONCE
+    pub max_total_amount: Option<f64>,
+    pub take_profit_percent: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,3 +282,42 @@ pub struct DeleteInvestmentPlanRequest {
     pub id: i64,
     pub user_id: i64,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreviewPlanAmountRequest {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanSettlementSummary {
+    pub confirmed: usize,
+    pub failed: usize,
+    pub pending: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanExecution {
+    pub id: i64,
+    pub plan_id: i64,
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub scheduled_date: i64,
+    pub executed_at: i64,
+    pub status: String,
+    pub catch_up: bool, // 是否为错过执行日后的补执行
+    pub amount: f64,    // 定投金额（含费用）
+    pub fee: f64,
+    pub price: Option<f64>,  // 成交价，交易确认后为成交日净值
+    pub shares: Option<f64>, // 买入份额，交易确认后按成交日净值计算
+    pub transaction_id: Option<i64>,
+    pub transaction_status: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetPlanExecutionsRequest {
+    pub user_id: i64,
+    pub plan_id: i64,
+    pub limit: Option<i64>, // 为空时返回全部
+}
@@ -0,0 +1,52 @@
+/// 行情同步结果结构体。
+///
+/// 字段说明：
+/// - `AssetSyncResult`: 单个资产的同步结果，失败时 `error` 记录原因，不影响其他资产。
+///   `quality_report` 为本次拉取日线的质量报告（去重排序前生成），数据有问题时 `error` 中同时给出提示。
+/// - `MarketSyncReport`: 一次同步任务的汇总结果。
+/// - `AssetSyncStatus`: `asset_sync_status` 表中记录的资产最近一次同步状态。
+///
+/// 所有时间戳均为秒级 Unix 时间戳。
+use crate::models::DataQualityReport;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetSyncResult {
+    pub asset_id: i64,
+    pub asset_type: String,
+    pub code: String,
+    pub source: String,
+    pub success: bool,
+    pub price: Option<f64>,
+    pub bars_saved: usize,
+    pub error: Option<String>,
+    pub quality_report: Option<DataQualityReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarketSyncReport {
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<AssetSyncResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetSyncStatus {
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub asset_code: String,
+    pub source: String,
+    pub status: String, // success / failed
+    pub message: Option<String>,
+    pub last_attempt: i64,
+    pub last_success: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncMarketDataRequest {
+    pub user_id: i64,
+    pub asset_type: Option<String>, // 为空时同步该用户全部资产
+}
@@ -1,41 +1,75 @@
 // 导出所有子模块
 pub mod adapter;
+pub mod alert_rule;
+pub mod allocation;
 pub mod asset;
 pub mod asset_type;
 pub mod auth;
+pub mod calendar;
 pub mod candle;
+pub mod corporate_action;
+pub mod data_quality;
+pub mod digest;
 pub mod event;
+pub mod exit_rule;
+pub mod fee;
+pub mod fx;
 pub mod import;
 pub mod investment_plan;
 pub mod investment_strategy;
+pub mod market_sync;
+pub mod notification;
 pub mod order;
+pub mod performance;
+pub mod plan_backtest;
 pub mod portfolio;
+pub mod position;
 pub mod price_history;
+pub mod snapshot;
 pub mod strategy;
+pub mod strategy_signal;
 pub mod ticker;
 pub mod trade_alert;
 pub mod trader;
 pub mod transaction;
 pub mod user;
 pub mod user_group;
+pub mod user_settings;
 
 // 重新导出所有类型，以便可以直接从 models 模块访问
 pub use adapter::*;
+pub use alert_rule::*;
+pub use allocation::*;
 pub use asset::*;
 pub use asset_type::*;
 pub use auth::*;
+pub use calendar::*;
 pub use candle::*;
+pub use corporate_action::*;
+pub use data_quality::*;
+pub use digest::*;
 pub use event::*;
+pub use exit_rule::*;
+pub use fee::*;
+pub use fx::*;
 pub use import::*;
 pub use investment_plan::*;
 pub use investment_strategy::*;
+pub use market_sync::*;
+pub use notification::*;
 pub use order::*;
+pub use performance::*;
+pub use plan_backtest::*;
 pub use portfolio::*;
+pub use position::*;
 pub use price_history::*;
+pub use snapshot::*;
 pub use strategy::*;
+pub use strategy_signal::*;
 pub use ticker::*;
 pub use trade_alert::*;
 pub use trader::*;
 pub use transaction::*;
 pub use user::*;
 pub use user_group::*;
+pub use user_settings::*;
@@ -0,0 +1,177 @@
+/// 通知推送相关结构体。
+///
+/// 字段说明：
+/// - `NotificationChannel`: 用户的通知渠道，`target` 和 `secret` 的含义见 `NotificationChannelType`。
+/// - `NotificationPreferences`: 用户的通知偏好。
+///   - `alert_types`: 需要推送的提醒类型（如 BUY、STOP_LOSS、PRICE_CROSS），为空时推送全部。
+///   - `quiet_hours_start` / `quiet_hours_end`: 免打扰时段（北京时间 HH:MM，可跨零点），期间的通知推迟到时段结束后发送。
+/// - `NotificationDelivery`: 一条通知在一个渠道上的发送记录，失败后按间隔重试，超过最大次数后标记为失败。
+/// - `DesktopNotification`: 桌面通知事件（`desktop-notification`）的内容，由前端弹出系统通知。
+use serde::{Deserialize, Serialize};
+
+/// 通知渠道类型
+///
+/// | 类型 | target | secret |
+/// |------|--------|--------|
+/// | DESKTOP 桌面通知 | 不需要 | 不需要 |
+/// | EMAIL 邮件 | 收件邮箱 | 不需要 |
+/// | WEBHOOK 通用 Webhook | 接收 JSON 的 URL | 可选，作为 `X-WolfQuant-Token` 请求头 |
+/// | WECOM 企业微信机器人 | 机器人 Webhook URL | 不需要 |
+/// | DINGTALK 钉钉机器人 | 机器人 Webhook URL | 可选，加签密钥 |
+/// | FEISHU 飞书机器人 | 机器人 Webhook URL | 可选，签名校验密钥 |
+/// | TELEGRAM Telegram 机器人 | chat_id | Bot Token |
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NotificationChannelType {
+    Desktop,
+    Email,
+    Webhook,
+    WeCom,
+    DingTalk,
+    Feishu,
+    Telegram,
+}
+
+impl NotificationChannelType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "DESKTOP" => Some(NotificationChannelType::Desktop),
+            "EMAIL" => Some(NotificationChannelType::Email),
+            "WEBHOOK" => Some(NotificationChannelType::Webhook),
+            "WECOM" => Some(NotificationChannelType::WeCom),
+            "DINGTALK" => Some(NotificationChannelType::DingTalk),
+            "FEISHU" => Some(NotificationChannelType::Feishu),
+            "TELEGRAM" => Some(NotificationChannelType::Telegram),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            NotificationChannelType::Desktop => "DESKTOP",
+            NotificationChannelType::Email => "EMAIL",
+            NotificationChannelType::Webhook => "WEBHOOK",
+            NotificationChannelType::WeCom => "WECOM",
+            NotificationChannelType::DingTalk => "DINGTALK",
+            NotificationChannelType::Feishu => "FEISHU",
+            NotificationChannelType::Telegram => "TELEGRAM",
+        }
+    }
+}
+
+/// 通知发送状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DeliveryStatus {
+    Pending, // 等待发送（含等待重试、免打扰推迟）
+    Sent,
+    Failed, // 超过最大重试次数
+}
+
+impl DeliveryStatus {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "PENDING" => Some(DeliveryStatus::Pending),
+            "SENT" => Some(DeliveryStatus::Sent),
+            "FAILED" => Some(DeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "PENDING",
+            DeliveryStatus::Sent => "SENT",
+            DeliveryStatus::Failed => "FAILED",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationChannel {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub channel_type: String,
+    pub target: Option<String>,
+    pub secret: Option<String>,
+    pub is_enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveNotificationChannelRequest {
+    pub id: Option<i64>,
+    pub user_id: i64,
+    pub name: String,
+    pub channel_type: String,
+    pub target: Option<String>,
+    pub secret: Option<String>,
+    pub is_enabled: Option<bool>, // 为空时为 true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationChannelRequest {
+    pub id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    pub user_id: i64,
+    pub alert_types: Vec<String>,
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub user_id: i64,
+    #[serde(default)]
+    pub alert_types: Vec<String>,
+    pub quiet_hours_start: Option<String>, // 与 quiet_hours_end 同时为空时关闭免打扰
+    pub quiet_hours_end: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationDelivery {
+    pub id: i64,
+    pub user_id: i64,
+    pub channel_id: i64,
+    pub channel_name: String,
+    pub channel_type: String,
+    pub alert_id: Option<i64>,
+    pub title: String,
+    pub body: String,
+    pub status: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<i64>,
+    pub sent_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetNotificationDeliveriesRequest {
+    pub user_id: i64,
+    pub status: Option<String>, // PENDING / SENT / FAILED，为空时返回全部
+    pub limit: Option<i64>,     // 为空时为 100
+}
+
+/// 一次推送任务的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationDispatchReport {
+    pub queued: usize,   // 新加入发送队列的通知
+    pub sent: usize,     // 发送成功
+    pub retrying: usize, // 发送失败，等待重试
+    pub failed: usize,   // 超过最大重试次数
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesktopNotification {
+    pub user_id: i64,
+    pub delivery_id: Option<i64>,
+    pub alert_id: Option<i64>,
+    pub title: String,
+    pub body: String,
+}
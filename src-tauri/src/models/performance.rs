@@ -0,0 +1,70 @@
+/// 收益率计算相关结构体。
+///
+/// 字段说明：
+/// - `ReturnScope`: 统计范围（单个资产、分组、资产类型、定投计划、整个组合）。
+/// - `PerformanceReport`: 指定区间的收益率报告，金额均为用户的基准币种
+///   - `start_value` / `end_value`: 区间期初 / 期末市值
+///   - `net_contribution`: 区间净投入（买入、转入、独立费用为正，卖出、转出、现金分红为负）
+///   - `profit`: 区间收益 = 期末市值 - 期初市值 - 净投入
+///   - `xirr_percent`: 资金加权收益率（年化，百分比），反映投资者实际的资金回报
+///   - `twr_percent`: 时间加权收益率（区间累计，百分比），剔除资金进出时点的影响
+///   - `annualized_twr_percent`: 年化时间加权收益率（百分比）
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ReturnScope {
+    Asset,
+    Group,
+    AssetType,
+    Plan,
+    Portfolio,
+}
+
+impl ReturnScope {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "ASSET" => Some(ReturnScope::Asset),
+            "GROUP" => Some(ReturnScope::Group),
+            "ASSET_TYPE" => Some(ReturnScope::AssetType),
+            "PLAN" => Some(ReturnScope::Plan),
+            "PORTFOLIO" => Some(ReturnScope::Portfolio),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ReturnScope::Asset => "ASSET",
+            ReturnScope::Group => "GROUP",
+            ReturnScope::AssetType => "ASSET_TYPE",
+            ReturnScope::Plan => "PLAN",
+            ReturnScope::Portfolio => "PORTFOLIO",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceReport {
+    pub scope: String,
+    pub scope_id: Option<i64>,
+    pub name: String,
+    pub currency: String, // 金额币种（用户的基准币种）
+    pub start_date: i64,
+    pub end_date: i64,
+    pub start_value: f64,
+    pub end_value: f64,
+    pub net_contribution: f64,
+    pub profit: f64,
+    pub xirr_percent: Option<f64>,
+    pub twr_percent: Option<f64>,
+    pub annualized_twr_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetReturnsRequest {
+    pub user_id: i64,
+    pub scope: String,          // ASSET / GROUP / ASSET_TYPE / PLAN / PORTFOLIO
+    pub scope_id: Option<i64>,  // 为空时返回该范围下的所有对象
+    pub start_date: Option<i64>, // 为空时从第一笔交易开始
+    pub end_date: Option<i64>,   // 为空时截至当前
+}
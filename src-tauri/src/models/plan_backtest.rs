@@ -0,0 +1,94 @@
+/// 定投计划回测相关结构体。
+///
+/// 字段说明：
+/// - `PlanBacktestRequest`: 回测请求，可指定已有计划（`plan_id`），其余字段不为空时覆盖计划的设置；
+///   不指定计划时 `asset_id`、`frequency`、`amount` 必填。
+/// - `PlanBacktestExecution`: 单期执行明细，`scheduled_date` 为计划日期，`date` 为实际成交的交易日。
+/// - `PlanBacktestPoint`: 每日累计投入和市值。
+/// - `LumpSumComparison`: 在第一期执行日一次性投入相同总金额的对照结果。
+/// - `PlanBacktestResult`: 回测结果，金额均为资产的报价币种，收益率和回撤为百分比；
+///   年化波动率按资产所在交易所每年的交易日数（`trading_days_per_year`）年化。
+use serde::{Deserialize, Serialize};
+
+use crate::models::SmartDcaConfig;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanBacktestRequest {
+    pub user_id: i64,
+    pub plan_id: Option<i64>,
+    pub asset_id: Option<i64>,
+    pub frequency: Option<String>,
+    pub day_of_week: Option<i64>,
+    pub day_of_month: Option<i64>,
+    pub days_of_month: Option<Vec<i64>>,
+    pub cron_expression: Option<String>,
+    pub amount: Option<f64>,
+    pub mode: Option<String>,
+    pub mode_config: Option<SmartDcaConfig>,
+    pub start_date: i64,
+    pub end_date: Option<i64>, // 为空时回测到最新价格
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanBacktestExecution {
+    pub period: i64,
+    pub scheduled_date: i64,
+    pub date: i64,
+    pub price: f64,
+    pub amount: f64, // 本期投入（含费用），暂停的期数为 0
+    pub fee: f64,
+    pub shares: f64,
+    pub indicator: Option<f64>,
+    pub multiplier: Option<f64>,
+    pub reason: String,
+    pub total_invested: f64,
+    pub total_shares: f64,
+    pub market_value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanBacktestPoint {
+    pub date: i64,
+    pub invested: f64,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LumpSumComparison {
+    pub invest_date: i64,
+    pub invested: f64,
+    pub fee: f64,
+    pub shares: f64,
+    pub final_value: f64,
+    pub profit: f64,
+    pub return_percent: f64,
+    pub xirr_percent: Option<f64>,
+    pub max_drawdown_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanBacktestResult {
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub currency: String,
+    pub mode: String,
+    pub frequency: String,
+    pub start_date: i64,
+    pub end_date: i64,
+    pub executions: i64, // 实际买入的期数
+    pub total_invested: f64,
+    pub total_fee: f64,
+    pub total_shares: f64,
+    pub average_cost: f64,
+    pub final_value: f64,
+    pub profit: f64,
+    pub return_percent: f64,
+    pub xirr_percent: Option<f64>,
+    pub max_drawdown_percent: f64, // 按时间加权净值计算，剔除投入资金的影响
+    pub annualized_volatility_percent: Option<f64>,
+    pub exchange: String,
+    pub trading_days_per_year: f64,
+    pub ledger: Vec<PlanBacktestExecution>,
+    pub curve: Vec<PlanBacktestPoint>,
+    pub lump_sum: Option<LumpSumComparison>,
+}
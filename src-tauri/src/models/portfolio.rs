@@ -18,15 +18,23 @@ pub struct AssetSummary {
     pub total_profit: f64,
     /// 总收益率（百分比）
     pub total_profit_percent: f64,
+    /// 已实现收益（含已清仓资产）
+    pub realized_profit: f64,
+    /// 浮动盈亏
+    pub unrealized_profit: f64,
     /// 当日收益
     pub daily_profit: f64,
     /// 当日收益率（百分比）
     pub daily_profit_percent: f64,
+    /// 金额是否已全部换算为基准币种（有币种缺少汇率时为 false）
+    pub converted: bool,
 }
 
 /// 投资组合汇总信息
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PortfolioSummary {
+    /// 基准币种（所有金额均已换算为该币种）
+    pub base_currency: String,
     /// 当前总市值
     pub total_value: f64,
     /// 总成本
@@ -35,12 +43,22 @@ pub struct PortfolioSummary {
     pub total_profit: f64,
     /// 总收益率（百分比）
     pub total_profit_percent: f64,
+    /// 已实现收益（含已清仓资产）
+    pub realized_profit: f64,
+    /// 浮动盈亏
+    pub unrealized_profit: f64,
+    /// 资金加权收益率（年化，百分比）
+    pub xirr_percent: Option<f64>,
+    /// 时间加权收益率（自建仓以来累计，百分比）
+    pub twr_percent: Option<f64>,
     /// 当日收益
     pub daily_profit: f64,
     /// 当日收益率（百分比）
     pub daily_profit_percent: f64,
     /// 各类资产汇总信息
     pub asset_summaries: Vec<AssetSummary>,
+    /// 提示信息（如缺少汇率）
+    pub warnings: Vec<String>,
 }
 
 /// 持仓信息
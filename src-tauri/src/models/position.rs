@@ -0,0 +1,475 @@
+/// 持仓与批次台账相关结构体，由交易流水逐笔计算得到。
+///
+/// 字段说明：
+/// - `AssetPosition`: 资产持仓汇总
+///   - `shares`: 当前持仓份额
+///   - `cost`: 当前持仓的投入成本（总额）
+///   - `realized_profit`: 已实现收益（卖出盈亏 + 现金分红 - 独立费用，卖出盈亏已扣除卖出费用，
+///     卖出份额超过持仓时只计算匹配到的份额）
+///   - `dividend_income`: 累计现金分红
+///   - `fee_total`: 累计费用（交易费用 + 独立费用）
+///   - `transaction_count`: 参与计算的交易笔数
+/// - `Lot`: 持仓批次，每笔买入、转入、红利再投资形成一个批次，记录剩余份额、剩余成本和持有天数。
+/// - `RealizedTrade`: 单笔卖出的已实现盈亏，`matches` 为匹配到的批次明细。
+/// - `LotLedger`: 批次台账，按成本计算方法（`CostBasisMethod`）结转卖出成本。
+/// - `PositionLedger`: 返回给前端的资产台账，包含按现价计算的浮动盈亏。
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::models::TransactionType;
+
+/// 份额小于该值时视为清仓，避免浮点误差残留
+const SHARE_EPSILON: f64 = 1e-8;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 成本计算方法
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CostBasisMethod {
+    Fifo,            // 先进先出
+    Lifo,            // 后进先出
+    WeightedAverage, // 移动加权平均
+}
+
+impl CostBasisMethod {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "FIFO" => Some(CostBasisMethod::Fifo),
+            "LIFO" => Some(CostBasisMethod::Lifo),
+            "AVERAGE" => Some(CostBasisMethod::WeightedAverage),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            CostBasisMethod::Fifo => "FIFO",
+            CostBasisMethod::Lifo => "LIFO",
+            CostBasisMethod::WeightedAverage => "AVERAGE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetPosition {
+    pub asset_id: i64,
+    pub shares: f64,
+    pub cost: f64,
+    pub realized_profit: f64,
+    pub dividend_income: f64,
+    pub fee_total: f64,
+    pub transaction_count: i64,
+}
+
+impl AssetPosition {
+    pub fn new(asset_id: i64) -> Self {
+        Self {
+            asset_id,
+            ..Default::default()
+        }
+    }
+
+    /// 单位持仓成本
+    pub fn average_cost(&self) -> f64 {
+        if self.shares > 0.0 {
+            self.cost / self.shares
+        } else {
+            0.0
+        }
+    }
+
+    /// 按当前价格计算的浮动盈亏
+    pub fn unrealized_profit(&self, price: f64) -> f64 {
+        price * self.shares - self.cost
+    }
+
+    /// 总收益 = 浮动盈亏 + 已实现收益
+    pub fn total_profit(&self, price: f64) -> f64 {
+        self.unrealized_profit(price) + self.realized_profit
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lot {
+    pub transaction_id: i64,
+    pub transaction_type: String,
+    pub acquired_date: i64,
+    pub original_shares: f64,
+    pub remaining_shares: f64,
+    pub remaining_cost: f64,
+    pub holding_days: i64, // 截至台账计算日的持有天数
+}
+
+impl Lot {
+    /// 批次单位成本
+    pub fn unit_cost(&self) -> f64 {
+        if self.remaining_shares > 0.0 {
+            self.remaining_cost / self.remaining_shares
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LotMatch {
+    pub lot_transaction_id: i64,
+    pub acquired_date: i64,
+    pub shares: f64,
+    pub cost: f64,
+    pub holding_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealizedTrade {
+    pub asset_id: i64,
+    pub transaction_id: i64,
+    pub sell_date: i64,
+    pub shares: f64,
+    pub proceeds: f64,        // 卖出金额（已扣除卖出费用）
+    pub cost_basis: f64,      // 结转成本
+    pub realized_profit: f64, // proceeds - cost_basis
+    pub realized_profit_percent: f64,
+    pub matches: Vec<LotMatch>,
+}
+
+/// 批次台账
+///
+/// - 先进先出 / 后进先出：卖出时从最早 / 最晚的批次扣减，按批次成本结转。
+/// - 移动加权平均：卖出份额按先进先出扣减（用于计算持有天数），成本按平均成本结转，
+///   剩余批次的单位成本始终等于平均成本。
+#[derive(Debug, Clone)]
+pub struct LotLedger {
+    pub method: CostBasisMethod,
+    pub position: AssetPosition,
+    pub lots: Vec<Lot>,
+    pub realized_trades: Vec<RealizedTrade>,
+}
+
+impl LotLedger {
+    pub fn new(asset_id: i64, method: CostBasisMethod) -> Self {
+        Self {
+            method,
+            position: AssetPosition::new(asset_id),
+            lots: Vec::new(),
+            realized_trades: Vec::new(),
+        }
+    }
+
+    /// 摊平所有批次的单位成本（仅移动加权平均）
+    fn rebalance_average(&mut self) {
+        if self.method != CostBasisMethod::WeightedAverage {
+            return;
+        }
+        let shares: f64 = self.lots.iter().map(|lot| lot.remaining_shares).sum();
+        let cost: f64 = self.lots.iter().map(|lot| lot.remaining_cost).sum();
+        if shares > 0.0 {
+            for lot in self.lots.iter_mut() {
+                lot.remaining_cost = lot.remaining_shares * cost / shares;
+            }
+        }
+    }
+
+    fn add_lot(&mut self, transaction_id: i64, kind: TransactionType, date: i64, shares: f64, cost: f64) {
+        self.lots.push(Lot {
+            transaction_id,
+            transaction_type: kind.to_str().to_string(),
+            acquired_date: date,
+            original_shares: shares,
+            remaining_shares: shares,
+            remaining_cost: cost,
+            holding_days: 0,
+        });
+        self.rebalance_average();
+    }
+
+    /// 从批次中扣减份额，返回匹配明细
+    fn consume(&mut self, quantity: f64, date: i64) -> Vec<LotMatch> {
+        let mut remaining = quantity;
+        let mut matches = Vec::new();
+
+        while remaining > SHARE_EPSILON && !self.lots.is_empty() {
+            let index = match self.method {
+                CostBasisMethod::Lifo => self.lots.len() - 1,
+                _ => 0,
+            };
+
+            let lot = &mut self.lots[index];
+            let shares = lot.remaining_shares.min(remaining);
+            let cost = lot.unit_cost() * shares;
+
+            matches.push(LotMatch {
+                lot_transaction_id: lot.transaction_id,
+                acquired_date: lot.acquired_date,
+                shares,
+                cost,
+                holding_days: (date - lot.acquired_date).max(0) / SECONDS_PER_DAY,
+            });
+
+            lot.remaining_cost -= cost;
+            lot.remaining_shares -= shares;
+            remaining -= shares;
+
+            if lot.remaining_shares < SHARE_EPSILON {
+                self.lots.remove(index);
+            }
+        }
+
+        matches
+    }
+
+    /// 按交易类型更新台账，返回本笔交易产生的已实现收益
+    ///
+    /// `fee` 为交易费用：买入、转入和再投资计入成本，其余类型冲减已实现收益。
+    pub fn apply(
+        &mut self,
+        transaction_id: i64,
+        date: i64,
+        kind: TransactionType,
+        amount: f64,
+        total_cost: f64,
+        fee: f64,
+    ) -> f64 {
+        self.position.transaction_count += 1;
+        self.position.fee_total += fee;
+
+        let realized = match kind {
+            TransactionType::Buy | TransactionType::TransferIn => {
+                self.add_lot(transaction_id, kind, date, amount, total_cost + fee);
+                0.0
+            }
+            TransactionType::Reinvest => {
+                // 红利再投资不增加投入本金，批次成本只包含费用
+                self.add_lot(transaction_id, kind, date, amount, fee);
+                0.0
+            }
+            TransactionType::Sell => {
+                let matches = self.consume(amount, date);
+                let shares: f64 = matches.iter().map(|m| m.shares).sum();
+                let cost_basis: f64 = matches.iter().map(|m| m.cost).sum();
+                // 卖出份额超过持仓时（交易日期倒填或数据不完整），只按匹配到的份额结转卖出金额
+                if amount - shares > SHARE_EPSILON {
+                    warn!(
+                        "Sell transaction {} of asset {} exceeds holdings: {} > {}",
+                        transaction_id, self.position.asset_id, amount, shares
+                    );
+                }
+                let matched_ratio = if amount > 0.0 { (shares / amount).min(1.0) } else { 0.0 };
+                let proceeds = (total_cost - fee) * matched_ratio;
+                let realized = proceeds - cost_basis;
+
+                self.realized_trades.push(RealizedTrade {
+                    asset_id: self.position.asset_id,
+                    transaction_id,
+                    sell_date: date,
+                    shares,
+                    proceeds,
+                    cost_basis,
+                    realized_profit: realized,
+                    realized_profit_percent: if cost_basis > 0.0 {
+                        realized / cost_basis * 100.0
+                    } else {
+                        0.0
+                    },
+                    matches,
+                });
+                realized
+            }
+            TransactionType::TransferOut => {
+                self.consume(amount, date);
+                -fee
+            }
+            TransactionType::Dividend => {
+                self.position.dividend_income += total_cost;
+                total_cost - fee
+            }
+            TransactionType::Split => {
+                for lot in self.lots.iter_mut() {
+                    lot.original_shares *= amount;
+                    lot.remaining_shares *= amount;
+                }
+                -fee
+            }
+            TransactionType::Fee => {
+                // 费用交易的金额与附带费用各计一次，费用合计与已实现收益保持一致
+                self.position.fee_total += total_cost;
+                -(total_cost + fee)
+            }
+        };
+
+        self.position.shares = self.lots.iter().map(|lot| lot.remaining_shares).sum();
+        self.position.cost = self.lots.iter().map(|lot| lot.remaining_cost).sum();
+        if self.position.shares < SHARE_EPSILON {
+            self.lots.clear();
+            self.position.shares = 0.0;
+            self.position.cost = 0.0;
+        }
+
+        self.position.realized_profit += realized;
+        realized
+    }
+
+    /// 更新各批次截至指定日期的持有天数
+    pub fn mark_holding_days(&mut self, as_of: i64) {
+        for lot in self.lots.iter_mut() {
+            lot.holding_days = (as_of - lot.acquired_date).max(0) / SECONDS_PER_DAY;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionLedger {
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub asset_code: String,
+    pub currency: String, // 台账金额均为资产的报价币种
+    pub method: String,
+    pub shares: f64,
+    pub cost: f64,
+    pub average_cost: f64,
+    pub current_price: Option<f64>,
+    pub market_value: Option<f64>,
+    pub unrealized_profit: Option<f64>,
+    pub unrealized_profit_percent: Option<f64>,
+    pub realized_profit: f64,
+    pub dividend_income: f64,
+    pub fee_total: f64,
+    pub lots: Vec<Lot>,
+    pub realized_trades: Vec<RealizedTrade>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetPositionLedgerRequest {
+    pub user_id: i64,
+    pub asset_id: i64,
+    pub method: Option<String>, // FIFO / LIFO / AVERAGE，为空时使用用户设置
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetRealizedTradesRequest {
+    pub user_id: i64,
+    pub asset_id: Option<i64>,
+    pub start_date: Option<i64>,
+    pub end_date: Option<i64>,
+    pub method: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = SECONDS_PER_DAY;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    /// 买入 100 份（成本 100）、买入 100 份（成本 200）后卖出 150 份（金额 450）
+    fn buy_buy_sell(method: CostBasisMethod) -> LotLedger {
+        let mut ledger = LotLedger::new(1, method);
+        ledger.apply(1, 0, TransactionType::Buy, 100.0, 100.0, 0.0);
+        ledger.apply(2, DAY, TransactionType::Buy, 100.0, 200.0, 0.0);
+        ledger.apply(3, 10 * DAY, TransactionType::Sell, 150.0, 450.0, 0.0);
+        ledger
+    }
+
+    #[test]
+    fn fifo_consumes_earliest_lots() {
+        let ledger = buy_buy_sell(CostBasisMethod::Fifo);
+        let trade = &ledger.realized_trades[0];
+
+        assert_close(trade.cost_basis, 200.0);
+        assert_close(trade.realized_profit, 250.0);
+        assert_eq!(trade.matches.len(), 2);
+        assert_eq!(trade.matches[0].lot_transaction_id, 1);
+        assert_eq!(trade.matches[0].holding_days, 10);
+        assert_close(ledger.position.shares, 50.0);
+        assert_close(ledger.position.cost, 100.0);
+        assert_eq!(ledger.lots[0].transaction_id, 2);
+    }
+
+    #[test]
+    fn lifo_consumes_latest_lots() {
+        let ledger = buy_buy_sell(CostBasisMethod::Lifo);
+        let trade = &ledger.realized_trades[0];
+
+        assert_close(trade.cost_basis, 250.0);
+        assert_close(trade.realized_profit, 200.0);
+        assert_eq!(trade.matches[0].lot_transaction_id, 2);
+        assert_close(ledger.position.shares, 50.0);
+        assert_close(ledger.position.cost, 50.0);
+        assert_eq!(ledger.lots[0].transaction_id, 1);
+    }
+
+    #[test]
+    fn average_uses_average_cost() {
+        let ledger = buy_buy_sell(CostBasisMethod::WeightedAverage);
+        let trade = &ledger.realized_trades[0];
+
+        assert_close(trade.cost_basis, 225.0);
+        assert_close(trade.realized_profit, 225.0);
+        assert_close(ledger.position.shares, 50.0);
+        assert_close(ledger.position.cost, 75.0);
+        assert_close(ledger.position.average_cost(), 1.5);
+    }
+
+    #[test]
+    fn split_after_partial_sell_keeps_total_cost() {
+        let mut ledger = LotLedger::new(1, CostBasisMethod::Fifo);
+        ledger.apply(1, 0, TransactionType::Buy, 100.0, 100.0, 0.0);
+        ledger.apply(2, DAY, TransactionType::Sell, 40.0, 60.0, 0.0);
+        ledger.apply(3, 2 * DAY, TransactionType::Split, 2.0, 0.0, 0.0);
+
+        assert_close(ledger.position.shares, 120.0);
+        assert_close(ledger.position.cost, 60.0);
+        assert_close(ledger.lots[0].original_shares, 200.0);
+
+        let realized = ledger.apply(4, 3 * DAY, TransactionType::Sell, 120.0, 90.0, 0.0);
+        assert_close(realized, 30.0);
+        assert_close(ledger.position.realized_profit, 50.0);
+        assert_close(ledger.position.shares, 0.0);
+        assert!(ledger.lots.is_empty());
+    }
+
+    #[test]
+    fn reinvest_adds_shares_at_fee_cost() {
+        let mut ledger = LotLedger::new(1, CostBasisMethod::WeightedAverage);
+        ledger.apply(1, 0, TransactionType::Buy, 100.0, 100.0, 1.0);
+        ledger.apply(2, DAY, TransactionType::Reinvest, 5.0, 5.5, 0.5);
+
+        assert_close(ledger.position.shares, 105.0);
+        assert_close(ledger.position.cost, 101.5);
+        assert_close(ledger.position.fee_total, 1.5);
+        assert_close(ledger.position.realized_profit, 0.0);
+    }
+
+    #[test]
+    fn fee_transaction_counts_amount_and_fee_once() {
+        let mut ledger = LotLedger::new(1, CostBasisMethod::WeightedAverage);
+        ledger.apply(1, 0, TransactionType::Buy, 100.0, 100.0, 0.0);
+        let realized = ledger.apply(2, DAY, TransactionType::Fee, 3.0, 3.0, 1.0);
+
+        assert_close(realized, -4.0);
+        assert_close(ledger.position.fee_total, 4.0);
+        assert_close(ledger.position.realized_profit, -4.0);
+        assert_close(ledger.position.cost, 100.0);
+    }
+
+    #[test]
+    fn oversell_realizes_only_matched_shares() {
+        let mut ledger = LotLedger::new(1, CostBasisMethod::Fifo);
+        ledger.apply(1, 0, TransactionType::Buy, 100.0, 100.0, 0.0);
+        let realized = ledger.apply(2, DAY, TransactionType::Sell, 150.0, 300.0, 3.0);
+        let trade = &ledger.realized_trades[0];
+
+        assert_close(trade.shares, 100.0);
+        assert_close(trade.proceeds, 198.0);
+        assert_close(realized, 98.0);
+        assert_close(ledger.position.shares, 0.0);
+    }
+}
@@ -0,0 +1,83 @@
+/// 组合净值快照相关结构体。
+///
+/// 字段说明：
+/// - `PortfolioSnapshot`: 每日收盘后的持仓快照，按资产、分组、资产类型和整个组合分别记录
+///   - `snapshot_date`: 快照日期（北京时间当天 0 点的时间戳）
+///   - `market_value`: 当日收盘市值
+///   - `cost`: 持仓成本
+///   - `realized_profit`: 截至当日的累计已实现收益
+///   - `net_contribution`: 截至当日的累计净投入
+///   - `daily_contribution`: 当日净投入
+///   - `unit_value`: 单位净值（首次建仓为 1，按剔除资金进出后的每日收益率累乘），用于回撤和区间收益率
+/// - `DrawdownPeriod`: 一次回撤（从高点回落到恢复高点），`recovery_date` 为空表示尚未恢复。
+/// - `DrawdownReport`: 回撤统计，回撤幅度为正数百分比。
+/// - `PeriodReturn`: 月度 / 年度收益率。
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    pub snapshot_date: i64,
+    pub scope: String,
+    pub scope_id: Option<i64>,
+    pub market_value: f64,
+    pub cost: f64,
+    pub realized_profit: f64,
+    pub net_contribution: f64,
+    pub daily_contribution: f64,
+    pub unit_value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawdownPeriod {
+    pub peak_date: i64,
+    pub peak_unit_value: f64,
+    pub trough_date: i64,
+    pub trough_unit_value: f64,
+    pub recovery_date: Option<i64>,
+    pub drawdown_percent: f64,
+    pub duration_days: i64, // 从高点到恢复（未恢复时到最新快照）的天数
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawdownReport {
+    pub scope: String,
+    pub scope_id: Option<i64>,
+    pub max_drawdown_percent: f64,
+    pub current_drawdown_percent: f64,
+    pub periods: Vec<DrawdownPeriod>, // 按回撤幅度从大到小排序
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodReturn {
+    pub period: String, // 月度为 "2024-01"，年度为 "2024"
+    pub start_date: i64,
+    pub end_date: i64,
+    pub start_value: f64,
+    pub end_value: f64,
+    pub net_contribution: f64,
+    pub profit: f64,
+    pub return_percent: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSnapshotsRequest {
+    pub user_id: i64,
+    pub scope: String, // ASSET / GROUP / ASSET_TYPE / PORTFOLIO
+    pub scope_id: Option<i64>,
+    pub start_date: Option<i64>,
+    pub end_date: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetPeriodReturnsRequest {
+    pub user_id: i64,
+    pub scope: String,
+    pub scope_id: Option<i64>,
+    pub period: String, // MONTH / YEAR
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackfillSnapshotsRequest {
+    pub user_id: i64,
+    pub start_date: Option<i64>, // 为空时从第一笔交易开始重建
+}
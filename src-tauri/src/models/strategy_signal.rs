@@ -0,0 +1,49 @@
+/// 策略信号相关结构体。
+///
+/// 字段说明：
+/// - `StrategySignal`: 策略应用在最新行情上生成的买卖信号，只写入交易提醒，不会自动下单。
+///   - `side`: BUY / SELL，与交易提醒的 `alert_type` 一致。
+///   - `indicators`: 触发信号的指标值（如 `fast_ma`、`slow_ma`、`rsi`），同时写入提醒内容。
+///   - `alert_id`: 生成的交易提醒。
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 信号方向
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SignalSide {
+    Buy,
+    Sell,
+}
+
+impl SignalSide {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "BUY" => Some(SignalSide::Buy),
+            "SELL" => Some(SignalSide::Sell),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            SignalSide::Buy => "BUY",
+            SignalSide::Sell => "SELL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategySignal {
+    pub application_id: i64,
+    pub user_id: i64,
+    pub strategy_id: i64,
+    pub strategy_name: String,
+    pub strategy_type: String,
+    pub asset_id: i64,
+    pub asset_name: String,
+    pub side: String,
+    pub price: f64,
+    pub indicators: BTreeMap<String, f64>,
+    pub message: String,
+    pub alert_id: i64,
+}
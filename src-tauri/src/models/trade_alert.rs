@@ -6,8 +6,12 @@
 /// - `asset_id`: 资产ID，关联的资产标识。
 /// - `asset_name`: 资产名称。
 /// - `asset_code`: 资产代码。
-/// - `strategy_id`: 策略ID，可选，关联的策略标识。
+/// - `strategy_id`: 策略ID，可选，关联的投资策略（`investment_strategies`）标识。
 /// - `strategy_name`: 策略名称，可选，关联的策略名称。
+/// - `signal_strategy_id`: 信号策略ID，可选，由策略信号自动生成时为策略应用对应的策略（`strategies`）。
+/// - `signal_strategy_name`: 信号策略名称，可选。
+/// - `rule_id`: 提醒规则ID，可选，由提醒规则自动生成时为触发的规则。
+/// - `rule_name`: 提醒规则名称，可选。
 /// - `alert_type`: 提醒类型，例如“买入”、“卖出”等。
 /// - `message`: 提醒内容。
 /// - `is_read`: 是否已读，布尔值。
@@ -25,6 +29,10 @@ pub struct TradeAlert {
     pub asset_code: String,
     pub strategy_id: Option<i64>,
     pub strategy_name: Option<String>,
+    pub signal_strategy_id: Option<i64>,
+    pub signal_strategy_name: Option<String>,
+    pub rule_id: Option<i64>,
+    pub rule_name: Option<String>,
     pub alert_type: String,
     pub message: String,
     pub is_read: bool,
@@ -6,15 +6,124 @@
 /// - `asset_id`: 资产ID
 /// - `asset_name`: 资产名称
 /// - `asset_code`: 资产代码
-/// - `transaction_type`: 交易类型（如买入、卖出等）
-/// - `amount`: 交易数量
+/// - `transaction_type`: 交易类型（见 `TransactionType`）
+/// - `amount`: 交易数量（含义随交易类型变化，见 `TransactionType`）
 /// - `price`: 交易价格
-/// - `total_cost`: 交易总金额
+/// - `total_cost`: 交易总金额（不含费用）
+/// - `fee`: 交易费用（申购费/赎回费/佣金/印花税等，买入计入成本，卖出冲减收益）
 /// - `transaction_date`: 交易日期（时间戳）
 /// - `notes`: 备注（可选）
+/// - `status`: 交易状态（见 `TransactionStatus`）
+/// - `plan_id`: 定投计划ID（定投生成的交易）
+/// - `settle_date`: 成交日期（定投按该交易日的收盘价 / 净值成交，北京时间当天 0 点的时间戳）
+/// - `confirm_date`: 预计确认日期（如基金 T+1 确认），到期后取得成交日净值时确认
+/// - `failure_reason`: 失败原因（状态为 FAILED 时）
 /// - `created_at`: 创建时间（时间戳）
 use serde::{Deserialize, Serialize};
 
+/// 交易类型，各类型下 `amount`、`price` 的含义及对持仓的影响：
+///
+/// | 类型 | amount | price | 总金额 | 持仓影响 |
+/// |------|--------|-------|--------|----------|
+/// | BUY 买入 | 份额 | 成交价 | 份额×价格 | 份额增加，成本增加 |
+/// | SELL 卖出 | 份额 | 成交价 | 份额×价格 | 份额减少，按平均成本结转已实现收益 |
+/// | DIVIDEND 现金分红 | 参与分红份额 | 每份分红 | 份额×每份分红 | 份额不变，分红计入已实现收益 |
+/// | REINVEST 红利再投资 | 再投资份额 | 再投资净值 | 份额×净值 | 份额增加，投入成本不变 |
+/// | SPLIT 份额折算 | 折算比例（新份额/旧份额） | 忽略 | 0 | 份额按比例调整，总成本不变 |
+/// | TRANSFER_IN 转入 | 份额 | 成本价 | 份额×成本价 | 份额和成本增加 |
+/// | TRANSFER_OUT 转出 | 份额 | 参考价 | 份额×参考价 | 按平均成本转出份额和成本，不产生收益 |
+/// | FEE 费用 | 费用金额 | 忽略 | 费用金额 | 份额不变，费用冲减已实现收益 |
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TransactionType {
+    Buy,
+    Sell,
+    Dividend,
+    Reinvest,
+    Split,
+    TransferIn,
+    TransferOut,
+    Fee,
+}
+
+impl TransactionType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "BUY" => Some(TransactionType::Buy),
+            "SELL" => Some(TransactionType::Sell),
+            "DIVIDEND" => Some(TransactionType::Dividend),
+            "REINVEST" => Some(TransactionType::Reinvest),
+            "SPLIT" => Some(TransactionType::Split),
+            "TRANSFER_IN" => Some(TransactionType::TransferIn),
+            "TRANSFER_OUT" => Some(TransactionType::TransferOut),
+            "FEE" => Some(TransactionType::Fee),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            TransactionType::Buy => "BUY",
+            TransactionType::Sell => "SELL",
+            TransactionType::Dividend => "DIVIDEND",
+            TransactionType::Reinvest => "REINVEST",
+            TransactionType::Split => "SPLIT",
+            TransactionType::TransferIn => "TRANSFER_IN",
+            TransactionType::TransferOut => "TRANSFER_OUT",
+            TransactionType::Fee => "FEE",
+        }
+    }
+
+    /// 是否减少持仓份额（需要校验持仓是否足够）
+    pub fn reduces_holdings(&self) -> bool {
+        matches!(self, TransactionType::Sell | TransactionType::TransferOut)
+    }
+
+    /// 成交价是否为市场价格（可用于更新资产现价和历史价格）
+    pub fn has_market_price(&self) -> bool {
+        matches!(
+            self,
+            TransactionType::Buy | TransactionType::Sell | TransactionType::Reinvest
+        )
+    }
+
+    /// 计算交易总金额
+    pub fn total_cost(&self, amount: f64, price: f64) -> f64 {
+        match self {
+            TransactionType::Split => 0.0,
+            TransactionType::Fee => amount,
+            _ => amount * price,
+        }
+    }
+}
+
+/// 交易状态：待确认的交易（如再平衡建议生成的交易、等待净值确认的定投）不参与持仓和收益计算，
+/// 确认后生效；失败的交易（如超时未取得成交日净值的定投）始终不参与计算
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TransactionStatus {
+    Pending,
+    Confirmed,
+    Failed,
+}
+
+impl TransactionStatus {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "PENDING" => Some(TransactionStatus::Pending),
+            "CONFIRMED" => Some(TransactionStatus::Confirmed),
+            "FAILED" => Some(TransactionStatus::Failed),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Pending => "PENDING",
+            TransactionStatus::Confirmed => "CONFIRMED",
+            TransactionStatus::Failed => "FAILED",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Transaction {
     pub id: i64,
@@ -26,8 +135,14 @@ pub struct Transaction {
     pub amount: f64,
     pub price: f64,
     pub total_cost: f64,
+    pub fee: f64,
     pub transaction_date: i64,
     pub notes: Option<String>,
+    pub status: String,
+    pub plan_id: Option<i64>,
+    pub settle_date: Option<i64>,
+    pub confirm_date: Option<i64>,
+    pub failure_reason: Option<String>,
     pub created_at: i64,
 }
 /// 创建交易请求结构体，用于新增一条交易记录。
@@ -38,6 +153,7 @@ pub struct Transaction {
 /// - `transaction_type`: 交易类型
 /// - `amount`: 交易数量
 /// - `price`: 交易价格
+/// - `fee`: 交易费用（可选，为空时按费率方案计算）
 /// - `transaction_date`: 交易日期（时间戳）
 /// - `notes`: 备注（可选）
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,6 +163,7 @@ pub struct CreateTransactionRequest {
     pub transaction_type: String,
     pub amount: f64,
     pub price: f64,
+    pub fee: Option<f64>,
     pub transaction_date: i64,
     pub notes: Option<String>,
 }
@@ -58,6 +175,7 @@ pub struct CreateTransactionRequest {
 /// - `transaction_type`: 交易类型
 /// - `amount`: 交易数量
 /// - `price`: 交易价格
+/// - `fee`: 交易费用（可选，为空时按费率方案重新计算）
 /// - `transaction_date`: 交易日期（时间戳）
 /// - `notes`: 备注（可选）
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,6 +185,7 @@ pub struct UpdateTransactionRequest {
     pub transaction_type: String,
     pub amount: f64,
     pub price: f64,
+    pub fee: Option<f64>,
     pub transaction_date: i64,
     pub notes: Option<String>,
 }
@@ -87,10 +206,22 @@ pub struct DeleteTransactionRequest {
 /// - `asset_id`: 资产ID（可选）
 /// - `start_date`: 查询起始日期（时间戳，可选）
 /// - `end_date`: 查询结束日期（时间戳，可选）
+/// - `status`: 交易状态（PENDING / CONFIRMED / FAILED，可选）
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetUserTransactionsRequest {
     pub user_id: i64,
     pub asset_id: Option<i64>,
     pub start_date: Option<i64>,
     pub end_date: Option<i64>,
+    pub status: Option<String>,
+}
+/// 确认待确认交易请求结构体。
+/// 
+/// 字段说明：
+/// - `id`: 交易记录唯一标识
+/// - `user_id`: 用户ID
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmTransactionRequest {
+    pub id: i64,
+    pub user_id: i64,
 }
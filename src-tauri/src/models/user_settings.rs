@@ -0,0 +1,42 @@
+/// 用户偏好设置结构体。
+///
+/// 字段说明：
+/// - `user_id`: 用户ID。
+/// - `cost_basis_method`: 成本计算方法（FIFO / LIFO / AVERAGE），影响持仓成本和已实现收益的计算。
+/// - `base_currency`: 基准币种，组合汇总、收益率和净值快照统一换算为该币种。
+/// - `updated_at`: 最后更新时间（时间戳，单位为秒）。
+use serde::{Deserialize, Serialize};
+
+use crate::models::CostBasisMethod;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserSettings {
+    pub user_id: i64,
+    pub cost_basis_method: String,
+    pub base_currency: String,
+    pub updated_at: i64,
+}
+
+impl UserSettings {
+    /// 默认设置
+    pub fn default_for(user_id: i64) -> Self {
+        Self {
+            user_id,
+            cost_basis_method: CostBasisMethod::WeightedAverage.to_str().to_string(),
+            base_currency: "CNY".to_string(),
+            updated_at: 0,
+        }
+    }
+
+    /// 成本计算方法，无法识别时按移动加权平均处理
+    pub fn cost_basis(&self) -> CostBasisMethod {
+        CostBasisMethod::from_str(&self.cost_basis_method).unwrap_or(CostBasisMethod::WeightedAverage)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateUserSettingsRequest {
+    pub user_id: i64,
+    pub cost_basis_method: Option<String>,
+    pub base_currency: Option<String>,
+}
@@ -0,0 +1,558 @@
+/**
+ * 提醒规则模块
+ *
+ * 用户为资产设置提醒条件（见 `AlertRuleType`），行情同步和手动更新价格后评估相关资产的规则：
+ * - 指标按日线计算：每日取最后一个收盘价，当天以现价为最新值（与智能定投的口径一致）。
+ * - 价格穿越以上一次评估时的价格为前值，首次评估时以前一交易日收盘价为前值。
+ * - 均线交叉比较最新一期和前一期的短期、长期均线。
+ *
+ * 满足条件时生成交易提醒（`trade_alerts.rule_id` 关联规则），触发后在冷却时间内不重复提醒；
+ * 持续满足的条件（如 RSI 低于阈值）在冷却时间过后会再次提醒。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{AlertDirection, AlertRule, AlertRuleTrigger, AlertRuleType, SaveAlertRuleRequest};
+use crate::services::indicator::{rsi, sma};
+use crate::services::performance::{load_price_series, price_on};
+use crate::services::smart_dca::daily_closes;
+use chrono::Utc;
+use log::{error, info};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 默认冷却时间（分钟）
+const DEFAULT_COOLDOWN_MINUTES: i64 = 1440;
+
+/// 默认 RSI 周期
+const DEFAULT_RSI_PERIOD: i64 = 14;
+
+/// 待评估的规则
+struct ActiveRule {
+    id: i64,
+    user_id: i64,
+    asset_id: i64,
+    asset_name: String,
+    rule_type: AlertRuleType,
+    direction: AlertDirection,
+    threshold: f64,
+    period: i64,
+    long_period: i64,
+    cooldown_minutes: i64,
+    last_value: Option<f64>,
+    last_triggered: Option<i64>,
+}
+
+/// 规则的评估结果：指标值、是否满足条件和提醒内容
+struct Evaluation {
+    value: f64,
+    matched: bool,
+    message: String,
+}
+
+const RULE_COLUMNS: &str = "r.id, r.user_id, r.asset_id, a.name, r.name, r.rule_type, r.direction, r.threshold,
+    r.period, r.long_period, r.cooldown_minutes, r.is_active, r.last_value, r.last_evaluated,
+    r.last_triggered, r.created_at, r.updated_at";
+
+fn map_alert_rule(row: &rusqlite::Row) -> Result<AlertRule, rusqlite::Error> {
+    Ok(AlertRule {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        asset_id: row.get(2)?,
+        asset_name: row.get(3)?,
+        name: row.get(4)?,
+        rule_type: row.get(5)?,
+        direction: row.get(6)?,
+        threshold: row.get(7)?,
+        period: row.get(8)?,
+        long_period: row.get(9)?,
+        cooldown_minutes: row.get(10)?,
+        is_active: row.get(11)?,
+        last_value: row.get(12)?,
+        last_evaluated: row.get(13)?,
+        last_triggered: row.get(14)?,
+        created_at: row.get(15)?,
+        updated_at: row.get(16)?,
+    })
+}
+
+fn load_alert_rule(conn: &Connection, id: i64) -> Result<AlertRule, rusqlite::Error> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM alert_rules r JOIN assets a ON r.asset_id = a.id WHERE r.id = ?1",
+            RULE_COLUMNS
+        ),
+        params![id],
+        map_alert_rule,
+    )
+}
+
+/// 校验规则参数，返回规范化后的方向、周期和默认名称
+fn validate_rule(
+    rule_type: AlertRuleType,
+    request: &SaveAlertRuleRequest,
+) -> Result<(AlertDirection, Option<i64>, String), AuthError> {
+    let invalid = |message: &str| Err(AuthError::InvalidCredentials(message.to_string()));
+
+    let direction = match (rule_type, request.direction.as_deref()) {
+        (AlertRuleType::NavDrop, _) => AlertDirection::Below,
+        (_, None) => AlertDirection::Above,
+        (_, Some(direction)) => AlertDirection::from_str(direction)
+            .ok_or_else(|| AuthError::InvalidCredentials("无效的提醒方向，必须为 ABOVE 或 BELOW".to_string()))?,
+    };
+    let above = direction == AlertDirection::Above;
+
+    let threshold = request.threshold.filter(|threshold| *threshold > 0.0);
+    if rule_type != AlertRuleType::MaCross && threshold.is_none() {
+        return invalid("阈值必须大于0");
+    }
+    if request.period.is_some_and(|period| period <= 0) || request.long_period.is_some_and(|period| period <= 0) {
+        return invalid("周期必须大于0");
+    }
+    if request.cooldown_minutes.is_some_and(|minutes| minutes < 0) {
+        return invalid("冷却时间不能小于0");
+    }
+
+    let threshold = threshold.unwrap_or_default();
+    let (period, name) = match rule_type {
+        AlertRuleType::PriceCross => (None, format!("价格{}穿 {}", if above { "上" } else { "下" }, threshold)),
+        AlertRuleType::PriceChange => {
+            let days = match request.period {
+                Some(days) => days,
+                None => return invalid("区间涨跌幅需要指定天数"),
+            };
+            (Some(days), format!("{}日{} {}%", days, if above { "涨幅" } else { "跌幅" }, threshold))
+        }
+        AlertRuleType::Rsi => {
+            if threshold >= 100.0 {
+                return invalid("RSI 阈值必须小于100");
+            }
+            let period = request.period.unwrap_or(DEFAULT_RSI_PERIOD);
+            (Some(period), format!("RSI({}) {} {}", period, if above { "高于" } else { "低于" }, threshold))
+        }
+        AlertRuleType::MaCross => {
+            let (short, long) = match (request.period, request.long_period) {
+                (Some(short), Some(long)) if short < long => (short, long),
+                _ => return invalid("均线交叉需要指定短期和长期均线天数，且短期小于长期"),
+            };
+            (Some(short), format!("MA{} {} MA{}", short, if above { "上穿" } else { "下穿" }, long))
+        }
+        AlertRuleType::NavDrop => (None, format!("净值单日跌幅 {}%", threshold)),
+    };
+
+    Ok((direction, period, name))
+}
+
+/**
+ * @dev 创建或更新提醒规则
+ * 重新保存会清除评估状态和冷却时间
+ */
+pub fn save_alert_rule(request: &SaveAlertRuleRequest) -> Result<AlertRule, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let user_id = request.user_id;
+
+    let rule_type = AlertRuleType::from_str(&request.rule_type).ok_or_else(|| {
+        AuthError::InvalidCredentials(
+            "无效的规则类型，支持的类型：PRICE_CROSS, PRICE_CHANGE, RSI, MA_CROSS, NAV_DROP".to_string(),
+        )
+    })?;
+    let (direction, period, default_name) = validate_rule(rule_type, request)?;
+    let long_period = match rule_type {
+        AlertRuleType::MaCross => request.long_period,
+        _ => None,
+    };
+    let threshold = match rule_type {
+        AlertRuleType::MaCross => None,
+        _ => request.threshold,
+    };
+    let name = request
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .unwrap_or(default_name);
+    let cooldown_minutes = request.cooldown_minutes.unwrap_or(DEFAULT_COOLDOWN_MINUTES);
+    let is_active = request.is_active.unwrap_or(true);
+
+    let asset_exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM assets WHERE id = ?1 AND user_id = ?2",
+            params![request.asset_id, user_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if !asset_exists {
+        return Err(AuthError::InvalidCredentials("资产不存在或无权限".to_string()));
+    }
+
+    let id = match request.id {
+        Some(id) => {
+            let rule_exists: bool = conn
+                .query_row(
+                    "SELECT 1 FROM alert_rules WHERE id = ?1 AND user_id = ?2",
+                    params![id, user_id],
+                    |_| Ok(true),
+                )
+                .unwrap_or(false);
+
+            if !rule_exists {
+                return Err(AuthError::InvalidCredentials("提醒规则不存在或无权限".to_string()));
+            }
+
+            conn.execute(
+                "UPDATE alert_rules
+                 SET asset_id = ?1, name = ?2, rule_type = ?3, direction = ?4, threshold = ?5, period = ?6,
+                     long_period = ?7, cooldown_minutes = ?8, is_active = ?9, last_value = NULL,
+                     last_evaluated = NULL, last_triggered = NULL, updated_at = ?10
+                 WHERE id = ?11",
+                params![
+                    request.asset_id,
+                    name,
+                    rule_type.to_str(),
+                    direction.to_str(),
+                    threshold,
+                    period,
+                    long_period,
+                    cooldown_minutes,
+                    is_active,
+                    now,
+                    id
+                ],
+            )?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO alert_rules (
+                    user_id, asset_id, name, rule_type, direction, threshold, period, long_period,
+                    cooldown_minutes, is_active, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    user_id,
+                    request.asset_id,
+                    name,
+                    rule_type.to_str(),
+                    direction.to_str(),
+                    threshold,
+                    period,
+                    long_period,
+                    cooldown_minutes,
+                    is_active,
+                    now,
+                    now
+                ],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    info!("Alert rule saved: {} ({}) for user: {}", id, rule_type.to_str(), user_id);
+    Ok(load_alert_rule(&conn, id)?)
+}
+
+/**
+ * @dev 删除提醒规则，已生成的交易提醒保留
+ */
+pub fn delete_alert_rule(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "UPDATE trade_alerts SET rule_id = NULL WHERE rule_id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    let deleted = tx.execute(
+        "DELETE FROM alert_rules WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    if deleted == 0 {
+        return Err(AuthError::InvalidCredentials("提醒规则不存在或无权限".to_string()));
+    }
+    tx.commit()?;
+
+    info!("Alert rule deleted: {} for user: {}", id, user_id);
+    Ok(())
+}
+
+/**
+ * @dev 获取用户的提醒规则，可按资产筛选
+ */
+pub fn get_alert_rules(user_id: i64, asset_id: Option<i64>) -> Result<Vec<AlertRule>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM alert_rules r
+         JOIN assets a ON r.asset_id = a.id
+         WHERE r.user_id = ?1 AND (?2 IS NULL OR r.asset_id = ?2)
+         ORDER BY a.name, r.id",
+        RULE_COLUMNS
+    ))?;
+
+    let rules = stmt
+        .query_map(params![user_id, asset_id], map_alert_rule)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch alert rules: {}", e);
+            AuthError::DatabaseError(format!("获取提醒规则失败: {}", e))
+        })?;
+
+    Ok(rules)
+}
+
+/// 按日线收盘价计算规则的指标，数据不足时返回 None
+fn evaluate_rule(rule: &ActiveRule, series: &[(i64, f64)], closes: &[f64], price: f64, now: i64) -> Option<Evaluation> {
+    let above = rule.direction == AlertDirection::Above;
+    let previous_close = closes.len().checked_sub(2).map(|index| closes[index]);
+
+    match rule.rule_type {
+        AlertRuleType::PriceCross => {
+            let previous = rule.last_value.or(previous_close)?;
+            let matched = if above {
+                previous < rule.threshold && price >= rule.threshold
+            } else {
+                previous > rule.threshold && price <= rule.threshold
+            };
+            Some(Evaluation {
+                value: price,
+                matched,
+                message: format!(
+                    "{} 价格 {:.4} {}穿 {:.4}",
+                    rule.asset_name,
+                    price,
+                    if above { "上" } else { "下" },
+                    rule.threshold
+                ),
+            })
+        }
+        AlertRuleType::PriceChange => {
+            let base = price_on(series, now - rule.period * SECONDS_PER_DAY).filter(|base| *base > 0.0)?;
+            let change = (price / base - 1.0) * 100.0;
+            let matched = if above { change >= rule.threshold } else { change <= -rule.threshold };
+            Some(Evaluation {
+                value: change,
+                matched,
+                message: format!("{} {} 日涨跌幅 {:.2}%，价格 {:.4}", rule.asset_name, rule.period, change, price),
+            })
+        }
+        AlertRuleType::Rsi => {
+            let value = rsi(closes, rule.period as usize)?;
+            let matched = if above { value >= rule.threshold } else { value <= rule.threshold };
+            Some(Evaluation {
+                value,
+                matched,
+                message: format!(
+                    "{} RSI({}) 为 {:.2}，{}阈值 {:.2}",
+                    rule.asset_name,
+                    rule.period,
+                    value,
+                    if above { "高于" } else { "低于" },
+                    rule.threshold
+                ),
+            })
+        }
+        AlertRuleType::MaCross => {
+            let (short, long) = (rule.period as usize, rule.long_period as usize);
+            let previous = &closes[..closes.len().saturating_sub(1)];
+            let spread = sma(closes, short)? - sma(closes, long)?;
+            let previous_spread = sma(previous, short)? - sma(previous, long)?;
+            let matched = if above {
+                previous_spread <= 0.0 && spread > 0.0
+            } else {
+                previous_spread >= 0.0 && spread < 0.0
+            };
+            Some(Evaluation {
+                value: spread,
+                matched,
+                message: format!(
+                    "{} MA{} {} MA{}（{}），价格 {:.4}",
+                    rule.asset_name,
+                    short,
+                    if above { "上穿" } else { "下穿" },
+                    long,
+                    if above { "金叉" } else { "死叉" },
+                    price
+                ),
+            })
+        }
+        AlertRuleType::NavDrop => {
+            let previous = previous_close.filter(|previous| *previous > 0.0)?;
+            let change = (price / previous - 1.0) * 100.0;
+            Some(Evaluation {
+                value: change,
+                matched: change <= -rule.threshold,
+                message: format!(
+                    "{} 净值 {:.4} 较前一交易日 {:.4} 下跌 {:.2}%",
+                    rule.asset_name, price, previous, -change
+                ),
+            })
+        }
+    }
+}
+
+/// 记录评估结果，满足条件且不在冷却时间内时生成交易提醒
+fn record_evaluation(
+    conn: &mut Connection,
+    rule: &ActiveRule,
+    evaluation: &Evaluation,
+    price: f64,
+    now: i64,
+) -> Result<Option<AlertRuleTrigger>, AuthError> {
+    let cooling = rule
+        .last_triggered
+        .is_some_and(|triggered| now - triggered < rule.cooldown_minutes * 60);
+
+    if !evaluation.matched || cooling {
+        conn.execute(
+            "UPDATE alert_rules SET last_value = ?1, last_evaluated = ?2 WHERE id = ?3",
+            params![evaluation.value, now, rule.id],
+        )?;
+        return Ok(None);
+    }
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO trade_alerts (user_id, asset_id, strategy_id, rule_id, alert_type, message, is_read, created_at)
+         VALUES (?1, ?2, NULL, ?3, ?4, ?5, 0, ?6)",
+        params![
+            rule.user_id,
+            rule.asset_id,
+            rule.id,
+            rule.rule_type.to_str(),
+            evaluation.message,
+            now
+        ],
+    )?;
+    let alert_id = tx.last_insert_rowid();
+    tx.execute(
+        "UPDATE alert_rules SET last_value = ?1, last_evaluated = ?2, last_triggered = ?2 WHERE id = ?3",
+        params![evaluation.value, now, rule.id],
+    )?;
+    tx.commit()?;
+
+    Ok(Some(AlertRuleTrigger {
+        rule_id: rule.id,
+        user_id: rule.user_id,
+        asset_id: rule.asset_id,
+        rule_type: rule.rule_type.to_str().to_string(),
+        value: evaluation.value,
+        price,
+        message: evaluation.message.clone(),
+        alert_id,
+    }))
+}
+
+/**
+ * @dev 评估启用的提醒规则
+ * @param user_id 为 None 时评估所有用户的规则
+ * @param asset_ids 为 None 时评估所有资产的规则（价格更新后只评估更新的资产）
+ * @return 本次触发的规则
+ */
+pub fn evaluate_alert_rules(
+    user_id: Option<i64>,
+    asset_ids: Option<&[i64]>,
+) -> Result<Vec<AlertRuleTrigger>, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let rules = {
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.user_id, r.asset_id, a.name, r.rule_type, r.direction, r.threshold, r.period,
+                    r.long_period, r.cooldown_minutes, r.last_value, r.last_triggered
+             FROM alert_rules r
+             JOIN assets a ON r.asset_id = a.id
+             WHERE r.is_active = 1 AND (?1 IS NULL OR r.user_id = ?1)
+             ORDER BY r.asset_id, r.id",
+        )?;
+
+        let result = stmt
+            .query_map(params![user_id], |row| {
+                let rule_type: String = row.get(4)?;
+                let direction: String = row.get(5)?;
+                let kind = match AlertRuleType::from_str(&rule_type) {
+                    Some(rule_type) => rule_type,
+                    None => return Ok(None),
+                };
+                Ok(Some(ActiveRule {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    asset_id: row.get(2)?,
+                    asset_name: row.get(3)?,
+                    rule_type: kind,
+                    direction: AlertDirection::from_str(&direction).unwrap_or(AlertDirection::Above),
+                    threshold: row.get::<_, Option<f64>>(6)?.unwrap_or_default(),
+                    period: row.get::<_, Option<i64>>(7)?.unwrap_or(DEFAULT_RSI_PERIOD),
+                    long_period: row.get::<_, Option<i64>>(8)?.unwrap_or_default(),
+                    cooldown_minutes: row.get(9)?,
+                    last_value: row.get(10)?,
+                    last_triggered: row.get(11)?,
+                }))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                error!("Failed to fetch alert rules: {}", e);
+                AuthError::DatabaseError(format!("获取提醒规则失败: {}", e))
+            });
+
+        result?
+    };
+
+    // 同一资产的规则共用价格序列
+    let mut prices: HashMap<i64, Option<(Vec<(i64, f64)>, Vec<f64>, f64)>> = HashMap::new();
+    let mut triggers = Vec::new();
+
+    for rule in rules.into_iter().flatten() {
+        let asset_id = rule.asset_id;
+        if asset_ids.is_some_and(|ids| !ids.contains(&asset_id)) {
+            continue;
+        }
+
+        if !prices.contains_key(&asset_id) {
+            let series = load_price_series(&conn, asset_id)?;
+            let loaded = series.last().map(|(_, price)| *price).filter(|price| *price > 0.0).map(|price| {
+                let closes = daily_closes(&series, now, price);
+                (series, closes, price)
+            });
+            prices.insert(asset_id, loaded);
+        }
+        let (series, closes, price) = match prices.get(&asset_id) {
+            Some(Some(loaded)) => loaded,
+            _ => continue,
+        };
+
+        let evaluation = match evaluate_rule(&rule, series, closes, *price, now) {
+            Some(evaluation) => evaluation,
+            None => continue,
+        };
+        match record_evaluation(&mut conn, &rule, &evaluation, *price, now) {
+            Ok(Some(trigger)) => {
+                info!("Alert rule {} triggered: {}", rule.id, trigger.message);
+                triggers.push(trigger);
+            }
+            Ok(None) => {}
+            Err(e) => error!("Failed to record alert rule {}: {}", rule.id, e),
+        }
+    }
+
+    Ok(triggers)
+}
+
+/**
+ * @dev 价格更新后评估相关资产的规则，失败只记录日志，不影响价格更新
+ */
+pub fn evaluate_after_price_update(asset_ids: &[i64]) {
+    if asset_ids.is_empty() {
+        return;
+    }
+
+    match evaluate_alert_rules(None, Some(asset_ids)) {
+        Ok(triggers) if !triggers.is_empty() => {
+            info!("{} alert rules triggered after price update", triggers.len())
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to evaluate alert rules after price update: {}", e),
+    }
+}
@@ -0,0 +1,868 @@
+/**
+ * 目标配置与再平衡模块
+ *
+ * 用户可按资产、分组或资产类型设置目标权重和容忍区间，同一范围内的目标权重合计不超过 100%。
+ * 当前持仓与 `get_portfolio_summary` 口径一致：持仓份额 × 现价，按当日汇率换算为基准币种。
+ *
+ * 主要函数说明：
+ * - `save_target_allocation` / `get_target_allocations` / `delete_target_allocation`: 目标配置的增删改查。
+ * - `get_drift_report(user_id, scope)`: 各目标配置的当前权重、偏离幅度以及是否超出容忍区间。
+ * - `plan_rebalance(...)`: 生成再平衡建议交易，可选写入待确认交易（确认后才影响持仓）。
+ *
+ * 再平衡规则：
+ * - 只调整超出容忍区间的目标，调整到目标权重，区间内的目标不产生交易，以减少交易次数。
+ * - 先卖后买：卖出所得（扣除费用）加上追加资金为可用资金，不足时按比例缩减买入，
+ *   有剩余时按缺口比例补足区间内低于目标权重的配置。
+ * - 分组和资产类型的调整金额按持仓市值比例分配到其中的资产，没有持仓时平均分配到有价格的资产。
+ * - 买入金额包含费用（按费率方案拆分），份额按最小交易单位向下取整；调整到清仓时卖出全部份额。
+ * - 最小交易单位依次取请求中指定的、资产设置的和资产类型默认的；港股每手股数因股票而异，
+ *   资产未设置时按 1 股取整并提示用户按实际每手股数调整。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    AllocationDrift, AllocationScope, CostBasisMethod, DriftReport, RebalancePlan, RebalanceTrade,
+    TargetAllocation, TransactionType,
+};
+use crate::services::fee::{calculate_fee, load_fee_schedule, split_buy_budget};
+use crate::services::fx::FxConverter;
+use crate::services::position::load_user_positions;
+use crate::services::transaction::insert_pending_transaction;
+use chrono::Utc;
+use log::{error, info, warn};
+use rusqlite::{params, Connection, Row};
+use std::collections::HashMap;
+
+const DEFAULT_TOLERANCE_PERCENT: f64 = 5.0;
+
+const WEIGHT_EPSILON: f64 = 1e-6;
+
+const REBALANCE_NOTE: &str = "再平衡建议";
+
+/// 资产类型默认的最小交易单位（港股每手股数因股票而异，没有默认值）
+fn default_lot_size(asset_type: &str) -> Option<f64> {
+    match asset_type.to_uppercase().as_str() {
+        "STOCK" => Some(100.0), // A股每手 100 股
+        "HK_STOCK" => None,
+        "US_STOCK" => Some(1.0),
+        "CRYPTO" => Some(0.000001),
+        _ => Some(0.01), // 基金、黄金按 0.01 份/克
+    }
+}
+
+/// 按最小交易单位向下取整
+fn floor_to_lot(quantity: f64, lot_size: f64) -> f64 {
+    ((quantity / lot_size) + 1e-9).floor() * lot_size
+}
+
+/// 参与配置计算的资产
+struct Holding {
+    asset_id: i64,
+    name: String,
+    code: String,
+    asset_type: String,
+    asset_type_id: i64,
+    group_id: Option<i64>,
+    currency: String,
+    lot_size: Option<f64>, // 资产设置的最小交易单位
+    price: Option<f64>, // 报价币种现价
+    rate: f64,          // 报价币种 -> 基准币种
+    converted: bool,    // 缺少汇率按 1:1 计算时为 false
+    shares: f64,
+    value: f64, // 基准币种市值
+}
+
+impl Holding {
+    fn scope_id(&self, scope: AllocationScope) -> Option<i64> {
+        match scope {
+            AllocationScope::Asset => Some(self.asset_id),
+            AllocationScope::Group => self.group_id,
+            AllocationScope::AssetType => Some(self.asset_type_id),
+        }
+    }
+
+    fn tradable_price(&self) -> Option<f64> {
+        self.price.filter(|price| *price > 0.0)
+    }
+}
+
+/// 加载用户的全部资产及当前持仓市值（基准币种）
+fn load_holdings(
+    conn: &Connection,
+    user_id: i64,
+    fx: &mut FxConverter,
+) -> Result<Vec<Holding>, rusqlite::Error> {
+    let positions = load_user_positions(conn, user_id, CostBasisMethod::WeightedAverage)?;
+    let now = Utc::now().timestamp();
+
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.name, a.code, t.name, a.asset_type_id, a.group_id, a.currency, a.lot_size, a.current_price
+         FROM assets a
+         JOIN asset_types t ON a.asset_type_id = t.id
+         WHERE a.user_id = ?1
+         ORDER BY a.id",
+    )?;
+
+    let mut holdings = stmt
+        .query_map(params![user_id], |row| {
+            Ok(Holding {
+                asset_id: row.get(0)?,
+                name: row.get(1)?,
+                code: row.get(2)?,
+                asset_type: row.get(3)?,
+                asset_type_id: row.get(4)?,
+                group_id: row.get(5)?,
+                currency: row.get(6)?,
+                lot_size: row.get(7)?,
+                price: row.get(8)?,
+                rate: 1.0,
+                converted: true,
+                shares: 0.0,
+                value: 0.0,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for holding in holdings.iter_mut() {
+        holding.shares = positions.get(&holding.asset_id).map_or(0.0, |p| p.shares);
+        holding.rate = fx.rate(&holding.currency, now)?;
+        holding.converted = !fx.is_missing(&holding.currency);
+        holding.value = holding.price.map_or(0.0, |price| price * holding.shares * holding.rate);
+    }
+
+    Ok(holdings)
+}
+
+fn parse_scope(scope: &str) -> Result<AllocationScope, AuthError> {
+    AllocationScope::from_str(scope).ok_or_else(|| {
+        AuthError::InvalidCredentials(format!(
+            "不支持的配置范围: {}，必须为 ASSET、GROUP 或 ASSET_TYPE",
+            scope
+        ))
+    })
+}
+
+/// 获取配置对象的名称，对象不存在或不属于该用户时返回 None
+fn scope_name(conn: &Connection, user_id: i64, scope: AllocationScope, scope_id: i64) -> Option<String> {
+    match scope {
+        AllocationScope::Asset => conn.query_row(
+            "SELECT name FROM assets WHERE id = ?1 AND user_id = ?2",
+            params![scope_id, user_id],
+            |row| row.get(0),
+        ),
+        AllocationScope::Group => conn.query_row(
+            "SELECT name FROM user_groups WHERE id = ?1 AND user_id = ?2",
+            params![scope_id, user_id],
+            |row| row.get(0),
+        ),
+        AllocationScope::AssetType => conn.query_row(
+            "SELECT name FROM asset_types WHERE id = ?1",
+            params![scope_id],
+            |row| row.get(0),
+        ),
+    }
+    .ok()
+}
+
+fn row_to_allocation(row: &Row) -> rusqlite::Result<TargetAllocation> {
+    Ok(TargetAllocation {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        scope: row.get(2)?,
+        scope_id: row.get(3)?,
+        scope_name: String::new(),
+        target_percent: row.get(4)?,
+        tolerance_percent: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// 加载用户的目标配置，可按范围筛选
+fn load_allocations(
+    conn: &Connection,
+    user_id: i64,
+    scope: Option<AllocationScope>,
+) -> Result<Vec<TargetAllocation>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, user_id, scope, scope_id, target_percent, tolerance_percent, created_at, updated_at
+         FROM target_allocations
+         WHERE user_id = ?1 AND (?2 IS NULL OR scope = ?2)
+         ORDER BY scope, target_percent DESC, id",
+    )?;
+
+    let mut allocations = stmt
+        .query_map(params![user_id, scope.map(|s| s.to_str())], row_to_allocation)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for allocation in allocations.iter_mut() {
+        if let Some(scope) = AllocationScope::from_str(&allocation.scope) {
+            allocation.scope_name =
+                scope_name(conn, user_id, scope, allocation.scope_id).unwrap_or_default();
+        }
+    }
+
+    Ok(allocations)
+}
+
+/// 新增或修改目标配置
+pub fn save_target_allocation(
+    id: Option<i64>,
+    user_id: i64,
+    scope: &str,
+    scope_id: i64,
+    target_percent: f64,
+    tolerance_percent: Option<f64>,
+) -> Result<TargetAllocation, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let scope = parse_scope(scope)?;
+    let tolerance_percent = tolerance_percent.unwrap_or(DEFAULT_TOLERANCE_PERCENT);
+
+    if !(0.0..=100.0).contains(&target_percent) {
+        return Err(AuthError::InvalidCredentials("目标权重必须在 0 到 100 之间".to_string()));
+    }
+    if !(0.0..=100.0).contains(&tolerance_percent) {
+        return Err(AuthError::InvalidCredentials("容忍区间必须在 0 到 100 之间".to_string()));
+    }
+
+    if scope_name(&conn, user_id, scope, scope_id).is_none() {
+        return Err(AuthError::InvalidCredentials("配置对象不存在或无权限".to_string()));
+    }
+
+    if let Some(id) = id {
+        let allocation_exists: bool = conn
+            .query_row(
+                "SELECT 1 FROM target_allocations WHERE id = ?1 AND user_id = ?2",
+                params![id, user_id],
+                |_| Ok(true),
+            )
+            .unwrap_or(false);
+
+        if !allocation_exists {
+            return Err(AuthError::InvalidCredentials("目标配置不存在或无权限".to_string()));
+        }
+    }
+
+    let duplicated: bool = conn
+        .query_row(
+            "SELECT 1 FROM target_allocations
+             WHERE user_id = ?1 AND scope = ?2 AND scope_id = ?3 AND (?4 IS NULL OR id != ?4)",
+            params![user_id, scope.to_str(), scope_id, id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if duplicated {
+        return Err(AuthError::InvalidCredentials("该对象已设置目标配置".to_string()));
+    }
+
+    // 同一范围内的目标权重合计不超过 100%
+    let allocated: f64 = conn.query_row(
+        "SELECT COALESCE(SUM(target_percent), 0) FROM target_allocations
+         WHERE user_id = ?1 AND scope = ?2 AND (?3 IS NULL OR id != ?3)",
+        params![user_id, scope.to_str(), id],
+        |row| row.get(0),
+    )?;
+
+    if allocated + target_percent > 100.0 + WEIGHT_EPSILON {
+        return Err(AuthError::InvalidCredentials(format!(
+            "同一范围的目标权重合计不能超过100%，已配置: {:.2}%",
+            allocated
+        )));
+    }
+
+    let allocation_id = match id {
+        Some(id) => {
+            conn.execute(
+                "UPDATE target_allocations
+                 SET scope = ?1, scope_id = ?2, target_percent = ?3, tolerance_percent = ?4, updated_at = ?5
+                 WHERE id = ?6",
+                params![scope.to_str(), scope_id, target_percent, tolerance_percent, now, id],
+            )?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO target_allocations (user_id, scope, scope_id, target_percent, tolerance_percent, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![user_id, scope.to_str(), scope_id, target_percent, tolerance_percent, now, now],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    let mut allocation = conn.query_row(
+        "SELECT id, user_id, scope, scope_id, target_percent, tolerance_percent, created_at, updated_at
+         FROM target_allocations WHERE id = ?1",
+        params![allocation_id],
+        row_to_allocation,
+    )?;
+    allocation.scope_name = scope_name(&conn, user_id, scope, scope_id).unwrap_or_default();
+
+    info!(
+        "Target allocation saved: {} {} -> {}% for user: {}",
+        allocation.scope, scope_id, target_percent, user_id
+    );
+    Ok(allocation)
+}
+
+/// 获取用户的所有目标配置
+pub fn get_target_allocations(user_id: i64) -> Result<Vec<TargetAllocation>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    load_allocations(&conn, user_id, None).map_err(|e| {
+        error!("Failed to fetch target allocations: {}", e);
+        AuthError::DatabaseError(format!("获取目标配置失败: {}", e))
+    })
+}
+
+/// 删除目标配置
+pub fn delete_target_allocation(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let allocation_exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM target_allocations WHERE id = ?1 AND user_id = ?2",
+            params![id, user_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if !allocation_exists {
+        return Err(AuthError::InvalidCredentials("目标配置不存在或无权限".to_string()));
+    }
+
+    conn.execute("DELETE FROM target_allocations WHERE id = ?1", params![id])?;
+
+    info!("Target allocation deleted: {} for user: {}", id, user_id);
+    Ok(())
+}
+
+/// 计算各目标配置相对组合总市值的偏离
+fn compute_drifts(
+    allocations: &[TargetAllocation],
+    holdings: &[Holding],
+    total_value: f64,
+) -> Vec<AllocationDrift> {
+    allocations
+        .iter()
+        .filter_map(|allocation| {
+            let scope = AllocationScope::from_str(&allocation.scope)?;
+            let in_scope: Vec<&Holding> = holdings
+                .iter()
+                .filter(|h| h.scope_id(scope) == Some(allocation.scope_id))
+                .collect();
+            let current_value: f64 = in_scope.iter().map(|h| h.value).sum();
+            let current_percent = if total_value > 0.0 {
+                current_value / total_value * 100.0
+            } else {
+                0.0
+            };
+            let drift_percent = current_percent - allocation.target_percent;
+            let target_value = total_value * allocation.target_percent / 100.0;
+
+            Some(AllocationDrift {
+                allocation_id: allocation.id,
+                scope: allocation.scope.clone(),
+                scope_id: allocation.scope_id,
+                scope_name: allocation.scope_name.clone(),
+                target_percent: allocation.target_percent,
+                tolerance_percent: allocation.tolerance_percent,
+                current_value,
+                current_percent,
+                drift_percent,
+                target_value,
+                deviation_value: current_value - target_value,
+                out_of_band: total_value > 0.0
+                    && drift_percent.abs() > allocation.tolerance_percent + WEIGHT_EPSILON,
+                converted: in_scope.iter().all(|h| h.converted),
+            })
+        })
+        .collect()
+}
+
+/// 获取目标配置偏离报告
+pub fn get_drift_report(user_id: i64, scope: Option<&str>) -> Result<DriftReport, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let scope = scope.map(parse_scope).transpose()?;
+
+    let mut fx = FxConverter::for_user(&conn, user_id)?;
+    let holdings = load_holdings(&conn, user_id, &mut fx).map_err(|e| {
+        error!("Failed to load holdings for user {}: {}", user_id, e);
+        AuthError::DatabaseError(format!("计算当前持仓失败: {}", e))
+    })?;
+    fx.ensure_rates()?;
+    let allocations = load_allocations(&conn, user_id, scope).map_err(|e| {
+        error!("Failed to fetch target allocations: {}", e);
+        AuthError::DatabaseError(format!("获取目标配置失败: {}", e))
+    })?;
+
+    let total_value: f64 = holdings.iter().map(|h| h.value).sum();
+    let items = compute_drifts(&allocations, &holdings, total_value);
+
+    Ok(DriftReport {
+        base_currency: fx.base_currency().to_string(),
+        total_value,
+        needs_rebalance: items.iter().any(|item| item.out_of_band),
+        items,
+        warnings: fx.missing_rate_warnings(),
+    })
+}
+
+/// 再平衡交易生成器
+struct RebalanceBuilder<'a> {
+    conn: &'a Connection,
+    lot_sizes: &'a HashMap<i64, f64>,
+    min_trade_amount: f64,
+    now: i64,
+    trades: Vec<RebalanceTrade>,
+    warnings: Vec<String>,
+}
+
+impl<'a> RebalanceBuilder<'a> {
+    /// 最小交易单位：请求中指定的 > 资产设置的 > 资产类型默认的，都没有时按 1 取整并提示
+    fn lot_size(&mut self, holding: &Holding) -> f64 {
+        let lot_size = self
+            .lot_sizes
+            .get(&holding.asset_id)
+            .copied()
+            .or(holding.lot_size)
+            .filter(|lot| *lot > 0.0)
+            .or_else(|| default_lot_size(&holding.asset_type));
+
+        lot_size.unwrap_or_else(|| {
+            let warning = format!("{} 未设置每手股数，份额按 1 股取整，请按实际每手股数调整", holding.name);
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+            1.0
+        })
+    }
+
+    fn push_trade(&mut self, holding: &Holding, scope_id: i64, kind: TransactionType, shares: f64, price: f64, fee: f64) {
+        let amount = shares * price;
+        self.trades.push(RebalanceTrade {
+            asset_id: holding.asset_id,
+            asset_name: holding.name.clone(),
+            asset_code: holding.code.clone(),
+            currency: holding.currency.clone(),
+            scope_id,
+            transaction_type: kind.to_str().to_string(),
+            shares,
+            price,
+            amount,
+            amount_base: amount * holding.rate,
+            fee,
+        });
+    }
+
+    /// 卖出配置对象中的资产，返回卖出所得（基准币种，已扣除费用）
+    fn sell(
+        &mut self,
+        scope: AllocationScope,
+        drift: &AllocationDrift,
+        holdings: &[Holding],
+        amount_base: f64,
+    ) -> Result<f64, rusqlite::Error> {
+        let members: Vec<&Holding> = holdings
+            .iter()
+            .filter(|h| h.scope_id(scope) == Some(drift.scope_id))
+            .filter(|h| h.shares > 0.0 && h.value > 0.0 && h.tradable_price().is_some())
+            .collect();
+        let member_value: f64 = members.iter().map(|h| h.value).sum();
+
+        let mut proceeds = 0.0;
+        for holding in members {
+            let target = amount_base * holding.value / member_value;
+            if target < self.min_trade_amount {
+                continue;
+            }
+
+            let price = holding.tradable_price().unwrap_or_default();
+            let shares = if target >= holding.value * (1.0 - WEIGHT_EPSILON) {
+                holding.shares
+            } else {
+                let lot_size = self.lot_size(holding);
+                floor_to_lot(target / holding.rate / price, lot_size).min(holding.shares)
+            };
+
+            if shares <= 0.0 {
+                self.warnings.push(format!("{} 卖出金额不足一个交易单位", holding.name));
+                continue;
+            }
+
+            let fee = calculate_fee(self.conn, holding.asset_id, TransactionType::Sell, shares, price, self.now, None)?
+                .total();
+            proceeds += (shares * price - fee) * holding.rate;
+            self.push_trade(holding, drift.scope_id, TransactionType::Sell, shares, price, fee);
+        }
+
+        Ok(proceeds)
+    }
+
+    /// 按预算（基准币种，含费用）买入配置对象中的资产，返回实际花费
+    fn buy(
+        &mut self,
+        scope: AllocationScope,
+        drift: &AllocationDrift,
+        holdings: &[Holding],
+        budget_base: f64,
+    ) -> Result<f64, rusqlite::Error> {
+        let members: Vec<&Holding> = holdings
+            .iter()
+            .filter(|h| h.scope_id(scope) == Some(drift.scope_id))
+            .filter(|h| h.tradable_price().is_some())
+            .collect();
+
+        if members.is_empty() {
+            self.warnings.push(format!("{} 中没有可买入（有现价）的资产", drift.scope_name));
+            return Ok(0.0);
+        }
+
+        let member_value: f64 = members.iter().map(|h| h.value).sum();
+        let member_count = members.len() as f64;
+
+        let mut spent = 0.0;
+        for holding in members {
+            let budget = if member_value > 0.0 {
+                budget_base * holding.value / member_value
+            } else {
+                budget_base / member_count
+            };
+            if budget <= 0.0 || budget < self.min_trade_amount {
+                continue;
+            }
+
+            let price = holding.tradable_price().unwrap_or_default();
+            let schedule = load_fee_schedule(self.conn, holding.asset_id)?;
+            let (net, _) = split_buy_budget(schedule.as_ref(), budget / holding.rate);
+            let lot_size = self.lot_size(holding);
+            let shares = floor_to_lot(net / price, lot_size);
+
+            if shares <= 0.0 {
+                self.warnings.push(format!("{} 买入金额不足一个交易单位", holding.name));
+                continue;
+            }
+
+            let fee = calculate_fee(self.conn, holding.asset_id, TransactionType::Buy, shares, price, self.now, None)?
+                .total();
+            spent += (shares * price + fee) * holding.rate;
+            self.push_trade(holding, drift.scope_id, TransactionType::Buy, shares, price, fee);
+        }
+
+        Ok(spent)
+    }
+}
+
+/// 分配买入预算：资金不足时按缺口比例缩减需调整的配置，有剩余时补足区间内低于目标的配置
+fn buy_budgets(shortfalls: &[f64], adjust: &[bool], cash: f64) -> Vec<f64> {
+    let demand: f64 = (0..shortfalls.len()).filter(|i| adjust[*i]).map(|i| shortfalls[i]).sum();
+    let scale = if demand > cash && demand > 0.0 { cash.max(0.0) / demand } else { 1.0 };
+    let mut budgets: Vec<f64> = (0..shortfalls.len())
+        .map(|i| if adjust[i] { shortfalls[i] * scale } else { 0.0 })
+        .collect();
+
+    let excess = cash - demand * scale;
+    let in_band_shortfall: f64 = (0..shortfalls.len()).filter(|i| !adjust[*i]).map(|i| shortfalls[i]).sum();
+    if excess > 0.0 && in_band_shortfall > 0.0 {
+        let fill = (excess / in_band_shortfall).min(1.0);
+        for i in (0..shortfalls.len()).filter(|i| !adjust[*i]) {
+            budgets[i] = shortfalls[i] * fill;
+        }
+    }
+
+    budgets
+}
+
+/// 生成再平衡方案
+pub fn plan_rebalance(
+    user_id: i64,
+    scope: &str,
+    additional_cash: f64,
+    min_trade_amount: f64,
+    lot_sizes: &HashMap<i64, f64>,
+    create_pending: bool,
+) -> Result<RebalancePlan, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let scope = parse_scope(scope)?;
+
+    if additional_cash.is_nan() || additional_cash < 0.0 {
+        return Err(AuthError::InvalidCredentials("追加资金不能为负数".to_string()));
+    }
+    if min_trade_amount.is_nan() || min_trade_amount < 0.0 {
+        return Err(AuthError::InvalidCredentials("最小交易金额不能为负数".to_string()));
+    }
+
+    let mut fx = FxConverter::for_user(&conn, user_id)?;
+    let base_currency = fx.base_currency().to_string();
+    let holdings = load_holdings(&conn, user_id, &mut fx).map_err(|e| {
+        error!("Failed to load holdings for user {}: {}", user_id, e);
+        AuthError::DatabaseError(format!("计算当前持仓失败: {}", e))
+    })?;
+    fx.ensure_rates()?;
+    let allocations = load_allocations(&conn, user_id, Some(scope)).map_err(|e| {
+        error!("Failed to fetch target allocations: {}", e);
+        AuthError::DatabaseError(format!("获取目标配置失败: {}", e))
+    })?;
+
+    if allocations.is_empty() {
+        return Err(AuthError::InvalidCredentials("该范围尚未设置目标配置".to_string()));
+    }
+
+    let total_value: f64 = holdings.iter().map(|h| h.value).sum();
+    let investable = total_value + additional_cash;
+    if investable <= 0.0 {
+        return Err(AuthError::InvalidCredentials("组合市值和追加资金均为0，无法再平衡".to_string()));
+    }
+
+    let drifts = compute_drifts(&allocations, &holdings, total_value);
+
+    // 再平衡后的目标市值按组合市值加追加资金计算；没有持仓时全部视为需要调整
+    let targets: Vec<f64> = drifts
+        .iter()
+        .map(|d| investable * d.target_percent / 100.0)
+        .collect();
+    let adjust: Vec<bool> = drifts
+        .iter()
+        .map(|d| d.out_of_band || total_value <= 0.0)
+        .collect();
+
+    let mut builder = RebalanceBuilder {
+        conn: &conn,
+        lot_sizes,
+        min_trade_amount,
+        now,
+        trades: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let plan_error = |e: rusqlite::Error| {
+        error!("Failed to plan rebalance for user {}: {}", user_id, e);
+        AuthError::DatabaseError(format!("计算再平衡方案失败: {}", e))
+    };
+
+    // 先卖出超配部分
+    let mut cash = additional_cash;
+    for (i, drift) in drifts.iter().enumerate() {
+        if adjust[i] && drift.current_value > targets[i] {
+            cash += builder
+                .sell(scope, drift, &holdings, drift.current_value - targets[i])
+                .map_err(plan_error)?;
+        }
+    }
+
+    // 买入低配部分
+    let shortfalls: Vec<f64> = drifts
+        .iter()
+        .zip(&targets)
+        .map(|(d, target)| (target - d.current_value).max(0.0))
+        .collect();
+    let budgets = buy_budgets(&shortfalls, &adjust, cash);
+
+    let mut spent = 0.0;
+    for (i, drift) in drifts.iter().enumerate() {
+        if budgets[i] > 0.0 {
+            spent += builder
+                .buy(scope, drift, &holdings, budgets[i])
+                .map_err(plan_error)?;
+        }
+    }
+
+    let RebalanceBuilder { trades, warnings: trade_warnings, .. } = builder;
+    let mut warnings = fx.missing_rate_warnings();
+    warnings.extend(trade_warnings);
+
+    let rate_of = |asset_id: i64| {
+        holdings
+            .iter()
+            .find(|h| h.asset_id == asset_id)
+            .map_or(1.0, |h| h.rate)
+    };
+    let total_buy: f64 = trades
+        .iter()
+        .filter(|t| t.transaction_type == TransactionType::Buy.to_str())
+        .map(|t| t.amount_base)
+        .sum();
+    let total_sell: f64 = trades
+        .iter()
+        .filter(|t| t.transaction_type == TransactionType::Sell.to_str())
+        .map(|t| t.amount_base)
+        .sum();
+    let total_fee: f64 = trades.iter().map(|t| t.fee * rate_of(t.asset_id)).sum();
+
+    // 生成待确认交易
+    let mut pending_transaction_ids = Vec::new();
+    if create_pending && !trades.is_empty() {
+        let tx = conn.transaction()?;
+        for trade in &trades {
+            let kind = TransactionType::from_str(&trade.transaction_type).unwrap_or(TransactionType::Buy);
+            let id = insert_pending_transaction(
+                &tx,
+                user_id,
+                trade.asset_id,
+                kind,
+                trade.shares,
+                trade.price,
+                trade.fee,
+                now,
+                Some(REBALANCE_NOTE),
+            )?;
+            pending_transaction_ids.push(id);
+        }
+        tx.commit()?;
+    }
+
+    for warning in &warnings {
+        warn!("Rebalance for user {}: {}", user_id, warning);
+    }
+    info!(
+        "Rebalance planned for user {}: {} trades, {} pending transactions created",
+        user_id,
+        trades.len(),
+        pending_transaction_ids.len()
+    );
+
+    Ok(RebalancePlan {
+        base_currency,
+        scope: scope.to_str().to_string(),
+        total_value,
+        additional_cash,
+        trades,
+        total_buy,
+        total_sell,
+        total_fee,
+        cash_remaining: cash - spent,
+        warnings,
+        pending_transaction_ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(asset_id: i64, group_id: Option<i64>, value: f64) -> Holding {
+        Holding {
+            asset_id,
+            name: format!("资产{}", asset_id),
+            code: format!("{:06}", asset_id),
+            asset_type: "FUND".to_string(),
+            asset_type_id: 1,
+            group_id,
+            currency: "CNY".to_string(),
+            lot_size: None,
+            price: Some(1.0),
+            rate: 1.0,
+            converted: true,
+            shares: value,
+            value,
+        }
+    }
+
+    fn allocation(id: i64, scope: AllocationScope, scope_id: i64, target: f64, tolerance: f64) -> TargetAllocation {
+        TargetAllocation {
+            id,
+            user_id: 1,
+            scope: scope.to_str().to_string(),
+            scope_id,
+            scope_name: String::new(),
+            target_percent: target,
+            tolerance_percent: tolerance,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn drift_against_total_value() {
+        let holdings = vec![holding(1, None, 6000.0), holding(2, None, 4000.0)];
+        let allocations = vec![
+            allocation(1, AllocationScope::Asset, 1, 50.0, 5.0),
+            allocation(2, AllocationScope::Asset, 2, 45.0, 5.0),
+        ];
+
+        let drifts = compute_drifts(&allocations, &holdings, 10000.0);
+
+        assert_close(drifts[0].current_percent, 60.0);
+        assert_close(drifts[0].drift_percent, 10.0);
+        assert_close(drifts[0].target_value, 5000.0);
+        assert_close(drifts[0].deviation_value, 1000.0);
+        assert!(drifts[0].out_of_band);
+        assert_close(drifts[1].drift_percent, -5.0);
+        assert!(!drifts[1].out_of_band);
+    }
+
+    #[test]
+    fn drift_sums_group_members() {
+        let holdings = vec![
+            holding(1, Some(10), 3000.0),
+            holding(2, Some(10), 2000.0),
+            holding(3, None, 5000.0),
+        ];
+        let allocations = vec![allocation(1, AllocationScope::Group, 10, 40.0, 5.0)];
+
+        let drifts = compute_drifts(&allocations, &holdings, 10000.0);
+
+        assert_close(drifts[0].current_value, 5000.0);
+        assert_close(drifts[0].drift_percent, 10.0);
+        assert!(drifts[0].out_of_band);
+    }
+
+    #[test]
+    fn empty_portfolio_is_never_out_of_band() {
+        let holdings = vec![holding(1, None, 0.0)];
+        let allocations = vec![allocation(1, AllocationScope::Asset, 1, 50.0, 5.0)];
+
+        let drifts = compute_drifts(&allocations, &holdings, 0.0);
+
+        assert_close(drifts[0].current_percent, 0.0);
+        assert!(!drifts[0].out_of_band);
+    }
+
+    #[test]
+    fn budgets_scale_down_when_cash_is_short() {
+        let budgets = buy_budgets(&[600.0, 400.0, 100.0], &[true, true, false], 500.0);
+
+        assert_close(budgets[0], 300.0);
+        assert_close(budgets[1], 200.0);
+        assert_close(budgets[2], 0.0);
+    }
+
+    #[test]
+    fn budgets_fill_in_band_shortfalls_with_excess_cash() {
+        let budgets = buy_budgets(&[600.0, 200.0, 400.0], &[true, false, false], 900.0);
+
+        assert_close(budgets[0], 600.0);
+        assert_close(budgets[1], 100.0);
+        assert_close(budgets[2], 200.0);
+
+        let budgets = buy_budgets(&[600.0, 200.0], &[true, false], 5000.0);
+        assert_close(budgets[1], 200.0);
+    }
+
+    #[test]
+    fn budgets_are_zero_without_cash() {
+        let budgets = buy_budgets(&[600.0, 200.0], &[true, false], -100.0);
+
+        assert_close(budgets[0], 0.0);
+        assert_close(budgets[1], 0.0);
+    }
+
+    #[test]
+    fn shares_floor_to_lot_size() {
+        assert_close(floor_to_lot(250.0, 100.0), 200.0);
+        assert_close(floor_to_lot(0.3 / 0.1 * 0.1, 0.1), 0.3);
+        assert_close(floor_to_lot(99.0, 100.0), 0.0);
+        assert_eq!(default_lot_size("HK_STOCK"), None);
+        assert_eq!(default_lot_size("stock"), Some(100.0));
+    }
+}
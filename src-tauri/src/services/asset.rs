@@ -4,6 +4,9 @@
 use crate::database::get_connection_from_pool;
 use crate::error::auth::AuthError;
 use crate::models::{Asset, AssetType, UserGroup};
+use crate::services::position::load_user_positions;
+use crate::services::fx::{default_currency, validate_currency};
+use crate::services::settings::load_user_settings;
 use chrono::Utc;
 use log::{error, info};
 use rusqlite::{params, Connection};
@@ -190,6 +193,12 @@ pub fn delete_user_group(id: i64, user_id: i64) -> Result<(), AuthError> {
         params![Utc::now().timestamp(), id],
     )?;
     
+    // 删除该分组的目标配置
+    conn.execute(
+        "DELETE FROM target_allocations WHERE scope = 'GROUP' AND scope_id = ?1",
+        params![id],
+    )?;
+    
     // 删除分组
     conn.execute(
         "DELETE FROM user_groups WHERE id = ?1",
@@ -275,20 +284,31 @@ pub fn create_asset(
     code: &str,
     name: &str,
     current_price: Option<f64>,
+    currency: Option<&str>,
+    lot_size: Option<f64>,
 ) -> Result<Asset, AuthError> {
     let conn = get_connection_from_pool()?;
     let now = Utc::now().timestamp();
     
+    validate_lot_size(lot_size)?;
+    
     // 检查资产类型是否存在
-    let asset_type_exists: bool = conn.query_row(
-        "SELECT 1 FROM asset_types WHERE id = ?1",
+    let asset_type_name: Option<String> = conn.query_row(
+        "SELECT name FROM asset_types WHERE id = ?1",
         params![asset_type_id],
-        |_| Ok(true),
-    ).unwrap_or(false);
+        |row| row.get(0),
+    ).ok();
     
-    if !asset_type_exists {
-        return Err(AuthError::InvalidCredentials("资产类型不存在".to_string()));
-    }
+    let asset_type_name = match asset_type_name {
+        Some(name) => name,
+        None => return Err(AuthError::InvalidCredentials("资产类型不存在".to_string())),
+    };
+    
+    // 未指定币种时按资产类型取默认报价币种
+    let currency = match currency {
+        Some(currency) => validate_currency(currency)?,
+        None => default_currency(&asset_type_name).to_string(),
+    };
     
     // 如果指定了分组，检查分组是否存在且属于该用户
     if let Some(gid) = group_id {
@@ -318,8 +338,8 @@ pub fn create_asset(
     
     // 创建资产
     conn.execute(
-        "INSERT INTO assets (user_id, group_id, asset_type_id, code, name, current_price, last_updated, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        "INSERT INTO assets (user_id, group_id, asset_type_id, code, name, current_price, currency, lot_size, last_updated, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
         params![
             user_id,
             group_id,
@@ -327,6 +347,8 @@ pub fn create_asset(
             code,
             name,
             current_price,
+            currency,
+            lot_size,
             current_price.map(|_| now),
             now,
             now
@@ -335,13 +357,6 @@ pub fn create_asset(
     
     let asset_id = conn.last_insert_rowid();
     
-    // 获取资产类型名称
-    let asset_type_name: String = conn.query_row(
-        "SELECT name FROM asset_types WHERE id = ?1",
-        params![asset_type_id],
-        |row| row.get(0),
-    )?;
-    
     // 获取分组名称
     let group_name: Option<String> = if let Some(gid) = group_id {
         conn.query_row(
@@ -363,6 +378,8 @@ pub fn create_asset(
         code: code.to_string(),
         name: name.to_string(),
         current_price,
+        currency,
+        lot_size,
         position_amount:None,
         position_cost:None,
         last_updated: current_price.map(|_| now),
@@ -376,6 +393,14 @@ pub fn create_asset(
     Ok(asset)
 }
 
+/// 校验最小交易单位
+fn validate_lot_size(lot_size: Option<f64>) -> Result<(), AuthError> {
+    if lot_size.is_some_and(|lot| !lot.is_finite() || lot <= 0.0) {
+        return Err(AuthError::InvalidCredentials("最小交易单位必须大于0".to_string()));
+    }
+    Ok(())
+}
+
 pub fn update_asset(
     id: i64,
     user_id: i64,
@@ -384,10 +409,14 @@ pub fn update_asset(
     current_price: Option<f64>,
     position_amount: Option<f64>,
     position_cost: Option<f64>,
+    currency: Option<&str>,
+    lot_size: Option<f64>,
 ) -> Result<Asset, AuthError> {
     let conn = get_connection_from_pool()?;
     let now = Utc::now().timestamp();
     
+    validate_lot_size(lot_size)?;
+    
     // Check if asset exists and belongs to the user
     let asset_exists: bool = conn.query_row(
         "SELECT 1 FROM assets WHERE id = ?1 AND user_id = ?2",
@@ -420,18 +449,22 @@ pub fn update_asset(
         }
     }
     
+    let currency = currency.map(validate_currency).transpose()?;
+    
     // Update asset with new position fields
     conn.execute(
         "UPDATE assets 
          SET group_id = ?1, name = ?2, current_price = ?3, position_amount = ?4, position_cost = ?5, 
-         last_updated = ?6, updated_at = ?7 
-         WHERE id = ?8",
+         currency = COALESCE(?6, currency), lot_size = COALESCE(?7, lot_size), last_updated = ?8, updated_at = ?9 
+         WHERE id = ?10",
         params![
             group_id,
             name,
             current_price,
             position_amount,
             position_cost,
+            currency,
+            lot_size,
             current_price.map(|_| now),
             now,
             id
@@ -439,10 +472,10 @@ pub fn update_asset(
     )?;
     
     // Get asset information
-    let code: String = conn.query_row(
-        "SELECT code FROM assets WHERE id = ?1",
+    let (code, currency, lot_size): (String, String, Option<f64>) = conn.query_row(
+        "SELECT code, currency, lot_size FROM assets WHERE id = ?1",
         params![id],
-        |row| row.get(0),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
     )?;
     
     // Get asset type name
@@ -479,6 +512,8 @@ pub fn update_asset(
         code,
         name: name.to_string(),
         current_price,
+        currency,
+        lot_size,
         position_amount,
         position_cost,
         last_updated: current_price.map(|_| now),
@@ -540,6 +575,22 @@ pub fn delete_asset(id: i64, user_id: i64) -> Result<(), AuthError> {
         params![id],
     )?;
     
+    // 删除相关的定投执行记录和止盈止损规则
+    tx.execute(
+        "DELETE FROM plan_executions WHERE asset_id = ?1",
+        params![id],
+    )?;
+    tx.execute(
+        "DELETE FROM exit_rules WHERE asset_id = ?1",
+        params![id],
+    )?;
+    
+    // 删除相关的提醒规则
+    tx.execute(
+        "DELETE FROM alert_rules WHERE asset_id = ?1",
+        params![id],
+    )?;
+    
     // 删除相关的定投计划
     tx.execute(
         "DELETE FROM investment_plans WHERE asset_id = ?1",
@@ -552,6 +603,12 @@ pub fn delete_asset(id: i64, user_id: i64) -> Result<(), AuthError> {
         params![id],
     )?;
     
+    // 删除相关的目标配置
+    tx.execute(
+        "DELETE FROM target_allocations WHERE scope = 'ASSET' AND scope_id = ?1",
+        params![id],
+    )?;
+    
     // 删除相关的历史价格
     tx.execute(
         "DELETE FROM price_history WHERE asset_id = ?1",
@@ -609,25 +666,39 @@ pub fn get_user_assets(
     let query = format!(
         "SELECT 
             a.id, a.user_id, a.group_id, g.name, a.asset_type_id, t.name, 
-            a.code, a.name, a.current_price,a.position_amount,a.position_cost, a.last_updated, a.created_at, a.updated_at,
-            (SELECT SUM(CASE WHEN transaction_type = 'BUY' THEN amount ELSE -amount END) 
-             FROM transactions WHERE asset_id = a.id) as total_amount,
-            (SELECT SUM(CASE WHEN transaction_type = 'BUY' THEN total_cost ELSE -total_cost END) 
-             FROM transactions WHERE asset_id = a.id) as total_cost
+            a.code, a.name, a.current_price,a.position_amount,a.position_cost, a.last_updated, a.created_at, a.updated_at, a.currency, a.lot_size
          FROM assets a
          JOIN asset_types t ON a.asset_type_id = t.id
          LEFT JOIN user_groups g ON a.group_id = g.id
          WHERE {}
          ORDER BY a.name", condition_str);
     
+    // 有交易记录的资产按交易流水和用户的成本计算方法计算持仓，否则使用手动录入的持仓
+    let method = load_user_settings(&conn, user_id)?.cost_basis();
+    let positions = load_user_positions(&conn, user_id, method).map_err(|e| {
+        error!("Failed to calculate positions: {}", e);
+        AuthError::DatabaseError(format!("计算持仓失败: {}", e))
+    })?;
+    
     let mut stmt = conn.prepare(&query)?;
     
     let assets = stmt.query_map(rusqlite::params_from_iter(params), |row| {
         let id: i64 = row.get(0)?;
         let current_price: Option<f64> = row.get(8)?;
-        let position_amount: Option<f64> = row.get(9)?;
-        let position_cost: Option<f64> = row.get(10)?;
+        let mut position_amount: Option<f64> = row.get(9)?;
+        let mut position_cost: Option<f64> = row.get(10)?;
+        let mut total_profit = calculate_profit(current_price, position_amount, position_cost);
+        let mut total_profit_percent = calculate_profit_percent(current_price, position_amount, position_cost);
 
+        if let Some(position) = positions.get(&id) {
+            position_amount = Some(position.shares);
+            position_cost = Some(position.average_cost());
+            total_profit = current_price.map(|price| position.total_profit(price));
+            total_profit_percent = match total_profit {
+                Some(profit) if position.cost > 0.0 => Some(profit / position.cost * 100.0),
+                _ => None,
+            };
+        }
         
         Ok(Asset {
             id,
@@ -639,13 +710,15 @@ pub fn get_user_assets(
             code: row.get(6)?,
             name: row.get(7)?,
             current_price,
+            currency: row.get(14)?,
+            lot_size: row.get(15)?,
             position_amount,
             position_cost,
             last_updated: row.get(11)?,
             created_at: row.get(12)?,
             updated_at: row.get(13)?,
-            total_profit:calculate_profit(current_price, position_amount, position_cost),
-            total_profit_percent:calculate_profit_percent(current_price, position_amount, position_cost),
+            total_profit,
+            total_profit_percent,
         })
     })?
     .collect::<Result<Vec<_>, _>>()
@@ -0,0 +1,404 @@
+/**
+ * 交易日历模块
+ *
+ * 判断各交易所的交易日，用于：
+ * - 定投计划的执行日期顺延到下一个交易日、计算每月最后一个交易日（`plan_schedule`）
+ * - 日线数据质量检查按交易日计算缺失K线（`data_quality`）
+ * - 按交易所每年的交易日数年化波动率
+ *
+ * 休市日数据随应用打包（resources/calendars 目录下的 cn.txt、hk.txt、us.txt），每行为 `YYYY-MM-DD 名称`。
+ * `CalendarConfig.holiday_dir` 目录下的同名文件会与内置数据合并，用于补充新年份的休市安排，
+ * 修改后调用 `reload_calendars` 生效。超出休市日数据年份的日期只按周末判断，每个年份首次用到时记录警告，
+ * `get_trading_days` 查询区间超出时在结果中提示。
+ *
+ * 资产使用的交易所由资产类型决定（`CalendarConfig.asset_types`），未配置的类型按上交所处理。
+ * 日期均为北京时间的自然日，与快照的日期口径一致。
+ */
+use crate::config::Config;
+use crate::error::auth::AuthError;
+use crate::models::{Exchange, Holiday, TradingDays};
+use crate::services::snapshot::local_date;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 顺延查找交易日的最大天数，避免休市日数据异常时无限循环
+const MAX_ROLL_DAYS: i64 = 366;
+
+/// 查询交易日列表的最大区间
+const MAX_QUERY_DAYS: i64 = 3660;
+
+lazy_static! {
+    static ref CALENDARS: RwLock<HashMap<Exchange, Arc<TradingCalendar>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// 内置的休市日数据
+fn bundled_holidays(file: &str) -> &'static str {
+    match file {
+        "cn" => include_str!("../../resources/calendars/cn.txt"),
+        "hk" => include_str!("../../resources/calendars/hk.txt"),
+        "us" => include_str!("../../resources/calendars/us.txt"),
+        _ => "",
+    }
+}
+
+/// 解析休市日数据，格式错误的行记录警告后跳过
+fn parse_holidays(source: &str, content: &str, holidays: &mut BTreeMap<NaiveDate, String>) {
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (date, name) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => {
+                holidays.insert(date, name.trim().to_string());
+            }
+            Err(e) => warn!("Invalid holiday at {}:{}: {} ({})", source, number + 1, line, e),
+        }
+    }
+}
+
+/// 交易所的交易日历
+#[derive(Debug)]
+pub struct TradingCalendar {
+    pub exchange: Exchange,
+    holidays: BTreeMap<NaiveDate, String>,
+    years: BTreeSet<i32>,                // 有休市日数据的年份
+    warned_years: Mutex<BTreeSet<i32>>, // 已记录过缺少休市日数据警告的年份
+}
+
+impl TradingCalendar {
+    /// 加载交易所的交易日历：内置数据 + 自定义目录下的同名文件
+    fn load(exchange: Exchange) -> Self {
+        let mut holidays = BTreeMap::new();
+
+        if let Some(file) = exchange.holiday_file() {
+            parse_holidays(file, bundled_holidays(file), &mut holidays);
+
+            let path = Path::new(&Config::get().calendar.holiday_dir).join(format!("{}.txt", file));
+            if path.exists() {
+                match fs::read_to_string(&path) {
+                    Ok(content) => parse_holidays(&path.to_string_lossy(), &content, &mut holidays),
+                    Err(e) => warn!("Failed to read holiday file {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        info!(
+            "Loaded trading calendar {} with {} holidays",
+            exchange.to_str(),
+            holidays.len()
+        );
+
+        Self::new(exchange, holidays)
+    }
+
+    pub(crate) fn new(exchange: Exchange, holidays: BTreeMap<NaiveDate, String>) -> Self {
+        let years = holidays.keys().map(|date| date.year()).collect();
+        Self {
+            exchange,
+            holidays,
+            years,
+            warned_years: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// 是否有该年份的休市日数据（数字货币全年无休，始终为 true）
+    pub fn covers(&self, year: i32) -> bool {
+        self.exchange == Exchange::Crypto || self.years.contains(&year)
+    }
+
+    /// 超出休市日数据年份时记录警告（每个年份只记录一次）
+    fn check_coverage(&self, date: NaiveDate) {
+        if self.covers(date.year()) {
+            return;
+        }
+        let mut warned = self.warned_years.lock().unwrap_or_else(PoisonError::into_inner);
+        if warned.insert(date.year()) {
+            warn!(
+                "No holiday data for {} in {}, only weekends are treated as closed",
+                self.exchange.to_str(),
+                date.year()
+            );
+        }
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        if self.exchange == Exchange::Crypto {
+            return true;
+        }
+        self.check_coverage(date);
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains_key(&date)
+    }
+
+    /// 当天或之后的第一个交易日
+    pub fn next_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        (0..MAX_ROLL_DAYS)
+            .map(|offset| date + Duration::days(offset))
+            .find(|day| self.is_trading_day(*day))
+            .unwrap_or(date)
+    }
+
+    /// 当天或之前的最后一个交易日（如每月最后一个交易日）
+    pub fn previous_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        (0..MAX_ROLL_DAYS)
+            .map(|offset| date - Duration::days(offset))
+            .find(|day| self.is_trading_day(*day))
+            .unwrap_or(date)
+    }
+
+    /// 当天或之后第一个交易日再往后数 `days` 个交易日（如基金 T+1 确认日）
+    pub fn add_trading_days(&self, date: NaiveDate, days: i64) -> NaiveDate {
+        let mut result = self.next_trading_day(date);
+        for _ in 0..days {
+            result = self.next_trading_day(result + Duration::days(1));
+        }
+        result
+    }
+
+    /// 区间 [start, end] 内的交易日
+    pub fn trading_days(&self, start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+        start
+            .iter_days()
+            .take_while(|day| *day <= end)
+            .filter(|day| self.is_trading_day(*day))
+            .collect()
+    }
+
+    /// 两个日期之间（不含两端）的交易日数
+    pub fn trading_days_between(&self, start: NaiveDate, end: NaiveDate) -> i64 {
+        match start.succ_opt() {
+            Some(first) if first < end => self.trading_days(first, end - Duration::days(1)).len() as i64,
+            _ => 0,
+        }
+    }
+
+    /// 每年的交易日数：有休市日数据时取这些年份的平均值
+    pub fn trading_days_per_year(&self) -> f64 {
+        if self.exchange == Exchange::Crypto || self.years.is_empty() {
+            return self.exchange.default_trading_days_per_year();
+        }
+
+        let total: usize = self
+            .years
+            .iter()
+            .filter_map(|year| {
+                Some(self.trading_days(
+                    NaiveDate::from_ymd_opt(*year, 1, 1)?,
+                    NaiveDate::from_ymd_opt(*year, 12, 31)?,
+                ))
+            })
+            .map(|days| days.len())
+            .sum();
+        total as f64 / self.years.len() as f64
+    }
+
+    /// 将时间顺延到当天或之后的第一个交易日（保留时刻）
+    pub fn roll_forward(&self, timestamp: i64) -> i64 {
+        let date = local_date(timestamp);
+        let next = self.next_trading_day(date);
+        timestamp + (next - date).num_days() * SECONDS_PER_DAY
+    }
+}
+
+/// 获取交易所的交易日历（首次使用时加载并缓存）
+///
+/// 缓存只保存已加载完成的日历，其他线程持锁时 panic 不会留下不完整的数据，锁中毒时继续使用
+pub fn get_calendar(exchange: Exchange) -> Arc<TradingCalendar> {
+    if let Some(calendar) = CALENDARS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&exchange)
+    {
+        return calendar.clone();
+    }
+
+    let calendar = Arc::new(TradingCalendar::load(exchange));
+    CALENDARS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(exchange)
+        .or_insert(calendar)
+        .clone()
+}
+
+/// 清空缓存，下次使用时重新加载休市日数据
+pub fn reload_calendars() {
+    CALENDARS.write().unwrap_or_else(PoisonError::into_inner).clear();
+    info!("Trading calendars will be reloaded on next use");
+}
+
+/// 资产类型对应的交易所
+pub fn exchange_for_asset_type(asset_type: &str) -> Exchange {
+    Config::get()
+        .calendar
+        .asset_types
+        .get(&asset_type.to_uppercase())
+        .and_then(|exchange| Exchange::from_str(exchange))
+        .unwrap_or(Exchange::Sse)
+}
+
+/// 获取资产适用的交易日历，资产不存在时使用上交所日历
+pub fn load_asset_calendar(
+    conn: &Connection,
+    asset_id: i64,
+) -> Result<Arc<TradingCalendar>, rusqlite::Error> {
+    let asset_type: Option<String> = conn
+        .query_row(
+            "SELECT t.name FROM assets a JOIN asset_types t ON a.asset_type_id = t.id WHERE a.id = ?1",
+            params![asset_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(get_calendar(
+        asset_type
+            .map(|asset_type| exchange_for_asset_type(&asset_type))
+            .unwrap_or(Exchange::Sse),
+    ))
+}
+
+/// 解析交易所代码
+pub fn parse_exchange(exchange: &str) -> Result<Exchange, AuthError> {
+    Exchange::from_str(&exchange.to_uppercase()).ok_or_else(|| {
+        AuthError::InvalidCredentials(format!(
+            "不支持的交易所: {}，必须为 SSE、SZSE、HKEX、NYSE 或 CRYPTO",
+            exchange
+        ))
+    })
+}
+
+/// 获取区间内的交易日和休市日
+pub fn get_trading_days(exchange: &str, start_date: &str, end_date: &str) -> Result<TradingDays, AuthError> {
+    let exchange = parse_exchange(exchange)?;
+    let parse_date = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| AuthError::InvalidCredentials(format!("无效的日期: {}", date)))
+    };
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+
+    if end < start {
+        return Err(AuthError::InvalidCredentials("结束日期不能早于开始日期".to_string()));
+    }
+    if (end - start).num_days() > MAX_QUERY_DAYS {
+        return Err(AuthError::InvalidCredentials("查询区间不能超过10年".to_string()));
+    }
+
+    let calendar = get_calendar(exchange);
+    let warnings = (start.year()..=end.year())
+        .filter(|year| !calendar.covers(*year))
+        .map(|year| {
+            format!(
+                "缺少 {} 年的休市日数据，只按周末判断，请在 {} 目录补充后重新加载",
+                year,
+                Config::get().calendar.holiday_dir
+            )
+        })
+        .collect();
+    let holidays = calendar
+        .holidays
+        .range(start..=end)
+        .filter(|(date, _)| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+        .map(|(date, name)| Holiday {
+            date: date.format("%Y-%m-%d").to_string(),
+            name: name.clone(),
+        })
+        .collect();
+
+    Ok(TradingDays {
+        exchange: exchange.to_str().to_string(),
+        start_date: start.format("%Y-%m-%d").to_string(),
+        end_date: end.format("%Y-%m-%d").to_string(),
+        trading_days: calendar
+            .trading_days(start, end)
+            .iter()
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .collect(),
+        holidays,
+        covered_years: calendar.years.iter().copied().collect(),
+        trading_days_per_year: calendar.trading_days_per_year(),
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::services::scheduler::beijing_offset;
+
+    fn sse() -> TradingCalendar {
+        let mut holidays = BTreeMap::new();
+        parse_holidays("cn", bundled_holidays("cn"), &mut holidays);
+        TradingCalendar::new(Exchange::Sse, holidays)
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn beijing(value: &str, hour: u32) -> i64 {
+        beijing_offset()
+            .from_local_datetime(&date(value).and_hms_opt(hour, 0, 0).unwrap())
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn roll_forward_skips_weekends_and_holidays() {
+        let calendar = sse();
+
+        // 交易日不变
+        assert_eq!(calendar.roll_forward(beijing("2024-09-30", 10)), beijing("2024-09-30", 10));
+        // 周六顺延到周一，保留时刻
+        assert_eq!(calendar.roll_forward(beijing("2024-06-01", 10)), beijing("2024-06-03", 10));
+        // 国庆长假顺延到节后第一个交易日
+        assert_eq!(calendar.roll_forward(beijing("2024-10-01", 15)), beijing("2024-10-08", 15));
+        // 北京时间凌晨按北京日期判断
+        assert_eq!(calendar.roll_forward(beijing("2024-10-07", 1)), beijing("2024-10-08", 1));
+    }
+
+    #[test]
+    fn add_trading_days_counts_from_next_trading_day() {
+        let calendar = sse();
+
+        assert_eq!(calendar.add_trading_days(date("2024-09-27"), 0), date("2024-09-27"));
+        assert_eq!(calendar.add_trading_days(date("2024-09-27"), 1), date("2024-09-30"));
+        assert_eq!(calendar.add_trading_days(date("2024-09-30"), 1), date("2024-10-08"));
+        // 休市日先顺延再计数
+        assert_eq!(calendar.add_trading_days(date("2024-10-03"), 2), date("2024-10-10"));
+    }
+
+    #[test]
+    fn previous_trading_day_rolls_back() {
+        let calendar = sse();
+
+        assert_eq!(calendar.previous_trading_day(date("2024-09-30")), date("2024-09-30"));
+        assert_eq!(calendar.previous_trading_day(date("2024-10-07")), date("2024-09-30"));
+        // 2024 年 3 月最后一天为周日，月末最后一个交易日为 3 月 29 日
+        assert_eq!(calendar.previous_trading_day(date("2024-03-31")), date("2024-03-29"));
+    }
+
+    #[test]
+    fn uncovered_years_only_skip_weekends() {
+        let calendar = sse();
+
+        assert!(calendar.covers(2024));
+        assert!(!calendar.covers(2030));
+        // 2030-01-01 为周二，没有休市日数据时按交易日处理
+        assert!(calendar.is_trading_day(date("2030-01-01")));
+        assert!(!calendar.is_trading_day(date("2024-01-01")));
+        assert!(TradingCalendar::new(Exchange::Crypto, BTreeMap::new()).covers(2030));
+    }
+}
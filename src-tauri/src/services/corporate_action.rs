@@ -0,0 +1,371 @@
+/**
+ * 公司行为（分红、份额折算）同步模块
+ *
+ * 主要函数说明：
+ * - `sync_corporate_actions(user_id, lookback_days)`: 通过适配器的 `get_corporate_actions`
+ *   获取资产的分红/折算事件并写入 `corporate_actions`（同一事件只保存一次），
+ *   然后为 `dividend_method` 不为 NONE 的资产自动生成对应交易。
+ * - `apply_corporate_actions(user_id)`: 为已保存但尚未生成交易的事件补充生成交易。
+ * - `get_asset_corporate_actions(user_id, asset_id)`: 获取资产对应的公司行为列表。
+ * - `set_dividend_method(user_id, asset_id, method)`: 设置资产的分红处理方式。
+ *
+ * 自动生成交易规则（按除息日前的持仓份额计算）：
+ * - 分红 + CASH：DIVIDEND 交易，数量为持仓份额，价格为每份分红。
+ * - 分红 + REINVEST：REINVEST 交易，份额 = 持仓份额 × 每份分红 / 除息日净值。
+ * - 折算：SPLIT 交易，数量为折算比例（CASH 和 REINVEST 都会生成）。
+ * 交易通过 `transactions.corporate_action_id` 关联事件，同一资产的同一事件只生成一次。
+ */
+use crate::adapters;
+use crate::config::Config;
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    CorporateAction, CorporateActionEvent, CorporateActionSyncReport, CorporateActionType,
+    DividendMethod, TransactionType,
+};
+use crate::services::position::load_asset_position;
+use chrono::{Duration, TimeZone, Utc};
+use log::{error, info, warn};
+use rusqlite::params;
+use tokio::task::JoinSet;
+
+/// 手动同步时默认回溯的天数
+pub const DEFAULT_LOOKBACK_DAYS: i64 = 365;
+
+/// 需要检查公司行为的 (资产类型, 代码, 数据源)
+fn load_symbols(user_id: Option<i64>) -> Result<Vec<(String, String, String)>, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let sources = Config::get().market_sync.sources;
+
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT UPPER(t.name), a.code
+         FROM assets a
+         JOIN asset_types t ON a.asset_type_id = t.id
+         WHERE (?1 IS NULL OR a.user_id = ?1)",
+    )?;
+
+    let rows = stmt
+        .query_map(params![user_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to load assets for corporate actions: {}", e);
+            AuthError::DatabaseError(format!("获取资产失败: {}", e))
+        })?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(asset_type, code)| {
+            sources
+                .get(&asset_type)
+                .map(|source| (asset_type, code, source.clone()))
+        })
+        .collect())
+}
+
+/// 保存事件，返回新增条数
+fn save_events(
+    asset_type: &str,
+    source: &str,
+    events: &[CorporateActionEvent],
+) -> Result<usize, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let mut saved = 0;
+
+    for event in events {
+        saved += conn.execute(
+            "INSERT OR IGNORE INTO corporate_actions
+             (asset_type, symbol, action_type, ex_date, value, nav, source, detected_from, description, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                asset_type,
+                event.symbol,
+                event.action_type.to_str(),
+                event.ex_date,
+                event.value,
+                event.nav,
+                source,
+                event.detected_from,
+                event.description,
+                now
+            ],
+        )?;
+    }
+
+    Ok(saved)
+}
+
+/// 同步公司行为并自动生成交易
+///
+/// - `user_id`: 为 None 时检查所有用户的资产
+/// - `lookback_days`: 回溯天数，需要覆盖除息日前一天的净值才能推算分红
+pub async fn sync_corporate_actions(
+    user_id: Option<i64>,
+    lookback_days: i64,
+) -> Result<CorporateActionSyncReport, AuthError> {
+    let started_at = Utc::now().timestamp();
+    let symbols = load_symbols(user_id)?;
+    let timeout = std::time::Duration::from_secs(
+        Config::get().market_sync.request_timeout_seconds.max(1),
+    );
+
+    let end = Utc::now();
+    let start = end - Duration::days(lookback_days.max(2));
+
+    let mut tasks = JoinSet::new();
+    for (asset_type, code, source) in symbols.iter().cloned() {
+        tasks.spawn(async move {
+            let fetched = tokio::time::timeout(timeout, async {
+                let adapter = adapters::get_adapter(&asset_type.to_lowercase(), &source)?;
+                adapter.get_corporate_actions(&code, start, end).await
+            })
+            .await
+            .unwrap_or_else(|_| Err("请求超时".to_string()));
+            (asset_type, code, source, fetched)
+        });
+    }
+
+    let mut actions_found = 0;
+    let mut actions_saved = 0;
+    let mut errors = Vec::new();
+
+    while let Some(joined) = tasks.join_next().await {
+        let (asset_type, code, source, fetched) = match joined {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Corporate action task panicked: {}", e);
+                continue;
+            }
+        };
+
+        let saved = fetched.and_then(|events| {
+            actions_found += events.len();
+            save_events(&asset_type, &source, &events).map_err(|e| e.to_string())
+        });
+
+        match saved {
+            Ok(count) => actions_saved += count,
+            Err(e) => {
+                warn!("Corporate action sync failed for {} {}: {}", asset_type, code, e);
+                errors.push(format!("{} {}: {}", asset_type, code, e));
+            }
+        }
+    }
+
+    let transactions_created = apply_corporate_actions(user_id)?;
+
+    let report = CorporateActionSyncReport {
+        started_at,
+        finished_at: Utc::now().timestamp(),
+        symbols_checked: symbols.len(),
+        actions_found,
+        actions_saved,
+        transactions_created,
+        errors,
+    };
+
+    info!(
+        "Corporate action sync finished: {} new actions, {} transactions created",
+        report.actions_saved, report.transactions_created
+    );
+    Ok(report)
+}
+
+/// 为开启自动处理的资产生成尚未生成的分红/再投资/折算交易，返回生成的交易数
+pub fn apply_corporate_actions(user_id: Option<i64>) -> Result<usize, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.user_id, a.dividend_method, c.id, c.action_type, c.ex_date, c.value, c.nav, c.description
+         FROM assets a
+         JOIN asset_types t ON a.asset_type_id = t.id
+         JOIN corporate_actions c ON c.asset_type = UPPER(t.name) AND c.symbol = a.code
+         WHERE a.dividend_method != 'NONE'
+           AND (?1 IS NULL OR a.user_id = ?1)
+           AND NOT EXISTS (
+               SELECT 1 FROM transactions x
+               WHERE x.asset_id = a.id AND x.corporate_action_id = c.id
+           )
+         ORDER BY c.ex_date, c.id",
+    )?;
+
+    let pending = stmt
+        .query_map(params![user_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, f64>(6)?,
+                row.get::<_, Option<f64>>(7)?,
+                row.get::<_, String>(8)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to load pending corporate actions: {}", e);
+            AuthError::DatabaseError(format!("获取待处理公司行为失败: {}", e))
+        })?;
+
+    let mut created = 0;
+
+    for (asset_id, owner_id, method, action_id, action_type, ex_date, value, nav, description) in
+        pending
+    {
+        let method = DividendMethod::from_str(&method).unwrap_or(DividendMethod::None);
+        let action_type = match CorporateActionType::from_str(&action_type) {
+            Some(action_type) => action_type,
+            None => continue,
+        };
+
+        // 除息日当天的交易不参与分红
+        let position = load_asset_position(&conn, asset_id, Some(ex_date - 1), None)?;
+        if position.shares <= 0.0 {
+            continue;
+        }
+
+        let (kind, amount, price) = match (action_type, method) {
+            (CorporateActionType::Split, _) => (TransactionType::Split, value, 0.0),
+            (CorporateActionType::Dividend, DividendMethod::Cash) => {
+                (TransactionType::Dividend, position.shares, value)
+            }
+            (CorporateActionType::Dividend, DividendMethod::Reinvest) => {
+                let reinvest_nav = match nav.or_else(|| {
+                    conn.query_row(
+                        "SELECT close_price FROM price_history WHERE asset_id = ?1 AND date = ?2",
+                        params![asset_id, ex_date],
+                        |row| row.get(0),
+                    )
+                    .ok()
+                }) {
+                    Some(nav) if nav > 0.0 => nav,
+                    _ => {
+                        warn!(
+                            "Missing NAV for reinvestment of action {} on asset {}",
+                            action_id, asset_id
+                        );
+                        continue;
+                    }
+                };
+                (
+                    TransactionType::Reinvest,
+                    position.shares * value / reinvest_nav,
+                    reinvest_nav,
+                )
+            }
+            (CorporateActionType::Dividend, DividendMethod::None) => continue,
+        };
+
+        conn.execute(
+            "INSERT INTO transactions (
+                user_id, asset_id, transaction_type, amount, price,
+                total_cost, transaction_date, notes, corporate_action_id, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                owner_id,
+                asset_id,
+                kind.to_str(),
+                amount,
+                price,
+                kind.total_cost(amount, price),
+                ex_date,
+                format!("自动生成：{}", description),
+                action_id,
+                now
+            ],
+        )?;
+        created += 1;
+
+        info!(
+            "Created {} transaction for asset {} from corporate action {} ({})",
+            kind.to_str(),
+            asset_id,
+            action_id,
+            Utc.timestamp_opt(ex_date, 0)
+                .single()
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        );
+    }
+
+    Ok(created)
+}
+
+/// 获取资产对应的公司行为，按除息日倒序
+pub fn get_asset_corporate_actions(
+    user_id: i64,
+    asset_id: i64,
+) -> Result<Vec<CorporateAction>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let (asset_type, code): (String, String) = conn
+        .query_row(
+            "SELECT UPPER(t.name), a.code
+             FROM assets a
+             JOIN asset_types t ON a.asset_type_id = t.id
+             WHERE a.id = ?1 AND a.user_id = ?2",
+            params![asset_id, user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| AuthError::InvalidCredentials("资产不存在或无权限".to_string()))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, asset_type, symbol, action_type, ex_date, value, nav, source, detected_from, description, created_at
+         FROM corporate_actions
+         WHERE asset_type = ?1 AND symbol = ?2
+         ORDER BY ex_date DESC",
+    )?;
+
+    let actions = stmt
+        .query_map(params![asset_type, code], |row| {
+            Ok(CorporateAction {
+                id: row.get(0)?,
+                asset_type: row.get(1)?,
+                symbol: row.get(2)?,
+                action_type: row.get(3)?,
+                ex_date: row.get(4)?,
+                value: row.get(5)?,
+                nav: row.get(6)?,
+                source: row.get(7)?,
+                detected_from: row.get(8)?,
+                description: row.get(9)?,
+                created_at: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch corporate actions: {}", e);
+            AuthError::DatabaseError(format!("获取公司行为失败: {}", e))
+        })?;
+
+    Ok(actions)
+}
+
+/// 设置资产的分红处理方式
+pub fn set_dividend_method(
+    user_id: i64,
+    asset_id: i64,
+    dividend_method: &str,
+) -> Result<(), AuthError> {
+    let method = DividendMethod::from_str(dividend_method).ok_or_else(|| {
+        AuthError::InvalidCredentials("分红方式无效，必须为 NONE、CASH 或 REINVEST".to_string())
+    })?;
+
+    let conn = get_connection_from_pool()?;
+    let updated = conn.execute(
+        "UPDATE assets SET dividend_method = ?1, updated_at = ?2 WHERE id = ?3 AND user_id = ?4",
+        params![method.to_str(), Utc::now().timestamp(), asset_id, user_id],
+    )?;
+
+    if updated == 0 {
+        return Err(AuthError::InvalidCredentials("资产不存在或无权限".to_string()));
+    }
+
+    info!("Dividend method of asset {} set to {}", asset_id, method.to_str());
+    Ok(())
+}
@@ -6,12 +6,13 @@
  * 包含的主要功能有：
  * - 更新单个或批量资产价格，并同步价格历史记录。
  * - 查询资产的价格历史数据，支持按时间范围筛选。
- * - 创建、标记已读和获取用户的交易提醒（如买卖信号等）。
+ * - 创建、标记已读和获取用户的交易提醒（如买卖信号等），提醒规则自动生成的提醒关联触发的规则。
  * - 获取用户投资组合的汇总信息，包括各类资产的市值、成本、收益、日收益等。
  *
  * 主要函数说明：
  * - `update_asset_price(asset_id, price, date)`: 更新指定资产的当前价格及价格历史。
  * - `update_asset_price_batch(asset_prices)`: 批量更新多个资产的价格及价格历史。
+ *   价格更新后评估相关资产的止盈止损规则、提醒规则和策略信号（`evaluate_price_triggers`）。
  * - `get_asset_price_history(asset_id, start_date, end_date)`: 获取指定资产在时间区间内的价格历史。
  * - `create_trade_alert(user_id, asset_id, strategy_id, alert_type, message)`: 为用户创建交易提醒。
  * - `mark_alert_read(id, user_id)`: 标记指定交易提醒为已读。
@@ -31,11 +32,23 @@
 use crate::database::get_connection_from_pool;
 use crate::error::auth::AuthError;
 use crate::models::{AssetSummary, PortfolioSummary, PriceHistory, TradeAlert};
+use crate::services::{alert_rule, exit_rule, strategy_signal};
+use crate::services::performance::get_portfolio_returns;
+use crate::services::fx::FxConverter;
+use crate::services::position::load_user_ledgers_converted;
+use crate::services::settings::load_user_settings;
 use chrono::{Duration, NaiveDate, Utc};
 use log::{error, info};
 use rusqlite::params;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// 价格更新后评估相关资产的止盈止损规则、提醒规则和策略信号，失败只记录日志
+pub fn evaluate_price_triggers(asset_ids: &[i64]) {
+    exit_rule::evaluate_after_price_update(asset_ids);
+    alert_rule::evaluate_after_price_update(asset_ids);
+    strategy_signal::evaluate_after_price_update(asset_ids);
+}
 
 pub fn update_asset_price(asset_id: i64, price: f64, date: i64) -> Result<(), AuthError> {
     let mut conn = get_connection_from_pool()?;
@@ -72,6 +85,7 @@ pub fn update_asset_price(asset_id: i64, price: f64, date: i64) -> Result<(), Au
     }
 
     info!("Asset price updated: {} at {}", asset_id, date);
+    evaluate_price_triggers(&[asset_id]);
     Ok(())
 }
 
@@ -122,6 +136,8 @@ pub fn update_asset_price_batch(asset_prices: &[(i64, f64, i64)]) -> Result<usiz
     tx.commit()?;
 
     info!("Batch updated {} asset prices", updated_count);
+    let asset_ids: Vec<i64> = asset_prices.iter().map(|(asset_id, _, _)| *asset_id).collect();
+    evaluate_price_triggers(&asset_ids);
     Ok(updated_count)
 }
 
@@ -335,6 +351,10 @@ pub fn create_trade_alert(
         asset_code,
         strategy_id,
         strategy_name,
+        signal_strategy_id: None,
+        signal_strategy_name: None,
+        rule_id: None,
+        rule_name: None,
         alert_type: alert_type.to_string(),
         message: message.to_string(),
         is_read: false,
@@ -386,39 +406,51 @@ pub fn get_user_trade_alerts(
     let mut query = match (is_read, limit) {
         (Some(read), Some(lim)) => conn.prepare(
             "SELECT a.id, a.user_id, a.asset_id, ast.name, ast.code, a.strategy_id, 
-                        s.name, a.alert_type, a.message, a.is_read, a.created_at
+                        s.name, a.alert_type, a.message, a.is_read, a.created_at, a.rule_id, r.name,
+                        a.signal_strategy_id, ss.name
                  FROM trade_alerts a
                  JOIN assets ast ON a.asset_id = ast.id
                  LEFT JOIN investment_strategies s ON a.strategy_id = s.id
+                 LEFT JOIN strategies ss ON a.signal_strategy_id = ss.id
+                 LEFT JOIN alert_rules r ON a.rule_id = r.id
                  WHERE a.user_id = ?1 AND a.is_read = ?2
                  ORDER BY a.created_at DESC
                  LIMIT ?3",
         )?,
         (Some(read), None) => conn.prepare(
             "SELECT a.id, a.user_id, a.asset_id, ast.name, ast.code, a.strategy_id, 
-                        s.name, a.alert_type, a.message, a.is_read, a.created_at
+                        s.name, a.alert_type, a.message, a.is_read, a.created_at, a.rule_id, r.name,
+                        a.signal_strategy_id, ss.name
                  FROM trade_alerts a
                  JOIN assets ast ON a.asset_id = ast.id
                  LEFT JOIN investment_strategies s ON a.strategy_id = s.id
+                 LEFT JOIN strategies ss ON a.signal_strategy_id = ss.id
+                 LEFT JOIN alert_rules r ON a.rule_id = r.id
                  WHERE a.user_id = ?1 AND a.is_read = ?2
                  ORDER BY a.created_at DESC",
         )?,
         (None, Some(lim)) => conn.prepare(
             "SELECT a.id, a.user_id, a.asset_id, ast.name, ast.code, a.strategy_id, 
-                        s.name, a.alert_type, a.message, a.is_read, a.created_at
+                        s.name, a.alert_type, a.message, a.is_read, a.created_at, a.rule_id, r.name,
+                        a.signal_strategy_id, ss.name
                  FROM trade_alerts a
                  JOIN assets ast ON a.asset_id = ast.id
                  LEFT JOIN investment_strategies s ON a.strategy_id = s.id
+                 LEFT JOIN strategies ss ON a.signal_strategy_id = ss.id
+                 LEFT JOIN alert_rules r ON a.rule_id = r.id
                  WHERE a.user_id = ?1
                  ORDER BY a.created_at DESC
                  LIMIT ?2",
         )?,
         (None, None) => conn.prepare(
             "SELECT a.id, a.user_id, a.asset_id, ast.name, ast.code, a.strategy_id, 
-                        s.name, a.alert_type, a.message, a.is_read, a.created_at
+                        s.name, a.alert_type, a.message, a.is_read, a.created_at, a.rule_id, r.name,
+                        a.signal_strategy_id, ss.name
                  FROM trade_alerts a
                  JOIN assets ast ON a.asset_id = ast.id
                  LEFT JOIN investment_strategies s ON a.strategy_id = s.id
+                 LEFT JOIN strategies ss ON a.signal_strategy_id = ss.id
+                 LEFT JOIN alert_rules r ON a.rule_id = r.id
                  WHERE a.user_id = ?1
                  ORDER BY a.created_at DESC",
         )?,
@@ -435,6 +467,10 @@ pub fn get_user_trade_alerts(
                     asset_code: row.get(4)?,
                     strategy_id: row.get(5)?,
                     strategy_name: row.get(6)?,
+                    signal_strategy_id: row.get(13)?,
+                    signal_strategy_name: row.get(14)?,
+                    rule_id: row.get(11)?,
+                    rule_name: row.get(12)?,
                     alert_type: row.get(7)?,
                     message: row.get(8)?,
                     is_read: row.get(9)?,
@@ -456,6 +492,10 @@ pub fn get_user_trade_alerts(
                     asset_code: row.get(4)?,
                     strategy_id: row.get(5)?,
                     strategy_name: row.get(6)?,
+                    signal_strategy_id: row.get(13)?,
+                    signal_strategy_name: row.get(14)?,
+                    rule_id: row.get(11)?,
+                    rule_name: row.get(12)?,
                     alert_type: row.get(7)?,
                     message: row.get(8)?,
                     is_read: row.get(9)?,
@@ -477,6 +517,10 @@ pub fn get_user_trade_alerts(
                     asset_code: row.get(4)?,
                     strategy_id: row.get(5)?,
                     strategy_name: row.get(6)?,
+                    signal_strategy_id: row.get(13)?,
+                    signal_strategy_name: row.get(14)?,
+                    rule_id: row.get(11)?,
+                    rule_name: row.get(12)?,
                     alert_type: row.get(7)?,
                     message: row.get(8)?,
                     is_read: row.get(9)?,
@@ -498,6 +542,10 @@ pub fn get_user_trade_alerts(
                     asset_code: row.get(4)?,
                     strategy_id: row.get(5)?,
                     strategy_name: row.get(6)?,
+                    signal_strategy_id: row.get(13)?,
+                    signal_strategy_name: row.get(14)?,
+                    rule_id: row.get(11)?,
+                    rule_name: row.get(12)?,
                     alert_type: row.get(7)?,
                     message: row.get(8)?,
                     is_read: row.get(9)?,
@@ -519,11 +567,7 @@ pub fn get_portfolio_summary(user_id: i64) -> Result<PortfolioSummary, AuthError
 
     // 获取用户所有资产
     let mut stmt = conn.prepare(
-        "SELECT a.id, a.asset_type_id, t.name, a.current_price,
-                (SELECT SUM(CASE WHEN transaction_type = 'BUY' THEN amount ELSE -amount END) 
-                 FROM transactions WHERE asset_id = a.id) as total_amount,
-                (SELECT SUM(CASE WHEN transaction_type = 'BUY' THEN total_cost ELSE -total_cost END) 
-                 FROM transactions WHERE asset_id = a.id) as total_cost
+        "SELECT a.id, a.asset_type_id, t.name, a.current_price, a.currency
          FROM assets a
          JOIN asset_types t ON a.asset_type_id = t.id
          WHERE a.user_id = ?1"
@@ -536,8 +580,7 @@ pub fn get_portfolio_summary(user_id: i64) -> Result<PortfolioSummary, AuthError
                 row.get::<_, i64>(1)?,
                 row.get::<_, String>(2)?,
                 row.get::<_, Option<f64>>(3)?,
-                row.get::<_, Option<f64>>(4)?,
-                row.get::<_, Option<f64>>(5)?,
+                row.get::<_, String>(4)?,
             ))
         })?
         .collect::<Result<Vec<_>, _>>()
@@ -546,77 +589,90 @@ pub fn get_portfolio_summary(user_id: i64) -> Result<PortfolioSummary, AuthError
             AuthError::DatabaseError(format!("获取用户资产失败: {}", e))
         })?;
 
-    // 按资产类型分组计算
+    // 按交易流水和用户的成本计算方法计算持仓（含分红、再投资、折算、转入转出和费用），
+    // 成本按交易当日汇率、市值按当前汇率换算为基准币种
+    let method = load_user_settings(&conn, user_id)?.cost_basis();
+    let mut fx = FxConverter::for_user(&conn, user_id)?;
+    let positions = load_user_ledgers_converted(&conn, user_id, method, &mut fx).map_err(|e| {
+        error!("Failed to calculate positions: {}", e);
+        AuthError::DatabaseError(format!("计算持仓失败: {}", e))
+    })?;
+
+    // 按资产类型分组计算：(市值, 成本, 已实现收益, 浮动盈亏, 当日收益)
     let mut asset_summaries = Vec::new();
-    let mut asset_type_map: HashMap<String, (f64, f64, f64, f64)> = HashMap::new();
+    let mut asset_type_map: HashMap<String, (f64, f64, f64, f64, f64)> = HashMap::new();
+    let mut unconverted_types: HashSet<String> = HashSet::new();
 
+    let now = Utc::now().timestamp();
     let yesterday = (Utc::now() - Duration::days(1)).timestamp();
 
     let mut total_value = 0.0;
     let mut total_cost = 0.0;
+    let mut total_realized_profit = 0.0;
+    let mut total_unrealized_profit = 0.0;
     let mut total_daily_profit = 0.0;
 
-    for (asset_id, _, asset_type, current_price, total_amount, total_cost_asset) in assets {
-        if let (Some(price), Some(amount), Some(cost)) =
-            (current_price, total_amount, total_cost_asset)
-        {
-            if amount <= 0.0 {
-                continue; // 跳过没有持仓的资产
-            }
-
-            let current_value = price * amount;
-            let profit = current_value - cost;
-            let profit_percent = if cost > 0.0 {
-                profit / cost * 100.0
-            } else {
-                0.0
-            };
-
-            // 获取昨日价格
-            let yesterday_price: Option<f64> = conn
-                .query_row(
-                    "SELECT close_price FROM price_history 
-                 WHERE asset_id = ?1 AND date <= ?2 
-                 ORDER BY date DESC LIMIT 1",
-                    params![asset_id, yesterday],
-                    |row| row.get(0),
-                )
-                .ok();
-
-            let daily_profit = if let Some(prev_price) = yesterday_price {
-                (price - prev_price) * amount
-            } else {
-                0.0
-            };
-
-            let daily_profit_percent = if let Some(prev_price) = yesterday_price {
-                if prev_price > 0.0 {
-                    (price - prev_price) / prev_price * 100.0
-                } else {
-                    0.0
-                }
-            } else {
-                0.0
-            };
-
-            // 更新资产类型统计
-            let entry = asset_type_map
-                .entry(asset_type.clone())
-                .or_insert((0.0, 0.0, 0.0, 0.0));
-            entry.0 += current_value;
-            entry.1 += cost;
-            entry.2 += profit;
-            entry.3 += daily_profit;
-
-            // 更新总计
-            total_value += current_value;
-            total_cost += cost;
-            total_daily_profit += daily_profit;
+    for (asset_id, _, asset_type, current_price, currency) in assets {
+        let position = match positions.get(&asset_id) {
+            Some(ledger) => &ledger.position,
+            None => continue,
+        };
+
+        // 已清仓资产的已实现收益同样计入总收益
+        let entry = asset_type_map
+            .entry(asset_type.clone())
+            .or_insert((0.0, 0.0, 0.0, 0.0, 0.0));
+        entry.2 += position.realized_profit;
+        total_realized_profit += position.realized_profit;
+
+        // 台账已按交易日换算，缺少该币种汇率时标记该类资产未换算
+        if fx.is_missing(&currency) {
+            unconverted_types.insert(asset_type.clone());
         }
+
+        let amount = position.shares;
+        let cost = position.cost;
+        let price = match current_price {
+            Some(price) if amount > 0.0 => fx.convert(price, &currency, now)?,
+            _ => continue, // 跳过没有持仓或没有价格的资产
+        };
+
+        let current_value = price * amount;
+        let unrealized_profit = position.unrealized_profit(price);
+
+        // 获取昨日价格
+        let yesterday_price: Option<f64> = conn
+            .query_row(
+                "SELECT close_price FROM price_history 
+             WHERE asset_id = ?1 AND date <= ?2 
+             ORDER BY date DESC LIMIT 1",
+                params![asset_id, yesterday],
+                |row| row.get(0),
+            )
+            .ok();
+
+        let daily_profit = if let Some(prev_price) = yesterday_price {
+            (price - fx.convert(prev_price, &currency, yesterday)?) * amount
+        } else {
+            0.0
+        };
+
+        // 更新资产类型统计
+        entry.0 += current_value;
+        entry.1 += cost;
+        entry.3 += unrealized_profit;
+        entry.4 += daily_profit;
+
+        // 更新总计
+        total_value += current_value;
+        total_cost += cost;
+        total_unrealized_profit += unrealized_profit;
+        total_daily_profit += daily_profit;
     }
 
     // 生成资产类型摘要
-    for (asset_type, (value, cost, profit, daily_profit)) in asset_type_map {
+    for (asset_type, (value, cost, realized_profit, unrealized_profit, daily_profit)) in asset_type_map {
+        let profit = realized_profit + unrealized_profit;
         let profit_percent = if cost > 0.0 {
             profit / cost * 100.0
         } else {
@@ -629,18 +685,23 @@ pub fn get_portfolio_summary(user_id: i64) -> Result<PortfolioSummary, AuthError
         };
 
         asset_summaries.push(AssetSummary {
+            converted: !unconverted_types.contains(&asset_type),
             asset_type,
             total_value: value,
             total_cost: cost,
             total_profit: profit,
             total_profit_percent: profit_percent,
+            realized_profit,
+            unrealized_profit,
             daily_profit,
             daily_profit_percent,
         });
     }
 
+    fx.ensure_rates()?;
+
     // 计算总体摘要
-    let total_profit = total_value - total_cost;
+    let total_profit = total_realized_profit + total_unrealized_profit;
     let total_profit_percent = if total_cost > 0.0 {
         total_profit / total_cost * 100.0
     } else {
@@ -652,14 +713,26 @@ pub fn get_portfolio_summary(user_id: i64) -> Result<PortfolioSummary, AuthError
         0.0
     };
 
+    // 资金加权和时间加权收益率
+    let returns = get_portfolio_returns(&conn, user_id).map_err(|e| {
+        error!("Failed to calculate portfolio returns: {}", e);
+        AuthError::DatabaseError(format!("计算组合收益率失败: {}", e))
+    })?;
+
     let summary = PortfolioSummary {
+        base_currency: fx.base_currency().to_string(),
         total_value,
         total_cost,
         total_profit,
         total_profit_percent,
+        realized_profit: total_realized_profit,
+        unrealized_profit: total_unrealized_profit,
+        xirr_percent: returns.xirr_percent,
+        twr_percent: returns.twr_percent,
         daily_profit: total_daily_profit,
         daily_profit_percent,
         asset_summaries,
+        warnings: fx.missing_rate_warnings(),
     };
 
     Ok(summary)
@@ -0,0 +1,558 @@
+/**
+ * K线数据质量检查模块
+ *
+ * 导入的K线在进入 `candles` 表和回测之前，需要检查以下问题：
+ * - 重复时间戳、时间戳乱序
+ * - 价格为零/负数/非数字
+ * - 最高价低于最低价，开盘价或收盘价超出高低区间
+ * - 缺失K线（按周期计算缺口，日线指定交易所时按交易日计算，周末和休市日不算缺失）
+ * - 相对前一根K线的异常跳变
+ *
+ * 主要函数说明：
+ * - `check_candles(...)`: 纯函数，对一组K线生成质量报告，行情同步和独立命令共用。
+ * - `repair_candles(...)`: 纯函数，按修复模式（去重、前值填充、剔除异常值）返回修复后的K线。
+ * - `check_dataset(request)`: 从 `candles` 表读取数据集，生成报告，可选修复并回写。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    AnomalyKind, Candle, CandleAnomaly, CandleGap, CheckCandleQualityRequest, DataQualityReport,
+    RepairMode, RepairSummary,
+};
+use crate::services::calendar::{get_calendar, parse_exchange, TradingCalendar};
+use crate::services::snapshot::local_date;
+use chrono::{Duration, TimeZone, Utc};
+use log::{error, info, warn};
+use rusqlite::params;
+use std::collections::{BTreeMap, HashSet};
+
+/// 默认跳变阈值：收盘价相对前一根K线变化超过 20% 视为异常
+pub const DEFAULT_SPIKE_THRESHOLD: f64 = 0.2;
+
+/// 周期对应的秒数，未知周期返回 None（不做缺口检查）
+pub fn interval_seconds(interval: &str) -> Option<i64> {
+    match interval {
+        "1m" => Some(60),
+        "5m" => Some(5 * 60),
+        "15m" => Some(15 * 60),
+        "30m" => Some(30 * 60),
+        "1h" => Some(60 * 60),
+        "4h" => Some(4 * 60 * 60),
+        "1d" => Some(24 * 60 * 60),
+        "1w" => Some(7 * 24 * 60 * 60),
+        _ => None,
+    }
+}
+
+/// 只有日线按交易日历计算缺口和填充
+fn daily_calendar<'a>(
+    interval: &str,
+    calendar: Option<&'a TradingCalendar>,
+) -> Option<&'a TradingCalendar> {
+    calendar.filter(|_| interval == "1d")
+}
+
+fn is_valid_price(candle: &Candle) -> bool {
+    [candle.open, candle.high, candle.low, candle.close]
+        .iter()
+        .all(|p| p.is_finite() && *p > 0.0)
+        && candle.volume.is_finite()
+        && candle.volume >= 0.0
+}
+
+fn is_high_low_consistent(candle: &Candle) -> bool {
+    candle.high >= candle.low
+        && candle.open <= candle.high
+        && candle.open >= candle.low
+        && candle.close <= candle.high
+        && candle.close >= candle.low
+}
+
+fn change_ratio(from: f64, to: f64) -> f64 {
+    (to / from - 1.0).abs()
+}
+
+/// 对一组K线生成质量报告（按传入顺序检查重复和乱序）
+pub fn check_candles(
+    symbol: &str,
+    source: &str,
+    interval: &str,
+    candles: &[Candle],
+    spike_threshold: f64,
+    calendar: Option<&TradingCalendar>,
+) -> DataQualityReport {
+    let mut anomalies = Vec::new();
+    let mut duplicate_count = 0;
+    let mut out_of_order_count = 0;
+    let mut invalid_price_count = 0;
+    let mut high_low_count = 0;
+    let mut spike_count = 0;
+
+    // 重复与乱序
+    let mut seen = HashSet::new();
+    let mut max_ts: Option<i64> = None;
+    for candle in candles {
+        let ts = candle.timestamp.timestamp();
+        if !seen.insert(ts) {
+            duplicate_count += 1;
+            anomalies.push(CandleAnomaly {
+                timestamp: ts,
+                kind: AnomalyKind::Duplicate.to_str().to_string(),
+                detail: "时间戳重复".to_string(),
+            });
+        } else if max_ts.map_or(false, |m| ts < m) {
+            out_of_order_count += 1;
+            anomalies.push(CandleAnomaly {
+                timestamp: ts,
+                kind: AnomalyKind::OutOfOrder.to_str().to_string(),
+                detail: format!("时间戳早于前序K线 {}", max_ts.unwrap_or_default()),
+            });
+        }
+        max_ts = Some(max_ts.map_or(ts, |m| m.max(ts)));
+    }
+
+    // 价格有效性
+    for candle in candles {
+        let ts = candle.timestamp.timestamp();
+        if !is_valid_price(candle) {
+            invalid_price_count += 1;
+            anomalies.push(CandleAnomaly {
+                timestamp: ts,
+                kind: AnomalyKind::InvalidPrice.to_str().to_string(),
+                detail: format!(
+                    "O:{} H:{} L:{} C:{} V:{}",
+                    candle.open, candle.high, candle.low, candle.close, candle.volume
+                ),
+            });
+        } else if !is_high_low_consistent(candle) {
+            high_low_count += 1;
+            anomalies.push(CandleAnomaly {
+                timestamp: ts,
+                kind: AnomalyKind::HighLowInverted.to_str().to_string(),
+                detail: format!(
+                    "O:{} H:{} L:{} C:{}",
+                    candle.open, candle.high, candle.low, candle.close
+                ),
+            });
+        }
+    }
+
+    // 排序去重后检查跳变和缺口（同一时间戳保留最后一条）
+    let sorted: BTreeMap<i64, &Candle> = candles
+        .iter()
+        .map(|c| (c.timestamp.timestamp(), c))
+        .collect();
+
+    let mut prev_close: Option<f64> = None;
+    for (ts, candle) in &sorted {
+        if !is_valid_price(candle) {
+            continue;
+        }
+        if let Some(prev) = prev_close {
+            let ratio = change_ratio(prev, candle.close);
+            if ratio > spike_threshold {
+                spike_count += 1;
+                anomalies.push(CandleAnomaly {
+                    timestamp: *ts,
+                    kind: AnomalyKind::Spike.to_str().to_string(),
+                    detail: format!(
+                        "收盘价 {} 相对前值 {} 变化 {:.2}%",
+                        candle.close,
+                        prev,
+                        ratio * 100.0
+                    ),
+                });
+            }
+        }
+        prev_close = Some(candle.close);
+    }
+
+    let mut gaps = Vec::new();
+    let mut missing_bars = 0;
+    if let Some(step) = interval_seconds(interval) {
+        let timestamps: Vec<i64> = sorted.keys().copied().collect();
+        for pair in timestamps.windows(2) {
+            let missing = match daily_calendar(interval, calendar) {
+                Some(calendar) => {
+                    calendar.trading_days_between(local_date(pair[0]), local_date(pair[1]))
+                }
+                None => (pair[1] - pair[0]) / step - 1,
+            };
+            if missing > 0 {
+                missing_bars += missing;
+                gaps.push(CandleGap {
+                    start: pair[0],
+                    end: pair[1],
+                    missing_bars: missing,
+                });
+            }
+        }
+    }
+
+    anomalies.sort_by_key(|a| a.timestamp);
+
+    DataQualityReport {
+        symbol: symbol.to_string(),
+        source: source.to_string(),
+        interval: interval.to_string(),
+        checked_at: Utc::now().timestamp(),
+        total_candles: candles.len(),
+        first_timestamp: sorted.keys().next().copied(),
+        last_timestamp: sorted.keys().next_back().copied(),
+        duplicate_count,
+        out_of_order_count,
+        invalid_price_count,
+        high_low_count,
+        spike_count,
+        missing_bars,
+        exchange: calendar.map(|calendar| calendar.exchange.to_str().to_string()),
+        gaps,
+        anomalies,
+        repair: None,
+    }
+}
+
+/// 按修复模式修复K线，返回按时间排序的结果
+///
+/// 剔除跳变时只剔除“孤立尖峰”：相对前值跳变超过阈值、且下一根K线又回到前值附近的K线，
+/// 避免真实的趋势性大涨大跌导致后续K线被连续剔除。
+pub fn repair_candles(
+    candles: &[Candle],
+    interval: &str,
+    modes: &[RepairMode],
+    spike_threshold: f64,
+    calendar: Option<&TradingCalendar>,
+) -> (Vec<Candle>, RepairSummary) {
+    let mut summary = RepairSummary {
+        modes: modes.iter().map(|m| m.to_str().to_string()).collect(),
+        ..Default::default()
+    };
+
+    let mut result: Vec<Candle> = candles.to_vec();
+    result.sort_by_key(|c| c.timestamp);
+
+    if modes.contains(&RepairMode::Dedupe) {
+        let deduped: BTreeMap<i64, Candle> = candles
+            .iter()
+            .map(|c| (c.timestamp.timestamp(), c.clone()))
+            .collect();
+        summary.removed_duplicates = candles.len() - deduped.len();
+        result = deduped.into_values().collect();
+    }
+
+    if modes.contains(&RepairMode::DropOutliers) {
+        let before = result.len();
+        let valid: Vec<Candle> = result
+            .into_iter()
+            .filter(|c| is_valid_price(c) && is_high_low_consistent(c))
+            .collect();
+
+        let mut kept: Vec<Candle> = Vec::with_capacity(valid.len());
+        for (i, candle) in valid.iter().enumerate() {
+            if let Some(prev) = kept.last() {
+                let jumped = change_ratio(prev.close, candle.close) > spike_threshold;
+                let reverted = valid
+                    .get(i + 1)
+                    .map_or(true, |next| change_ratio(prev.close, next.close) <= spike_threshold);
+                if jumped && reverted {
+                    continue;
+                }
+            }
+            kept.push(candle.clone());
+        }
+
+        summary.dropped_outliers = before - kept.len();
+        result = kept;
+    }
+
+    if modes.contains(&RepairMode::ForwardFill) {
+        if let Some(step) = interval_seconds(interval) {
+            let mut filled: Vec<Candle> = Vec::with_capacity(result.len());
+            for candle in result {
+                if let Some(prev) = filled.last().cloned() {
+                    let mut next_ts = prev.timestamp + Duration::seconds(step);
+                    while next_ts < candle.timestamp {
+                        // 日线只填充交易日
+                        let trading = daily_calendar(interval, calendar).map_or(true, |calendar| {
+                            calendar.is_trading_day(local_date(next_ts.timestamp()))
+                        });
+                        if trading {
+                            filled.push(Candle::new(
+                                next_ts, prev.close, prev.close, prev.close, prev.close, 0.0,
+                            ));
+                            summary.filled_bars += 1;
+                        }
+                        next_ts = next_ts + Duration::seconds(step);
+                    }
+                }
+                filled.push(candle);
+            }
+            result = filled;
+        }
+    }
+
+    summary.candles_after = result.len();
+    (result, summary)
+}
+
+/// 从 `candles` 表读取数据集（按写入顺序，以便检查乱序）
+fn load_dataset(
+    symbol: &str,
+    source: &str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> Result<Vec<Candle>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, open, high, low, close, volume FROM candles
+         WHERE symbol = ?1 AND source = ?2 AND timestamp >= ?3 AND timestamp <= ?4
+         ORDER BY id ASC",
+    )?;
+
+    let rows = stmt
+        .query_map(
+            params![
+                symbol,
+                source,
+                start_time.unwrap_or(i64::MIN),
+                end_time.unwrap_or(i64::MAX)
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, f64>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, f64>(3)?,
+                    row.get::<_, f64>(4)?,
+                    row.get::<_, f64>(5)?,
+                ))
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to load candles for {}/{}: {}", source, symbol, e);
+            AuthError::DatabaseError(format!("获取K线数据失败: {}", e))
+        })?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(ts, open, high, low, close, volume)| {
+            Utc.timestamp_opt(ts, 0)
+                .single()
+                .map(|t| Candle::new(t, open, high, low, close, volume))
+        })
+        .collect())
+}
+
+/// 用修复后的K线替换数据集在 [first, last] 范围内的数据
+fn replace_dataset(
+    symbol: &str,
+    source: &str,
+    first: i64,
+    last: i64,
+    candles: &[Candle],
+) -> Result<(), AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "DELETE FROM candles WHERE symbol = ?1 AND source = ?2 AND timestamp >= ?3 AND timestamp <= ?4",
+        params![symbol, source, first, last],
+    )?;
+
+    for candle in candles {
+        tx.execute(
+            "INSERT INTO candles (symbol, source, timestamp, open, high, low, close, volume)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(symbol, source, timestamp) DO UPDATE SET
+             open = excluded.open,
+             high = excluded.high,
+             low = excluded.low,
+             close = excluded.close,
+             volume = excluded.volume",
+            params![
+                symbol,
+                source,
+                candle.timestamp.timestamp(),
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// 检查 `candles` 表中的数据集，可选修复并保存报告
+pub fn check_dataset(request: &CheckCandleQualityRequest) -> Result<DataQualityReport, AuthError> {
+    let spike_threshold = request.spike_threshold.unwrap_or(DEFAULT_SPIKE_THRESHOLD);
+    if spike_threshold.is_nan() || spike_threshold <= 0.0 {
+        return Err(AuthError::InvalidCredentials("跳变阈值必须大于0".to_string()));
+    }
+
+    let mut modes = Vec::new();
+    for mode in request.repair_modes.iter().flatten() {
+        match RepairMode::from_str(mode) {
+            Some(m) => modes.push(m),
+            None => {
+                return Err(AuthError::InvalidCredentials(format!(
+                    "无效的修复模式: {}，必须为 dedupe、forward_fill 或 drop_outliers",
+                    mode
+                )))
+            }
+        }
+    }
+
+    let calendar = match &request.exchange {
+        Some(exchange) => Some(get_calendar(parse_exchange(exchange)?)),
+        None => None,
+    };
+
+    let candles = load_dataset(
+        &request.symbol,
+        &request.source,
+        request.start_time,
+        request.end_time,
+    )?;
+
+    let mut report = check_candles(
+        &request.symbol,
+        &request.source,
+        &request.interval,
+        &candles,
+        spike_threshold,
+        calendar.as_deref(),
+    );
+
+    if !modes.is_empty() {
+        if let (Some(first), Some(last)) = (report.first_timestamp, report.last_timestamp) {
+            let (repaired, summary) =
+                repair_candles(&candles, &request.interval, &modes, spike_threshold, calendar.as_deref());
+            replace_dataset(&request.symbol, &request.source, first, last, &repaired)?;
+            info!(
+                "Candles repaired for {}/{}: {:?}",
+                request.source, request.symbol, summary
+            );
+            report.repair = Some(summary);
+        }
+    }
+
+    if !report.is_clean() {
+        warn!(
+            "Data quality issues in {}/{} ({}): {} anomalies, {} gaps",
+            request.source,
+            request.symbol,
+            request.interval,
+            report.anomalies.len(),
+            report.gaps.len()
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+
+    /// 按小时编号生成K线，开高低收相同
+    fn bar(hour: i64, close: f64) -> Candle {
+        let timestamp = Utc.timestamp_opt(1_704_067_200 + hour * HOUR, 0).unwrap();
+        Candle::new(timestamp, close, close, close, close, 100.0)
+    }
+
+    fn hours(candles: &[Candle]) -> Vec<i64> {
+        candles
+            .iter()
+            .map(|c| (c.timestamp.timestamp() - 1_704_067_200) / HOUR)
+            .collect()
+    }
+
+    #[test]
+    fn check_reports_every_anomaly_kind() {
+        let mut inverted = bar(8, 10.4);
+        inverted.high = 10.0;
+        inverted.low = 11.0;
+        let candles = vec![
+            bar(0, 10.0),
+            bar(1, 10.1),
+            bar(1, 10.1),
+            bar(4, 10.2),
+            bar(3, 10.2),
+            bar(5, 15.0),
+            bar(6, 10.3),
+            bar(7, 0.0),
+            inverted,
+        ];
+
+        let report = check_candles("TEST", "test", "1h", &candles, DEFAULT_SPIKE_THRESHOLD, None);
+
+        assert_eq!(report.total_candles, 9);
+        assert_eq!(report.duplicate_count, 1);
+        assert_eq!(report.out_of_order_count, 1);
+        assert_eq!(report.invalid_price_count, 1);
+        assert_eq!(report.high_low_count, 1);
+        assert_eq!(report.spike_count, 2);
+        assert_eq!(report.missing_bars, 1);
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(report.gaps[0].missing_bars, 1);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn check_clean_series() {
+        let candles: Vec<Candle> = (0..5).map(|hour| bar(hour, 10.0 + hour as f64 * 0.1)).collect();
+        let report = check_candles("TEST", "test", "1h", &candles, DEFAULT_SPIKE_THRESHOLD, None);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn dedupe_keeps_last_bar_and_sorts() {
+        let candles = vec![bar(2, 10.2), bar(0, 10.0), bar(1, 10.1), bar(1, 10.15)];
+        let (repaired, summary) =
+            repair_candles(&candles, "1h", &[RepairMode::Dedupe], DEFAULT_SPIKE_THRESHOLD, None);
+
+        assert_eq!(hours(&repaired), vec![0, 1, 2]);
+        assert_eq!(repaired[1].close, 10.15);
+        assert_eq!(summary.removed_duplicates, 1);
+        assert_eq!(summary.candles_after, 3);
+    }
+
+    #[test]
+    fn forward_fill_uses_previous_close() {
+        let candles = vec![bar(0, 10.0), bar(3, 10.3)];
+        let (repaired, summary) =
+            repair_candles(&candles, "1h", &[RepairMode::ForwardFill], DEFAULT_SPIKE_THRESHOLD, None);
+
+        assert_eq!(hours(&repaired), vec![0, 1, 2, 3]);
+        assert_eq!(repaired[1].close, 10.0);
+        assert_eq!(repaired[2].open, 10.0);
+        assert_eq!(repaired[2].volume, 0.0);
+        assert_eq!(summary.filled_bars, 2);
+    }
+
+    #[test]
+    fn drop_outliers_removes_isolated_spikes_only() {
+        let candles = vec![
+            bar(0, 10.0),
+            bar(1, 15.0),
+            bar(2, 10.1),
+            bar(3, -1.0),
+            bar(4, 13.0),
+            bar(5, 13.2),
+        ];
+        let (repaired, summary) =
+            repair_candles(&candles, "1h", &[RepairMode::DropOutliers], DEFAULT_SPIKE_THRESHOLD, None);
+
+        // 1 点是孤立尖峰，3 点价格无效；4 点之后持续在高位，是真实的跳涨
+        assert_eq!(hours(&repaired), vec![0, 2, 4, 5]);
+        assert_eq!(summary.dropped_outliers, 2);
+    }
+}
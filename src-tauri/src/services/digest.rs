@@ -0,0 +1,698 @@
+/**
+ * 组合摘要报告模块
+ *
+ * 生成日报 / 周报，让用户不打开应用也能了解组合情况：
+ * - 组合概览：区间前一日和最新的组合净值快照（市值、累计净投入），计算市值变化和扣除净投入后的收益。
+ * - 涨跌幅排行：当前持仓资产在区间内的价格涨跌幅（资产币种），按绝对值取前 `digest.top_movers` 个。
+ * - 定投执行：区间内的定投执行记录；交易提醒：区间内生成的交易提醒。
+ * - 今日待执行定投：`get_today_investment_plans` 的结果。
+ *
+ * 报告渲染为 Markdown 和 HTML，按用户设置推送到通知渠道（邮件渠道发送 HTML，其余渠道发送 Markdown，
+ * 走通知队列，同样遵守免打扰和重试规则）或保存到 `digest.output_dir/user_<id>/`。
+ * 后台调度每天 `digest.send_hour` 之后为开启订阅的用户发送日报，周报在用户设置的星期发送。
+ */
+use crate::config::Config;
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    CostBasisMethod, DigestAlert, DigestDeliveryResult, DigestMover, DigestPeriod, DigestPlanExecution,
+    DigestPreview, DigestReport, DigestSettings, ReturnScope, UpdateDigestSettingsRequest,
+};
+use crate::services::investment_plan::get_today_investment_plans;
+use crate::services::notification::enqueue_user_notification;
+use crate::services::performance::{load_price_series, price_on};
+use crate::services::position::load_user_positions;
+use crate::services::scheduler::beijing_offset;
+use crate::services::settings::load_user_settings;
+use crate::services::snapshot::{day_start, local_date};
+use crate::utils::email_template::escape_html;
+use chrono::{DateTime, Datelike, Duration, Utc};
+use log::{error, info};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::path::Path;
+
+const VALUE_EPSILON: f64 = 1e-6;
+
+/// 报告中的一节
+struct Section {
+    title: String,
+    content: SectionContent,
+}
+
+enum SectionContent {
+    Table { headers: Vec<&'static str>, rows: Vec<Vec<String>> },
+    List(Vec<String>),
+}
+
+fn parse_period(period: &str) -> Result<DigestPeriod, AuthError> {
+    DigestPeriod::from_str(period).ok_or_else(|| {
+        AuthError::InvalidCredentials("无效的报告周期，支持的周期：DAILY, WEEKLY".to_string())
+    })
+}
+
+fn format_date(timestamp: i64) -> String {
+    local_date(timestamp).format("%Y-%m-%d").to_string()
+}
+
+fn format_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&beijing_offset())
+        .format("%m-%d %H:%M")
+        .to_string()
+}
+
+fn format_amount(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+fn format_signed(value: f64) -> String {
+    format!("{:+.2}", value)
+}
+
+fn load_settings(conn: &Connection, user_id: i64) -> Result<DigestSettings, rusqlite::Error> {
+    let settings = conn
+        .query_row(
+            "SELECT daily_enabled, weekly_enabled, weekly_day, send_to_channels, save_to_disk,
+                    last_daily_date, last_weekly_date, updated_at
+             FROM digest_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| {
+                Ok(DigestSettings {
+                    user_id,
+                    daily_enabled: row.get(0)?,
+                    weekly_enabled: row.get(1)?,
+                    weekly_day: row.get(2)?,
+                    send_to_channels: row.get(3)?,
+                    save_to_disk: row.get(4)?,
+                    last_daily_date: row.get(5)?,
+                    last_weekly_date: row.get(6)?,
+                    updated_at: row.get(7)?,
+                })
+            },
+        )
+        .optional()?;
+
+    // 未设置时不订阅，手动发送时推送到通知渠道
+    Ok(settings.unwrap_or(DigestSettings {
+        user_id,
+        daily_enabled: false,
+        weekly_enabled: false,
+        weekly_day: 7,
+        send_to_channels: true,
+        save_to_disk: false,
+        last_daily_date: None,
+        last_weekly_date: None,
+        updated_at: 0,
+    }))
+}
+
+/**
+ * @dev 获取用户的摘要报告设置
+ */
+pub fn get_digest_settings(user_id: i64) -> Result<DigestSettings, AuthError> {
+    let conn = get_connection_from_pool()?;
+    Ok(load_settings(&conn, user_id)?)
+}
+
+/**
+ * @dev 更新用户的摘要报告设置
+ */
+pub fn update_digest_settings(request: &UpdateDigestSettingsRequest) -> Result<DigestSettings, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let weekly_day = request.weekly_day.unwrap_or(7);
+    if !(1..=7).contains(&weekly_day) {
+        return Err(AuthError::InvalidCredentials("周报发送日应为 1-7（周一至周日）".to_string()));
+    }
+    if (request.daily_enabled || request.weekly_enabled) && !request.send_to_channels && !request.save_to_disk {
+        return Err(AuthError::InvalidCredentials(
+            "请至少选择一种报告投递方式（通知渠道或保存到本地）".to_string(),
+        ));
+    }
+
+    conn.execute(
+        "INSERT INTO digest_settings (
+            user_id, daily_enabled, weekly_enabled, weekly_day, send_to_channels, save_to_disk, updated_at
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(user_id) DO UPDATE SET
+            daily_enabled = excluded.daily_enabled,
+            weekly_enabled = excluded.weekly_enabled,
+            weekly_day = excluded.weekly_day,
+            send_to_channels = excluded.send_to_channels,
+            save_to_disk = excluded.save_to_disk,
+            updated_at = excluded.updated_at",
+        params![
+            request.user_id,
+            request.daily_enabled,
+            request.weekly_enabled,
+            weekly_day,
+            request.send_to_channels,
+            request.save_to_disk,
+            now
+        ],
+    )?;
+
+    info!("Digest settings updated for user: {}", request.user_id);
+    Ok(load_settings(&conn, request.user_id)?)
+}
+
+/// 组合快照：(市值, 累计净投入)
+fn load_portfolio_snapshot(
+    conn: &Connection,
+    user_id: i64,
+    before: i64,
+    inclusive: bool,
+) -> Result<Option<(f64, f64)>, rusqlite::Error> {
+    let operator = if inclusive { "<=" } else { "<" };
+    conn.query_row(
+        &format!(
+            "SELECT market_value, net_contribution FROM portfolio_snapshots
+             WHERE user_id = ?1 AND scope = ?2 AND scope_id = 0 AND snapshot_date {} ?3
+             ORDER BY snapshot_date DESC LIMIT 1",
+            operator
+        ),
+        params![user_id, ReturnScope::Portfolio.to_str(), before],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+/// 持仓资产的区间涨跌幅，按绝对值降序
+fn load_top_movers(
+    conn: &Connection,
+    user_id: i64,
+    start: i64,
+    end: i64,
+    limit: usize,
+) -> Result<Vec<DigestMover>, rusqlite::Error> {
+    let positions = load_user_positions(conn, user_id, CostBasisMethod::WeightedAverage)?;
+    let mut movers = Vec::new();
+
+    for (asset_id, position) in positions {
+        if position.shares <= VALUE_EPSILON {
+            continue;
+        }
+
+        let (asset_name, asset_code, currency): (String, String, String) = conn.query_row(
+            "SELECT name, code, currency FROM assets WHERE id = ?1",
+            params![asset_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        let series = load_price_series(conn, asset_id)?;
+        let (Some(start_price), Some(end_price)) = (price_on(&series, start - 1), price_on(&series, end)) else {
+            continue;
+        };
+        if start_price <= VALUE_EPSILON {
+            continue;
+        }
+
+        movers.push(DigestMover {
+            asset_id,
+            asset_name,
+            asset_code,
+            currency,
+            start_price,
+            end_price,
+            change_percent: (end_price / start_price - 1.0) * 100.0,
+            market_value: position.shares * end_price,
+        });
+    }
+
+    movers.sort_by(|a, b| b.change_percent.abs().total_cmp(&a.change_percent.abs()));
+    movers.truncate(limit);
+    Ok(movers)
+}
+
+fn load_plan_executions(
+    conn: &Connection,
+    user_id: i64,
+    start: i64,
+    end: i64,
+) -> Result<Vec<DigestPlanExecution>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT e.plan_id, p.name, a.name, e.scheduled_date, e.status, e.amount, e.fee, e.price, e.shares, e.message
+         FROM plan_executions e
+         JOIN investment_plans p ON e.plan_id = p.id
+         JOIN assets a ON e.asset_id = a.id
+         WHERE e.user_id = ?1 AND e.executed_at >= ?2 AND e.executed_at <= ?3
+         ORDER BY e.executed_at, e.id",
+    )?;
+
+    let executions = stmt
+        .query_map(params![user_id, start, end], |row| {
+            Ok(DigestPlanExecution {
+                plan_id: row.get(0)?,
+                plan_name: row.get(1)?,
+                asset_name: row.get(2)?,
+                scheduled_date: row.get(3)?,
+                status: row.get(4)?,
+                amount: row.get(5)?,
+                fee: row.get(6)?,
+                price: row.get(7)?,
+                shares: row.get(8)?,
+                message: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(executions)
+}
+
+fn load_alerts(conn: &Connection, user_id: i64, start: i64, end: i64) -> Result<Vec<DigestAlert>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT t.id, a.name, t.alert_type, t.message, t.created_at
+         FROM trade_alerts t
+         JOIN assets a ON t.asset_id = a.id
+         WHERE t.user_id = ?1 AND t.created_at >= ?2 AND t.created_at <= ?3
+         ORDER BY t.created_at, t.id",
+    )?;
+
+    let alerts = stmt
+        .query_map(params![user_id, start, end], |row| {
+            Ok(DigestAlert {
+                alert_id: row.get(0)?,
+                asset_name: row.get(1)?,
+                alert_type: row.get(2)?,
+                message: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(alerts)
+}
+
+/**
+ * @dev 生成摘要报告
+ * @param period 日报统计当天，周报统计最近 7 天（北京时间）
+ */
+pub fn generate_digest(user_id: i64, period: DigestPeriod) -> Result<DigestReport, AuthError> {
+    let now = Utc::now().timestamp();
+    let today = local_date(now);
+    let start_date = match period {
+        DigestPeriod::Daily => day_start(today),
+        DigestPeriod::Weekly => day_start(today - Duration::days(6)),
+    };
+
+    let upcoming_plans = get_today_investment_plans(user_id, 0)?;
+
+    let conn = get_connection_from_pool()?;
+    let base_currency = load_user_settings(&conn, user_id)?.base_currency;
+
+    let start_snapshot = load_portfolio_snapshot(&conn, user_id, start_date, false)?;
+    let end_snapshot = load_portfolio_snapshot(&conn, user_id, now, true)?;
+    let start_value = start_snapshot.map(|(value, _)| value);
+    let end_value = end_snapshot.map(|(value, _)| value);
+
+    let (value_change, value_change_percent, net_contribution, profit) = match (start_snapshot, end_snapshot) {
+        (Some((start_value, start_contribution)), Some((end_value, end_contribution))) => {
+            let change = end_value - start_value;
+            let contribution = end_contribution - start_contribution;
+            let percent = (start_value.abs() > VALUE_EPSILON).then(|| change / start_value * 100.0);
+            (Some(change), percent, Some(contribution), Some(change - contribution))
+        }
+        _ => (None, None, None, None),
+    };
+
+    let report = DigestReport {
+        user_id,
+        period: period.to_str().to_string(),
+        start_date,
+        end_date: now,
+        base_currency,
+        start_value,
+        end_value,
+        value_change,
+        value_change_percent,
+        net_contribution,
+        profit,
+        top_movers: load_top_movers(&conn, user_id, start_date, now, Config::get().digest.top_movers)?,
+        plan_executions: load_plan_executions(&conn, user_id, start_date, now)?,
+        alerts: load_alerts(&conn, user_id, start_date, now)?,
+        upcoming_plans,
+        generated_at: now,
+    };
+
+    Ok(report)
+}
+
+/// 报告标题
+fn digest_title(report: &DigestReport) -> String {
+    match DigestPeriod::from_str(&report.period) {
+        Some(DigestPeriod::Weekly) => format!(
+            "WolfQuant 周报 · {} ~ {}",
+            format_date(report.start_date),
+            format_date(report.end_date)
+        ),
+        _ => format!("WolfQuant 日报 · {}", format_date(report.end_date)),
+    }
+}
+
+/// 把报告整理为各节内容，供 Markdown 和 HTML 渲染
+fn build_sections(report: &DigestReport) -> Vec<Section> {
+    let currency = &report.base_currency;
+    let optional = |value: Option<f64>, signed: bool| match value {
+        Some(value) if signed => format!("{} {}", format_signed(value), currency),
+        Some(value) => format!("{} {}", format_amount(value), currency),
+        None => "-".to_string(),
+    };
+
+    let mut overview = vec![
+        format!("期初市值：{}", optional(report.start_value, false)),
+        format!("最新市值：{}", optional(report.end_value, false)),
+        format!(
+            "市值变化：{}{}",
+            optional(report.value_change, true),
+            report
+                .value_change_percent
+                .map(|percent| format!("（{:+.2}%）", percent))
+                .unwrap_or_default()
+        ),
+        format!("净投入：{}", optional(report.net_contribution, true)),
+        format!("收益：{}", optional(report.profit, true)),
+    ];
+    if report.end_value.is_none() {
+        overview.push("暂无净值快照，组合数据将在每日收盘同步后生成。".to_string());
+    }
+
+    let movers = report
+        .top_movers
+        .iter()
+        .map(|mover| {
+            vec![
+                mover.asset_name.clone(),
+                mover.asset_code.clone(),
+                format!("{:+.2}%", mover.change_percent),
+                format!("{} {}", format_amount(mover.end_price), mover.currency),
+                format!("{} {}", format_amount(mover.market_value), mover.currency),
+            ]
+        })
+        .collect();
+
+    let executions = report
+        .plan_executions
+        .iter()
+        .map(|execution| {
+            vec![
+                execution.plan_name.clone(),
+                execution.asset_name.clone(),
+                format_date(execution.scheduled_date),
+                execution.status.clone(),
+                format_amount(execution.amount),
+                execution.shares.map(|shares| format!("{:.4}", shares)).unwrap_or_else(|| "-".to_string()),
+                execution.message.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let alerts = report
+        .alerts
+        .iter()
+        .map(|alert| {
+            vec![
+                format_time(alert.created_at),
+                alert.asset_name.clone(),
+                alert.alert_type.clone(),
+                alert.message.clone(),
+            ]
+        })
+        .collect();
+
+    let upcoming = report
+        .upcoming_plans
+        .iter()
+        .map(|plan| {
+            vec![
+                plan.name.clone(),
+                plan.asset_name.clone(),
+                format_amount(plan.amount),
+                plan.next_execution.map(format_time).unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    vec![
+        Section {
+            title: "组合概览".to_string(),
+            content: SectionContent::List(overview),
+        },
+        Section {
+            title: "涨跌幅排行".to_string(),
+            content: SectionContent::Table {
+                headers: vec!["资产", "代码", "涨跌幅", "最新价", "持仓市值"],
+                rows: movers,
+            },
+        },
+        Section {
+            title: "定投执行".to_string(),
+            content: SectionContent::Table {
+                headers: vec!["计划", "资产", "计划日期", "状态", "金额", "份额", "说明"],
+                rows: executions,
+            },
+        },
+        Section {
+            title: "交易提醒".to_string(),
+            content: SectionContent::Table {
+                headers: vec!["时间", "资产", "类型", "内容"],
+                rows: alerts,
+            },
+        },
+        Section {
+            title: "今日待执行定投".to_string(),
+            content: SectionContent::Table {
+                headers: vec!["计划", "资产", "金额", "执行时间"],
+                rows: upcoming,
+            },
+        },
+    ]
+}
+
+fn escape_markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// 各节的 Markdown（不含标题，推送到通知渠道时使用）
+fn render_markdown_sections(sections: &[Section]) -> String {
+    let mut markdown = String::new();
+
+    for section in sections {
+        markdown.push_str(&format!("## {}\n\n", section.title));
+        match &section.content {
+            SectionContent::List(items) => {
+                for item in items {
+                    markdown.push_str(&format!("- {}\n", item));
+                }
+            }
+            SectionContent::Table { rows, .. } if rows.is_empty() => markdown.push_str("无\n"),
+            SectionContent::Table { headers, rows } => {
+                markdown.push_str(&format!("| {} |\n", headers.join(" | ")));
+                markdown.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|cell| escape_markdown_cell(cell)).collect();
+                    markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+            }
+        }
+        markdown.push('\n');
+    }
+
+    markdown.trim_end().to_string()
+}
+
+fn render_html(title: &str, sections: &[Section]) -> String {
+    let mut body = String::new();
+
+    for section in sections {
+        body.push_str(&format!(
+            "<h3 style=\"margin:24px 0 8px;\">{}</h3>\n",
+            escape_html(&section.title)
+        ));
+        match &section.content {
+            SectionContent::List(items) => {
+                body.push_str("<ul style=\"padding-left:20px;margin:0;\">\n");
+                for item in items {
+                    body.push_str(&format!("<li>{}</li>\n", escape_html(item)));
+                }
+                body.push_str("</ul>\n");
+            }
+            SectionContent::Table { rows, .. } if rows.is_empty() => {
+                body.push_str("<p style=\"color:#8f959e;margin:0;\">无</p>\n");
+            }
+            SectionContent::Table { headers, rows } => {
+                body.push_str("<table style=\"border-collapse:collapse;width:100%;font-size:13px;\">\n<tr>");
+                for header in headers {
+                    body.push_str(&format!(
+                        "<th style=\"text-align:left;border-bottom:1px solid #dee0e3;padding:6px;\">{}</th>",
+                        escape_html(header)
+                    ));
+                }
+                body.push_str("</tr>\n");
+                for row in rows {
+                    body.push_str("<tr>");
+                    for cell in row {
+                        body.push_str(&format!(
+                            "<td style=\"border-bottom:1px solid #f0f1f2;padding:6px;\">{}</td>",
+                            escape_html(cell)
+                        ));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</table>\n");
+            }
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"zh-CN\">
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+</head>
+<body style=\"margin:0;padding:24px;background:#f5f6f8;font-family:-apple-system,'PingFang SC','Microsoft YaHei',Arial,sans-serif;color:#1f2329;\">
+<div style=\"max-width:720px;margin:0 auto;background:#ffffff;border-radius:8px;padding:32px;\">
+<h2 style=\"margin:0 0 8px;color:#1f6feb;\">{title}</h2>
+{body}</div>
+</body>
+</html>
+",
+        title = escape_html(title),
+        body = body
+    )
+}
+
+/// 渲染报告：(标题, 不含标题的 Markdown, 完整 Markdown, HTML)
+fn render(report: &DigestReport) -> (String, String, String, String) {
+    let title = digest_title(report);
+    let sections = build_sections(report);
+    let markdown_body = render_markdown_sections(&sections);
+    let markdown = format!("# {}\n\n{}\n", title, markdown_body);
+    let html = render_html(&title, &sections);
+    (title, markdown_body, markdown, html)
+}
+
+/**
+ * @dev 生成并渲染摘要报告（预览，不投递）
+ */
+pub fn preview_digest(user_id: i64, period: &str) -> Result<DigestPreview, AuthError> {
+    let report = generate_digest(user_id, parse_period(period)?)?;
+    let (_, _, markdown, html) = render(&report);
+
+    Ok(DigestPreview { report, markdown, html })
+}
+
+/// 按设置投递报告
+fn deliver(report: &DigestReport, settings: &DigestSettings) -> Result<DigestDeliveryResult, AuthError> {
+    let (title, markdown_body, markdown, html) = render(report);
+    let mut result = DigestDeliveryResult::default();
+
+    if settings.send_to_channels {
+        result.queued = enqueue_user_notification(report.user_id, &title, &markdown_body, Some(&html))?;
+    }
+
+    if settings.save_to_disk {
+        let dir = Path::new(&Config::get().digest.output_dir).join(format!("user_{}", report.user_id));
+        fs::create_dir_all(&dir)
+            .map_err(|e| AuthError::InternalError(format!("创建报告目录失败: {}", e)))?;
+
+        let file_stem = format!(
+            "{}-{}",
+            report.period.to_lowercase(),
+            local_date(report.end_date).format("%Y-%m-%d")
+        );
+        for (extension, content) in [("md", &markdown), ("html", &html)] {
+            let path = dir.join(format!("{}.{}", file_stem, extension));
+            fs::write(&path, content)
+                .map_err(|e| AuthError::InternalError(format!("保存报告失败: {}", e)))?;
+            result.files.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    info!(
+        "Digest {} delivered for user {}: {} queued, {} files",
+        report.period,
+        report.user_id,
+        result.queued,
+        result.files.len()
+    );
+    Ok(result)
+}
+
+/**
+ * @dev 立即生成并按用户设置投递摘要报告
+ */
+pub fn send_digest(user_id: i64, period: &str) -> Result<DigestDeliveryResult, AuthError> {
+    let period = parse_period(period)?;
+    let settings = {
+        let conn = get_connection_from_pool()?;
+        load_settings(&conn, user_id)?
+    };
+    if !settings.send_to_channels && !settings.save_to_disk {
+        return Err(AuthError::InvalidCredentials(
+            "请先在报告设置中选择投递方式（通知渠道或保存到本地）".to_string(),
+        ));
+    }
+
+    let report = generate_digest(user_id, period)?;
+    deliver(&report, &settings)
+}
+
+/**
+ * @dev 为开启订阅的用户发送今天到期的日报 / 周报（后台任务调用，每个周期每天只发送一次）
+ * @return 发送的报告数
+ */
+pub fn send_scheduled_digests() -> Result<usize, AuthError> {
+    let now = Utc::now().timestamp();
+    let today = local_date(now);
+    let today_start = day_start(today);
+    let weekday = today.weekday().number_from_monday() as i64;
+
+    let user_ids: Vec<i64> = {
+        let conn = get_connection_from_pool()?;
+        let mut stmt = conn.prepare(
+            "SELECT user_id FROM digest_settings WHERE daily_enabled = 1 OR weekly_enabled = 1 ORDER BY user_id",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        ids
+    };
+
+    let mut sent = 0;
+    for user_id in user_ids {
+        let settings = {
+            let conn = get_connection_from_pool()?;
+            load_settings(&conn, user_id)?
+        };
+
+        let mut due = Vec::new();
+        if settings.daily_enabled && settings.last_daily_date != Some(today_start) {
+            due.push((DigestPeriod::Daily, "last_daily_date"));
+        }
+        if settings.weekly_enabled && settings.weekly_day == weekday && settings.last_weekly_date != Some(today_start) {
+            due.push((DigestPeriod::Weekly, "last_weekly_date"));
+        }
+
+        for (period, column) in due {
+            let result = generate_digest(user_id, period).and_then(|report| deliver(&report, &settings));
+            match result {
+                Ok(_) => {
+                    let conn = get_connection_from_pool()?;
+                    conn.execute(
+                        &format!("UPDATE digest_settings SET {} = ?1 WHERE user_id = ?2", column),
+                        params![today_start, user_id],
+                    )?;
+                    sent += 1;
+                }
+                Err(e) => error!("Failed to send {} digest for user {}: {}", period.to_str(), user_id, e),
+            }
+        }
+    }
+
+    if sent > 0 {
+        info!("Sent {} scheduled digests", sent);
+    }
+    Ok(sent)
+}
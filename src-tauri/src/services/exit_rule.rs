@@ -0,0 +1,610 @@
+/**
+ * 止盈止损模块
+ *
+ * 为资产持仓或单个定投计划设置退出规则（见 `ExitRuleType`），在价格更新后评估：
+ * - 行情同步（`market_sync::sync_assets`）和手动更新价格（`data::update_asset_price`）后评估相关资产的规则。
+ * - 资产规则按资产的全部持仓计算收益率（与资产列表的 `total_profit_percent` 一致，含已实现收益）；
+ *   计划规则只按该计划已确认买入的份额和投入计算（待确认的定投不计入），不扣除卖出。
+ * - 年化收益率按持仓现金流和当前市值计算 XIRR。
+ *
+ * 规则触发时：
+ * - 生成交易提醒（止盈为 TAKE_PROFIT，止损为 STOP_LOSS）。
+ * - 设置了 `sell_percent` 时按比例生成待确认卖出交易（不超过当前持仓），由用户确认后生效。
+ * - 设置了 `pause_plan` 时暂停相关定投计划（停止原因为 EXIT_RULE）。
+ * - 规则停用，避免重复提醒；重新保存规则后恢复评估。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    CostBasisMethod, ExitRule, ExitRuleTrigger, ExitRuleType, PlanStopReason, SaveExitRuleRequest,
+    TransactionType,
+};
+use crate::services::fee::calculate_fee;
+use crate::services::performance::{transaction_contribution, xirr};
+use crate::services::position::{load_asset_ledger, load_asset_position};
+use crate::services::settings::load_user_settings;
+use crate::services::transaction::insert_pending_transaction;
+use chrono::Utc;
+use log::{error, info};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 持有不足该天数时不计算年化收益率，避免短期收益被放大
+const MIN_ANNUALIZE_DAYS: i64 = 30;
+
+/// 待评估的规则
+struct ActiveRule {
+    id: i64,
+    user_id: i64,
+    asset_id: i64,
+    plan_id: Option<i64>,
+    rule_type: ExitRuleType,
+    threshold: f64,
+    sell_percent: Option<f64>,
+    pause_plan: bool,
+    peak_price: Option<f64>,
+    price: Option<f64>,
+}
+
+/// 规则范围内的持仓
+struct Holding {
+    shares: f64,
+    cost: f64,
+    profit: f64,
+    flows: Vec<(i64, f64)>, // 现金流（投入为负），不含当前市值
+}
+
+impl Holding {
+    fn return_percent(&self) -> Option<f64> {
+        (self.cost > 0.0).then(|| self.profit / self.cost * 100.0)
+    }
+
+    /// 年化收益率（百分比），持有不足 `MIN_ANNUALIZE_DAYS` 天时为 None
+    fn annualized_percent(&self, price: f64, now: i64) -> Option<f64> {
+        let first = self.flows.iter().map(|(date, _)| *date).min()?;
+        if now - first < MIN_ANNUALIZE_DAYS * SECONDS_PER_DAY {
+            return None;
+        }
+
+        let mut flows = self.flows.clone();
+        flows.push((now, self.shares * price));
+        xirr(&flows).map(|rate| rate * 100.0)
+    }
+}
+
+const RULE_COLUMNS: &str = "r.id, r.user_id, r.asset_id, a.name, r.plan_id, p.name, r.rule_type, r.threshold,
+    r.sell_percent, r.pause_plan, r.is_active, r.peak_price, r.last_value, r.last_evaluated,
+    r.triggered_at, r.created_at, r.updated_at";
+
+fn map_exit_rule(row: &rusqlite::Row) -> Result<ExitRule, rusqlite::Error> {
+    Ok(ExitRule {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        asset_id: row.get(2)?,
+        asset_name: row.get(3)?,
+        plan_id: row.get(4)?,
+        plan_name: row.get(5)?,
+        rule_type: row.get(6)?,
+        threshold: row.get(7)?,
+        sell_percent: row.get(8)?,
+        pause_plan: row.get(9)?,
+        is_active: row.get(10)?,
+        peak_price: row.get(11)?,
+        last_value: row.get(12)?,
+        last_evaluated: row.get(13)?,
+        triggered_at: row.get(14)?,
+        created_at: row.get(15)?,
+        updated_at: row.get(16)?,
+    })
+}
+
+fn load_exit_rule(conn: &Connection, id: i64) -> Result<ExitRule, rusqlite::Error> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM exit_rules r
+             JOIN assets a ON r.asset_id = a.id
+             LEFT JOIN investment_plans p ON r.plan_id = p.id
+             WHERE r.id = ?1",
+            RULE_COLUMNS
+        ),
+        params![id],
+        map_exit_rule,
+    )
+}
+
+/// 校验规则类型和阈值
+fn validate_rule(rule_type: &str, threshold: f64, sell_percent: Option<f64>) -> Result<ExitRuleType, AuthError> {
+    let rule_type = ExitRuleType::from_str(rule_type).ok_or_else(|| {
+        AuthError::InvalidCredentials(
+            "无效的规则类型，支持的类型：TARGET_PROFIT, TRAILING_STOP, MAX_LOSS, ANNUALIZED_RETURN".to_string(),
+        )
+    })?;
+
+    if threshold.is_nan() || threshold <= 0.0 {
+        return Err(AuthError::InvalidCredentials("阈值必须大于0".to_string()));
+    }
+    if matches!(rule_type, ExitRuleType::TrailingStop | ExitRuleType::MaxLoss) && threshold >= 100.0 {
+        return Err(AuthError::InvalidCredentials("止损阈值必须小于100".to_string()));
+    }
+    if sell_percent.is_some_and(|percent| percent.is_nan() || percent <= 0.0 || percent > 100.0) {
+        return Err(AuthError::InvalidCredentials("卖出比例必须在0到100之间".to_string()));
+    }
+
+    Ok(rule_type)
+}
+
+/**
+ * @dev 创建或更新止盈止损规则
+ * 重新保存会清除触发状态，移动止损的最高价从当前价格重新开始跟踪
+ */
+pub fn save_exit_rule(request: &SaveExitRuleRequest) -> Result<ExitRule, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let user_id = request.user_id;
+
+    let rule_type = validate_rule(&request.rule_type, request.threshold, request.sell_percent)?;
+
+    // 计划规则使用计划的资产
+    let asset_id = match request.plan_id {
+        Some(plan_id) => {
+            let plan_asset_id: Option<i64> = conn
+                .query_row(
+                    "SELECT asset_id FROM investment_plans WHERE id = ?1 AND user_id = ?2",
+                    params![plan_id, user_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match (plan_asset_id, request.asset_id) {
+                (None, _) => {
+                    return Err(AuthError::InvalidCredentials("定投计划不存在或无权限".to_string()))
+                }
+                (Some(plan_asset_id), Some(asset_id)) if plan_asset_id != asset_id => {
+                    return Err(AuthError::InvalidCredentials("资产与定投计划的资产不一致".to_string()))
+                }
+                (Some(plan_asset_id), _) => plan_asset_id,
+            }
+        }
+        None => request
+            .asset_id
+            .ok_or_else(|| AuthError::InvalidCredentials("请指定资产或定投计划".to_string()))?,
+    };
+
+    let current_price: Option<Option<f64>> = conn
+        .query_row(
+            "SELECT current_price FROM assets WHERE id = ?1 AND user_id = ?2",
+            params![asset_id, user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let peak_price = match current_price {
+        Some(price) => price,
+        None => return Err(AuthError::InvalidCredentials("资产不存在或无权限".to_string())),
+    };
+
+    let pause_plan = request.pause_plan.unwrap_or(false);
+    let is_active = request.is_active.unwrap_or(true);
+
+    let id = match request.id {
+        Some(id) => {
+            let rule_exists: bool = conn
+                .query_row(
+                    "SELECT 1 FROM exit_rules WHERE id = ?1 AND user_id = ?2",
+                    params![id, user_id],
+                    |_| Ok(true),
+                )
+                .unwrap_or(false);
+
+            if !rule_exists {
+                return Err(AuthError::InvalidCredentials("止盈止损规则不存在或无权限".to_string()));
+            }
+
+            conn.execute(
+                "UPDATE exit_rules
+                 SET asset_id = ?1, plan_id = ?2, rule_type = ?3, threshold = ?4, sell_percent = ?5,
+                     pause_plan = ?6, is_active = ?7, peak_price = ?8, last_value = NULL,
+                     last_evaluated = NULL, triggered_at = NULL, updated_at = ?9
+                 WHERE id = ?10",
+                params![
+                    asset_id,
+                    request.plan_id,
+                    rule_type.to_str(),
+                    request.threshold,
+                    request.sell_percent,
+                    pause_plan,
+                    is_active,
+                    peak_price,
+                    now,
+                    id
+                ],
+            )?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO exit_rules (
+                    user_id, asset_id, plan_id, rule_type, threshold, sell_percent, pause_plan,
+                    is_active, peak_price, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    user_id,
+                    asset_id,
+                    request.plan_id,
+                    rule_type.to_str(),
+                    request.threshold,
+                    request.sell_percent,
+                    pause_plan,
+                    is_active,
+                    peak_price,
+                    now,
+                    now
+                ],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    info!("Exit rule saved: {} ({}) for user: {}", id, rule_type.to_str(), user_id);
+    Ok(load_exit_rule(&conn, id)?)
+}
+
+/**
+ * @dev 删除止盈止损规则
+ */
+pub fn delete_exit_rule(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let deleted = conn.execute(
+        "DELETE FROM exit_rules WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    if deleted == 0 {
+        return Err(AuthError::InvalidCredentials("止盈止损规则不存在或无权限".to_string()));
+    }
+
+    info!("Exit rule deleted: {} for user: {}", id, user_id);
+    Ok(())
+}
+
+/**
+ * @dev 获取用户的止盈止损规则，可按资产筛选
+ */
+pub fn get_exit_rules(user_id: i64, asset_id: Option<i64>) -> Result<Vec<ExitRule>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM exit_rules r
+         JOIN assets a ON r.asset_id = a.id
+         LEFT JOIN investment_plans p ON r.plan_id = p.id
+         WHERE r.user_id = ?1 AND (?2 IS NULL OR r.asset_id = ?2)
+         ORDER BY a.name, r.id",
+        RULE_COLUMNS
+    ))?;
+
+    let rules = stmt
+        .query_map(params![user_id, asset_id], map_exit_rule)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch exit rules: {}", e);
+            AuthError::DatabaseError(format!("获取止盈止损规则失败: {}", e))
+        })?;
+
+    Ok(rules)
+}
+
+/// 计划已确认买入的累计投入（含费用）和份额（待确认交易的份额为预估值，不参与规则判断）
+fn load_confirmed_plan_totals(conn: &Connection, plan_id: i64) -> Result<(f64, f64), rusqlite::Error> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(total_cost + fee), 0), COALESCE(SUM(amount), 0) FROM transactions
+         WHERE plan_id = ?1 AND transaction_type = 'BUY' AND status = 'CONFIRMED'",
+        params![plan_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+
+/// 规则范围内的持仓：资产规则按交易台账计算，计划规则按计划已确认买入的交易计算
+fn load_holding(
+    conn: &Connection,
+    rule: &ActiveRule,
+    method: CostBasisMethod,
+    price: f64,
+) -> Result<Holding, rusqlite::Error> {
+    let flow_query = match rule.plan_id {
+        Some(_) => {
+            "SELECT transaction_date, transaction_type, total_cost, fee FROM transactions
+             WHERE plan_id = ?1 AND transaction_type = 'BUY' AND status = 'CONFIRMED'"
+        }
+        None => {
+            "SELECT transaction_date, transaction_type, total_cost, fee FROM transactions
+             WHERE asset_id = ?1 AND status = 'CONFIRMED'"
+        }
+    };
+
+    let mut stmt = conn.prepare(flow_query)?;
+    let flows = stmt
+        .query_map(params![rule.plan_id.unwrap_or(rule.asset_id)], |row| {
+            let kind: String = row.get(1)?;
+            Ok((row.get::<_, i64>(0)?, kind, row.get::<_, f64>(2)?, row.get::<_, f64>(3)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter_map(|(date, kind, total_cost, fee)| {
+            TransactionType::from_str(&kind)
+                .map(|kind| (date, -transaction_contribution(kind, total_cost, fee)))
+        })
+        .collect();
+
+    match rule.plan_id {
+        Some(plan_id) => {
+            let (invested, shares) = load_confirmed_plan_totals(conn, plan_id)?;
+            Ok(Holding {
+                shares,
+                cost: invested,
+                profit: shares * price - invested,
+                flows,
+            })
+        }
+        None => {
+            let position = load_asset_ledger(conn, rule.asset_id, method, None, None)?.position;
+            Ok(Holding {
+                shares: position.shares,
+                cost: position.cost,
+                profit: position.total_profit(price),
+                flows,
+            })
+        }
+    }
+}
+
+/// 规则触发后的处理：生成提醒、待确认卖出交易，暂停定投计划并停用规则
+fn trigger_rule(
+    conn: &Connection,
+    rule: &ActiveRule,
+    holding: &Holding,
+    price: f64,
+    value: f64,
+    peak_price: Option<f64>,
+    now: i64,
+) -> Result<ExitRuleTrigger, AuthError> {
+    let description = match rule.rule_type {
+        ExitRuleType::TargetProfit => format!("收益率 {:.2}% 达到止盈目标 {:.2}%", value, rule.threshold),
+        ExitRuleType::TrailingStop => format!(
+            "价格 {:.4} 较最高价 {:.4} 回撤 {:.2}%，达到移动止损 {:.2}%",
+            price,
+            peak_price.unwrap_or(price),
+            value,
+            rule.threshold
+        ),
+        ExitRuleType::MaxLoss => format!("收益率 {:.2}% 达到最大亏损 -{:.2}%", value, rule.threshold),
+        ExitRuleType::AnnualizedReturn => {
+            format!("年化收益率 {:.2}% 达到止盈目标 {:.2}%", value, rule.threshold)
+        }
+    };
+    let scope = match rule.plan_id {
+        Some(plan_id) => format!("定投计划(ID: {})", plan_id),
+        None => "持仓".to_string(),
+    };
+    let mut message = format!("{}{}", scope, description);
+
+    // 按比例生成待确认卖出交易，不超过资产当前持仓
+    let mut transaction_id = None;
+    if let Some(sell_percent) = rule.sell_percent {
+        let held = load_asset_position(conn, rule.asset_id, None, None)?.shares;
+        let shares = (holding.shares * sell_percent / 100.0).min(held);
+        if shares > 0.0 {
+            let fee = calculate_fee(conn, rule.asset_id, TransactionType::Sell, shares, price, now, None)?;
+            transaction_id = Some(insert_pending_transaction(
+                conn,
+                rule.user_id,
+                rule.asset_id,
+                TransactionType::Sell,
+                shares,
+                price,
+                fee.total(),
+                now,
+                Some(&format!("止盈止损规则(ID: {}) 触发卖出", rule.id)),
+            )?);
+            message.push_str(&format!("，已生成待确认卖出 {:.4} 份", shares));
+        }
+    }
+
+    // 暂停相关定投计划：计划规则暂停该计划，资产规则暂停该资产的所有计划
+    let mut paused_plans = 0;
+    if rule.pause_plan {
+        paused_plans = conn.execute(
+            "UPDATE investment_plans
+             SET is_active = 0, next_execution = NULL, stop_reason = ?1, updated_at = ?2
+             WHERE user_id = ?3 AND is_active = 1
+               AND CASE WHEN ?4 IS NULL THEN asset_id = ?5 ELSE id = ?4 END",
+            params![
+                PlanStopReason::ExitRule.to_str(),
+                now,
+                rule.user_id,
+                rule.plan_id,
+                rule.asset_id
+            ],
+        )?;
+        if paused_plans > 0 {
+            message.push_str(&format!("，已暂停 {} 个定投计划", paused_plans));
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO trade_alerts (user_id, asset_id, strategy_id, alert_type, message, is_read, created_at)
+         VALUES (?1, ?2, NULL, ?3, ?4, 0, ?5)",
+        params![rule.user_id, rule.asset_id, rule.rule_type.alert_type(), message, now],
+    )?;
+    let alert_id = conn.last_insert_rowid();
+
+    conn.execute(
+        "UPDATE exit_rules
+         SET is_active = 0, peak_price = ?1, last_value = ?2, last_evaluated = ?3, triggered_at = ?3,
+             updated_at = ?3
+         WHERE id = ?4",
+        params![peak_price, value, now, rule.id],
+    )?;
+
+    Ok(ExitRuleTrigger {
+        rule_id: rule.id,
+        user_id: rule.user_id,
+        asset_id: rule.asset_id,
+        plan_id: rule.plan_id,
+        rule_type: rule.rule_type.to_str().to_string(),
+        value,
+        threshold: rule.threshold,
+        price,
+        message,
+        alert_id,
+        transaction_id,
+        paused_plans,
+    })
+}
+
+/// 评估单条规则，触发时返回触发结果
+fn evaluate_rule(
+    conn: &mut Connection,
+    rule: &ActiveRule,
+    method: CostBasisMethod,
+    now: i64,
+) -> Result<Option<ExitRuleTrigger>, AuthError> {
+    let price = match rule.price.filter(|price| *price > 0.0) {
+        Some(price) => price,
+        None => return Ok(None),
+    };
+
+    let holding = load_holding(conn, rule, method, price)?;
+    let peak_price = match rule.rule_type {
+        ExitRuleType::TrailingStop => Some(rule.peak_price.unwrap_or(price).max(price)),
+        _ => rule.peak_price,
+    };
+
+    // 没有持仓时只跟踪最高价
+    let value = if holding.shares > 0.0 {
+        match rule.rule_type {
+            ExitRuleType::TargetProfit | ExitRuleType::MaxLoss => holding.return_percent(),
+            ExitRuleType::TrailingStop => peak_price.map(|peak| (peak - price) / peak * 100.0),
+            ExitRuleType::AnnualizedReturn => holding.annualized_percent(price, now),
+        }
+    } else {
+        None
+    };
+
+    let triggered = value.is_some_and(|value| match rule.rule_type {
+        ExitRuleType::MaxLoss => value <= -rule.threshold,
+        _ => value >= rule.threshold,
+    });
+
+    if let (true, Some(value)) = (triggered, value) {
+        let tx = conn.transaction()?;
+        let trigger = trigger_rule(&tx, rule, &holding, price, value, peak_price, now)?;
+        tx.commit()?;
+        return Ok(Some(trigger));
+    }
+
+    conn.execute(
+        "UPDATE exit_rules SET peak_price = ?1, last_value = ?2, last_evaluated = ?3 WHERE id = ?4",
+        params![peak_price, value, now, rule.id],
+    )?;
+    Ok(None)
+}
+
+/**
+ * @dev 评估启用的止盈止损规则
+ * @param user_id 为 None 时评估所有用户的规则
+ * @param asset_ids 为 None 时评估所有资产的规则（价格更新后只评估更新的资产）
+ * @return 本次触发的规则
+ */
+pub fn evaluate_exit_rules(
+    user_id: Option<i64>,
+    asset_ids: Option<&[i64]>,
+) -> Result<Vec<ExitRuleTrigger>, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let rules = {
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.user_id, r.asset_id, r.plan_id, r.rule_type, r.threshold, r.sell_percent,
+                    r.pause_plan, r.peak_price, a.current_price
+             FROM exit_rules r
+             JOIN assets a ON r.asset_id = a.id
+             WHERE r.is_active = 1 AND (?1 IS NULL OR r.user_id = ?1)",
+        )?;
+
+        let result = stmt
+            .query_map(params![user_id], |row| {
+                Ok((
+                    row.get::<_, String>(4)?,
+                    ActiveRule {
+                        id: row.get(0)?,
+                        user_id: row.get(1)?,
+                        asset_id: row.get(2)?,
+                        plan_id: row.get(3)?,
+                        rule_type: ExitRuleType::TargetProfit,
+                        threshold: row.get(5)?,
+                        sell_percent: row.get(6)?,
+                        pause_plan: row.get(7)?,
+                        peak_price: row.get(8)?,
+                        price: row.get(9)?,
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                error!("Failed to fetch exit rules: {}", e);
+                AuthError::DatabaseError(format!("获取止盈止损规则失败: {}", e))
+            });
+
+        result?
+    };
+
+    let mut methods: HashMap<i64, CostBasisMethod> = HashMap::new();
+    let mut triggers = Vec::new();
+
+    for (rule_type, mut rule) in rules {
+        if asset_ids.is_some_and(|ids| !ids.contains(&rule.asset_id)) {
+            continue;
+        }
+        rule.rule_type = match ExitRuleType::from_str(&rule_type) {
+            Some(rule_type) => rule_type,
+            None => continue,
+        };
+
+        let method = match methods.get(&rule.user_id) {
+            Some(method) => *method,
+            None => {
+                let method = load_user_settings(&conn, rule.user_id)?.cost_basis();
+                methods.insert(rule.user_id, method);
+                method
+            }
+        };
+
+        match evaluate_rule(&mut conn, &rule, method, now) {
+            Ok(Some(trigger)) => {
+                info!("Exit rule {} triggered: {}", rule.id, trigger.message);
+                triggers.push(trigger);
+            }
+            Ok(None) => {}
+            Err(e) => error!("Failed to evaluate exit rule {}: {}", rule.id, e),
+        }
+    }
+
+    Ok(triggers)
+}
+
+/**
+ * @dev 价格更新后评估相关资产的规则，失败只记录日志，不影响价格更新
+ */
+pub fn evaluate_after_price_update(asset_ids: &[i64]) {
+    if asset_ids.is_empty() {
+        return;
+    }
+
+    match evaluate_exit_rules(None, Some(asset_ids)) {
+        Ok(triggers) if !triggers.is_empty() => {
+            info!("{} exit rules triggered after price update", triggers.len())
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to evaluate exit rules after price update: {}", e),
+    }
+}
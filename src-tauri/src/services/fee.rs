@@ -0,0 +1,520 @@
+/**
+ * 交易费用模块
+ *
+ * 按费率方案计算每笔交易的费用，费用单独记录在 `transactions.fee` 中：
+ * - 买入：费用计入持仓成本。
+ * - 卖出：费用从卖出金额中扣除，减少已实现收益。
+ *
+ * 主要函数说明：
+ * - `load_fee_schedule(conn, asset_id)`: 获取资产所属用户为该资产设置的费率方案（单个资产优先，其次为资产类型）。
+ * - `calculate_fee(conn, asset_id, kind, amount, price, date, exclude_id)`: 计算单笔交易费用明细。
+ * - `split_buy_budget(schedule, budget)`: 按金额买入（如定投）时，把总金额拆分为净申购金额和费用。
+ * - `save_fee_schedule` / `get_fee_schedules` / `delete_fee_schedule`: 费率方案的增删改查。
+ *
+ * 计算规则：
+ * - 申购费：外扣法，费用 = 净申购金额 × 费率 × 折扣，固定费用档按笔收取。
+ *   档位按申购总金额（净申购金额 + 买入费用）确定，按成交记录计算和按总金额拆分（`split_buy_budget`）使用同一口径。
+ * - 赎回费：卖出份额按先进先出匹配买入批次，按每批持有天数适用不同费率。
+ * - 佣金：买卖双向，不足 `min_commission` 按最低佣金收取；印花税仅卖出收取；过户费买卖双向。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    CostBasisMethod, FeeBreakdown, FeeSchedule, FeeTier, HoldingPeriodFee, TransactionType,
+};
+use crate::services::position::load_asset_ledger;
+use chrono::Utc;
+use log::{error, info};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+const SCHEDULE_COLUMNS: &str = "id, user_id, asset_type_id, asset_id, name, buy_tiers, buy_discount,
+    redemption_tiers, commission_rate, min_commission, stamp_duty_rate, transfer_fee_rate,
+    created_at, updated_at";
+
+fn row_to_schedule(row: &Row) -> rusqlite::Result<FeeSchedule> {
+    let buy_tiers: String = row.get(5)?;
+    let redemption_tiers: String = row.get(7)?;
+
+    Ok(FeeSchedule {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        asset_type_id: row.get(2)?,
+        asset_id: row.get(3)?,
+        name: row.get(4)?,
+        buy_tiers: serde_json::from_str(&buy_tiers).unwrap_or_default(),
+        buy_discount: row.get(6)?,
+        redemption_tiers: serde_json::from_str(&redemption_tiers).unwrap_or_default(),
+        commission_rate: row.get(8)?,
+        min_commission: row.get(9)?,
+        stamp_duty_rate: row.get(10)?,
+        transfer_fee_rate: row.get(11)?,
+        created_at: row.get(12)?,
+        updated_at: row.get(13)?,
+    })
+}
+
+/// 获取资产适用的费率方案，单个资产的方案优先于资产类型的方案
+pub fn load_fee_schedule(
+    conn: &Connection,
+    asset_id: i64,
+) -> Result<Option<FeeSchedule>, rusqlite::Error> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM fee_schedules
+             WHERE user_id = (SELECT user_id FROM assets WHERE id = ?1)
+               AND (asset_id = ?1
+                    OR (asset_id IS NULL AND asset_type_id = (SELECT asset_type_id FROM assets WHERE id = ?1)))
+             ORDER BY asset_id IS NULL, id DESC
+             LIMIT 1",
+            SCHEDULE_COLUMNS
+        ),
+        params![asset_id],
+        row_to_schedule,
+    )
+    .optional()
+}
+
+/// 按先进先出计算卖出份额对应的各批次持有天数，返回 (份额, 持有天数)
+fn holding_periods(
+    conn: &Connection,
+    asset_id: i64,
+    quantity: f64,
+    sell_date: i64,
+    exclude_transaction_id: Option<i64>,
+) -> Result<Vec<(f64, i64)>, rusqlite::Error> {
+    let ledger = load_asset_ledger(
+        conn,
+        asset_id,
+        CostBasisMethod::Fifo,
+        Some(sell_date),
+        exclude_transaction_id,
+    )?;
+
+    let mut periods = Vec::new();
+    let mut remaining = quantity;
+    for lot in ledger.lots {
+        if remaining <= 0.0 {
+            break;
+        }
+        let matched = lot.remaining_shares.min(remaining);
+        periods.push((matched, (sell_date - lot.acquired_date).max(0) / SECONDS_PER_DAY));
+        remaining -= matched;
+    }
+
+    Ok(periods)
+}
+
+/// 计算单笔交易的费用明细，没有费率方案或交易类型不收费时返回全零
+pub fn calculate_fee(
+    conn: &Connection,
+    asset_id: i64,
+    kind: TransactionType,
+    amount: f64,
+    price: f64,
+    transaction_date: i64,
+    exclude_transaction_id: Option<i64>,
+) -> Result<FeeBreakdown, rusqlite::Error> {
+    let mut fee = FeeBreakdown::default();
+
+    let schedule = match load_fee_schedule(conn, asset_id)? {
+        Some(schedule) => schedule,
+        None => return Ok(fee),
+    };
+
+    let trade_amount = amount * price;
+
+    match kind {
+        TransactionType::Buy => fee = buy_fee(&schedule, trade_amount),
+        TransactionType::Sell => {
+            if !schedule.redemption_tiers.is_empty() {
+                fee.redemption_fee = holding_periods(
+                    conn,
+                    asset_id,
+                    amount,
+                    transaction_date,
+                    exclude_transaction_id,
+                )?
+                .into_iter()
+                .map(|(shares, days)| shares * price * schedule.redemption_rate(days))
+                .sum();
+            }
+            fee.commission = schedule.commission(trade_amount);
+            fee.stamp_duty = trade_amount * schedule.stamp_duty_rate;
+            fee.transfer_fee = trade_amount * schedule.transfer_fee_rate;
+        }
+        _ => {}
+    }
+
+    Ok(fee)
+}
+
+/// 按净买入金额（份额 × 价格）计算买入费用
+///
+/// 申购费档位按申购总金额（净买入金额 + 费用）确定：从最高档开始，取总金额落在本档的档位。
+/// 档位边界附近某些净买入金额无法由任何总金额拆分得到，此时按净买入金额确定档位。
+fn buy_fee(schedule: &FeeSchedule, trade_amount: f64) -> FeeBreakdown {
+    let commission = schedule.commission(trade_amount);
+    let transfer_fee = trade_amount * schedule.transfer_fee_rate;
+    let subscription_fee = |tier: &FeeTier| match tier.fixed_fee {
+        Some(fixed) => fixed,
+        None => trade_amount * tier.rate * schedule.buy_discount,
+    };
+
+    let mut tiers: Vec<&FeeTier> = schedule.buy_tiers.iter().collect();
+    tiers.sort_by(|a, b| b.min_amount.total_cmp(&a.min_amount));
+
+    let tier = tiers
+        .into_iter()
+        .find(|tier| {
+            let gross = trade_amount + subscription_fee(tier) + commission + transfer_fee;
+            schedule
+                .buy_tier(gross)
+                .is_some_and(|matched| matched.min_amount == tier.min_amount)
+        })
+        .or_else(|| schedule.buy_tier(trade_amount));
+
+    FeeBreakdown {
+        subscription_fee: tier.map(subscription_fee).unwrap_or(0.0),
+        commission,
+        transfer_fee,
+        ..Default::default()
+    }
+}
+
+/// 按总金额买入时拆分为 (净买入金额, 费用)
+pub fn split_buy_budget(schedule: Option<&FeeSchedule>, budget: f64) -> (f64, f64) {
+    let schedule = match schedule {
+        Some(schedule) => schedule,
+        None => return (budget, 0.0),
+    };
+
+    // 申购费档位按申购总金额确定
+    let (subscription_rate, fixed_fee) = match schedule.buy_tier(budget) {
+        Some(FeeTier {
+            fixed_fee: Some(fixed),
+            ..
+        }) => (0.0, *fixed),
+        Some(tier) => (tier.rate * schedule.buy_discount, 0.0),
+        None => (0.0, 0.0),
+    };
+
+    let rate = subscription_rate + schedule.commission_rate + schedule.transfer_fee_rate;
+    let mut net = (budget - fixed_fee) / (1.0 + rate);
+
+    // 佣金不足最低佣金时按最低佣金扣除
+    if net * schedule.commission_rate < schedule.min_commission {
+        net = (budget - fixed_fee - schedule.min_commission)
+            / (1.0 + subscription_rate + schedule.transfer_fee_rate);
+    }
+
+    let net = net.max(0.0);
+    (net, budget - net)
+}
+
+/// 校验费率方案参数
+fn validate_schedule(
+    buy_tiers: &[FeeTier],
+    buy_discount: f64,
+    redemption_tiers: &[HoldingPeriodFee],
+    rates: &[f64],
+) -> Result<(), AuthError> {
+    let invalid_rate = |rate: f64| !(0.0..1.0).contains(&rate);
+
+    if buy_tiers
+        .iter()
+        .any(|t| invalid_rate(t.rate) || t.min_amount < 0.0 || t.fixed_fee.map_or(false, |f| f < 0.0))
+    {
+        return Err(AuthError::InvalidCredentials("申购费档位无效".to_string()));
+    }
+    if !(0.0..=1.0).contains(&buy_discount) {
+        return Err(AuthError::InvalidCredentials("申购费折扣必须在 0 到 1 之间".to_string()));
+    }
+    if redemption_tiers
+        .iter()
+        .any(|t| invalid_rate(t.rate) || t.min_days < 0)
+    {
+        return Err(AuthError::InvalidCredentials("赎回费档位无效".to_string()));
+    }
+    if rates.iter().any(|rate| invalid_rate(*rate)) {
+        return Err(AuthError::InvalidCredentials("费率必须在 0 到 1 之间".to_string()));
+    }
+    Ok(())
+}
+
+/// 新增或更新费率方案
+pub fn save_fee_schedule(
+    schedule_id: Option<i64>,
+    user_id: i64,
+    asset_type_id: Option<i64>,
+    asset_id: Option<i64>,
+    name: &str,
+    buy_tiers: &[FeeTier],
+    buy_discount: f64,
+    redemption_tiers: &[HoldingPeriodFee],
+    commission_rate: f64,
+    min_commission: f64,
+    stamp_duty_rate: f64,
+    transfer_fee_rate: f64,
+) -> Result<FeeSchedule, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    if asset_type_id.is_none() == asset_id.is_none() {
+        return Err(AuthError::InvalidCredentials(
+            "费率方案需要指定资产类型或资产（二选一）".to_string(),
+        ));
+    }
+
+    validate_schedule(
+        buy_tiers,
+        buy_discount,
+        redemption_tiers,
+        &[commission_rate, stamp_duty_rate, transfer_fee_rate],
+    )?;
+
+    if min_commission < 0.0 {
+        return Err(AuthError::InvalidCredentials("最低佣金不能为负数".to_string()));
+    }
+
+    if let Some(a_id) = asset_id {
+        let asset_exists: bool = conn
+            .query_row(
+                "SELECT 1 FROM assets WHERE id = ?1 AND user_id = ?2",
+                params![a_id, user_id],
+                |_| Ok(true),
+            )
+            .unwrap_or(false);
+
+        if !asset_exists {
+            return Err(AuthError::InvalidCredentials("资产不存在或无权限".to_string()));
+        }
+    }
+
+    // 同一资产或资产类型只保留一个方案
+    let duplicate: bool = conn
+        .query_row(
+            "SELECT 1 FROM fee_schedules
+             WHERE user_id = ?1 AND asset_type_id IS ?2 AND asset_id IS ?3 AND (?4 IS NULL OR id != ?4)",
+            params![user_id, asset_type_id, asset_id, schedule_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if duplicate {
+        return Err(AuthError::InvalidCredentials("该资产或资产类型已存在费率方案".to_string()));
+    }
+
+    let buy_tiers_json = serde_json::to_string(buy_tiers)
+        .map_err(|e| AuthError::InvalidCredentials(format!("申购费档位无效: {}", e)))?;
+    let redemption_tiers_json = serde_json::to_string(redemption_tiers)
+        .map_err(|e| AuthError::InvalidCredentials(format!("赎回费档位无效: {}", e)))?;
+
+    let id = match schedule_id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE fee_schedules
+                 SET asset_type_id = ?1, asset_id = ?2, name = ?3, buy_tiers = ?4, buy_discount = ?5,
+                     redemption_tiers = ?6, commission_rate = ?7, min_commission = ?8,
+                     stamp_duty_rate = ?9, transfer_fee_rate = ?10, updated_at = ?11
+                 WHERE id = ?12 AND user_id = ?13",
+                params![
+                    asset_type_id,
+                    asset_id,
+                    name,
+                    buy_tiers_json,
+                    buy_discount,
+                    redemption_tiers_json,
+                    commission_rate,
+                    min_commission,
+                    stamp_duty_rate,
+                    transfer_fee_rate,
+                    now,
+                    id,
+                    user_id
+                ],
+            )?;
+
+            if updated == 0 {
+                return Err(AuthError::InvalidCredentials("费率方案不存在或无权限".to_string()));
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO fee_schedules (
+                    user_id, asset_type_id, asset_id, name, buy_tiers, buy_discount, redemption_tiers,
+                    commission_rate, min_commission, stamp_duty_rate, transfer_fee_rate, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    user_id,
+                    asset_type_id,
+                    asset_id,
+                    name,
+                    buy_tiers_json,
+                    buy_discount,
+                    redemption_tiers_json,
+                    commission_rate,
+                    min_commission,
+                    stamp_duty_rate,
+                    transfer_fee_rate,
+                    now,
+                    now
+                ],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    let schedule = conn.query_row(
+        &format!("SELECT {} FROM fee_schedules WHERE id = ?1", SCHEDULE_COLUMNS),
+        params![id],
+        row_to_schedule,
+    )?;
+
+    info!("Fee schedule saved: {} for user: {}", name, user_id);
+    Ok(schedule)
+}
+
+/// 获取用户的所有费率方案
+pub fn get_fee_schedules(user_id: i64) -> Result<Vec<FeeSchedule>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM fee_schedules WHERE user_id = ?1 ORDER BY asset_type_id, asset_id, id",
+        SCHEDULE_COLUMNS
+    ))?;
+
+    let schedules = stmt
+        .query_map(params![user_id], row_to_schedule)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch fee schedules: {}", e);
+            AuthError::DatabaseError(format!("获取费率方案失败: {}", e))
+        })?;
+
+    Ok(schedules)
+}
+
+/// 删除费率方案（已记录的交易费用不受影响）
+pub fn delete_fee_schedule(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let deleted = conn.execute(
+        "DELETE FROM fee_schedules WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+
+    if deleted == 0 {
+        return Err(AuthError::InvalidCredentials("费率方案不存在或无权限".to_string()));
+    }
+
+    info!("Fee schedule deleted: {} for user: {}", id, user_id);
+    Ok(())
+}
+
+/// 预估一笔交易的费用
+pub fn estimate_fee(
+    user_id: i64,
+    asset_id: i64,
+    transaction_type: &str,
+    amount: f64,
+    price: f64,
+    transaction_date: i64,
+) -> Result<FeeBreakdown, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let kind = TransactionType::from_str(transaction_type)
+        .ok_or_else(|| AuthError::InvalidCredentials("交易类型无效".to_string()))?;
+
+    let asset_exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM assets WHERE id = ?1 AND user_id = ?2",
+            params![asset_id, user_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if !asset_exists {
+        return Err(AuthError::InvalidCredentials("资产不存在或无权限".to_string()));
+    }
+
+    calculate_fee(&conn, asset_id, kind, amount, price, transaction_date, None)
+        .map_err(|e| AuthError::DatabaseError(format!("计算交易费用失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(buy_tiers: Vec<FeeTier>) -> FeeSchedule {
+        FeeSchedule {
+            id: 1,
+            user_id: 1,
+            asset_type_id: None,
+            asset_id: Some(1),
+            name: "test".to_string(),
+            buy_tiers,
+            buy_discount: 1.0,
+            redemption_tiers: Vec::new(),
+            commission_rate: 0.0,
+            min_commission: 0.0,
+            stamp_duty_rate: 0.0,
+            transfer_fee_rate: 0.0,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn tier(min_amount: f64, rate: f64, fixed_fee: Option<f64>) -> FeeTier {
+        FeeTier {
+            min_amount,
+            rate,
+            fixed_fee,
+        }
+    }
+
+    /// 按总金额拆分后，按成交记录计算的费用与拆分出的费用一致
+    fn assert_round_trip(schedule: &FeeSchedule, budget: f64, expected_fee: f64) {
+        let (net, fee) = split_buy_budget(Some(schedule), budget);
+        assert!((fee - expected_fee).abs() < 1e-6, "split fee {}", fee);
+        let recorded = buy_fee(schedule, net).total();
+        assert!((recorded - fee).abs() < 1e-6, "recorded fee {} != split fee {}", recorded, fee);
+    }
+
+    #[test]
+    fn fixed_fee_tier_at_boundary() {
+        let schedule = schedule(vec![tier(0.0, 0.015, None), tier(1_000_000.0, 0.0, Some(1000.0))]);
+
+        // 总金额刚过 100 万，净申购金额低于 100 万，仍按固定费用档
+        assert_round_trip(&schedule, 1_000_500.0, 1000.0);
+        assert_round_trip(&schedule, 999_000.0, 999_000.0 - 999_000.0 / 1.015);
+    }
+
+    #[test]
+    fn rate_tier_at_boundary() {
+        let schedule = schedule(vec![tier(0.0, 0.015, None), tier(1_000_000.0, 0.012, None)]);
+
+        assert_round_trip(&schedule, 1_000_000.0, 1_000_000.0 - 1_000_000.0 / 1.012);
+        assert_round_trip(&schedule, 999_999.0, 999_999.0 - 999_999.0 / 1.015);
+    }
+
+    #[test]
+    fn unreachable_net_amount_uses_net_tier() {
+        let schedule = schedule(vec![tier(0.0, 0.015, None), tier(1_000_000.0, 0.0, Some(1000.0))]);
+        let fee = buy_fee(&schedule, 990_000.0);
+        assert!((fee.subscription_fee - 990_000.0 * 0.015).abs() < 1e-6);
+    }
+
+    #[test]
+    fn commission_counts_towards_gross_amount() {
+        let mut schedule = schedule(Vec::new());
+        schedule.commission_rate = 0.0003;
+        schedule.min_commission = 5.0;
+        schedule.transfer_fee_rate = 0.00001;
+
+        assert_round_trip(&schedule, 1000.0, 5.0 + (1000.0 - 5.0) / 1.00001 * 0.00001);
+        assert_round_trip(&schedule, 100_000.0, 100_000.0 - 100_000.0 / 1.00031);
+    }
+}
@@ -0,0 +1,452 @@
+/**
+ * 汇率模块
+ *
+ * 不同资产的价格币种不同（基金为人民币、港股为港币、币安为 USDT 等），
+ * 组合汇总和收益率计算前统一按用户设置的基准币种换算。
+ *
+ * 主要函数说明：
+ * - `canonical_currency(code)`: 统一币种代码，稳定币按 1:1 视为美元，离岸人民币视为人民币。
+ * - `default_currency(asset_type)`: 资产类型的默认报价币种（创建资产未指定币种时使用）。
+ * - `lookup_rate(conn, user_id, from, to, date)`: 查询某日汇率，依次尝试直接报价、反向报价和经人民币交叉换算。
+ * - `FxConverter`: 按日期换算为基准币种的换算器（带缓存），用于汇总、收益率和净值快照。
+ * - `sync_fx_rates(lookback_days)`: 通过汇率适配器同步资产和用户基准币种相关的汇率历史。
+ * - `save_manual_rate` / `get_fx_rates` / `delete_fx_rate`: 手动汇率和汇率历史管理。
+ *
+ * 手动汇率属于录入的用户，只参与该用户的换算，也只能由该用户修改和删除；同步的汇率为公共汇率（user_id 为 0），不能删除。
+ *
+ * 查询规则：优先取指定日期当天或之前最近的汇率，没有时取之后最近的汇率；同一天手动汇率优先。
+ * 找不到任何汇率时换算器记录缺失的币种，调用方通过 `ensure_rates` 检查：
+ * - 默认返回错误，提示同步或手动录入汇率，避免不同币种的金额被直接相加；
+ * - 配置 `fx.allow_missing_rate_fallback = true` 时按 1:1 计算，组合汇总和配置偏离报告把相关条目标记为未换算，
+ *   并通过 `missing_rate_warnings` 把提示返回给调用方。
+ */
+use crate::adapters;
+use crate::config::Config;
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{FxRate, FxSyncReport};
+use crate::services::settings::load_user_settings;
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+
+/// 交叉换算使用的中间币种，汇率适配器以该币种报价
+const PIVOT_CURRENCY: &str = "CNY";
+
+/// 手动录入汇率的来源标识
+pub const MANUAL_SOURCE: &str = "MANUAL";
+
+/// 与法币 1:1 锚定的币种
+const PEGGED_CURRENCIES: [(&str, &str); 5] = [
+    ("USDT", "USD"),
+    ("USDC", "USD"),
+    ("BUSD", "USD"),
+    ("FDUSD", "USD"),
+    ("CNH", "CNY"),
+];
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 统一币种代码
+pub fn canonical_currency(code: &str) -> String {
+    let code = code.trim().to_uppercase();
+    PEGGED_CURRENCIES
+        .iter()
+        .find(|(pegged, _)| *pegged == code)
+        .map(|(_, fiat)| fiat.to_string())
+        .unwrap_or(code)
+}
+
+/// 校验并规范化币种代码（3-5 位字母）
+pub fn validate_currency(code: &str) -> Result<String, AuthError> {
+    let code = code.trim().to_uppercase();
+    if code.len() < 3 || code.len() > 5 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AuthError::InvalidCredentials(format!("无效的币种代码: {}", code)));
+    }
+    Ok(code)
+}
+
+/// 资产类型的默认报价币种
+pub fn default_currency(asset_type: &str) -> &'static str {
+    match asset_type.to_uppercase().as_str() {
+        "HK_STOCK" => "HKD",
+        "US_STOCK" => "USD",
+        "CRYPTO" => "USDT",
+        _ => "CNY",
+    }
+}
+
+/// 日期对应的 UTC 当日零点
+fn rate_day(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(SECONDS_PER_DAY)
+}
+
+/// 查询直接报价的汇率（公共汇率和该用户的手动汇率）
+fn quoted_rate(
+    conn: &Connection,
+    user_id: i64,
+    base: &str,
+    quote: &str,
+    date: i64,
+) -> Result<Option<f64>, rusqlite::Error> {
+    conn.query_row(
+        "SELECT rate FROM fx_rates
+         WHERE base_currency = ?1 AND quote_currency = ?2 AND rate > 0 AND user_id IN (0, ?5)
+         ORDER BY rate_date > ?3,
+                  CASE WHEN rate_date <= ?3 THEN ?3 - rate_date ELSE rate_date - ?3 END,
+                  source = ?4 DESC
+         LIMIT 1",
+        params![base, quote, date, MANUAL_SOURCE, user_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// 查询直接报价或反向报价的汇率
+fn pair_rate(
+    conn: &Connection,
+    user_id: i64,
+    base: &str,
+    quote: &str,
+    date: i64,
+) -> Result<Option<f64>, rusqlite::Error> {
+    if let Some(rate) = quoted_rate(conn, user_id, base, quote, date)? {
+        return Ok(Some(rate));
+    }
+    Ok(quoted_rate(conn, user_id, quote, base, date)?.map(|rate| 1.0 / rate))
+}
+
+/// 查询 1 单位 `from` 在指定日期兑换的 `to` 数量（使用公共汇率和 `user_id` 的手动汇率）
+pub fn lookup_rate(
+    conn: &Connection,
+    user_id: i64,
+    from: &str,
+    to: &str,
+    date: i64,
+) -> Result<Option<f64>, rusqlite::Error> {
+    let from = canonical_currency(from);
+    let to = canonical_currency(to);
+
+    if from == to {
+        return Ok(Some(1.0));
+    }
+
+    if let Some(rate) = pair_rate(conn, user_id, &from, &to, date)? {
+        return Ok(Some(rate));
+    }
+
+    // 经人民币交叉换算
+    if from != PIVOT_CURRENCY && to != PIVOT_CURRENCY {
+        if let (Some(from_pivot), Some(pivot_to)) = (
+            pair_rate(conn, user_id, &from, PIVOT_CURRENCY, date)?,
+            pair_rate(conn, user_id, PIVOT_CURRENCY, &to, date)?,
+        ) {
+            return Ok(Some(from_pivot * pivot_to));
+        }
+    }
+
+    Ok(None)
+}
+
+/// 换算为基准币种的换算器，按 (币种, 日期) 缓存汇率
+pub struct FxConverter<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+    base_currency: String,
+    cache: HashMap<(String, i64), f64>,
+    missing: HashSet<String>,
+    allow_missing_fallback: bool,
+}
+
+impl<'a> FxConverter<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64, base_currency: &str) -> Self {
+        Self {
+            conn,
+            user_id,
+            base_currency: canonical_currency(base_currency),
+            cache: HashMap::new(),
+            missing: HashSet::new(),
+            allow_missing_fallback: Config::get().fx.allow_missing_rate_fallback,
+        }
+    }
+
+    /// 按用户设置的基准币种创建换算器
+    pub fn for_user(conn: &'a Connection, user_id: i64) -> Result<Self, rusqlite::Error> {
+        let settings = load_user_settings(conn, user_id)?;
+        Ok(Self::new(conn, user_id, &settings.base_currency))
+    }
+
+    pub fn base_currency(&self) -> &str {
+        &self.base_currency
+    }
+
+    /// 1 单位 `currency` 在指定日期兑换的基准币种数量（缺少汇率时记录缺失并按 1:1 计算，由 `ensure_rates` 检查）
+    pub fn rate(&mut self, currency: &str, date: i64) -> Result<f64, rusqlite::Error> {
+        let currency = canonical_currency(currency);
+        if currency == self.base_currency {
+            return Ok(1.0);
+        }
+
+        let day = rate_day(date);
+        if let Some(rate) = self.cache.get(&(currency.clone(), day)) {
+            return Ok(*rate);
+        }
+
+        let end_of_day = day + SECONDS_PER_DAY - 1;
+        let rate = match lookup_rate(self.conn, self.user_id, &currency, &self.base_currency, end_of_day)? {
+            Some(rate) => rate,
+            None => {
+                if self.missing.insert(currency.clone()) {
+                    warn!(
+                        "No fx rate for {}/{}, amounts left unconverted",
+                        currency, self.base_currency
+                    );
+                }
+                1.0
+            }
+        };
+
+        self.cache.insert((currency, day), rate);
+        Ok(rate)
+    }
+
+    /// 把 `currency` 计价的金额按指定日期的汇率换算为基准币种
+    pub fn convert(&mut self, amount: f64, currency: &str, date: i64) -> Result<f64, rusqlite::Error> {
+        Ok(amount * self.rate(currency, date)?)
+    }
+
+    /// 是否缺少 `currency` 的汇率（已按 1:1 计算，结果未换算）
+    pub fn is_missing(&self, currency: &str) -> bool {
+        self.missing.contains(&canonical_currency(currency))
+    }
+
+    /// 检查换算过程中是否缺少汇率：未开启 1:1 兜底时返回错误，开启时由调用方返回 `missing_rate_warnings`
+    pub fn ensure_rates(&self) -> Result<(), AuthError> {
+        if self.missing.is_empty() || self.allow_missing_fallback {
+            return Ok(());
+        }
+
+        let mut currencies: Vec<&String> = self.missing.iter().collect();
+        currencies.sort();
+        let pairs: Vec<String> = currencies
+            .into_iter()
+            .map(|currency| format!("{}/{}", currency, self.base_currency))
+            .collect();
+        Err(AuthError::InvalidCredentials(format!(
+            "缺少 {} 汇率，请同步或手动录入汇率",
+            pairs.join("、")
+        )))
+    }
+
+    /// 缺少汇率的提示，按币种排序
+    pub fn missing_rate_warnings(&self) -> Vec<String> {
+        let mut currencies: Vec<&String> = self.missing.iter().collect();
+        currencies.sort();
+        currencies
+            .into_iter()
+            .map(|currency| {
+                format!(
+                    "缺少 {}/{} 汇率，相关金额未换算（按 1:1 计算），请同步或手动录入汇率",
+                    currency, self.base_currency
+                )
+            })
+            .collect()
+    }
+}
+
+/// 同步汇率历史：资产币种和用户基准币种均与人民币同步，其他币种之间经人民币交叉换算
+pub async fn sync_fx_rates(lookback_days: i64) -> Result<FxSyncReport, AuthError> {
+    let started_at = Utc::now().timestamp();
+    let source = Config::get().fx.source;
+
+    let currencies: Vec<String> = {
+        let conn = get_connection_from_pool()?;
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT currency FROM assets
+             UNION
+             SELECT DISTINCT base_currency FROM user_settings",
+        )?;
+        let codes = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut currencies: Vec<String> = codes
+            .iter()
+            .map(|code| canonical_currency(code))
+            .filter(|code| code != PIVOT_CURRENCY)
+            .collect();
+        currencies.sort();
+        currencies.dedup();
+        currencies
+    };
+
+    let adapter = adapters::get_fx_adapter(&source).map_err(AuthError::InternalError)?;
+    let end_time = Utc::now();
+    let start_time = end_time - Duration::days(lookback_days.max(1));
+
+    let mut fetched: Vec<(String, i64, f64)> = Vec::new();
+    let mut errors = Vec::new();
+
+    for currency in &currencies {
+        match adapter
+            .get_rate_history(currency, PIVOT_CURRENCY, start_time, end_time)
+            .await
+        {
+            Ok(history) => fetched.extend(
+                history
+                    .into_iter()
+                    .map(|(date, rate)| (currency.clone(), date.timestamp(), rate)),
+            ),
+            Err(e) => errors.push(format!("{}/{}: {}", currency, PIVOT_CURRENCY, e)),
+        }
+
+        // 当天日线可能尚未生成，用最新报价补齐
+        match adapter.get_rate(currency, PIVOT_CURRENCY).await {
+            Ok(rate) => fetched.push((currency.clone(), end_time.timestamp(), rate)),
+            Err(e) => warn!("Failed to fetch latest rate for {}: {}", currency, e),
+        }
+    }
+
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let mut rates_saved = 0;
+
+    for (currency, date, rate) in fetched {
+        match conn.execute(
+            "INSERT INTO fx_rates (base_currency, quote_currency, rate_date, rate, source, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(base_currency, quote_currency, rate_date, source, user_id)
+             DO UPDATE SET rate = excluded.rate, created_at = excluded.created_at",
+            params![currency, PIVOT_CURRENCY, rate_day(date), rate, adapter.name(), now],
+        ) {
+            Ok(_) => rates_saved += 1,
+            Err(e) => errors.push(format!("{}/{}: {}", currency, PIVOT_CURRENCY, e)),
+        }
+    }
+
+    for e in &errors {
+        error!("Fx sync error: {}", e);
+    }
+    info!(
+        "Fx sync finished: {} pairs, {} rates saved, {} errors",
+        currencies.len(),
+        rates_saved,
+        errors.len()
+    );
+
+    Ok(FxSyncReport {
+        started_at,
+        finished_at: Utc::now().timestamp(),
+        pairs_checked: currencies.len(),
+        rates_saved,
+        errors,
+    })
+}
+
+fn row_to_rate(row: &rusqlite::Row) -> rusqlite::Result<FxRate> {
+    Ok(FxRate {
+        id: row.get(0)?,
+        base_currency: row.get(1)?,
+        quote_currency: row.get(2)?,
+        rate_date: row.get(3)?,
+        rate: row.get(4)?,
+        source: row.get(5)?,
+        user_id: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+/// 录入用户的手动汇率（同一天同一币种对覆盖更新）
+pub fn save_manual_rate(
+    user_id: i64,
+    base_currency: &str,
+    quote_currency: &str,
+    rate: f64,
+    rate_date: i64,
+) -> Result<FxRate, AuthError> {
+    let base = canonical_currency(&validate_currency(base_currency)?);
+    let quote = canonical_currency(&validate_currency(quote_currency)?);
+
+    if base == quote {
+        return Err(AuthError::InvalidCredentials("两种币种不能相同".to_string()));
+    }
+    if rate <= 0.0 {
+        return Err(AuthError::InvalidCredentials("汇率必须大于0".to_string()));
+    }
+
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    conn.execute(
+        "INSERT INTO fx_rates (base_currency, quote_currency, rate_date, rate, source, user_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(base_currency, quote_currency, rate_date, source, user_id)
+         DO UPDATE SET rate = excluded.rate, created_at = excluded.created_at",
+        params![base, quote, rate_day(rate_date), rate, MANUAL_SOURCE, user_id, now],
+    )
+    .map_err(|e| {
+        error!("Failed to save manual fx rate: {}", e);
+        AuthError::DatabaseError(format!("保存汇率失败: {}", e))
+    })?;
+
+    let saved = conn.query_row(
+        "SELECT id, base_currency, quote_currency, rate_date, rate, source, user_id, created_at
+         FROM fx_rates
+         WHERE base_currency = ?1 AND quote_currency = ?2 AND rate_date = ?3 AND source = ?4 AND user_id = ?5",
+        params![base, quote, rate_day(rate_date), MANUAL_SOURCE, user_id],
+        row_to_rate,
+    )?;
+
+    info!("Manual fx rate saved: {}/{} = {} for user: {}", base, quote, rate, user_id);
+    Ok(saved)
+}
+
+/// 获取币种对的汇率历史（公共汇率和该用户的手动汇率，包含反向报价的记录，按日期倒序）
+pub fn get_fx_rates(
+    user_id: i64,
+    base_currency: &str,
+    quote_currency: &str,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<FxRate>, AuthError> {
+    let base = canonical_currency(&validate_currency(base_currency)?);
+    let quote = canonical_currency(&validate_currency(quote_currency)?);
+
+    let conn = get_connection_from_pool()?;
+    let mut stmt = conn.prepare(
+        "SELECT id, base_currency, quote_currency, rate_date, rate, source, user_id, created_at
+         FROM fx_rates
+         WHERE ((base_currency = ?1 AND quote_currency = ?2) OR (base_currency = ?2 AND quote_currency = ?1))
+           AND (?3 IS NULL OR rate_date >= ?3)
+           AND (?4 IS NULL OR rate_date <= ?4)
+           AND user_id IN (0, ?5)
+         ORDER BY rate_date DESC, source",
+    )?;
+
+    let rates = stmt
+        .query_map(params![base, quote, start_date, end_date, user_id], row_to_rate)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch fx rates: {}", e);
+            AuthError::DatabaseError(format!("获取汇率失败: {}", e))
+        })?;
+
+    Ok(rates)
+}
+
+/// 删除用户的手动汇率（公共汇率不能删除）
+pub fn delete_fx_rate(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let deleted = conn.execute(
+        "DELETE FROM fx_rates WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    if deleted == 0 {
+        return Err(AuthError::InvalidCredentials("汇率记录不存在或无权限".to_string()));
+    }
+
+    info!("Manual fx rate deleted: {} for user: {}", id, user_id);
+    Ok(())
+}
@@ -0,0 +1,98 @@
+/**
+ * 技术指标计算
+ *
+ * 输入均为按日期升序排列的收盘价序列，返回最新一期的指标值，数据不足时返回 None。
+ */
+
+/// 简单移动平均
+pub fn sma(closes: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || closes.len() < period {
+        return None;
+    }
+    let window = &closes[closes.len() - period..];
+    Some(window.iter().sum::<f64>() / period as f64)
+}
+
+/// 最新价格相对序列最高价的回撤（百分比，0 表示处于最高点）
+pub fn drawdown_from_high(closes: &[f64]) -> Option<f64> {
+    let last = *closes.last()?;
+    let high = closes.iter().cloned().fold(f64::MIN, f64::max);
+    if high <= 0.0 {
+        return None;
+    }
+    Some((1.0 - last / high) * 100.0)
+}
+
+/// 最新价格在序列中的历史百分位（不高于最新价格的比例，百分比）
+pub fn percentile_rank(closes: &[f64]) -> Option<f64> {
+    let last = *closes.last()?;
+    let below = closes.iter().filter(|close| **close <= last).count();
+    Some(below as f64 / closes.len() as f64 * 100.0)
+}
+
+/// 相对强弱指标 RSI（Wilder 平滑），需要至少 period + 1 个收盘价
+pub fn rsi(closes: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || closes.len() <= period {
+        return None;
+    }
+
+    let changes: Vec<f64> = closes.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    let mut gain = changes[..period].iter().filter(|c| **c > 0.0).sum::<f64>() / period as f64;
+    let mut loss = -changes[..period].iter().filter(|c| **c < 0.0).sum::<f64>() / period as f64;
+    for change in &changes[period..] {
+        gain = (gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+        loss = (loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+    }
+
+    if loss == 0.0 {
+        return Some(if gain == 0.0 { 50.0 } else { 100.0 });
+    }
+    Some(100.0 - 100.0 / (1.0 + gain / loss))
+}
+
+/// 指数移动平均序列，以前 period 个收盘价的简单平均为初值，返回值与 closes[period - 1..] 对齐
+fn ema_series(closes: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || closes.len() < period {
+        return Vec::new();
+    }
+
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut ema = closes[..period].iter().sum::<f64>() / period as f64;
+    let mut series = vec![ema];
+    for close in &closes[period..] {
+        ema = alpha * close + (1.0 - alpha) * ema;
+        series.push(ema);
+    }
+    series
+}
+
+/// MACD 线（快线 EMA - 慢线 EMA）和信号线（MACD 线的 EMA）
+pub fn macd(closes: &[f64], fast: usize, slow: usize, signal: usize) -> Option<(f64, f64)> {
+    if fast == 0 || fast >= slow {
+        return None;
+    }
+
+    let fast_ema = ema_series(closes, fast);
+    let slow_ema = ema_series(closes, slow);
+    if slow_ema.is_empty() {
+        return None;
+    }
+    let offset = slow - fast;
+    let line: Vec<f64> = slow_ema
+        .iter()
+        .enumerate()
+        .map(|(index, slow)| fast_ema[index + offset] - slow)
+        .collect();
+
+    let signal_line = ema_series(&line, signal);
+    Some((*line.last()?, *signal_line.last()?))
+}
+
+/// 布林带（中轨、上轨、下轨），中轨为简单移动平均，带宽为 k 倍总体标准差
+pub fn bollinger(closes: &[f64], period: usize, k: f64) -> Option<(f64, f64, f64)> {
+    let middle = sma(closes, period)?;
+    let window = &closes[closes.len() - period..];
+    let variance = window.iter().map(|close| (close - middle).powi(2)).sum::<f64>() / period as f64;
+    let width = k * variance.sqrt();
+    Some((middle, middle + width, middle - width))
+}
@@ -1,9 +1,108 @@
 use crate::database::get_connection_from_pool;
 use crate::error::auth::AuthError;
-use crate::models::InvestmentPlan;
-use chrono::{Datelike, Duration, NaiveDateTime, TimeZone, Utc, Weekday};
+use crate::models::{
+    CatchUpPolicy, InvestmentPlan, PlanAmount, PlanMode, PlanSchedule, PlanStopConditions,
+    SmartDcaConfig,
+};
+use crate::services::calendar::load_asset_calendar;
+use crate::services::plan_schedule::{first_execution, validate_schedule};
+use crate::services::smart_dca::{evaluate_plan_amount, validate_smart_config};
+use chrono::Utc;
 use log::{error, info};
-use rusqlite::params;
+use rusqlite::{params, Row};
+
+/// 查询定投计划的字段，与 `map_investment_plan` 对应
+const PLAN_COLUMNS: &str = "p.id, p.user_id, p.asset_id, a.name, a.code, p.name, p.frequency,
+    p.day_of_week, p.day_of_month, p.amount, p.is_active,
+    p.last_executed, p.next_execution, p.created_at, p.updated_at,
+    p.mode, p.mode_config, p.catch_up_policy, p.days_of_month, p.cron_expression,
+    p.start_date, p.end_date, p.max_total_amount, p.take_profit_percent, p.stop_reason";
+
+fn map_investment_plan(row: &Row) -> Result<InvestmentPlan, rusqlite::Error> {
+    Ok(InvestmentPlan {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        asset_id: row.get(2)?,
+        asset_name: row.get(3)?,
+        asset_code: row.get(4)?,
+        name: row.get(5)?,
+        frequency: row.get(6)?,
+        day_of_week: row.get(7)?,
+        day_of_month: row.get(8)?,
+        days_of_month: parse_days_of_month(row.get(18)?),
+        cron_expression: row.get(19)?,
+        start_date: row.get(20)?,
+        end_date: row.get(21)?,
+        amount: row.get(9)?,
+        mode: row.get(15)?,
+        mode_config: parse_mode_config(row.get(16)?),
+        catch_up_policy: row.get(17)?,
+        max_total_amount: row.get(22)?,
+        take_profit_percent: row.get(23)?,
+        is_active: row.get(10)?,
+        stop_reason: row.get(24)?,
+        last_executed: row.get(11)?,
+        next_execution: row.get(12)?,
+        created_at: row.get(13)?,
+        updated_at: row.get(14)?,
+    })
+}
+
+/// 解析数据库中的智能定投规则
+pub fn parse_mode_config(config: Option<String>) -> Option<SmartDcaConfig> {
+    config.and_then(|config| serde_json::from_str(&config).ok())
+}
+
+/// 解析数据库中的每月执行日（JSON 数组）
+pub fn parse_days_of_month(days: Option<String>) -> Vec<i64> {
+    days.and_then(|days| serde_json::from_str(&days).ok())
+        .unwrap_or_default()
+}
+
+/// 校验计划的停止条件
+pub fn validate_stop_conditions(stop: &PlanStopConditions) -> Result<(), AuthError> {
+    if stop.max_total_amount.is_some_and(|amount| amount.is_nan() || amount <= 0.0) {
+        return Err(AuthError::InvalidCredentials("累计投入上限必须大于0".to_string()));
+    }
+    if stop.take_profit_percent.is_some_and(|percent| percent.is_nan() || percent <= 0.0) {
+        return Err(AuthError::InvalidCredentials("止盈目标必须大于0".to_string()));
+    }
+    Ok(())
+}
+
+/// 解析定投模式，智能定投未指定规则时使用默认规则
+pub fn resolve_plan_mode(
+    mode: Option<&str>,
+    mode_config: Option<&SmartDcaConfig>,
+) -> Result<(PlanMode, Option<SmartDcaConfig>), AuthError> {
+    let mode = match mode {
+        Some(mode) => PlanMode::from_str(mode).ok_or_else(|| {
+            AuthError::InvalidCredentials(
+                "无效的定投模式，支持的模式：FIXED, MA_DEVIATION, DRAWDOWN, PE_PERCENTILE, VALUE_AVERAGING"
+                    .to_string(),
+            )
+        })?,
+        None => PlanMode::Fixed,
+    };
+
+    if mode == PlanMode::Fixed {
+        return Ok((mode, None));
+    }
+
+    let config = mode_config.cloned().unwrap_or_default();
+    validate_smart_config(mode, &config)?;
+    Ok((mode, Some(config)))
+}
+
+/// 解析补执行策略，为空时为 ONCE
+pub fn resolve_catch_up_policy(policy: Option<&str>) -> Result<CatchUpPolicy, AuthError> {
+    match policy {
+        Some(policy) => CatchUpPolicy::from_str(policy).ok_or_else(|| {
+            AuthError::InvalidCredentials("无效的补执行策略，支持的策略：ALL, ONCE, SKIP".to_string())
+        }),
+        None => Ok(CatchUpPolicy::Once),
+    }
+}
 
 /**
  * 创建或更新定投计划
@@ -14,74 +113,45 @@ pub fn save_investment_plan(
     user_id: i64,
     asset_id: i64,
     name: &str,
-    frequency: &str,
-    day_of_week: Option<i64>,
-    day_of_month: Option<i64>,
+    schedule: &PlanSchedule,
     amount: f64,
+    mode: Option<&str>,
+    mode_config: Option<&SmartDcaConfig>,
+    catch_up_policy: Option<&str>,
+    stop: &PlanStopConditions,
     is_active: bool,
 ) -> Result<InvestmentPlan, AuthError> {
     let conn = get_connection_from_pool()?;
     let now = Utc::now().timestamp();
 
-    // 验证频率
-    match frequency {
-        "DAILY" => {
-            if day_of_week.is_some() || day_of_month.is_some() {
-                return Err(AuthError::InvalidCredentials(
-                    "每日定投不需要指定星期几或每月几号".to_string(),
-                ));
-            }
-        }
-        "WEEKLY" => {
-            if day_of_week.is_none() || day_of_week.unwrap() < 1 || day_of_week.unwrap() > 7 {
-                return Err(AuthError::InvalidCredentials(
-                    "每周定投需要指定星期几（1-7）".to_string(),
-                ));
-            }
-            if day_of_month.is_some() {
-                return Err(AuthError::InvalidCredentials(
-                    "每周定投不需要指定每月几号".to_string(),
-                ));
-            }
-        }
-        "BIWEEKLY" => {
-            if day_of_week.is_none() || day_of_week.unwrap() < 1 || day_of_week.unwrap() > 7 {
-                return Err(AuthError::InvalidCredentials(
-                    "每两周定投需要指定星期几（1-7）".to_string(),
-                ));
-            }
-            if day_of_month.is_some() {
-                return Err(AuthError::InvalidCredentials(
-                    "每两周定投不需要指定每月几号".to_string(),
-                ));
-            }
-        }
-        "MONTHLY" => {
-            if day_of_month.is_none() || day_of_month.unwrap() < 1 || day_of_month.unwrap() > 31 {
-                return Err(AuthError::InvalidCredentials(
-                    "每月定投需要指定每月几号（1-31）".to_string(),
-                ));
-            }
-            if day_of_week.is_some() {
-                return Err(AuthError::InvalidCredentials(
-                    "每月定投不需要指定星期几".to_string(),
-                ));
-            }
-        }
-        _ => {
-            return Err(AuthError::InvalidCredentials(
-                "无效的定投频率，支持的频率：DAILY, WEEKLY, BIWEEKLY, MONTHLY".to_string(),
-            ))
-        }
-    }
+    // 验证定投模式和规则
+    let (mode, mode_config) = resolve_plan_mode(mode, mode_config)?;
+    let mode_config_json = mode_config
+        .as_ref()
+        .map(|config| serde_json::to_string(config).unwrap_or_default());
+
+    // 验证补执行策略
+    let catch_up_policy = resolve_catch_up_policy(catch_up_policy)?;
+
+    // 验证执行日程和停止条件
+    validate_schedule(schedule)?;
+    validate_stop_conditions(stop)?;
+    let days_of_month_json = if schedule.days_of_month.is_empty() {
+        None
+    } else {
+        serde_json::to_string(&schedule.days_of_month).ok()
+    };
 
-    // 计算下次执行时间
+    // 计算下次执行时间，休市日顺延到下一个交易日
     let next_execution = if is_active {
-        Some(calculate_next_execution(
-            frequency,
-            day_of_week,
-            day_of_month,
-        )?)
+        let calendar = load_asset_calendar(&conn, asset_id)?;
+        let next = first_execution(schedule, &calendar, now)?;
+        if next.is_none() {
+            return Err(AuthError::InvalidCredentials(
+                "结束日期之前没有可执行的日期".to_string(),
+            ));
+        }
+        next
     } else {
         None
     };
@@ -132,18 +202,30 @@ pub fn save_investment_plan(
             conn.execute(
                 "UPDATE investment_plans 
                  SET asset_id = ?1, name = ?2, frequency = ?3, day_of_week = ?4, day_of_month = ?5, 
-                     amount = ?6, is_active = ?7, next_execution = ?8, updated_at = ?9
-                 WHERE id = ?10",
+                     amount = ?6, mode = ?7, mode_config = ?8, catch_up_policy = ?9, is_active = ?10,
+                     next_execution = ?11, updated_at = ?12, days_of_month = ?13, cron_expression = ?14,
+                     start_date = ?15, end_date = ?16, max_total_amount = ?17, take_profit_percent = ?18,
+                     stop_reason = NULL
+                 WHERE id = ?19",
                 params![
                     asset_id,
                     name,
-                    frequency,
-                    day_of_week,
-                    day_of_month,
+                    schedule.frequency,
+                    schedule.day_of_week,
+                    schedule.day_of_month,
                     amount,
+                    mode.to_str(),
+                    mode_config_json,
+                    catch_up_policy.to_str(),
                     is_active,
                     next_execution,
                     now,
+                    days_of_month_json,
+                    schedule.cron_expression,
+                    schedule.start_date,
+                    schedule.end_date,
+                    stop.max_total_amount,
+                    stop.take_profit_percent,
                     id
                 ],
             )?;
@@ -172,20 +254,31 @@ pub fn save_investment_plan(
             conn.execute(
                 "INSERT INTO investment_plans (
                     user_id, asset_id, name, frequency, day_of_week, day_of_month, 
-                    amount, is_active, next_execution, created_at, updated_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    amount, mode, mode_config, catch_up_policy, is_active, next_execution,
+                    created_at, updated_at, days_of_month, cron_expression, start_date, end_date,
+                    max_total_amount, take_profit_percent
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
                 params![
                     user_id,
                     asset_id,
                     name,
-                    frequency,
-                    day_of_week,
-                    day_of_month,
+                    schedule.frequency,
+                    schedule.day_of_week,
+                    schedule.day_of_month,
                     amount,
+                    mode.to_str(),
+                    mode_config_json,
+                    catch_up_policy.to_str(),
                     is_active,
                     next_execution,
                     now,
-                    now
+                    now,
+                    days_of_month_json,
+                    schedule.cron_expression,
+                    schedule.start_date,
+                    schedule.end_date,
+                    stop.max_total_amount,
+                    stop.take_profit_percent
                 ],
             )?;
 
@@ -217,11 +310,21 @@ pub fn save_investment_plan(
         asset_name,
         asset_code,
         name: name.to_string(),
-        frequency: frequency.to_string(),
-        day_of_week,
-        day_of_month,
+        frequency: schedule.frequency.clone(),
+        day_of_week: schedule.day_of_week,
+        day_of_month: schedule.day_of_month,
+        days_of_month: schedule.days_of_month.clone(),
+        cron_expression: schedule.cron_expression.clone(),
+        start_date: schedule.start_date,
+        end_date: schedule.end_date,
         amount,
+        mode: mode.to_str().to_string(),
+        mode_config,
+        catch_up_policy: catch_up_policy.to_str().to_string(),
+        max_total_amount: stop.max_total_amount,
+        take_profit_percent: stop.take_profit_percent,
         is_active,
+        stop_reason: None,
         last_executed,
         next_execution,
         created_at,
@@ -251,7 +354,9 @@ pub fn delete_investment_plan(id: i64, user_id: i64) -> Result<(), AuthError> {
         ));
     }
 
-    // 删除定投计划
+    // 删除定投计划及其执行记录、止盈止损规则
+    conn.execute("DELETE FROM plan_executions WHERE plan_id = ?1", params![id])?;
+    conn.execute("DELETE FROM exit_rules WHERE plan_id = ?1", params![id])?;
     conn.execute("DELETE FROM investment_plans WHERE id = ?1", params![id])?;
 
     info!("Investment plan deleted: {} for user: {}", id, user_id);
@@ -273,48 +378,26 @@ pub fn get_user_investment_plans(
     };
 
     let mut query = if let Some(a_id) = effective_asset_id {
-        conn.prepare(
-            "SELECT p.id, p.user_id, p.asset_id, a.name, a.code, p.name, p.frequency, 
-                    p.day_of_week, p.day_of_month, p.amount, p.is_active, 
-                    p.last_executed, p.next_execution, p.created_at, p.updated_at
-             FROM investment_plans p
+        conn.prepare(&format!(
+            "SELECT {} FROM investment_plans p
              JOIN assets a ON p.asset_id = a.id
              WHERE p.user_id = ?1 AND p.asset_id = ?2
              ORDER BY p.name",
-        )?
+            PLAN_COLUMNS
+        ))?
     } else {
-        conn.prepare(
-            "SELECT p.id, p.user_id, p.asset_id, a.name, a.code, p.name, p.frequency, 
-                    p.day_of_week, p.day_of_month, p.amount, p.is_active, 
-                    p.last_executed, p.next_execution, p.created_at, p.updated_at
-             FROM investment_plans p
+        conn.prepare(&format!(
+            "SELECT {} FROM investment_plans p
              JOIN assets a ON p.asset_id = a.id
              WHERE p.user_id = ?1
              ORDER BY p.name",
-        )?
+            PLAN_COLUMNS
+        ))?
     };
 
     let plans = if let Some(a_id) = effective_asset_id {
         query
-            .query_map(params![user_id, a_id], |row| {
-                Ok(InvestmentPlan {
-                    id: row.get(0)?,
-                    user_id: row.get(1)?,
-                    asset_id: row.get(2)?,
-                    asset_name: row.get(3)?,
-                    asset_code: row.get(4)?,
-                    name: row.get(5)?,
-                    frequency: row.get(6)?,
-                    day_of_week: row.get(7)?,
-                    day_of_month: row.get(8)?,
-                    amount: row.get(9)?,
-                    is_active: row.get(10)?,
-                    last_executed: row.get(11)?,
-                    next_execution: row.get(12)?,
-                    created_at: row.get(13)?,
-                    updated_at: row.get(14)?,
-                })
-            })?
+            .query_map(params![user_id, a_id], map_investment_plan)?
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| {
                 error!("Failed to fetch user investment plans: {}", e);
@@ -322,25 +405,7 @@ pub fn get_user_investment_plans(
             })?
     } else {
         query
-            .query_map(params![user_id], |row| {
-                Ok(InvestmentPlan {
-                    id: row.get(0)?,
-                    user_id: row.get(1)?,
-                    asset_id: row.get(2)?,
-                    asset_name: row.get(3)?,
-                    asset_code: row.get(4)?,
-                    name: row.get(5)?,
-                    frequency: row.get(6)?,
-                    day_of_week: row.get(7)?,
-                    day_of_month: row.get(8)?,
-                    amount: row.get(9)?,
-                    is_active: row.get(10)?,
-                    last_executed: row.get(11)?,
-                    next_execution: row.get(12)?,
-                    created_at: row.get(13)?,
-                    updated_at: row.get(14)?,
-                })
-            })?
+            .query_map(params![user_id], map_investment_plan)?
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| {
                 error!("Failed to fetch user investment plans: {}", e);
@@ -352,254 +417,49 @@ pub fn get_user_investment_plans(
 }
 
 /**
- * @dev 执行定投计划，并更新下一次定投的执行时间
+ * @dev 预览定投计划本期金额（按当前价格和价格历史计算，不产生交易）
  */
-pub fn execute_due_investment_plans() -> Result<usize, AuthError> {
-    let mut conn = get_connection_from_pool()?;
-    let now = Utc::now().timestamp();
+pub fn preview_plan_amount(plan_id: i64, user_id: i64) -> Result<PlanAmount, AuthError> {
+    let conn = get_connection_from_pool()?;
 
-    // 获取所有到期的定投计划
-    let plans = {
-        let mut stmt = conn.prepare(
-            "SELECT p.id, p.user_id, p.asset_id, p.amount, p.frequency, p.day_of_week, p.day_of_month
+    let plan: Option<(i64, f64, String, Option<String>, Option<f64>)> = conn
+        .query_row(
+            "SELECT p.asset_id, p.amount, p.mode, p.mode_config, a.current_price
              FROM investment_plans p
-             WHERE p.is_active = 1 AND p.next_execution <= ?1",
-        )?;
-
-        let result = stmt
-            .query_map(params![now], |row| {
-                Ok((
-                    row.get::<_, i64>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, i64>(2)?,
-                    row.get::<_, f64>(3)?,
-                    row.get::<_, String>(4)?,
-                    row.get::<_, Option<i64>>(5)?,
-                    row.get::<_, Option<i64>>(6)?,
-                ))
-            })?
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| {
-                error!("Failed to fetch due investment plans: {}", e);
-                AuthError::DatabaseError(format!("获取到期定投计划失败: {}", e))
-            });
-
-        result?
-    };
-
-    let mut executed_count = 0;
-
-    for (plan_id, user_id, asset_id, amount, frequency, day_of_week, day_of_month) in plans {
-        // 获取资产当前价格
-        let current_price: Option<f64> = conn
-            .query_row(
-                "SELECT current_price FROM assets WHERE id = ?1",
-                params![asset_id],
-                |row| row.get(0),
-            )
-            .ok();
-
-        if let Some(price) = current_price {
-            // 创建交易记录
-            let tx = conn.transaction()?;
-
-            tx.execute(
-                "INSERT INTO transactions (
-                    user_id, asset_id, transaction_type, amount, price, 
-                    total_cost, transaction_date, notes, created_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    user_id,
-                    asset_id,
-                    "BUY",
-                    amount / price, // 购买数量 = 金额 / 价格
-                    price,
-                    amount,
-                    now,
-                    format!("自动定投 (计划ID: {})", plan_id),
-                    now
-                ],
-            )?;
-
-            // 计算下次执行时间
-            let next_execution = calculate_next_execution(&frequency, day_of_week, day_of_month)?;
-
-            // 更新定投计划
-            tx.execute(
-                "UPDATE investment_plans 
-                 SET last_executed = ?1, next_execution = ?2, updated_at = ?3
-                 WHERE id = ?4",
-                params![now, next_execution, now, plan_id],
-            )?;
+             JOIN assets a ON p.asset_id = a.id
+             WHERE p.id = ?1 AND p.user_id = ?2",
+            params![plan_id, user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .ok();
 
-            tx.commit()?;
-            executed_count += 1;
-
-            info!(
-                "Executed investment plan: {} for asset: {}",
-                plan_id, asset_id
-            );
-        } else {
-            error!(
-                "Failed to execute investment plan: {} for asset: {} - No price available",
-                plan_id, asset_id
-            );
+    let (asset_id, base_amount, mode, mode_config, current_price) = match plan {
+        Some(plan) => plan,
+        None => {
+            return Err(AuthError::InvalidCredentials(
+                "定投计划不存在或无权限".to_string(),
+            ))
         }
-    }
-
-    Ok(executed_count)
-}
-
-/**
- * @dev 计算定投计划的下一次执行时间
- */
-fn calculate_next_execution(
-    frequency: &str,
-    day_of_week: Option<i64>,
-    day_of_month: Option<i64>,
-) -> Result<i64, AuthError> {
-    let now = Utc::now();
-    let today = now.date_naive();
+    };
 
-    match frequency {
-        "DAILY" => {
-            // 明天同一时间
-            let next = now + Duration::days(1);
-            Ok(next.timestamp())
-        }
-        "WEEKLY" => {
-            if let Some(dow) = day_of_week {
-                // 将 1-7 转换为 Weekday (其中 1 = Mon, 7 = Sun)
-                let target_weekday = match dow {
-                    1 => Weekday::Mon,
-                    2 => Weekday::Tue,
-                    3 => Weekday::Wed,
-                    4 => Weekday::Thu,
-                    5 => Weekday::Fri,
-                    6 => Weekday::Sat,
-                    7 => Weekday::Sun,
-                    _ => return Err(AuthError::InvalidCredentials("无效的星期几".to_string())),
-                };
-
-                let current_weekday = now.weekday();
-                let days_until_target = (7 + target_weekday.num_days_from_monday()
-                    - current_weekday.num_days_from_monday())
-                    % 7;
-
-                // 如果今天是目标日期但已经过了执行时间，则设置为下周
-                let days_to_add = if days_until_target == 0 {
-                    7
-                } else {
-                    days_until_target
-                };
-
-                let next = now + Duration::days(days_to_add as i64);
-                Ok(next.timestamp())
-            } else {
-                Err(AuthError::InvalidCredentials(
-                    "每周定投需要指定星期几".to_string(),
-                ))
-            }
-        }
-        "BIWEEKLY" => {
-            if let Some(dow) = day_of_week {
-                // 与每周定投类似，但间隔为两周
-                let target_weekday = match dow {
-                    1 => Weekday::Mon,
-                    2 => Weekday::Tue,
-                    3 => Weekday::Wed,
-                    4 => Weekday::Thu,
-                    5 => Weekday::Fri,
-                    6 => Weekday::Sat,
-                    7 => Weekday::Sun,
-                    _ => return Err(AuthError::InvalidCredentials("无效的星期几".to_string())),
-                };
-
-                let current_weekday = now.weekday();
-                let days_until_target = (7 + target_weekday.num_days_from_monday()
-                    - current_weekday.num_days_from_monday())
-                    % 7;
-
-                // 如果今天是目标日期但已经过了执行时间，则设置为两周后
-                let days_to_add = if days_until_target == 0 {
-                    14
-                } else {
-                    days_until_target + 7
-                };
-
-                let next = now + Duration::days(days_to_add as i64);
-                Ok(next.timestamp())
-            } else {
-                Err(AuthError::InvalidCredentials(
-                    "每两周定投需要指定星期几".to_string(),
-                ))
-            }
-        }
-        "MONTHLY" => {
-            if let Some(dom) = day_of_month {
-                if dom < 1 || dom > 31 {
-                    return Err(AuthError::InvalidCredentials("无效的每月日期".to_string()));
-                }
+    let price = current_price.ok_or_else(|| {
+        AuthError::InvalidCredentials("资产没有当前价格，无法计算定投金额".to_string())
+    })?;
 
-                let current_day = today.day() as i64;
-                let current_month = today.month();
-                let current_year = today.year();
-
-                // 如果当前日期小于目标日期，则设置为本月目标日期
-                // 否则设置为下月目标日期
-                let (target_year, target_month, target_day) = if current_day < dom {
-                    (current_year, current_month, dom as u32)
-                } else {
-                    // 计算下个月
-                    let next_month = if current_month == 12 {
-                        (current_year + 1, 1)
-                    } else {
-                        (current_year, current_month + 1)
-                    };
-
-                    (next_month.0, next_month.1, dom as u32)
-                };
-
-                // 处理月末日期问题（例如2月没有30日）
-                let days_in_month = match target_month {
-                    1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
-                    4 | 6 | 9 | 11 => 30,
-                    2 => {
-                        // 闰年2月有29天，平年28天
-                        if (target_year % 4 == 0 && target_year % 100 != 0)
-                            || target_year % 400 == 0
-                        {
-                            29
-                        } else {
-                            28
-                        }
-                    }
-                    _ => return Err(AuthError::InternalError("无效的月份".to_string())),
-                };
-
-                let actual_target_day = std::cmp::min(target_day, days_in_month);
-
-                // 创建目标日期时间
-                if let Some(target_date) = NaiveDateTime::new(
-                    chrono::NaiveDate::from_ymd_opt(target_year, target_month, actual_target_day)
-                        .ok_or_else(|| AuthError::InternalError("无效的日期".to_string()))?,
-                    now.time(),
-                )
-                .and_local_timezone(Utc)
-                .single()
-                {
-                    Ok(target_date.timestamp())
-                } else {
-                    Err(AuthError::InternalError("无法创建目标日期".to_string()))
-                }
-            } else {
-                Err(AuthError::InvalidCredentials(
-                    "每月定投需要指定每月几号".to_string(),
-                ))
-            }
-        }
-        _ => Err(AuthError::InvalidCredentials("无效的定投频率".to_string())),
-    }
+    evaluate_plan_amount(
+        &conn,
+        plan_id,
+        asset_id,
+        PlanMode::from_str(&mode).unwrap_or(PlanMode::Fixed),
+        &parse_mode_config(mode_config).unwrap_or_default(),
+        base_amount,
+        price,
+        Utc::now().timestamp(),
+    )
+    .map_err(|e| {
+        error!("Failed to evaluate plan amount for plan {}: {}", plan_id, e);
+        AuthError::DatabaseError(format!("计算定投金额失败: {}", e))
+    })
 }
 
 /**
@@ -622,16 +482,14 @@ pub fn get_today_investment_plans(
         .timestamp();
 
     // 构建查询SQL
-    let mut sql = String::from(
-        "SELECT p.id, p.user_id, p.asset_id, a.name, a.code, p.name, p.frequency, 
-                p.day_of_week, p.day_of_month, p.amount, p.is_active, 
-                p.last_executed, p.next_execution, p.created_at, p.updated_at
-         FROM investment_plans p
+    let mut sql = format!(
+        "SELECT {} FROM investment_plans p
          JOIN assets a ON p.asset_id = a.id
          WHERE p.user_id = ?1
          AND p.is_active = 1
          AND p.next_execution >= ?2
          AND p.next_execution <= ?3",
+        PLAN_COLUMNS
     );
 
     // 如果指定了资产类型且不为0，则添加资产类型过滤条件
@@ -644,35 +502,15 @@ pub fn get_today_investment_plans(
     let mut stmt = conn.prepare(&sql)?;
 
     // 执行查询，根据是否有资产类型过滤条件使用不同的参数
-    let investment_plan_mapper = |row: &rusqlite::Row| {
-        Ok(InvestmentPlan {
-            id: row.get(0)?,
-            user_id: row.get(1)?,
-            asset_id: row.get(2)?,
-            asset_name: row.get(3)?,
-            asset_code: row.get(4)?,
-            name: row.get(5)?,
-            frequency: row.get(6)?,
-            day_of_week: row.get(7)?,
-            day_of_month: row.get(8)?,
-            amount: row.get(9)?,
-            is_active: row.get(10)?,
-            last_executed: row.get(11)?,
-            next_execution: row.get(12)?,
-            created_at: row.get(13)?,
-            updated_at: row.get(14)?,
-        })
-    };
-
     let plans = if asset_type_id > 0 {
         stmt.query_map(
             params![user_id, today_start, today_end, asset_type_id],
-            investment_plan_mapper,
+            map_investment_plan,
         )?
     } else {
         stmt.query_map(
             params![user_id, today_start, today_end],
-            investment_plan_mapper,
+            map_investment_plan,
         )?
     };
 
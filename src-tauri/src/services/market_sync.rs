@@ -0,0 +1,460 @@
+/**
+ * 行情自动同步模块
+ *
+ * 为 `assets` 表中的资产自动刷新行情，替代前端手动调用 `data_update_asset_price`。
+ *
+ * 主要函数说明：
+ * - `sync_assets(user_id, asset_types, intraday)`: 同步指定范围内的资产。
+ *   按 (资产类型, 代码) 去重后通过 `adapters::get_adapter` 获取行情，
+ *   更新 `assets.current_price`，并把日线写入 `price_history`（同一日期覆盖更新）。
+ *   单个资产失败只记录在结果和 `asset_sync_status` 中，不影响其他资产。
+ *   日线写入前经过数据质量检查（`data_quality::check_candles`），并去重、按时间排序后再入库，
+ *   有问题时在结果和同步状态中给出提示。
+ *   同步完成后评估更新成功资产的止盈止损规则、提醒规则和策略信号（`data::evaluate_price_triggers`）。
+ * - `get_asset_sync_status(user_id)`: 获取用户资产最近一次同步状态。
+ *
+ * 数据源选择：
+ * - 资产类型名（asset_types.name，如 FUND）在 `MarketSyncConfig.sources` 中查找数据源，
+ *   适配器按小写类型名获取（如 ("fund", "tiantian")）。
+ * - 复权数据源（如 eastmoney_qfq）的价格会随分红送转调整，不能写入 `price_history`，配置后跳过同步。
+ * - 盘中同步（intraday）以 ticker 最新价为当前价；收盘同步以最新日线收盘价（基金即公布净值）为当前价。
+ */
+use crate::adapters;
+use crate::config::Config;
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    AssetSyncResult, AssetSyncStatus, Candle, DataQualityReport, MarketSyncReport, RepairMode,
+};
+use crate::services::calendar::{exchange_for_asset_type, get_calendar};
+use crate::services::data::evaluate_price_triggers;
+use crate::services::data_quality::{check_candles, repair_candles, DEFAULT_SPIKE_THRESHOLD};
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
+use rusqlite::params;
+use std::collections::HashMap;
+use tokio::task::JoinSet;
+
+/// 同一 (资产类型, 代码) 下的资产（不同用户可能持有同一资产）
+struct SyncTarget {
+    asset_type: String,
+    code: String,
+    source: String,
+    asset_ids: Vec<i64>,
+}
+
+/// 单个代码的行情拉取结果
+struct FetchedQuote {
+    price: f64,
+    bars: Vec<Candle>,
+    warning: Option<String>,
+}
+
+/// 日线日期统一为 UTC 当日零点
+pub fn day_start(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(86400)
+}
+
+fn load_targets(
+    user_id: Option<i64>,
+    asset_types: Option<&[String]>,
+) -> Result<(Vec<SyncTarget>, Vec<AssetSyncResult>), AuthError> {
+    let conn = get_connection_from_pool()?;
+    let config = Config::get().market_sync;
+
+    let mut stmt = conn.prepare(
+        "SELECT a.id, t.name, a.code
+         FROM assets a
+         JOIN asset_types t ON a.asset_type_id = t.id
+         WHERE (?1 IS NULL OR a.user_id = ?1)
+         ORDER BY t.name, a.code",
+    )?;
+
+    let rows = stmt
+        .query_map(params![user_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to load assets for sync: {}", e);
+            AuthError::DatabaseError(format!("获取同步资产失败: {}", e))
+        })?;
+
+    let mut targets: Vec<SyncTarget> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+    let mut skipped = Vec::new();
+
+    for (asset_id, asset_type, code) in rows {
+        let asset_type = asset_type.to_uppercase();
+        if let Some(types) = asset_types {
+            if !types.iter().any(|t| t.eq_ignore_ascii_case(&asset_type)) {
+                continue;
+            }
+        }
+
+        let source = match config.sources.get(&asset_type) {
+            Some(source) if adapters::is_adjusted_source(source) => Err((
+                source.clone(),
+                format!("数据源 {} 为复权行情，不能写入价格历史，请配置不复权数据源", source),
+            )),
+            Some(source) => Ok(source.clone()),
+            None => Err((String::new(), format!("未配置 {} 类型的数据源", asset_type))),
+        };
+        let source = match source {
+            Ok(source) => source,
+            Err((source, error)) => {
+                skipped.push(AssetSyncResult {
+                    asset_id,
+                    asset_type: asset_type.clone(),
+                    code: code.clone(),
+                    source,
+                    success: false,
+                    price: None,
+                    bars_saved: 0,
+                    error: Some(error),
+                    quality_report: None,
+                });
+                continue;
+            }
+        };
+
+        let key = (asset_type.clone(), code.clone());
+        match index.get(&key) {
+            Some(&i) => targets[i].asset_ids.push(asset_id),
+            None => {
+                index.insert(key, targets.len());
+                targets.push(SyncTarget {
+                    asset_type,
+                    code,
+                    source,
+                    asset_ids: vec![asset_id],
+                });
+            }
+        }
+    }
+
+    Ok((targets, skipped))
+}
+
+async fn fetch_quote(
+    asset_type: String,
+    code: String,
+    source: String,
+    intraday: bool,
+    history_days: i64,
+) -> Result<FetchedQuote, String> {
+    let adapter = adapters::get_adapter(&asset_type.to_lowercase(), &source)?;
+
+    let end = Utc::now();
+    let start = end - Duration::days(history_days.max(1));
+    let candles = adapter.get_candles(&code, start, end, "1d").await;
+    let ticker = if intraday || candles.is_err() {
+        Some(adapter.get_ticker(&code).await)
+    } else {
+        None
+    };
+
+    match (candles, ticker) {
+        (Ok(bars), Some(Ok(ticker))) => Ok(FetchedQuote {
+            price: ticker.price,
+            bars,
+            warning: None,
+        }),
+        (Ok(bars), None) => match bars.last() {
+            Some(last) => Ok(FetchedQuote {
+                price: last.close,
+                bars,
+                warning: None,
+            }),
+            None => Err("未获取到日线数据".to_string()),
+        },
+        (Ok(bars), Some(Err(e))) => match bars.last() {
+            Some(last) => Ok(FetchedQuote {
+                price: last.close,
+                bars,
+                warning: Some(format!("获取最新价失败，使用日线收盘价: {}", e)),
+            }),
+            None => Err(e),
+        },
+        (Err(e), Some(Ok(ticker))) => Ok(FetchedQuote {
+            price: ticker.price,
+            bars: Vec::new(),
+            warning: Some(format!("获取日线失败: {}", e)),
+        }),
+        (Err(candle_err), Some(Err(ticker_err))) => {
+            Err(format!("{}; {}", candle_err, ticker_err))
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// 检查拉取到的日线质量，并去重、按时间排序，避免重复和乱序数据直接入库
+fn check_quote_bars(target: &SyncTarget, quote: &mut FetchedQuote) -> Option<DataQualityReport> {
+    if quote.bars.is_empty() {
+        return None;
+    }
+
+    let calendar = get_calendar(exchange_for_asset_type(&target.asset_type));
+    let report = check_candles(
+        &target.code,
+        &target.source,
+        "1d",
+        &quote.bars,
+        DEFAULT_SPIKE_THRESHOLD,
+        Some(&calendar),
+    );
+    let (bars, _) = repair_candles(
+        &quote.bars,
+        "1d",
+        &[RepairMode::Dedupe],
+        DEFAULT_SPIKE_THRESHOLD,
+        Some(&calendar),
+    );
+    quote.bars = bars;
+
+    if !report.is_clean() {
+        warn!(
+            "Market sync data quality issues for {} {} via {}: {} anomalies, {} gaps",
+            target.asset_type,
+            target.code,
+            target.source,
+            report.anomalies.len(),
+            report.gaps.len()
+        );
+        let message = format!(
+            "日线数据质量问题: {} 个异常，{} 处缺口",
+            report.anomalies.len(),
+            report.gaps.len()
+        );
+        quote.warning = Some(match quote.warning.take() {
+            Some(warning) => format!("{}; {}", warning, message),
+            None => message,
+        });
+    }
+
+    Some(report)
+}
+
+/// 将行情写入资产和价格历史
+fn save_quote(asset_ids: &[i64], quote: &FetchedQuote) -> Result<usize, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let tx = conn.transaction()?;
+
+    for asset_id in asset_ids {
+        tx.execute(
+            "UPDATE assets SET current_price = ?1, last_updated = ?2, updated_at = ?3 WHERE id = ?4",
+            params![quote.price, now, now, asset_id],
+        )?;
+
+        for bar in &quote.bars {
+            tx.execute(
+                "INSERT INTO price_history (asset_id, date, open_price, close_price, high_price, low_price, volume, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT(asset_id, date) DO UPDATE SET
+                 open_price = excluded.open_price,
+                 close_price = excluded.close_price,
+                 high_price = excluded.high_price,
+                 low_price = excluded.low_price,
+                 volume = excluded.volume",
+                params![
+                    asset_id,
+                    day_start(bar.timestamp.timestamp()),
+                    bar.open,
+                    bar.close,
+                    bar.high,
+                    bar.low,
+                    bar.volume,
+                    now
+                ],
+            )?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(quote.bars.len())
+}
+
+fn record_status(result: &AssetSyncResult) {
+    let now = Utc::now().timestamp();
+    let outcome = get_connection_from_pool().and_then(|conn| {
+        conn.execute(
+            "INSERT INTO asset_sync_status (asset_id, source, status, message, last_attempt, last_success)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(asset_id) DO UPDATE SET
+             source = excluded.source,
+             status = excluded.status,
+             message = excluded.message,
+             last_attempt = excluded.last_attempt,
+             last_success = COALESCE(excluded.last_success, asset_sync_status.last_success)",
+            params![
+                result.asset_id,
+                result.source,
+                if result.success { "success" } else { "failed" },
+                result.error,
+                now,
+                if result.success { Some(now) } else { None }
+            ],
+        )
+        .map_err(AuthError::from)
+    });
+
+    if let Err(e) = outcome {
+        error!("Failed to record sync status for asset {}: {}", result.asset_id, e);
+    }
+}
+
+/// 同步资产行情
+///
+/// - `user_id`: 为 None 时同步所有用户的资产
+/// - `asset_types`: 为 None 时同步所有类型
+/// - `intraday`: 盘中同步使用 ticker 最新价，收盘同步使用日线收盘价
+pub async fn sync_assets(
+    user_id: Option<i64>,
+    asset_types: Option<Vec<String>>,
+    intraday: bool,
+) -> Result<MarketSyncReport, AuthError> {
+    let started_at = Utc::now().timestamp();
+    let config = Config::get().market_sync;
+    let (targets, mut results) = load_targets(user_id, asset_types.as_deref())?;
+
+    let mut tasks = JoinSet::new();
+    for target in targets {
+        let timeout = std::time::Duration::from_secs(config.request_timeout_seconds.max(1));
+        let history_days = config.history_days;
+        tasks.spawn(async move {
+            let fetched = tokio::time::timeout(
+                timeout,
+                fetch_quote(
+                    target.asset_type.clone(),
+                    target.code.clone(),
+                    target.source.clone(),
+                    intraday,
+                    history_days,
+                ),
+            )
+            .await
+            .unwrap_or_else(|_| Err("请求超时".to_string()));
+            (target, fetched)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        let (target, fetched) = match joined {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Market sync task panicked: {}", e);
+                continue;
+            }
+        };
+
+        let outcome = match fetched {
+            Ok(mut quote) => {
+                let quality_report = check_quote_bars(&target, &mut quote);
+                save_quote(&target.asset_ids, &quote)
+                    .map(|bars| (quote.price, bars, quote.warning, quality_report))
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e),
+        };
+
+        for asset_id in &target.asset_ids {
+            let result = match &outcome {
+                Ok((price, bars, warning, quality_report)) => AssetSyncResult {
+                    asset_id: *asset_id,
+                    asset_type: target.asset_type.clone(),
+                    code: target.code.clone(),
+                    source: target.source.clone(),
+                    success: true,
+                    price: Some(*price),
+                    bars_saved: *bars,
+                    error: warning.clone(),
+                    quality_report: quality_report.clone(),
+                },
+                Err(e) => AssetSyncResult {
+                    asset_id: *asset_id,
+                    asset_type: target.asset_type.clone(),
+                    code: target.code.clone(),
+                    source: target.source.clone(),
+                    success: false,
+                    price: None,
+                    bars_saved: 0,
+                    error: Some(e.clone()),
+                    quality_report: None,
+                },
+            };
+            results.push(result);
+        }
+
+        if let Err(e) = &outcome {
+            warn!(
+                "Market sync failed for {} {} via {}: {}",
+                target.asset_type, target.code, target.source, e
+            );
+        }
+    }
+
+    for result in &results {
+        record_status(result);
+    }
+
+    results.sort_by_key(|r| r.asset_id);
+
+    // 价格更新后评估止盈止损规则、提醒规则和策略信号
+    let updated: Vec<i64> = results.iter().filter(|r| r.success).map(|r| r.asset_id).collect();
+    evaluate_price_triggers(&updated);
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    let report = MarketSyncReport {
+        started_at,
+        finished_at: Utc::now().timestamp(),
+        total: results.len(),
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    };
+
+    info!(
+        "Market sync finished: {} succeeded, {} failed",
+        report.succeeded, report.failed
+    );
+    Ok(report)
+}
+
+/// 获取用户资产的同步状态
+pub fn get_asset_sync_status(user_id: i64) -> Result<Vec<AssetSyncStatus>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT s.asset_id, a.name, a.code, s.source, s.status, s.message, s.last_attempt, s.last_success
+         FROM asset_sync_status s
+         JOIN assets a ON s.asset_id = a.id
+         WHERE a.user_id = ?1
+         ORDER BY s.last_attempt DESC",
+    )?;
+
+    let statuses = stmt
+        .query_map(params![user_id], |row| {
+            Ok(AssetSyncStatus {
+                asset_id: row.get(0)?,
+                asset_name: row.get(1)?,
+                asset_code: row.get(2)?,
+                source: row.get(3)?,
+                status: row.get(4)?,
+                message: row.get(5)?,
+                last_attempt: row.get(6)?,
+                last_success: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch asset sync status: {}", e);
+            AuthError::DatabaseError(format!("获取资产同步状态失败: {}", e))
+        })?;
+
+    Ok(statuses)
+}
@@ -1,7 +1,31 @@
+pub mod alert_rule;
+pub mod allocation;
 pub mod asset;
 pub mod auth;
+pub mod calendar;
+pub mod corporate_action;
 pub mod data;
+pub mod data_quality;
+pub mod digest;
+pub mod exit_rule;
+pub mod fee;
+pub mod fx;
+pub mod indicator;
 pub mod investment_plan;
+pub mod market_sync;
+pub mod notification;
+pub mod notification_sender;
+pub mod performance;
+pub mod plan_backtest;
+pub mod plan_execution;
+pub mod plan_schedule;
+pub mod plan_settlement;
+pub mod position;
+pub mod scheduler;
+pub mod settings;
+pub mod smart_dca;
+pub mod snapshot;
 pub mod strategy;
+pub mod strategy_signal;
 pub mod transaction;
 pub mod verification;
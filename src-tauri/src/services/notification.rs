@@ -0,0 +1,968 @@
+/**
+ * 通知推送模块
+ *
+ * 把交易提醒（止盈止损、提醒规则、策略信号等生成的 `trade_alerts`）推送到用户配置的通知渠道：
+ * - 入队：未推送的提醒（`notified_at` 为空）按用户偏好的提醒类型筛选后，为每个启用的渠道生成一条发送记录。
+ *   超过 `alert_lookback_hours` 的旧提醒只标记为已处理，不再补发。
+ * - 发送：到期的发送记录逐条发送，失败后按 `retry_delay_seconds` 翻倍间隔重试（最长间隔一天），
+ *   发送次数达到 `max_attempts` 后标记为失败。
+ * - 免打扰：发送时处于用户的免打扰时段（北京时间）则推迟到时段结束；渠道被停用时记录保留，重新启用后继续发送。
+ *
+ * 后台调度每分钟执行一次入队和发送（`notification.enabled` 关闭时跳过），也可以手动触发。
+ */
+use crate::config::config::NotificationConfig;
+use crate::config::Config;
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    DeliveryStatus, NotificationChannel, NotificationChannelType, NotificationDelivery, NotificationDispatchReport,
+    NotificationPreferences, SaveNotificationChannelRequest, UpdateNotificationPreferencesRequest,
+};
+use crate::services::notification_sender::{send_notification, ChannelTarget, OutgoingNotification};
+use crate::services::scheduler::beijing_offset;
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use log::{error, info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+/// 单次发送的最大条数
+const DISPATCH_BATCH_SIZE: i64 = 100;
+
+/// 最长重试间隔（秒）
+const MAX_RETRY_DELAY_SECONDS: i64 = 86400;
+
+/// 默认返回的发送记录条数
+const DEFAULT_DELIVERY_LIMIT: i64 = 100;
+
+const CHANNEL_COLUMNS: &str =
+    "id, user_id, name, channel_type, target, secret, is_enabled, created_at, updated_at";
+
+/// 待发送的记录
+struct DueDelivery {
+    id: i64,
+    user_id: i64,
+    alert_id: Option<i64>,
+    alert_type: Option<String>,
+    title: String,
+    body: String,
+    html_body: Option<String>,
+    attempts: i64,
+    target: ChannelTarget,
+}
+
+fn map_channel(row: &rusqlite::Row) -> Result<NotificationChannel, rusqlite::Error> {
+    Ok(NotificationChannel {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        name: row.get(2)?,
+        channel_type: row.get(3)?,
+        target: row.get(4)?,
+        secret: row.get(5)?,
+        is_enabled: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn load_channel(conn: &Connection, id: i64, user_id: i64) -> Result<NotificationChannel, AuthError> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM notification_channels WHERE id = ?1 AND user_id = ?2",
+            CHANNEL_COLUMNS
+        ),
+        params![id, user_id],
+        map_channel,
+    )
+    .optional()?
+    .ok_or_else(|| AuthError::InvalidCredentials("通知渠道不存在或无权限".to_string()))
+}
+
+fn normalize(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// 校验渠道的 target 和 secret
+fn validate_channel(
+    channel_type: NotificationChannelType,
+    target: &Option<String>,
+    secret: &Option<String>,
+) -> Result<(), AuthError> {
+    match channel_type {
+        NotificationChannelType::Desktop => Ok(()),
+        NotificationChannelType::Email => match target {
+            Some(email) if email.contains('@') => Ok(()),
+            _ => Err(AuthError::InvalidCredentials("邮件渠道需要有效的收件邮箱".to_string())),
+        },
+        NotificationChannelType::Telegram => {
+            if target.is_none() || secret.is_none() {
+                return Err(AuthError::InvalidCredentials(
+                    "Telegram 渠道需要设置 chat_id 和 Bot Token".to_string(),
+                ));
+            }
+            Ok(())
+        }
+        _ => match target {
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => Ok(()),
+            _ => Err(AuthError::InvalidCredentials(
+                "Webhook 地址必须以 http:// 或 https:// 开头".to_string(),
+            )),
+        },
+    }
+}
+
+/**
+ * @dev 创建或更新通知渠道
+ */
+pub fn save_notification_channel(
+    request: &SaveNotificationChannelRequest,
+) -> Result<NotificationChannel, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let user_id = request.user_id;
+
+    let channel_type = NotificationChannelType::from_str(&request.channel_type).ok_or_else(|| {
+        AuthError::InvalidCredentials(
+            "无效的渠道类型，支持的类型：DESKTOP, EMAIL, WEBHOOK, WECOM, DINGTALK, FEISHU, TELEGRAM"
+                .to_string(),
+        )
+    })?;
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(AuthError::InvalidCredentials("渠道名称不能为空".to_string()));
+    }
+    let target = normalize(&request.target);
+    let secret = normalize(&request.secret);
+    validate_channel(channel_type, &target, &secret)?;
+    let is_enabled = request.is_enabled.unwrap_or(true);
+
+    let id = match request.id {
+        Some(id) => {
+            load_channel(&conn, id, user_id)?;
+            conn.execute(
+                "UPDATE notification_channels
+                 SET name = ?1, channel_type = ?2, target = ?3, secret = ?4, is_enabled = ?5, updated_at = ?6
+                 WHERE id = ?7",
+                params![name, channel_type.to_str(), target, secret, is_enabled, now, id],
+            )?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO notification_channels (
+                    user_id, name, channel_type, target, secret, is_enabled, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![user_id, name, channel_type.to_str(), target, secret, is_enabled, now, now],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    info!("Notification channel saved: {} ({}) for user: {}", id, channel_type.to_str(), user_id);
+    load_channel(&conn, id, user_id)
+}
+
+/**
+ * @dev 获取用户的通知渠道
+ */
+pub fn get_notification_channels(user_id: i64) -> Result<Vec<NotificationChannel>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM notification_channels WHERE user_id = ?1 ORDER BY id",
+        CHANNEL_COLUMNS
+    ))?;
+
+    let channels = stmt
+        .query_map(params![user_id], map_channel)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch notification channels: {}", e);
+            AuthError::DatabaseError(format!("获取通知渠道失败: {}", e))
+        })?;
+
+    Ok(channels)
+}
+
+/**
+ * @dev 删除通知渠道及其发送记录
+ */
+pub fn delete_notification_channel(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "DELETE FROM notification_deliveries WHERE channel_id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    let deleted = tx.execute(
+        "DELETE FROM notification_channels WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    if deleted == 0 {
+        return Err(AuthError::InvalidCredentials("通知渠道不存在或无权限".to_string()));
+    }
+    tx.commit()?;
+
+    info!("Notification channel deleted: {} for user: {}", id, user_id);
+    Ok(())
+}
+
+/**
+ * @dev 立即通过渠道发送一条测试通知（不进入发送队列）
+ */
+pub async fn send_test_notification(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let channel = {
+        let conn = get_connection_from_pool()?;
+        load_channel(&conn, id, user_id)?
+    };
+    let channel_type = NotificationChannelType::from_str(&channel.channel_type)
+        .ok_or_else(|| AuthError::InternalError(format!("未知的渠道类型: {}", channel.channel_type)))?;
+
+    let target = ChannelTarget {
+        channel_type,
+        target: channel.target,
+        secret: channel.secret,
+    };
+    let notification = OutgoingNotification {
+        user_id,
+        delivery_id: None,
+        alert_id: None,
+        alert_type: None,
+        title: "WolfQuant 测试通知".to_string(),
+        body: format!("通知渠道「{}」配置成功。", channel.name),
+        html: None,
+    };
+
+    send_notification(&target, &notification)
+        .await
+        .map_err(|e| AuthError::InternalError(format!("测试通知发送失败: {}", e)))?;
+
+    info!("Test notification sent via channel: {} for user: {}", id, user_id);
+    Ok(())
+}
+
+fn load_preferences(conn: &Connection, user_id: i64) -> Result<NotificationPreferences, rusqlite::Error> {
+    let preferences = conn
+        .query_row(
+            "SELECT alert_types, quiet_hours_start, quiet_hours_end, updated_at
+             FROM notification_preferences WHERE user_id = ?1",
+            params![user_id],
+            |row| {
+                let alert_types: String = row.get(0)?;
+                Ok(NotificationPreferences {
+                    user_id,
+                    alert_types: serde_json::from_str(&alert_types).unwrap_or_default(),
+                    quiet_hours_start: row.get(1)?,
+                    quiet_hours_end: row.get(2)?,
+                    updated_at: row.get(3)?,
+                })
+            },
+        )
+        .optional()?;
+
+    // 未设置时推送全部提醒，不开启免打扰
+    Ok(preferences.unwrap_or(NotificationPreferences {
+        user_id,
+        alert_types: Vec::new(),
+        quiet_hours_start: None,
+        quiet_hours_end: None,
+        updated_at: 0,
+    }))
+}
+
+/**
+ * @dev 获取用户的通知偏好
+ */
+pub fn get_notification_preferences(user_id: i64) -> Result<NotificationPreferences, AuthError> {
+    let conn = get_connection_from_pool()?;
+    Ok(load_preferences(&conn, user_id)?)
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+/**
+ * @dev 更新用户的通知偏好
+ */
+pub fn update_notification_preferences(
+    request: &UpdateNotificationPreferencesRequest,
+) -> Result<NotificationPreferences, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let quiet_hours_start = normalize(&request.quiet_hours_start);
+    let quiet_hours_end = normalize(&request.quiet_hours_end);
+    match (&quiet_hours_start, &quiet_hours_end) {
+        (None, None) => {}
+        (Some(start), Some(end)) => {
+            if parse_time(start).is_none() || parse_time(end).is_none() {
+                return Err(AuthError::InvalidCredentials("免打扰时间格式应为 HH:MM".to_string()));
+            }
+            if start == end {
+                return Err(AuthError::InvalidCredentials("免打扰开始和结束时间不能相同".to_string()));
+            }
+        }
+        _ => {
+            return Err(AuthError::InvalidCredentials(
+                "免打扰开始和结束时间需要同时设置".to_string(),
+            ))
+        }
+    }
+
+    let mut alert_types: Vec<String> = request
+        .alert_types
+        .iter()
+        .map(|alert_type| alert_type.trim().to_uppercase())
+        .filter(|alert_type| !alert_type.is_empty())
+        .collect();
+    alert_types.sort();
+    alert_types.dedup();
+    let alert_types_json = serde_json::to_string(&alert_types)
+        .map_err(|e| AuthError::InternalError(format!("序列化提醒类型失败: {}", e)))?;
+
+    conn.execute(
+        "INSERT INTO notification_preferences (user_id, alert_types, quiet_hours_start, quiet_hours_end, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(user_id) DO UPDATE SET
+            alert_types = excluded.alert_types,
+            quiet_hours_start = excluded.quiet_hours_start,
+            quiet_hours_end = excluded.quiet_hours_end,
+            updated_at = excluded.updated_at",
+        params![request.user_id, alert_types_json, quiet_hours_start, quiet_hours_end, now],
+    )?;
+
+    info!("Notification preferences updated for user: {}", request.user_id);
+    Ok(load_preferences(&conn, request.user_id)?)
+}
+
+/// 处于免打扰时段时返回时段结束的时间戳
+fn quiet_hours_end_at(preferences: &NotificationPreferences, now: i64) -> Option<i64> {
+    let start = parse_time(preferences.quiet_hours_start.as_deref()?)?;
+    let end = parse_time(preferences.quiet_hours_end.as_deref()?)?;
+    let local = DateTime::from_timestamp(now, 0)?.with_timezone(&beijing_offset());
+    let time = local.time();
+    let today = local.date_naive();
+
+    let end_date = if start < end {
+        // 同一天内的时段，如 12:00-14:00
+        if time < start || time >= end {
+            return None;
+        }
+        today
+    } else if time >= start {
+        // 跨零点的时段，如 22:00-08:00，当前在零点前
+        today + Duration::days(1)
+    } else if time < end {
+        today
+    } else {
+        return None;
+    };
+
+    beijing_offset()
+        .from_local_datetime(&end_date.and_time(end))
+        .single()
+        .map(|end_at| end_at.timestamp())
+}
+
+/**
+ * @dev 把未推送的交易提醒加入发送队列
+ * @return 新加入队列的通知条数
+ */
+pub fn enqueue_alert_notifications() -> Result<usize, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let lookback_start = now - Config::get().notification.alert_lookback_hours.max(0) * 3600;
+
+    let alerts = {
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.user_id, t.alert_type, t.message, t.created_at, a.name
+             FROM trade_alerts t
+             JOIN assets a ON t.asset_id = a.id
+             WHERE t.notified_at IS NULL
+             ORDER BY t.created_at, t.id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    if alerts.is_empty() {
+        return Ok(0);
+    }
+
+    let tx = conn.transaction()?;
+    let mut preferences: HashMap<i64, NotificationPreferences> = HashMap::new();
+    let mut channels: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut queued = 0;
+
+    for (alert_id, user_id, alert_type, message, created_at, asset_name) in alerts {
+        tx.execute(
+            "UPDATE trade_alerts SET notified_at = ?1 WHERE id = ?2",
+            params![now, alert_id],
+        )?;
+
+        if created_at < lookback_start {
+            continue;
+        }
+
+        if !preferences.contains_key(&user_id) {
+            preferences.insert(user_id, load_preferences(&tx, user_id)?);
+        }
+        let alert_types = &preferences[&user_id].alert_types;
+        if !alert_types.is_empty() && !alert_types.contains(&alert_type) {
+            continue;
+        }
+
+        if !channels.contains_key(&user_id) {
+            let mut stmt =
+                tx.prepare("SELECT id FROM notification_channels WHERE user_id = ?1 AND is_enabled = 1")?;
+            let ids = stmt
+                .query_map(params![user_id], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            channels.insert(user_id, ids);
+        }
+
+        let title = format!("WolfQuant 交易提醒：{}", asset_name);
+        for channel_id in &channels[&user_id] {
+            tx.execute(
+                "INSERT INTO notification_deliveries (
+                    user_id, channel_id, alert_id, title, body, status, attempts, next_attempt_at, created_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7, ?8)",
+                params![
+                    user_id,
+                    channel_id,
+                    alert_id,
+                    title,
+                    message,
+                    DeliveryStatus::Pending.to_str(),
+                    now,
+                    now
+                ],
+            )?;
+            queued += 1;
+        }
+    }
+
+    tx.commit()?;
+
+    if queued > 0 {
+        info!("Queued {} notifications", queued);
+    }
+    Ok(queued)
+}
+
+/**
+ * @dev 把一条通知（如摘要报告）加入用户所有启用渠道的发送队列
+ * @param html_body 邮件渠道使用的 HTML 正文
+ * @return 加入队列的条数
+ */
+pub fn enqueue_user_notification(
+    user_id: i64,
+    title: &str,
+    body: &str,
+    html_body: Option<&str>,
+) -> Result<usize, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+    let tx = conn.transaction()?;
+
+    let channel_ids = {
+        let mut stmt =
+            tx.prepare("SELECT id FROM notification_channels WHERE user_id = ?1 AND is_enabled = 1")?;
+        let ids = stmt
+            .query_map(params![user_id], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        ids
+    };
+
+    for channel_id in &channel_ids {
+        tx.execute(
+            "INSERT INTO notification_deliveries (
+                user_id, channel_id, title, body, html_body, status, attempts, next_attempt_at, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7, ?8)",
+            params![
+                user_id,
+                channel_id,
+                title,
+                body,
+                html_body,
+                DeliveryStatus::Pending.to_str(),
+                now,
+                now
+            ],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(channel_ids.len())
+}
+
+fn load_due_deliveries(conn: &Connection, now: i64) -> Result<Vec<DueDelivery>, AuthError> {
+    let mut stmt = conn.prepare(
+        "SELECT d.id, d.user_id, d.alert_id, t.alert_type, d.title, d.body, d.html_body, d.attempts,
+                c.channel_type, c.target, c.secret
+         FROM notification_deliveries d
+         JOIN notification_channels c ON d.channel_id = c.id
+         LEFT JOIN trade_alerts t ON d.alert_id = t.id
+         WHERE d.status = ?1 AND c.is_enabled = 1 AND (d.next_attempt_at IS NULL OR d.next_attempt_at <= ?2)
+         ORDER BY d.next_attempt_at, d.id
+         LIMIT ?3",
+    )?;
+
+    let rows = stmt
+        .query_map(params![DeliveryStatus::Pending.to_str(), now, DISPATCH_BATCH_SIZE], |row| {
+            Ok((
+                DueDelivery {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    alert_id: row.get(2)?,
+                    alert_type: row.get(3)?,
+                    title: row.get(4)?,
+                    body: row.get(5)?,
+                    html_body: row.get(6)?,
+                    attempts: row.get(7)?,
+                    target: ChannelTarget {
+                        channel_type: NotificationChannelType::Desktop,
+                        target: row.get(9)?,
+                        secret: row.get(10)?,
+                    },
+                },
+                row.get::<_, String>(8)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut deliveries = Vec::new();
+    for (mut delivery, channel_type) in rows {
+        match NotificationChannelType::from_str(&channel_type) {
+            Some(channel_type) => {
+                delivery.target.channel_type = channel_type;
+                deliveries.push(delivery);
+            }
+            None => warn!("Skipping delivery {} with unknown channel type: {}", delivery.id, channel_type),
+        }
+    }
+
+    Ok(deliveries)
+}
+
+/**
+ * @dev 发送到期的通知，失败的通知按间隔重试
+ */
+pub async fn dispatch_notifications() -> Result<NotificationDispatchReport, AuthError> {
+    let notification_config = Config::get().notification;
+    let now = Utc::now().timestamp();
+
+    // 先读出待发送的记录并释放连接，发送过程中不占用数据库连接
+    let (deliveries, quiet_until) = {
+        let conn = get_connection_from_pool()?;
+        let deliveries = load_due_deliveries(&conn, now)?;
+
+        let mut quiet_until: HashMap<i64, Option<i64>> = HashMap::new();
+        for delivery in &deliveries {
+            if !quiet_until.contains_key(&delivery.user_id) {
+                let preferences = load_preferences(&conn, delivery.user_id)?;
+                quiet_until.insert(delivery.user_id, quiet_hours_end_at(&preferences, now));
+            }
+        }
+        (deliveries, quiet_until)
+    };
+
+    let mut report = NotificationDispatchReport::default();
+
+    for delivery in deliveries {
+        // 免打扰时段内推迟到时段结束，不计入发送次数
+        if let Some(end_at) = quiet_until.get(&delivery.user_id).copied().flatten() {
+            let conn = get_connection_from_pool()?;
+            conn.execute(
+                "UPDATE notification_deliveries SET next_attempt_at = ?1 WHERE id = ?2",
+                params![end_at, delivery.id],
+            )?;
+            continue;
+        }
+
+        let notification = OutgoingNotification {
+            user_id: delivery.user_id,
+            delivery_id: Some(delivery.id),
+            alert_id: delivery.alert_id,
+            alert_type: delivery.alert_type,
+            title: delivery.title,
+            body: delivery.body,
+            html: delivery.html_body,
+        };
+        let result = send_notification(&delivery.target, &notification).await;
+
+        let conn = get_connection_from_pool()?;
+        match record_send_result(&conn, delivery.id, delivery.attempts + 1, result, &notification_config)? {
+            DeliveryStatus::Sent => report.sent += 1,
+            DeliveryStatus::Failed => report.failed += 1,
+            DeliveryStatus::Pending => report.retrying += 1,
+        }
+    }
+
+    Ok(report)
+}
+
+/**
+ * @dev 记录一次发送的结果：成功标记为已发送；失败且次数达到上限标记为失败，否则按翻倍间隔安排重试
+ * @param attempts 包含本次在内的发送次数
+ * @return 发送记录更新后的状态
+ */
+fn record_send_result(
+    conn: &Connection,
+    delivery_id: i64,
+    attempts: i64,
+    result: Result<(), String>,
+    config: &NotificationConfig,
+) -> Result<DeliveryStatus, AuthError> {
+    let now = Utc::now().timestamp();
+
+    match result {
+        Ok(()) => {
+            conn.execute(
+                "UPDATE notification_deliveries
+                 SET status = ?1, attempts = ?2, last_error = NULL, next_attempt_at = NULL, sent_at = ?3
+                 WHERE id = ?4",
+                params![DeliveryStatus::Sent.to_str(), attempts, now, delivery_id],
+            )?;
+            Ok(DeliveryStatus::Sent)
+        }
+        Err(e) if attempts >= config.max_attempts.max(1) as i64 => {
+            warn!("Notification delivery {} failed after {} attempts: {}", delivery_id, attempts, e);
+            conn.execute(
+                "UPDATE notification_deliveries
+                 SET status = ?1, attempts = ?2, last_error = ?3, next_attempt_at = NULL
+                 WHERE id = ?4",
+                params![DeliveryStatus::Failed.to_str(), attempts, e, delivery_id],
+            )?;
+            Ok(DeliveryStatus::Failed)
+        }
+        Err(e) => {
+            let delay = config
+                .retry_delay_seconds
+                .max(1)
+                .saturating_mul(1i64 << (attempts - 1).min(20))
+                .min(MAX_RETRY_DELAY_SECONDS);
+            warn!("Notification delivery {} failed, retrying in {}s: {}", delivery_id, delay, e);
+            conn.execute(
+                "UPDATE notification_deliveries
+                 SET attempts = ?1, last_error = ?2, next_attempt_at = ?3
+                 WHERE id = ?4",
+                params![attempts, e, now + delay, delivery_id],
+            )?;
+            Ok(DeliveryStatus::Pending)
+        }
+    }
+}
+
+/**
+ * @dev 把新提醒加入队列并发送到期的通知
+ */
+pub async fn process_notifications() -> Result<NotificationDispatchReport, AuthError> {
+    let queued = enqueue_alert_notifications()?;
+    let mut report = dispatch_notifications().await?;
+    report.queued = queued;
+
+    if report.sent + report.retrying + report.failed > 0 {
+        info!(
+            "Notifications processed: {} sent, {} retrying, {} failed",
+            report.sent, report.retrying, report.failed
+        );
+    }
+    Ok(report)
+}
+
+/**
+ * @dev 获取用户的通知发送记录，按创建时间倒序
+ */
+pub fn get_notification_deliveries(
+    user_id: i64,
+    status: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<NotificationDelivery>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let status = match status.filter(|status| !status.is_empty()) {
+        Some(status) => Some(
+            DeliveryStatus::from_str(status)
+                .ok_or_else(|| {
+                    AuthError::InvalidCredentials("无效的发送状态，支持的状态：PENDING, SENT, FAILED".to_string())
+                })?
+                .to_str(),
+        ),
+        None => None,
+    };
+    let limit = limit.filter(|limit| *limit > 0).unwrap_or(DEFAULT_DELIVERY_LIMIT);
+
+    let mut stmt = conn.prepare(
+        "SELECT d.id, d.user_id, d.channel_id, c.name, c.channel_type, d.alert_id, d.title, d.body,
+                d.status, d.attempts, d.last_error, d.next_attempt_at, d.sent_at, d.created_at
+         FROM notification_deliveries d
+         JOIN notification_channels c ON d.channel_id = c.id
+         WHERE d.user_id = ?1 AND (?2 IS NULL OR d.status = ?2)
+         ORDER BY d.created_at DESC, d.id DESC
+         LIMIT ?3",
+    )?;
+
+    let deliveries = stmt
+        .query_map(params![user_id, status, limit], |row| {
+            Ok(NotificationDelivery {
+                id: row.get(0)?,
+                user_id: row.get(1)?,
+                channel_id: row.get(2)?,
+                channel_name: row.get(3)?,
+                channel_type: row.get(4)?,
+                alert_id: row.get(5)?,
+                title: row.get(6)?,
+                body: row.get(7)?,
+                status: row.get(8)?,
+                attempts: row.get(9)?,
+                last_error: row.get(10)?,
+                next_attempt_at: row.get(11)?,
+                sent_at: row.get(12)?,
+                created_at: row.get(13)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch notification deliveries: {}", e);
+            AuthError::DatabaseError(format!("获取通知发送记录失败: {}", e))
+        })?;
+
+    Ok(deliveries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use hmac::{Hmac, Mac};
+    use serde_json::Value;
+    use sha2::Sha256;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 模拟服务收到的请求（请求路径含查询参数、JSON 内容）
+    struct CapturedRequest {
+        path: String,
+        body: Value,
+    }
+
+    /// 启动本地模拟 HTTP 服务，按顺序返回预设的响应（状态码、内容）
+    async fn start_mock_server(
+        responses: Vec<(u16, &'static str)>,
+    ) -> (String, Arc<Mutex<Vec<CapturedRequest>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(Mutex::new(VecDeque::from(responses)));
+
+        let requests = captured.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = Vec::new();
+                let mut chunk = [0u8; 4096];
+
+                // 读取请求头和按 Content-Length 读取请求体
+                let (head_end, content_length) = loop {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buffer.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&buffer[..pos]).to_lowercase();
+                        let length = head
+                            .lines()
+                            .find_map(|line| line.strip_prefix("content-length:"))
+                            .and_then(|value| value.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        break (pos + 4, length);
+                    }
+                };
+                while buffer.len() < head_end + content_length {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buffer.extend_from_slice(&chunk[..n]);
+                }
+
+                let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+                let path = head.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let body = serde_json::from_slice(&buffer[head_end..head_end + content_length]).unwrap_or(Value::Null);
+                requests.lock().unwrap().push(CapturedRequest { path, body });
+
+                let (status, content) = responses.lock().unwrap().pop_front().unwrap_or((200, "{}"));
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    content.len(),
+                    content
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (base_url, captured)
+    }
+
+    fn notification() -> OutgoingNotification {
+        OutgoingNotification {
+            user_id: 1,
+            delivery_id: Some(1),
+            alert_id: Some(2),
+            alert_type: Some("PRICE".to_string()),
+            title: "价格提醒".to_string(),
+            body: "沪深300ETF 价格 4.0000".to_string(),
+            html: None,
+        }
+    }
+
+    fn target(channel_type: NotificationChannelType, url: &str, secret: Option<&str>) -> ChannelTarget {
+        ChannelTarget {
+            channel_type,
+            target: Some(url.to_string()),
+            secret: secret.map(|secret| secret.to_string()),
+        }
+    }
+
+    fn verify_sign(key: &[u8], message: &[u8], sign: &str) {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(message);
+        mac.verify_slice(&STANDARD.decode(sign).unwrap()).expect("签名不正确");
+    }
+
+    fn query_param(path: &str, name: &str) -> String {
+        let url = reqwest::Url::parse(&format!("http://localhost{}", path)).unwrap();
+        let value = url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.to_string());
+        value.unwrap_or_else(|| panic!("缺少参数 {}", name))
+    }
+
+    #[tokio::test]
+    async fn sends_robot_payloads_and_tracks_retries() {
+        let (base_url, captured) = start_mock_server(vec![
+            (200, r#"{"errcode":0,"errmsg":"ok"}"#),
+            (200, r#"{"errcode":0,"errmsg":"ok"}"#),
+            (200, r#"{"code":0,"msg":"success"}"#),
+            (500, "internal error"),
+            (200, r#"{"errcode":310000,"errmsg":"sign not match"}"#),
+            (200, r#"{"errcode":0,"errmsg":"ok"}"#),
+        ])
+        .await;
+        let notification = notification();
+
+        // 企业微信：markdown 消息
+        let wecom = target(NotificationChannelType::WeCom, &format!("{}/wecom?key=k", base_url), None);
+        send_notification(&wecom, &notification).await.unwrap();
+
+        // 钉钉：markdown 消息，timestamp(毫秒) + "\n" + secret 以 secret 为密钥签名，放在查询参数中
+        let dingtalk = target(
+            NotificationChannelType::DingTalk,
+            &format!("{}/dingtalk?access_token=t", base_url),
+            Some("SECdingtalk"),
+        );
+        send_notification(&dingtalk, &notification).await.unwrap();
+
+        // 飞书：文本消息，timestamp(秒) + "\n" + secret 作为密钥对空内容签名，放在请求体中
+        let feishu = target(NotificationChannelType::Feishu, &format!("{}/feishu", base_url), Some("feishu-secret"));
+        send_notification(&feishu, &notification).await.unwrap();
+
+        {
+            let requests = captured.lock().unwrap();
+            assert_eq!(requests.len(), 3);
+
+            let wecom = &requests[0];
+            assert_eq!(wecom.path, "/wecom?key=k");
+            assert_eq!(wecom.body["msgtype"], "markdown");
+            assert_eq!(wecom.body["markdown"]["content"], "**价格提醒**\n\n沪深300ETF 价格 4.0000");
+
+            let dingtalk = &requests[1];
+            assert!(dingtalk.path.starts_with("/dingtalk?access_token=t&"));
+            assert_eq!(dingtalk.body["msgtype"], "markdown");
+            assert_eq!(dingtalk.body["markdown"]["title"], "价格提醒");
+            assert_eq!(dingtalk.body["markdown"]["text"], "**价格提醒**\n\n沪深300ETF 价格 4.0000");
+            let timestamp = query_param(&dingtalk.path, "timestamp");
+            verify_sign(
+                b"SECdingtalk",
+                format!("{}\nSECdingtalk", timestamp).as_bytes(),
+                &query_param(&dingtalk.path, "sign"),
+            );
+
+            let feishu = &requests[2];
+            assert_eq!(feishu.body["msg_type"], "text");
+            assert_eq!(feishu.body["content"]["text"], "价格提醒\n沪深300ETF 价格 4.0000");
+            let timestamp = feishu.body["timestamp"].as_str().unwrap();
+            verify_sign(
+                format!("{}\nfeishu-secret", timestamp).as_bytes(),
+                b"",
+                feishu.body["sign"].as_str().unwrap(),
+            );
+        }
+
+        // 发送状态：HTTP 错误和平台错误码按间隔重试，成功后标记为已发送；达到上限标记为失败
+        let conn = Connection::open_in_memory().unwrap();
+        let schemas = crate::database::load_all_schemas().unwrap();
+        conn.execute_batch(&schemas["notification_deliveries"]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO notification_deliveries (id, user_id, channel_id, title, body, status, attempts, created_at)
+             VALUES (1, 1, 1, 't', 'b', 'PENDING', 0, 0), (2, 1, 1, 't', 'b', 'PENDING', 0, 0)",
+        )
+        .unwrap();
+        let config = NotificationConfig {
+            max_attempts: 3,
+            retry_delay_seconds: 60,
+            ..Default::default()
+        };
+        let load = |id: i64| -> (String, i64, Option<String>, Option<i64>, Option<i64>) {
+            conn.query_row(
+                "SELECT status, attempts, last_error, next_attempt_at, sent_at FROM notification_deliveries WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .unwrap()
+        };
+
+        let before = Utc::now().timestamp();
+        let result = send_notification(&wecom, &notification).await;
+        assert!(result.as_ref().unwrap_err().contains("HTTP 500"));
+        let status = record_send_result(&conn, 1, 1, result, &config).unwrap();
+        assert_eq!(status, DeliveryStatus::Pending);
+        let (status, attempts, last_error, next_attempt_at, sent_at) = load(1);
+        assert_eq!((status.as_str(), attempts, sent_at), ("PENDING", 1, None));
+        assert!(last_error.unwrap().contains("HTTP 500"));
+        assert!(next_attempt_at.unwrap() >= before + 60);
+
+        let result = send_notification(&wecom, &notification).await;
+        assert!(result.as_ref().unwrap_err().contains("310000"));
+        assert_eq!(record_send_result(&conn, 1, 2, result, &config).unwrap(), DeliveryStatus::Pending);
+        let (_, attempts, _, next_attempt_at, _) = load(1);
+        assert_eq!(attempts, 2);
+        assert!(next_attempt_at.unwrap() >= before + 120);
+
+        let result = send_notification(&wecom, &notification).await;
+        assert_eq!(record_send_result(&conn, 1, 3, result, &config).unwrap(), DeliveryStatus::Sent);
+        let (status, attempts, last_error, next_attempt_at, sent_at) = load(1);
+        assert_eq!((status.as_str(), attempts, last_error, next_attempt_at), ("SENT", 3, None, None));
+        assert!(sent_at.is_some());
+
+        let status = record_send_result(&conn, 2, 3, Err("HTTP 500: internal error".to_string()), &config).unwrap();
+        assert_eq!(status, DeliveryStatus::Failed);
+        let (status, attempts, last_error, next_attempt_at, _) = load(2);
+        assert_eq!((status.as_str(), attempts, next_attempt_at), ("FAILED", 3, None));
+        assert_eq!(last_error.as_deref(), Some("HTTP 500: internal error"));
+    }
+}
@@ -0,0 +1,243 @@
+/**
+ * 通知发送模块
+ *
+ * 按渠道类型（见 `NotificationChannelType`）把一条通知发送出去，发送失败返回错误信息，由调用方记录并重试：
+ * - 桌面通知：向前端发送 `desktop-notification` 事件（内容为 `DesktopNotification`），由前端弹出系统通知。
+ * - 邮件：通过 `utils::email` 发送，有 HTML 正文（如摘要报告）时发送 HTML 邮件。
+ * - 通用 Webhook：POST JSON（title、body、alert_id、alert_type、sent_at），`secret` 作为 `X-WolfQuant-Token` 请求头。
+ * - 企业微信 / 钉钉 / 飞书 / Telegram 机器人：按各平台的消息格式发送，并检查返回的错误码。
+ *   钉钉和飞书设置了 `secret` 时按平台规则加签。
+ *
+ * 所有 HTTP 渠道的地址均可指向本地模拟服务（Telegram 通过 `NotificationConfig.telegram_api_base` 配置）。
+ */
+use crate::config::Config;
+use crate::models::{DesktopNotification, NotificationChannelType};
+use crate::utils::crypto::hmac_sha256;
+use crate::utils::email::{send_email, send_notification_email};
+use crate::utils::email_template::RenderedEmail;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 桌面通知事件名
+pub const DESKTOP_NOTIFICATION_EVENT: &str = "desktop-notification";
+
+lazy_static! {
+    static ref APP_HANDLE: RwLock<Option<AppHandle>> = RwLock::new(None);
+}
+
+/// 记录应用句柄，用于发送桌面通知事件（应用启动时调用）
+pub fn set_app_handle(handle: AppHandle) {
+    *APP_HANDLE.write().unwrap() = Some(handle);
+}
+
+/// 待发送的通知
+pub struct OutgoingNotification {
+    pub user_id: i64,
+    pub delivery_id: Option<i64>,
+    pub alert_id: Option<i64>,
+    pub alert_type: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub html: Option<String>, // 邮件渠道使用的 HTML 正文，为空时使用提醒摘要模板
+}
+
+/// 发送目标
+pub struct ChannelTarget {
+    pub channel_type: NotificationChannelType,
+    pub target: Option<String>,
+    pub secret: Option<String>,
+}
+
+impl OutgoingNotification {
+    /// Markdown 格式的内容（企业微信、钉钉）
+    fn markdown(&self) -> String {
+        format!("**{}**\n\n{}", self.title, self.body)
+    }
+
+    /// 纯文本内容（飞书、Telegram）
+    fn text(&self) -> String {
+        format!("{}\n{}", self.title, self.body)
+    }
+}
+
+fn http_client() -> Result<Client, String> {
+    let timeout = Config::get().notification.request_timeout_seconds.max(1);
+    Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+/// 发送 JSON 请求，检查 HTTP 状态并返回响应内容
+async fn post_json(request: reqwest::RequestBuilder, payload: &Value) -> Result<Value, String> {
+    let response = request
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("HTTP {}: {}", status.as_u16(), text));
+    }
+
+    Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+}
+
+/// 检查机器人接口返回的错误码（为 0 或缺省时成功）
+fn check_code(response: &Value, field: &str, message_field: &str) -> Result<(), String> {
+    match response[field].as_i64() {
+        Some(0) | None => Ok(()),
+        Some(code) => Err(format!(
+            "错误码 {}: {}",
+            code,
+            response[message_field].as_str().unwrap_or_default()
+        )),
+    }
+}
+
+fn required<'a>(value: &'a Option<String>, name: &str) -> Result<&'a str, String> {
+    value
+        .as_deref()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| format!("未设置{}", name))
+}
+
+fn send_desktop(notification: &OutgoingNotification) -> Result<(), String> {
+    let handle = APP_HANDLE.read().unwrap().clone().ok_or("桌面通知不可用")?;
+    handle
+        .emit(
+            DESKTOP_NOTIFICATION_EVENT,
+            DesktopNotification {
+                user_id: notification.user_id,
+                delivery_id: notification.delivery_id,
+                alert_id: notification.alert_id,
+                title: notification.title.clone(),
+                body: notification.body.clone(),
+            },
+        )
+        .map_err(|e| format!("发送桌面通知失败: {}", e))
+}
+
+async fn send_webhook(client: &Client, target: &ChannelTarget, notification: &OutgoingNotification) -> Result<(), String> {
+    let mut request = client.post(required(&target.target, "Webhook 地址")?);
+    if let Some(secret) = target.secret.as_deref().filter(|secret| !secret.is_empty()) {
+        request = request.header("X-WolfQuant-Token", secret);
+    }
+
+    let payload = json!({
+        "title": notification.title,
+        "body": notification.body,
+        "alert_id": notification.alert_id,
+        "alert_type": notification.alert_type,
+        "sent_at": Utc::now().timestamp(),
+    });
+    post_json(request, &payload).await.map(|_| ())
+}
+
+async fn send_wecom(client: &Client, target: &ChannelTarget, notification: &OutgoingNotification) -> Result<(), String> {
+    let payload = json!({
+        "msgtype": "markdown",
+        "markdown": { "content": notification.markdown() },
+    });
+    let response = post_json(client.post(required(&target.target, "Webhook 地址")?), &payload).await?;
+    check_code(&response, "errcode", "errmsg")
+}
+
+async fn send_dingtalk(client: &Client, target: &ChannelTarget, notification: &OutgoingNotification) -> Result<(), String> {
+    let mut url = Url::parse(required(&target.target, "Webhook 地址")?).map_err(|e| format!("无效的地址: {}", e))?;
+
+    // 加签：timestamp(毫秒) + "\n" + secret 作为内容，secret 作为密钥
+    if let Some(secret) = target.secret.as_deref().filter(|secret| !secret.is_empty()) {
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        let sign = STANDARD.encode(hmac_sha256(
+            secret.as_bytes(),
+            format!("{}\n{}", timestamp, secret).as_bytes(),
+        ));
+        url.query_pairs_mut()
+            .append_pair("timestamp", &timestamp)
+            .append_pair("sign", &sign);
+    }
+
+    let payload = json!({
+        "msgtype": "markdown",
+        "markdown": { "title": notification.title, "text": notification.markdown() },
+    });
+    let response = post_json(client.post(url), &payload).await?;
+    check_code(&response, "errcode", "errmsg")
+}
+
+async fn send_feishu(client: &Client, target: &ChannelTarget, notification: &OutgoingNotification) -> Result<(), String> {
+    let mut payload = json!({
+        "msg_type": "text",
+        "content": { "text": notification.text() },
+    });
+
+    // 签名校验：timestamp(秒) + "\n" + secret 作为密钥，对空内容签名
+    if let Some(secret) = target.secret.as_deref().filter(|secret| !secret.is_empty()) {
+        let timestamp = Utc::now().timestamp().to_string();
+        let sign = STANDARD.encode(hmac_sha256(format!("{}\n{}", timestamp, secret).as_bytes(), b""));
+        payload["timestamp"] = json!(timestamp);
+        payload["sign"] = json!(sign);
+    }
+
+    let response = post_json(client.post(required(&target.target, "Webhook 地址")?), &payload).await?;
+    check_code(&response, "code", "msg")
+}
+
+async fn send_telegram(client: &Client, target: &ChannelTarget, notification: &OutgoingNotification) -> Result<(), String> {
+    let url = format!(
+        "{}/bot{}/sendMessage",
+        Config::get().notification.telegram_api_base.trim_end_matches('/'),
+        required(&target.secret, "Bot Token")?
+    );
+    let payload = json!({
+        "chat_id": required(&target.target, "chat_id")?,
+        "text": notification.text(),
+    });
+
+    let response = post_json(client.post(url), &payload).await?;
+    match response["ok"].as_bool() {
+        Some(false) => Err(response["description"].as_str().unwrap_or("发送失败").to_string()),
+        _ => Ok(()),
+    }
+}
+
+/**
+ * @dev 通过渠道发送一条通知
+ * @return 失败时返回错误信息
+ */
+pub async fn send_notification(target: &ChannelTarget, notification: &OutgoingNotification) -> Result<(), String> {
+    match target.channel_type {
+        NotificationChannelType::Desktop => send_desktop(notification),
+        NotificationChannelType::Email => {
+            let email = required(&target.target, "收件邮箱")?;
+            match &notification.html {
+                Some(html) => send_email(
+                    email,
+                    &RenderedEmail {
+                        subject: notification.title.clone(),
+                        text: notification.body.clone(),
+                        html: html.clone(),
+                    },
+                )
+                .await,
+                None => send_notification_email(email, &notification.title, &notification.body).await,
+            }
+            .map_err(|e| e.to_string())
+        }
+        NotificationChannelType::Webhook => send_webhook(&http_client()?, target, notification).await,
+        NotificationChannelType::WeCom => send_wecom(&http_client()?, target, notification).await,
+        NotificationChannelType::DingTalk => send_dingtalk(&http_client()?, target, notification).await,
+        NotificationChannelType::Feishu => send_feishu(&http_client()?, target, notification).await,
+        NotificationChannelType::Telegram => send_telegram(&http_client()?, target, notification).await,
+    }
+}
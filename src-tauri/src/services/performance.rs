@@ -0,0 +1,652 @@
+/**
+ * 收益率计算模块
+ *
+ * 按交易流水（资金进出）和价格历史（估值）计算资金加权收益率（XIRR）和时间加权收益率（TWR）。
+ *
+ * 主要函数说明：
+ * - `load_scope_history(conn, user_id, scope, scope_id, fx)`: 读取统计范围内的交易流水和价格序列，并换算为基准币种。
+ * - `build_report(history, scope, scope_id, name, start, end)`: 计算指定区间的收益率报告。
+ * - `xirr(flows)`: 按不规则现金流计算年化内部收益率。
+ * - `transaction_contribution` / `load_price_series` / `price_on`: 净投入和估值的公共计算，净值快照也使用。
+ * - `get_returns(user_id, scope, scope_id, start, end)`: 获取资产 / 分组 / 资产类型 / 定投计划 / 组合的收益率。
+ * - `get_portfolio_returns(conn, user_id)`: 计算整个组合自建仓以来的收益率（用于组合摘要）。
+ *
+ * 计算规则：
+ * - 净投入：买入、转入计入（含费用），卖出、转出、现金分红视为取出（扣除费用），
+ *   独立费用及再投资、折算产生的费用视为额外投入；红利再投资和份额折算不产生资金进出。
+ * - 估值：某时点的份额 × 该时点最近的价格，价格取自价格历史、交易成交价和资产当前价格。
+ * - XIRR：期初市值视为投入，期末市值视为收回，按实际天数 / 365 折现。
+ * - TWR：在每个发生资金进出的时点切分子区间，子区间收益率 = 进出前市值 / 上一时点市值 - 1，再连乘。
+ * - 定投计划只统计该计划产生的买入交易（以及该资产的份额折算）。
+ * - 币种：交易金额按交易当日汇率、估值价格按价格日期的汇率换算为用户的基准币种，收益率包含汇率变动的影响。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{PerformanceReport, ReturnScope, TransactionType};
+use crate::services::fx::FxConverter;
+use chrono::Utc;
+use log::{error, warn};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: i64 = 86400;
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// 市值、现金流小于该值时视为零
+const VALUE_EPSILON: f64 = 1e-6;
+
+/// 参与收益率计算的交易
+struct FlowTransaction {
+    asset_id: i64,
+    date: i64,
+    kind: TransactionType,
+    amount: f64,
+    total_cost: f64,
+    fee: f64,
+}
+
+impl FlowTransaction {
+    fn contribution(&self) -> f64 {
+        transaction_contribution(self.kind, self.total_cost, self.fee)
+    }
+}
+
+/// 单笔交易带来的外部净投入（正数为投入，负数为取出）
+pub fn transaction_contribution(kind: TransactionType, total_cost: f64, fee: f64) -> f64 {
+    match kind {
+        TransactionType::Buy | TransactionType::TransferIn => total_cost + fee,
+        TransactionType::Sell | TransactionType::TransferOut | TransactionType::Dividend => {
+            -(total_cost - fee)
+        }
+        TransactionType::Fee => total_cost,
+        TransactionType::Reinvest | TransactionType::Split => fee,
+    }
+}
+
+/// 读取资产的估值价格序列（价格历史 + 当前价格），按日期排序
+pub fn load_price_series(conn: &Connection, asset_id: i64) -> Result<Vec<(i64, f64)>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT date, close_price FROM price_history WHERE asset_id = ?1 ORDER BY date",
+    )?;
+    let mut series = stmt
+        .query_map(params![asset_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(i64, f64)>, _>>()?;
+
+    let current: Option<(Option<i64>, Option<f64>)> = conn
+        .query_row(
+            "SELECT last_updated, current_price FROM assets WHERE id = ?1",
+            params![asset_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+    if let Some((Some(updated), Some(price))) = current {
+        series.push((updated, price));
+    }
+
+    series.sort_by_key(|(date, _)| *date);
+    Ok(series)
+}
+
+/// 价格序列中指定时点（含）最近的价格
+pub fn price_on(series: &[(i64, f64)], date: i64) -> Option<f64> {
+    let index = series.partition_point(|(d, _)| *d <= date);
+    if index == 0 {
+        None
+    } else {
+        Some(series[index - 1].1)
+    }
+}
+
+/// 统计范围内的交易流水和各资产的价格序列（均已换算为基准币种）
+pub struct ScopeHistory {
+    currency: String,
+    transactions: Vec<FlowTransaction>,
+    prices: HashMap<i64, Vec<(i64, f64)>>,
+}
+
+impl ScopeHistory {
+    /// 第一笔交易的日期
+    pub fn first_date(&self) -> Option<i64> {
+        self.transactions.first().map(|tx| tx.date)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// 指定时点（含当时的交易）的持仓市值
+    pub fn value_at(&self, date: i64) -> f64 {
+        self.values_at(&[date])[0]
+    }
+
+    /// 按时间顺序计算多个时点（含当时的交易）的持仓市值
+    ///
+    /// 交易流水和各资产的价格序列都只顺序遍历一次，`dates` 需按时间升序排列。
+    pub fn values_at(&self, dates: &[i64]) -> Vec<f64> {
+        let mut shares: HashMap<i64, f64> = HashMap::new();
+        let mut price_cursors: HashMap<i64, usize> = HashMap::new();
+        let mut transactions = self.transactions.iter().peekable();
+        let mut values = Vec::with_capacity(dates.len());
+
+        for &date in dates {
+            while let Some(tx) = transactions.next_if(|tx| tx.date <= date) {
+                let entry = shares.entry(tx.asset_id).or_insert(0.0);
+                match tx.kind {
+                    TransactionType::Buy | TransactionType::TransferIn | TransactionType::Reinvest => {
+                        *entry += tx.amount
+                    }
+                    TransactionType::Sell | TransactionType::TransferOut => {
+                        *entry = (*entry - tx.amount).max(0.0)
+                    }
+                    TransactionType::Split => *entry *= tx.amount,
+                    TransactionType::Dividend | TransactionType::Fee => {}
+                }
+            }
+
+            let mut value = 0.0;
+            for (asset_id, held) in shares.iter() {
+                let series = match self.prices.get(asset_id) {
+                    Some(series) => series,
+                    None => continue,
+                };
+                // 价格游标停在指定时点（含）之后的第一个价格
+                let cursor = price_cursors.entry(*asset_id).or_insert(0);
+                while *cursor < series.len() && series[*cursor].0 <= date {
+                    *cursor += 1;
+                }
+                if *held > 0.0 && *cursor > 0 {
+                    value += series[*cursor - 1].1 * held;
+                }
+            }
+            values.push(value);
+        }
+
+        values
+    }
+
+    /// 区间内（含两端）各时点的净投入，按时间排序
+    fn contributions(&self, start: i64, end: i64) -> Vec<(i64, f64)> {
+        let mut flows: Vec<(i64, f64)> = Vec::new();
+        for tx in self
+            .transactions
+            .iter()
+            .filter(|tx| tx.date >= start && tx.date <= end)
+        {
+            match flows.last_mut() {
+                Some((date, amount)) if *date == tx.date => *amount += tx.contribution(),
+                _ => flows.push((tx.date, tx.contribution())),
+            }
+        }
+        flows
+    }
+}
+
+/// 读取统计范围内的交易流水和价格序列
+pub fn load_scope_history(
+    conn: &Connection,
+    user_id: i64,
+    scope: ReturnScope,
+    scope_id: Option<i64>,
+    fx: &mut FxConverter,
+) -> Result<ScopeHistory, rusqlite::Error> {
+    let condition = match scope {
+        ReturnScope::Asset => "t.asset_id = ?2",
+        ReturnScope::Group => "a.group_id = ?2",
+        ReturnScope::AssetType => "a.asset_type_id = ?2",
+        ReturnScope::Plan => {
+            "(t.plan_id = ?2
+              OR (t.transaction_type = 'SPLIT'
+                  AND t.asset_id = (SELECT asset_id FROM investment_plans WHERE id = ?2)))"
+        }
+        ReturnScope::Portfolio => "?2 IS NULL",
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.asset_id, t.transaction_date, t.transaction_type, t.amount, t.price, t.total_cost, t.fee, a.currency
+         FROM transactions t
+         JOIN assets a ON t.asset_id = a.id
+         WHERE a.user_id = ?1 AND t.status = 'CONFIRMED' AND {}
+         ORDER BY t.transaction_date, t.id",
+        condition
+    ))?;
+
+    let rows = stmt
+        .query_map(params![user_id, scope_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f64>(3)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, f64>(5)?,
+                row.get::<_, f64>(6)?,
+                row.get::<_, String>(7)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut transactions = Vec::new();
+    let mut prices: HashMap<i64, Vec<(i64, f64)>> = HashMap::new();
+    let mut currencies: HashMap<i64, String> = HashMap::new();
+
+    for (asset_id, date, transaction_type, amount, price, total_cost, fee, currency) in rows {
+        let kind = match TransactionType::from_str(&transaction_type) {
+            Some(kind) => kind,
+            None => {
+                warn!("Unknown transaction type {} for asset {}", transaction_type, asset_id);
+                continue;
+            }
+        };
+
+        let rate = fx.rate(&currency, date)?;
+        currencies.insert(asset_id, currency);
+
+        // 成交价也作为估值价格
+        if kind.has_market_price() && price > 0.0 {
+            prices.entry(asset_id).or_default().push((date, price * rate));
+        }
+
+        transactions.push(FlowTransaction {
+            asset_id,
+            date,
+            kind,
+            amount,
+            total_cost: total_cost * rate,
+            fee: fee * rate,
+        });
+    }
+
+    let asset_ids: Vec<i64> = {
+        let mut ids: Vec<i64> = transactions.iter().map(|tx| tx.asset_id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    };
+
+    for asset_id in asset_ids {
+        let currency = currencies.get(&asset_id).cloned().unwrap_or_default();
+        let series = prices.entry(asset_id).or_default();
+        for (date, price) in load_price_series(conn, asset_id)? {
+            series.push((date, fx.convert(price, &currency, date)?));
+        }
+        series.sort_by_key(|(date, _)| *date);
+    }
+
+    Ok(ScopeHistory {
+        currency: fx.base_currency().to_string(),
+        transactions,
+        prices,
+    })
+}
+
+/// 按不规则现金流计算年化内部收益率（小数），现金流需同时包含正负值
+pub fn xirr(flows: &[(i64, f64)]) -> Option<f64> {
+    if !flows.iter().any(|(_, amount)| *amount > 0.0) || !flows.iter().any(|(_, amount)| *amount < 0.0) {
+        return None;
+    }
+
+    let first = flows.iter().map(|(date, _)| *date).min()?;
+    let points: Vec<(f64, f64)> = flows
+        .iter()
+        .map(|(date, amount)| {
+            (
+                (date - first) as f64 / SECONDS_PER_DAY as f64 / DAYS_PER_YEAR,
+                *amount,
+            )
+        })
+        .collect();
+
+    let npv = |rate: f64| -> f64 {
+        points
+            .iter()
+            .map(|(years, amount)| amount / (1.0 + rate).powf(*years))
+            .sum()
+    };
+    let derivative = |rate: f64| -> f64 {
+        points
+            .iter()
+            .map(|(years, amount)| -years * amount / (1.0 + rate).powf(years + 1.0))
+            .sum()
+    };
+
+    // 牛顿法
+    let mut rate = 0.1;
+    for _ in 0..100 {
+        let slope = derivative(rate);
+        if slope.abs() < 1e-12 {
+            break;
+        }
+        let next = rate - npv(rate) / slope;
+        if !next.is_finite() || next <= -1.0 {
+            break;
+        }
+        if (next - rate).abs() < 1e-9 {
+            return Some(next);
+        }
+        rate = next;
+    }
+
+    // 牛顿法不收敛时使用二分法
+    let (mut low, mut high) = (-0.9999, 100.0);
+    let mut low_value = npv(low);
+    if low_value * npv(high) > 0.0 {
+        return None;
+    }
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        let mid_value = npv(mid);
+        if mid_value.abs() < 1e-9 {
+            return Some(mid);
+        }
+        if low_value * mid_value < 0.0 {
+            high = mid;
+        } else {
+            low = mid;
+            low_value = mid_value;
+        }
+    }
+
+    Some((low + high) / 2.0)
+}
+
+/// 计算时间加权收益率（小数），区间内始终没有持仓时返回 None
+fn time_weighted_return(
+    history: &ScopeHistory,
+    contributions: &[(i64, f64)],
+    start_value: f64,
+    end_value: f64,
+) -> Option<f64> {
+    let mut growth = 1.0;
+    let mut previous_value = start_value;
+    let mut measured = false;
+
+    let dates: Vec<i64> = contributions.iter().map(|(date, _)| *date).collect();
+    let values = history.values_at(&dates);
+
+    for ((_, contribution), value_after) in contributions.iter().zip(values) {
+        let value_before = value_after - contribution;
+        if previous_value > VALUE_EPSILON {
+            growth *= value_before / previous_value;
+            measured = true;
+        }
+        previous_value = value_after;
+    }
+
+    if previous_value > VALUE_EPSILON {
+        growth *= end_value / previous_value;
+        measured = true;
+    }
+
+    measured.then_some(growth - 1.0)
+}
+
+/// 计算指定区间的收益率报告，`start` 为空时从第一笔交易开始
+pub fn build_report(
+    history: &ScopeHistory,
+    scope: ReturnScope,
+    scope_id: Option<i64>,
+    name: String,
+    start: Option<i64>,
+    end: i64,
+) -> PerformanceReport {
+    let start = start.or_else(|| history.first_date()).unwrap_or(end).min(end);
+
+    let start_value = history.value_at(start - 1);
+    let end_value = history.value_at(end);
+    let contributions = history.contributions(start, end);
+    let net_contribution: f64 = contributions.iter().map(|(_, amount)| amount).sum();
+
+    // 投资者视角的现金流：投入为负，取出为正
+    let mut cash_flows = Vec::new();
+    if start_value > VALUE_EPSILON {
+        cash_flows.push((start, -start_value));
+    }
+    cash_flows.extend(
+        contributions
+            .iter()
+            .filter(|(_, amount)| amount.abs() > VALUE_EPSILON)
+            .map(|(date, amount)| (*date, -amount)),
+    );
+    if end_value > VALUE_EPSILON {
+        cash_flows.push((end, end_value));
+    }
+
+    let twr = time_weighted_return(history, &contributions, start_value, end_value);
+    let days = (end - start) as f64 / SECONDS_PER_DAY as f64;
+    let annualized_twr = twr
+        .filter(|_| days >= 1.0)
+        .map(|twr| (1.0 + twr).powf(DAYS_PER_YEAR / days) - 1.0);
+
+    PerformanceReport {
+        scope: scope.to_str().to_string(),
+        scope_id,
+        name,
+        currency: history.currency.clone(),
+        start_date: start,
+        end_date: end,
+        start_value,
+        end_value,
+        net_contribution,
+        profit: end_value - start_value - net_contribution,
+        xirr_percent: xirr(&cash_flows).map(|rate| rate * 100.0),
+        twr_percent: twr.map(|rate| rate * 100.0),
+        annualized_twr_percent: annualized_twr.map(|rate| rate * 100.0),
+    }
+}
+
+/// 获取统计范围下的对象：(ID, 名称)
+fn load_scope_targets(
+    conn: &Connection,
+    user_id: i64,
+    scope: ReturnScope,
+    scope_id: Option<i64>,
+) -> Result<Vec<(Option<i64>, String)>, rusqlite::Error> {
+    let query = match scope {
+        ReturnScope::Asset => {
+            "SELECT id, name FROM assets WHERE user_id = ?1 AND (?2 IS NULL OR id = ?2) ORDER BY name"
+        }
+        ReturnScope::Group => {
+            "SELECT id, name FROM user_groups WHERE user_id = ?1 AND (?2 IS NULL OR id = ?2) ORDER BY name"
+        }
+        ReturnScope::AssetType => {
+            "SELECT id, name FROM asset_types
+             WHERE (?2 IS NULL AND id IN (SELECT asset_type_id FROM assets WHERE user_id = ?1))
+                OR id = ?2
+             ORDER BY id"
+        }
+        ReturnScope::Plan => {
+            "SELECT id, name FROM investment_plans WHERE user_id = ?1 AND (?2 IS NULL OR id = ?2) ORDER BY name"
+        }
+        ReturnScope::Portfolio => return Ok(vec![(None, "全部资产".to_string())]),
+    };
+
+    let mut stmt = conn.prepare(query)?;
+    let targets = stmt
+        .query_map(params![user_id, scope_id], |row| {
+            Ok((Some(row.get::<_, i64>(0)?), row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(targets)
+}
+
+/// 获取收益率报告，`scope_id` 为空时返回该范围下所有有交易记录的对象
+pub fn get_returns(
+    user_id: i64,
+    scope: &str,
+    scope_id: Option<i64>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<PerformanceReport>, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let mut fx = FxConverter::for_user(&conn, user_id)?;
+
+    let scope = ReturnScope::from_str(scope)
+        .ok_or_else(|| AuthError::InvalidCredentials(format!("不支持的统计范围: {}", scope)))?;
+
+    let end = end_date.unwrap_or_else(|| Utc::now().timestamp());
+    if let Some(start) = start_date {
+        if start > end {
+            return Err(AuthError::InvalidCredentials(
+                "开始日期不能晚于结束日期".to_string(),
+            ));
+        }
+    }
+
+    let targets = load_scope_targets(&conn, user_id, scope, scope_id).map_err(|e| {
+        error!("Failed to load return targets: {}", e);
+        AuthError::DatabaseError(format!("获取统计对象失败: {}", e))
+    })?;
+
+    if scope_id.is_some() && targets.is_empty() {
+        return Err(AuthError::InvalidCredentials(
+            "统计对象不存在或无权限".to_string(),
+        ));
+    }
+
+    let mut reports = Vec::new();
+    for (target_id, name) in targets {
+        let history = load_scope_history(&conn, user_id, scope, target_id, &mut fx).map_err(|e| {
+            error!("Failed to load history for {} {:?}: {}", scope.to_str(), target_id, e);
+            AuthError::DatabaseError(format!("获取交易流水失败: {}", e))
+        })?;
+
+        if history.is_empty() && scope_id.is_none() {
+            continue;
+        }
+
+        reports.push(build_report(&history, scope, target_id, name, start_date, end));
+    }
+    fx.ensure_rates()?;
+
+    Ok(reports)
+}
+
+/// 计算整个组合自建仓以来的收益率
+pub fn get_portfolio_returns(
+    conn: &Connection,
+    user_id: i64,
+) -> Result<PerformanceReport, rusqlite::Error> {
+    let mut fx = FxConverter::for_user(conn, user_id)?;
+    let history = load_scope_history(conn, user_id, ReturnScope::Portfolio, None, &mut fx)?;
+    Ok(build_report(
+        &history,
+        ReturnScope::Portfolio,
+        None,
+        "全部资产".to_string(),
+        None,
+        Utc::now().timestamp(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = SECONDS_PER_DAY;
+
+    fn buy(asset_id: i64, date: i64, amount: f64, price: f64) -> FlowTransaction {
+        FlowTransaction {
+            asset_id,
+            date,
+            kind: TransactionType::Buy,
+            amount,
+            total_cost: amount * price,
+            fee: 0.0,
+        }
+    }
+
+    fn npv(flows: &[(i64, f64)], rate: f64) -> f64 {
+        let first = flows[0].0;
+        flows
+            .iter()
+            .map(|(date, amount)| {
+                amount / (1.0 + rate).powf((date - first) as f64 / DAY as f64 / DAYS_PER_YEAR)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn xirr_of_one_year_return() {
+        let rate = xirr(&[(0, -1000.0), (365 * DAY, 1100.0)]).unwrap();
+        assert!((rate - 0.1).abs() < 1e-6, "rate {}", rate);
+    }
+
+    #[test]
+    fn xirr_converges_for_irregular_flows() {
+        let flows = [
+            (0, -1000.0),
+            (45 * DAY, -500.0),
+            (200 * DAY, 300.0),
+            (400 * DAY, -200.0),
+            (700 * DAY, 1700.0),
+        ];
+        let rate = xirr(&flows).unwrap();
+        assert!(npv(&flows, rate).abs() < 1e-6, "npv at {} is {}", rate, npv(&flows, rate));
+    }
+
+    #[test]
+    fn xirr_falls_back_to_bisection_for_large_losses() {
+        // 从 0.1 开始的牛顿法第一步就越过 -100%，需要二分法求解
+        let flows = [(0, -100.0), (365 * DAY, 1.0)];
+        let rate = xirr(&flows).unwrap();
+        assert!((rate + 0.99).abs() < 1e-6, "rate {}", rate);
+    }
+
+    #[test]
+    fn xirr_requires_sign_change() {
+        assert_eq!(xirr(&[]), None);
+        assert_eq!(xirr(&[(0, -1000.0)]), None);
+        assert_eq!(xirr(&[(0, -1000.0), (DAY, -500.0)]), None);
+        assert_eq!(xirr(&[(0, 1000.0), (DAY, 500.0)]), None);
+    }
+
+    fn sample_history() -> ScopeHistory {
+        let mut prices = HashMap::new();
+        prices.insert(1, vec![(0, 1.0), (10 * DAY, 1.1), (20 * DAY, 1.21)]);
+        prices.insert(2, vec![(5 * DAY, 2.0)]);
+        ScopeHistory {
+            currency: "CNY".to_string(),
+            transactions: vec![
+                buy(1, 0, 100.0, 1.0),
+                buy(2, 5 * DAY, 10.0, 2.0),
+                buy(1, 10 * DAY, 100.0, 1.1),
+            ],
+            prices,
+        }
+    }
+
+    #[test]
+    fn values_at_walks_transactions_and_prices() {
+        let history = sample_history();
+        let dates = [-1, 0, 7 * DAY, 10 * DAY, 30 * DAY];
+        let values = history.values_at(&dates);
+
+        let expected = [0.0, 100.0, 120.0, 240.0, 262.0];
+        for ((value, expected), date) in values.iter().zip(expected).zip(dates) {
+            assert!((value - expected).abs() < 1e-9, "value at {} is {}", date, value);
+            assert!((history.value_at(date) - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn twr_ignores_contributions() {
+        let mut history = sample_history();
+        history.transactions.remove(1);
+
+        let report = build_report(&history, ReturnScope::Portfolio, None, String::new(), None, 20 * DAY);
+        assert!((report.twr_percent.unwrap() - 21.0).abs() < 1e-9);
+        assert!((report.net_contribution - 210.0).abs() < 1e-9);
+        assert!((report.profit - 32.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn twr_is_none_without_holdings() {
+        let history = ScopeHistory {
+            currency: "CNY".to_string(),
+            transactions: Vec::new(),
+            prices: HashMap::new(),
+        };
+        assert_eq!(time_weighted_return(&history, &[], 0.0, 0.0), None);
+    }
+}
@@ -0,0 +1,379 @@
+/**
+ * 定投计划回测模块
+ *
+ * 用资产的历史价格模拟定投计划的执行，规则与实际执行（`plan_execution`）一致：
+ * - 执行日期：从回测开始日期起按计划的执行日程（见 `plan_schedule`）逐期推算，休市日顺延到下一个交易日。
+ * - 成交：计划日期没有价格时顺延到之后第一个有价格的交易日，同一交易日只成交一次。
+ * - 金额：按计划模式计算（见 `smart_dca`），指标只使用成交日之前的价格，当日以收盘价成交。
+ * - 费用：按资产的费率方案把定投金额拆分为净申购金额和费用。
+ *
+ * 价格优先取 `price_history`，没有时使用导入的 `candles`（按资产代码匹配）。
+ * 最大回撤和年化波动率按时间加权净值计算，避免持续投入掩盖亏损，波动率按交易所每年的交易日数年化；
+ * 一次性投入对照在第一期成交日投入与定投相同的总金额并持有到回测结束。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    LumpSumComparison, PlanBacktestExecution, PlanBacktestPoint, PlanBacktestRequest,
+    PlanBacktestResult, PlanSchedule,
+};
+use crate::services::calendar::load_asset_calendar;
+use crate::services::fee::{load_fee_schedule, split_buy_budget};
+use crate::services::investment_plan::{parse_days_of_month, parse_mode_config, resolve_plan_mode};
+use crate::services::performance::{load_price_series, xirr};
+use crate::services::plan_schedule::{next_execution_after, validate_schedule};
+use crate::services::smart_dca::{compute_plan_amount, window_closes, PlanState};
+use crate::services::snapshot::local_date;
+use log::{error, info};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 读取资产的日线收盘价（每天取最后一个价格），价格历史不足时使用导入的K线
+fn load_daily_bars(
+    conn: &Connection,
+    asset_id: i64,
+    code: &str,
+) -> Result<Vec<(i64, f64)>, rusqlite::Error> {
+    let mut series = load_price_series(conn, asset_id)?;
+
+    if series.len() < 2 {
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, close FROM candles WHERE symbol = ?1 ORDER BY timestamp",
+        )?;
+        series = stmt
+            .query_map(params![code], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(i64, f64)>, _>>()?;
+    }
+
+    let mut bars: Vec<(i64, f64)> = Vec::with_capacity(series.len());
+    for (date, close) in series.into_iter().filter(|(_, close)| *close > 0.0) {
+        match bars.last_mut() {
+            Some(last) if local_date(last.0) == local_date(date) => *last = (date, close),
+            _ => bars.push((date, close)),
+        }
+    }
+
+    Ok(bars)
+}
+
+/// 日收益率的年化波动率（百分比），样本不足时返回 None
+fn annualized_volatility(unit_values: &[f64], periods_per_year: f64) -> Option<f64> {
+    let returns: Vec<f64> = unit_values
+        .windows(2)
+        .filter(|pair| pair[0] > 0.0)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    Some(variance.sqrt() * periods_per_year.sqrt() * 100.0)
+}
+
+/// 序列的最大回撤（百分比）
+fn max_drawdown(values: impl Iterator<Item = f64>) -> f64 {
+    let mut peak = f64::MIN;
+    let mut drawdown: f64 = 0.0;
+    for value in values {
+        peak = peak.max(value);
+        if peak > 0.0 {
+            drawdown = drawdown.max((peak - value) / peak);
+        }
+    }
+    drawdown * 100.0
+}
+
+/// 回测定投计划
+pub fn backtest_investment_plan(request: &PlanBacktestRequest) -> Result<PlanBacktestResult, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let user_id = request.user_id;
+
+    // 已有计划的设置：(资产ID, 执行日程, 金额, 模式, 规则)，回测区间不受计划的开始和结束日期限制
+    let plan: Option<(i64, PlanSchedule, f64, String, Option<String>)> =
+        match request.plan_id {
+            Some(plan_id) => {
+                let plan = conn
+                    .query_row(
+                        "SELECT asset_id, frequency, day_of_week, day_of_month, days_of_month, cron_expression,
+                                amount, mode, mode_config
+                         FROM investment_plans WHERE id = ?1 AND user_id = ?2",
+                        params![plan_id, user_id],
+                        |row| {
+                            Ok((
+                                row.get(0)?,
+                                PlanSchedule {
+                                    frequency: row.get(1)?,
+                                    day_of_week: row.get(2)?,
+                                    day_of_month: row.get(3)?,
+                                    days_of_month: parse_days_of_month(row.get(4)?),
+                                    cron_expression: row.get(5)?,
+                                    start_date: None,
+                                    end_date: None,
+                                },
+                                row.get(6)?,
+                                row.get(7)?,
+                                row.get(8)?,
+                            ))
+                        },
+                    )
+                    .ok();
+                if plan.is_none() {
+                    return Err(AuthError::InvalidCredentials(
+                        "定投计划不存在或无权限".to_string(),
+                    ));
+                }
+                plan
+            }
+            None => None,
+        };
+
+    let asset_id = request
+        .asset_id
+        .or(plan.as_ref().map(|p| p.0))
+        .ok_or_else(|| AuthError::InvalidCredentials("请指定回测的资产或定投计划".to_string()))?;
+
+    // 指定了频率时使用请求中的执行日程，否则使用计划的设置
+    let plan_schedule = match (&request.frequency, &plan) {
+        (Some(frequency), _) => PlanSchedule {
+            frequency: frequency.clone(),
+            day_of_week: request.day_of_week,
+            day_of_month: request.day_of_month,
+            days_of_month: request.days_of_month.clone().unwrap_or_default(),
+            cron_expression: request.cron_expression.clone(),
+            start_date: None,
+            end_date: None,
+        },
+        (None, Some(plan)) => plan.1.clone(),
+        (None, None) => {
+            return Err(AuthError::InvalidCredentials("请指定定投频率".to_string()))
+        }
+    };
+    validate_schedule(&plan_schedule)?;
+
+    let base_amount = request
+        .amount
+        .or(plan.as_ref().map(|p| p.2))
+        .ok_or_else(|| AuthError::InvalidCredentials("请指定定投金额".to_string()))?;
+    if base_amount.is_nan() || base_amount <= 0.0 {
+        return Err(AuthError::InvalidCredentials("定投金额必须大于0".to_string()));
+    }
+
+    let mode = request.mode.clone().or(plan.as_ref().map(|p| p.3.clone()));
+    let mode_config = request
+        .mode_config
+        .clone()
+        .or_else(|| plan.as_ref().and_then(|p| parse_mode_config(p.4.clone())));
+    let (mode, config) = resolve_plan_mode(mode.as_deref(), mode_config.as_ref())?;
+    let config = config.unwrap_or_default();
+
+    if request.end_date.map_or(false, |end| end <= request.start_date) {
+        return Err(AuthError::InvalidCredentials("结束日期必须晚于开始日期".to_string()));
+    }
+
+    let asset: Option<(String, String, String)> = conn
+        .query_row(
+            "SELECT name, code, currency FROM assets WHERE id = ?1 AND user_id = ?2",
+            params![asset_id, user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .ok();
+    let (asset_name, asset_code, currency) = match asset {
+        Some(asset) => asset,
+        None => return Err(AuthError::InvalidCredentials("资产不存在或无权限".to_string())),
+    };
+
+    let end = request.end_date.unwrap_or(i64::MAX);
+    let bars: Vec<(i64, f64)> = load_daily_bars(&conn, asset_id, &asset_code)
+        .map_err(|e| {
+            error!("Failed to load prices for asset {}: {}", asset_id, e);
+            AuthError::DatabaseError(format!("获取价格历史失败: {}", e))
+        })?
+        .into_iter()
+        .filter(|(date, _)| *date <= end)
+        .collect();
+
+    let end_date = match bars.last() {
+        Some((date, _)) if *date >= request.start_date => *date,
+        _ => {
+            return Err(AuthError::InvalidCredentials(
+                "回测区间内没有价格数据".to_string(),
+            ))
+        }
+    };
+
+    let schedule = load_fee_schedule(&conn, asset_id)?;
+    let calendar = load_asset_calendar(&conn, asset_id)?;
+
+    // 逐期模拟执行
+    let mut ledger = Vec::new();
+    let mut contributions: HashMap<usize, (f64, f64)> = HashMap::new(); // 成交K线 -> (投入, 份额)
+    let mut cash_flows = Vec::new();
+    let mut state = PlanState::default();
+    let mut total_invested = 0.0;
+    let mut total_fee = 0.0;
+    let mut last_index: Option<usize> = None;
+    let mut first_index: Option<usize> = None;
+    let mut period = 0;
+
+    let mut next = next_execution_after(&plan_schedule, &calendar, request.start_date - SECONDS_PER_DAY)?;
+
+    while let Some(scheduled) = next {
+        let scheduled_day = local_date(scheduled);
+        let index = bars.partition_point(|(date, _)| local_date(*date) < scheduled_day);
+        if index >= bars.len() {
+            break;
+        }
+
+        if last_index != Some(index) {
+            last_index = Some(index);
+            period += 1;
+
+            let (date, price) = bars[index];
+            let closes = window_closes(mode, &config, &bars[..index], date, price);
+            let plan_amount = compute_plan_amount(mode, &config, base_amount, &closes, state);
+
+            let (amount, fee, shares) = if plan_amount.amount > 0.0 {
+                let (net_amount, fee) = split_buy_budget(schedule.as_ref(), plan_amount.amount);
+                (plan_amount.amount, fee, net_amount / price)
+            } else {
+                (0.0, 0.0, 0.0)
+            };
+
+            if amount > 0.0 {
+                state.executions += 1;
+                state.plan_shares += shares;
+                total_invested += amount;
+                total_fee += fee;
+                contributions.insert(index, (amount, shares));
+                cash_flows.push((date, -amount));
+                first_index.get_or_insert(index);
+            }
+
+            ledger.push(PlanBacktestExecution {
+                period,
+                scheduled_date: scheduled,
+                date,
+                price,
+                amount,
+                fee,
+                shares,
+                indicator: plan_amount.indicator,
+                multiplier: plan_amount.multiplier,
+                reason: plan_amount.reason,
+                total_invested,
+                total_shares: state.plan_shares,
+                market_value: state.plan_shares * price,
+            });
+        }
+
+        next = next_execution_after(&plan_schedule, &calendar, scheduled)?;
+    }
+
+    let final_price = bars.last().map(|(_, price)| *price).unwrap_or_default();
+    let final_value = state.plan_shares * final_price;
+
+    // 每日市值曲线和时间加权净值
+    let mut curve = Vec::new();
+    let mut unit_values = Vec::new();
+    if let Some(first) = first_index {
+        let mut invested = 0.0;
+        let mut shares = 0.0;
+        let mut unit_value = 1.0;
+        let mut previous_value = 0.0;
+
+        for (index, (date, price)) in bars.iter().enumerate().skip(first) {
+            let (contribution, bought) = contributions.get(&index).copied().unwrap_or_default();
+            invested += contribution;
+            shares += bought;
+            let value = shares * price;
+
+            if previous_value > 0.0 {
+                unit_value *= (value - contribution) / previous_value;
+            }
+            previous_value = value;
+            unit_values.push(unit_value);
+
+            curve.push(PlanBacktestPoint {
+                date: *date,
+                invested,
+                value,
+            });
+        }
+    }
+
+    if final_value > 0.0 {
+        cash_flows.push((end_date, final_value));
+    }
+
+    // 一次性投入对照
+    let lump_sum = first_index.map(|first| {
+        let (invest_date, invest_price) = bars[first];
+        let (net_amount, fee) = split_buy_budget(schedule.as_ref(), total_invested);
+        let shares = net_amount / invest_price;
+        let final_value = shares * final_price;
+        let profit = final_value - total_invested;
+
+        LumpSumComparison {
+            invest_date,
+            invested: total_invested,
+            fee,
+            shares,
+            final_value,
+            profit,
+            return_percent: profit / total_invested * 100.0,
+            xirr_percent: xirr(&[(invest_date, -total_invested), (end_date, final_value)])
+                .map(|rate| rate * 100.0),
+            max_drawdown_percent: max_drawdown(bars[first..].iter().map(|(_, price)| shares * price)),
+        }
+    });
+
+    let profit = final_value - total_invested;
+    let trading_days_per_year = calendar.trading_days_per_year();
+
+    info!(
+        "Backtested plan for asset {} ({}): {} executions, invested {:.2}, final value {:.2}",
+        asset_id,
+        mode.to_str(),
+        state.executions,
+        total_invested,
+        final_value
+    );
+
+    Ok(PlanBacktestResult {
+        asset_id,
+        asset_name,
+        currency,
+        mode: mode.to_str().to_string(),
+        frequency: plan_schedule.frequency.clone(),
+        start_date: request.start_date,
+        end_date,
+        executions: state.executions,
+        total_invested,
+        total_fee,
+        total_shares: state.plan_shares,
+        average_cost: if state.plan_shares > 0.0 {
+            total_invested / state.plan_shares
+        } else {
+            0.0
+        },
+        final_value,
+        profit,
+        return_percent: if total_invested > 0.0 {
+            profit / total_invested * 100.0
+        } else {
+            0.0
+        },
+        xirr_percent: xirr(&cash_flows).map(|rate| rate * 100.0),
+        max_drawdown_percent: max_drawdown(unit_values.iter().copied()),
+        annualized_volatility_percent: annualized_volatility(&unit_values, trading_days_per_year),
+        exchange: calendar.exchange.to_str().to_string(),
+        trading_days_per_year,
+        ledger,
+        curve,
+        lump_sum,
+    })
+}
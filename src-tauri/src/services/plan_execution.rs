@@ -0,0 +1,589 @@
+/**
+ * 定投计划执行模块
+ *
+ * 按计划日期逐期执行到期的定投计划，每一期在 `plan_executions` 中记录一条执行记录：
+ * - 从计划的 `next_execution` 开始，依次推算到当前时间为止所有到期的计划日期（遇休市日顺延）。
+ * - 计划日期早于今天的视为错过的期数（应用未运行），按计划的补执行策略（`CatchUpPolicy`）处理：
+ *   - ALL：每期按计划日期的历史价格补执行；成交日价格尚未同步时生成待确认交易，由成交确认任务按净值确认。
+ *   - ONCE：按当前价格执行一次（记在最近一期），其余期数记为跳过。
+ *   - SKIP：全部记为跳过。
+ * - 今天的期数按当前价格下单，按资产类型的成交确认规则确认（见 `plan_settlement`）。
+ * - 下一次执行时间从最后一个计划日期继续推算（见 `plan_schedule`），而不是从当前时间推算，保证计划日期不漂移。
+ * - 停止条件：已过结束日期、累计投入达到上限（最后一期按剩余额度投入）或计划收益率达到止盈目标时，
+ *   计划自动停止并记录原因，未执行的期数记为跳过。止盈按资产现价在执行前判断。
+ *
+ * 单个计划执行失败只记录日志并回滚该计划，不影响其他计划。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    CatchUpPolicy, PlanExecution, PlanExecutionStatus, PlanMode, PlanSchedule, PlanStopConditions,
+    PlanStopReason, SmartDcaConfig, TransactionStatus,
+};
+use crate::services::calendar::{load_asset_calendar, TradingCalendar};
+use crate::services::fee::{load_fee_schedule, split_buy_budget};
+use crate::services::investment_plan::{parse_days_of_month, parse_mode_config};
+use crate::services::plan_schedule::next_execution_after;
+use crate::services::plan_settlement::schedule_settlement;
+use crate::services::smart_dca::evaluate_plan_amount;
+use crate::services::snapshot::{day_start, local_date};
+use chrono::{NaiveDate, Utc};
+use log::{error, info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 单次最多补执行的期数，长时间未运行时只处理最近的期数
+const MAX_CATCH_UP_PERIODS: usize = 366;
+
+/// 到期计划的执行参数
+struct DuePlan {
+    id: i64,
+    user_id: i64,
+    asset_id: i64,
+    asset_type: String,
+    base_amount: f64,
+    schedule: PlanSchedule,
+    mode: PlanMode,
+    config: SmartDcaConfig,
+    catch_up_policy: CatchUpPolicy,
+    stop: PlanStopConditions,
+    next_execution: i64,
+}
+
+/// 单期的执行方式
+#[derive(Clone, Copy, PartialEq)]
+enum PeriodAction {
+    Historical, // 按计划日期的历史价格补执行
+    Current,    // 按当前价格执行
+    Skip,       // 按补执行策略跳过
+}
+
+/// 单期执行结果，写入 `plan_executions`
+struct ExecutionOutcome {
+    status: PlanExecutionStatus,
+    amount: f64,
+    fee: f64,
+    price: Option<f64>,
+    shares: Option<f64>,
+    transaction_id: Option<i64>,
+    message: String,
+}
+
+impl ExecutionOutcome {
+    fn without_trade(status: PlanExecutionStatus, message: String) -> Self {
+        Self {
+            status,
+            amount: 0.0,
+            fee: 0.0,
+            price: None,
+            shares: None,
+            transaction_id: None,
+            message,
+        }
+    }
+}
+
+/// 从 `next_execution` 起到当前时间为止的所有计划日期，以及之后的下一次执行时间（已过结束日期时为 None）
+fn due_periods(
+    plan: &DuePlan,
+    calendar: &TradingCalendar,
+    now: i64,
+) -> Result<(Vec<i64>, Option<i64>), AuthError> {
+    let mut periods = Vec::new();
+    let mut next = Some(plan.next_execution);
+
+    while let Some(scheduled) = next.filter(|scheduled| *scheduled <= now) {
+        periods.push(scheduled);
+        next = next_execution_after(&plan.schedule, calendar, scheduled)?;
+    }
+
+    if periods.len() > MAX_CATCH_UP_PERIODS {
+        warn!(
+            "Investment plan {} missed {} periods, only the latest {} are processed",
+            plan.id,
+            periods.len(),
+            MAX_CATCH_UP_PERIODS
+        );
+        periods.drain(..periods.len() - MAX_CATCH_UP_PERIODS);
+    }
+
+    Ok((periods, next))
+}
+
+/// 计划累计投入（含费用）和持有份额，待确认交易按预估份额计算
+pub fn load_plan_totals(conn: &Connection, plan_id: i64) -> Result<(f64, f64), rusqlite::Error> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(total_cost + fee), 0), COALESCE(SUM(amount), 0) FROM transactions
+         WHERE plan_id = ?1 AND transaction_type = 'BUY' AND status IN ('CONFIRMED', 'PENDING')",
+        params![plan_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+
+/// 检查累计投入上限，返回停止原因或剩余可投入金额
+fn check_amount_cap(
+    conn: &Connection,
+    plan: &DuePlan,
+) -> Result<(Option<(PlanStopReason, String)>, Option<f64>), AuthError> {
+    let max_total_amount = match plan.stop.max_total_amount {
+        Some(max_total_amount) => max_total_amount,
+        None => return Ok((None, None)),
+    };
+
+    let (invested, _) = load_plan_totals(conn, plan.id)?;
+    let remaining = max_total_amount - invested;
+    if remaining < 0.01 {
+        let message = format!("累计投入 {:.2} 已达到上限 {:.2}，计划已停止", invested, max_total_amount);
+        return Ok((Some((PlanStopReason::AmountCap, message)), None));
+    }
+    Ok((None, Some(remaining)))
+}
+
+/// 按资产现价检查计划收益率是否达到止盈目标
+fn check_take_profit(
+    conn: &Connection,
+    plan: &DuePlan,
+) -> Result<Option<(PlanStopReason, String)>, AuthError> {
+    let target = match plan.stop.take_profit_percent {
+        Some(target) => target,
+        None => return Ok(None),
+    };
+
+    let price: Option<f64> = conn
+        .query_row(
+            "SELECT current_price FROM assets WHERE id = ?1",
+            params![plan.asset_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    let (invested, shares) = load_plan_totals(conn, plan.id)?;
+
+    match price {
+        Some(price) if invested > 0.0 => {
+            let return_percent = (shares * price - invested) / invested * 100.0;
+            if return_percent >= target {
+                let message = format!(
+                    "计划收益率 {:.2}% 已达到止盈目标 {:.2}%，计划已停止",
+                    return_percent, target
+                );
+                return Ok(Some((PlanStopReason::TakeProfit, message)));
+            }
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// 执行一期定投
+///
+/// `historical` 为 true 时按计划日期的历史价格补执行，否则按当前价格下单。
+fn execute_period(
+    conn: &Connection,
+    plan: &DuePlan,
+    calendar: &TradingCalendar,
+    scheduled: i64,
+    now: i64,
+    historical: bool,
+    max_amount: Option<f64>,
+) -> Result<ExecutionOutcome, AuthError> {
+    let trade_date = calendar.next_trading_day(local_date(scheduled));
+
+    // 补执行取成交日（含）之前最近的收盘价，否则取资产现价
+    let quote: Option<(Option<i64>, f64)> = if historical {
+        conn.query_row(
+            "SELECT date, close_price FROM price_history
+             WHERE asset_id = ?1 AND date < ?2
+             ORDER BY date DESC LIMIT 1",
+            params![plan.asset_id, day_start(trade_date) + SECONDS_PER_DAY],
+            |row| Ok((Some(row.get(0)?), row.get(1)?)),
+        )
+        .optional()?
+    } else {
+        conn.query_row(
+            "SELECT current_price FROM assets WHERE id = ?1",
+            params![plan.asset_id],
+            |row| row.get::<_, Option<f64>>(0),
+        )
+        .optional()?
+        .flatten()
+        .map(|price| (None, price))
+    };
+
+    let (price_date, price) = match quote.filter(|(_, price)| *price > 0.0) {
+        Some(quote) => quote,
+        None => {
+            return Ok(ExecutionOutcome::without_trade(
+                PlanExecutionStatus::Failed,
+                "没有可用的价格".to_string(),
+            ))
+        }
+    };
+
+    // 按定投模式计算本期金额
+    let plan_amount = evaluate_plan_amount(
+        conn,
+        plan.id,
+        plan.asset_id,
+        plan.mode,
+        &plan.config,
+        plan.base_amount,
+        price,
+        if historical { scheduled } else { now },
+    )?;
+    let mut reason = plan_amount.reason;
+    let mut amount = plan_amount.amount;
+
+    // 接近累计投入上限时按剩余额度投入
+    if let Some(max_amount) = max_amount.filter(|max_amount| amount > *max_amount) {
+        amount = max_amount;
+        reason = format!("{}（按累计投入上限的剩余额度投入）", reason);
+    }
+
+    // 智能定投本期暂停时不买入
+    if amount <= 0.0 {
+        return Ok(ExecutionOutcome::without_trade(
+            PlanExecutionStatus::Paused,
+            reason,
+        ));
+    }
+
+    // 定投金额包含费用，扣除费用后的净额用于买入
+    let schedule = load_fee_schedule(conn, plan.asset_id)?;
+    let (net_amount, fee) = split_buy_budget(schedule.as_ref(), amount);
+
+    // 补执行：已有成交日价格时直接确认，否则等待成交确认任务按成交日价格确认；
+    // 当期执行：按成交确认规则确定成交日，需要确认的先按当前价格预估份额
+    let (status, transaction_date, settle_date, confirm_date) = if historical {
+        if price_date.map(local_date) == Some(trade_date) {
+            (TransactionStatus::Confirmed, scheduled, Some(day_start(trade_date)), None)
+        } else {
+            (
+                TransactionStatus::Pending,
+                scheduled,
+                Some(day_start(trade_date)),
+                Some(day_start(local_date(now))),
+            )
+        }
+    } else {
+        match schedule_settlement(&plan.asset_type, calendar, now) {
+            Some((settle_date, confirm_date)) => {
+                (TransactionStatus::Pending, now, Some(settle_date), Some(confirm_date))
+            }
+            None => (TransactionStatus::Confirmed, now, None, None),
+        }
+    };
+
+    let shares = net_amount / price; // 购买数量 = 净金额 / 价格
+    conn.execute(
+        "INSERT INTO transactions (
+            user_id, asset_id, transaction_type, amount, price,
+            total_cost, fee, transaction_date, notes, plan_id, status,
+            settle_date, confirm_date, created_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            plan.user_id,
+            plan.asset_id,
+            "BUY",
+            shares,
+            price,
+            net_amount,
+            fee,
+            transaction_date,
+            format!("自动定投 (计划ID: {}) {}", plan.id, reason),
+            plan.id,
+            status.to_str(),
+            settle_date,
+            confirm_date,
+            now
+        ],
+    )?;
+
+    Ok(ExecutionOutcome {
+        status: PlanExecutionStatus::Executed,
+        amount,
+        fee,
+        price: Some(price),
+        shares: Some(shares),
+        transaction_id: Some(conn.last_insert_rowid()),
+        message: reason,
+    })
+}
+
+/// 写入一条执行记录
+fn record_execution(
+    conn: &Connection,
+    plan: &DuePlan,
+    scheduled: i64,
+    now: i64,
+    catch_up: bool,
+    outcome: &ExecutionOutcome,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO plan_executions (
+            plan_id, user_id, asset_id, scheduled_date, executed_at, status, catch_up,
+            amount, fee, price, shares, transaction_id, message, created_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            plan.id,
+            plan.user_id,
+            plan.asset_id,
+            scheduled,
+            now,
+            outcome.status.to_str(),
+            catch_up,
+            outcome.amount,
+            outcome.fee,
+            outcome.price,
+            outcome.shares,
+            outcome.transaction_id,
+            outcome.message,
+            now
+        ],
+    )?;
+    Ok(())
+}
+
+/// 执行单个计划的所有到期期数，返回生成交易的期数
+fn execute_plan(conn: &mut Connection, plan: &DuePlan, now: i64) -> Result<usize, AuthError> {
+    let calendar = load_asset_calendar(conn, plan.asset_id)?;
+    let (periods, next_execution) = due_periods(plan, &calendar, now)?;
+
+    // 计划日期早于今天的为错过的期数
+    let today: NaiveDate = local_date(now);
+    let (mut missed, current): (Vec<i64>, Vec<i64>) =
+        periods.into_iter().partition(|scheduled| local_date(*scheduled) < today);
+
+    // ONCE：没有今天的期数时，在最近一个错过的期数按当前价格执行一次
+    let mut once = None;
+    if plan.catch_up_policy == CatchUpPolicy::Once && current.is_empty() {
+        once = missed.pop();
+    }
+
+    // 按执行顺序排列各期：(计划日期, 是否补执行, 执行方式)
+    let missed_action = match plan.catch_up_policy {
+        CatchUpPolicy::All => PeriodAction::Historical,
+        _ => PeriodAction::Skip,
+    };
+    let queue = missed
+        .into_iter()
+        .map(|scheduled| (scheduled, true, missed_action))
+        .chain(once.map(|scheduled| (scheduled, true, PeriodAction::Current)))
+        .chain(current.into_iter().map(|scheduled| (scheduled, false, PeriodAction::Current)));
+
+    let tx = conn.transaction()?;
+    let mut executed = 0;
+    let mut stop = check_take_profit(&tx, plan)?;
+
+    for (scheduled, catch_up, action) in queue {
+        let mut max_amount = None;
+        if stop.is_none() && action != PeriodAction::Skip {
+            (stop, max_amount) = check_amount_cap(&tx, plan)?;
+        }
+
+        let outcome = match (&stop, action) {
+            (Some((_, message)), _) => {
+                ExecutionOutcome::without_trade(PlanExecutionStatus::Skipped, message.clone())
+            }
+            (None, PeriodAction::Skip) => ExecutionOutcome::without_trade(
+                PlanExecutionStatus::Skipped,
+                format!("错过执行日，按补执行策略 {} 跳过", plan.catch_up_policy.to_str()),
+            ),
+            (None, action) => execute_period(
+                &tx,
+                plan,
+                &calendar,
+                scheduled,
+                now,
+                action == PeriodAction::Historical,
+                max_amount,
+            )?,
+        };
+        if outcome.status == PlanExecutionStatus::Executed {
+            executed += 1;
+        }
+        record_execution(&tx, plan, scheduled, now, catch_up, &outcome)?;
+    }
+
+    // 本次执行后达到投入上限或已过结束日期时停止计划
+    if stop.is_none() {
+        stop = check_amount_cap(&tx, plan)?.0;
+    }
+    let stop_reason = match (&stop, next_execution) {
+        (Some((reason, _)), _) => Some(*reason),
+        (None, None) => Some(PlanStopReason::EndDate),
+        (None, Some(_)) => None,
+    };
+
+    // 更新定投计划
+    match stop_reason {
+        Some(reason) => {
+            tx.execute(
+                "UPDATE investment_plans
+                 SET last_executed = CASE WHEN ?1 > 0 THEN ?2 ELSE last_executed END,
+                     is_active = 0, next_execution = NULL, stop_reason = ?3, updated_at = ?2
+                 WHERE id = ?4",
+                params![executed as i64, now, reason.to_str(), plan.id],
+            )?;
+            info!("Investment plan {} stopped: {}", plan.id, reason.to_str());
+        }
+        None => {
+            tx.execute(
+                "UPDATE investment_plans
+                 SET last_executed = CASE WHEN ?1 > 0 THEN ?2 ELSE last_executed END,
+                     next_execution = ?3, updated_at = ?2
+                 WHERE id = ?4",
+                params![executed as i64, now, next_execution, plan.id],
+            )?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(executed)
+}
+
+/**
+ * @dev 执行到期的定投计划（含错过的期数），并更新下一次定投的执行时间
+ * 需要按成交日净值确认的资产（如基金）生成待确认交易，由 `settle_pending_plan_executions` 确认
+ */
+pub fn execute_due_investment_plans() -> Result<usize, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    // 获取所有到期的定投计划
+    let plans = {
+        let mut stmt = conn.prepare(
+            "SELECT p.id, p.user_id, p.asset_id, t.name, p.amount, p.frequency, p.day_of_week,
+                    p.day_of_month, p.mode, p.mode_config, p.catch_up_policy, p.next_execution,
+                    p.days_of_month, p.cron_expression, p.start_date, p.end_date,
+                    p.max_total_amount, p.take_profit_percent
+             FROM investment_plans p
+             JOIN assets a ON p.asset_id = a.id
+             JOIN asset_types t ON a.asset_type_id = t.id
+             WHERE p.is_active = 1 AND p.next_execution <= ?1",
+        )?;
+
+        let result = stmt
+            .query_map(params![now], |row| {
+                Ok(DuePlan {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    asset_id: row.get(2)?,
+                    asset_type: row.get(3)?,
+                    base_amount: row.get(4)?,
+                    schedule: PlanSchedule {
+                        frequency: row.get(5)?,
+                        day_of_week: row.get(6)?,
+                        day_of_month: row.get(7)?,
+                        days_of_month: parse_days_of_month(row.get(12)?),
+                        cron_expression: row.get(13)?,
+                        start_date: row.get(14)?,
+                        end_date: row.get(15)?,
+                    },
+                    mode: PlanMode::from_str(&row.get::<_, String>(8)?).unwrap_or(PlanMode::Fixed),
+                    config: parse_mode_config(row.get(9)?).unwrap_or_default(),
+                    catch_up_policy: CatchUpPolicy::from_str(&row.get::<_, String>(10)?)
+                        .unwrap_or(CatchUpPolicy::Once),
+                    stop: PlanStopConditions {
+                        max_total_amount: row.get(16)?,
+                        take_profit_percent: row.get(17)?,
+                    },
+                    next_execution: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                error!("Failed to fetch due investment plans: {}", e);
+                AuthError::DatabaseError(format!("获取到期定投计划失败: {}", e))
+            });
+
+        result?
+    };
+
+    let mut executed_count = 0;
+
+    for plan in plans {
+        match execute_plan(&mut conn, &plan, now) {
+            Ok(executed) => {
+                executed_count += 1;
+                info!(
+                    "Executed investment plan: {} for asset: {} ({} trades)",
+                    plan.id, plan.asset_id, executed
+                );
+            }
+            Err(e) => error!(
+                "Failed to execute investment plan: {} for asset: {} - {}",
+                plan.id, plan.asset_id, e
+            ),
+        }
+    }
+
+    Ok(executed_count)
+}
+
+/**
+ * @dev 获取定投计划的执行记录（按计划日期倒序）
+ */
+pub fn get_plan_executions(
+    user_id: i64,
+    plan_id: i64,
+    limit: Option<i64>,
+) -> Result<Vec<PlanExecution>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let plan_exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM investment_plans WHERE id = ?1 AND user_id = ?2",
+            params![plan_id, user_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if !plan_exists {
+        return Err(AuthError::InvalidCredentials(
+            "定投计划不存在或无权限".to_string(),
+        ));
+    }
+
+    // 已生成交易的期数以交易的最新价格和份额为准（待确认交易确认后按成交日净值重算）
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.plan_id, e.asset_id, a.name, e.scheduled_date, e.executed_at, e.status,
+                e.catch_up, e.amount, e.fee, COALESCE(t.price, e.price), COALESCE(t.amount, e.shares),
+                e.transaction_id, t.status, COALESCE(t.failure_reason, e.message)
+         FROM plan_executions e
+         JOIN assets a ON e.asset_id = a.id
+         LEFT JOIN transactions t ON e.transaction_id = t.id
+         WHERE e.plan_id = ?1 AND e.user_id = ?2
+         ORDER BY e.scheduled_date DESC, e.id DESC
+         LIMIT ?3",
+    )?;
+
+    let executions = stmt
+        .query_map(params![plan_id, user_id, limit.unwrap_or(-1)], |row| {
+            Ok(PlanExecution {
+                id: row.get(0)?,
+                plan_id: row.get(1)?,
+                asset_id: row.get(2)?,
+                asset_name: row.get(3)?,
+                scheduled_date: row.get(4)?,
+                executed_at: row.get(5)?,
+                status: row.get(6)?,
+                catch_up: row.get(7)?,
+                amount: row.get(8)?,
+                fee: row.get(9)?,
+                price: row.get(10)?,
+                shares: row.get(11)?,
+                transaction_id: row.get(12)?,
+                transaction_status: row.get(13)?,
+                message: row.get(14)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch plan executions: {}", e);
+            AuthError::DatabaseError(format!("获取定投执行记录失败: {}", e))
+        })?;
+
+    Ok(executions)
+}
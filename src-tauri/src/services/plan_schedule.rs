@@ -0,0 +1,463 @@
+/**
+ * 定投执行日程模块
+ *
+ * 按计划的执行日程（见 `PlanSchedule`）推算执行日期，供计划保存、到期执行和回测共用：
+ * - 每日 / 每周 / 每两周：保留上一次执行的时刻，按北京时间的日期推算。
+ * - 每月 / 每季度：支持多个执行日（如每月 1 号和 15 号），超过当月天数时取月末。
+ * - 每月最后一个交易日：按资产所在交易所的交易日历计算。
+ * - CRON：5 位表达式（分 时 日 月 星期），按北京时间匹配，日期和星期同时限定时满足其一即可。
+ *
+ * 推算出的日期遇休市日顺延到下一个交易日；超过计划结束日期时返回 None，表示计划已结束。
+ */
+use crate::error::auth::AuthError;
+use crate::models::PlanSchedule;
+use crate::services::calendar::TradingCalendar;
+use crate::services::scheduler::beijing_offset;
+use crate::services::snapshot::{day_start, local_date};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 查找下一个执行月份的最大月数
+const MAX_SEARCH_MONTHS: i32 = 48;
+
+/// 查找 CRON 下一次触发时间的最大天数（覆盖 2 月 29 日）
+const MAX_CRON_DAYS: i64 = 366 * 4 + 1;
+
+/// 5 位 CRON 表达式，各字段按位保存允许的取值
+struct CronExpression {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64, // 0 为周日
+    any_day: bool,     // 日字段为 `*`（`*/2` 等带步长的写法视为限定）
+    any_weekday: bool, // 星期字段为 `*`
+}
+
+/// 解析 CRON 字段：`*`、`5`、`1-5`、`1,15`、`*/2`、`10-20/5`
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut mask = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0)?),
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let value = range.parse().ok()?;
+            (value, if part.contains('/') { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Some(mask)
+}
+
+impl CronExpression {
+    fn parse(expression: &str) -> Result<Self, AuthError> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(AuthError::InvalidCredentials(
+                "CRON 表达式需要 5 个字段：分 时 日 月 星期".to_string(),
+            ));
+        }
+
+        let field = |index: usize, min: u32, max: u32| {
+            parse_cron_field(fields[index], min, max).ok_or_else(|| {
+                AuthError::InvalidCredentials(format!("无效的 CRON 字段: {}", fields[index]))
+            })
+        };
+
+        // 星期 7 与 0 均为周日
+        let mut weekdays = field(4, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+
+        Ok(Self {
+            minutes: field(0, 0, 59)?,
+            hours: field(1, 0, 23)?,
+            days: field(2, 1, 31)?,
+            months: field(3, 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// 指定时间（北京时间）之后的下一次触发时间
+    fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after + Duration::minutes(1);
+        let start_minute = start.hour() * 60 + start.minute();
+
+        (0..MAX_CRON_DAYS)
+            .map(|offset| start.date() + Duration::days(offset))
+            .filter(|date| self.matches_date(*date))
+            .find_map(|date| {
+                let from = if date == start.date() { start_minute } else { 0 };
+                (from..24 * 60)
+                    .find(|minute| {
+                        self.hours & (1 << (minute / 60)) != 0 && self.minutes & (1 << (minute % 60)) != 0
+                    })
+                    .and_then(|minute| date.and_hms_opt(minute / 60, minute % 60, 0))
+            })
+    }
+}
+
+/// 当月最后一天
+fn last_day_of_month(year: i32, month: u32) -> NaiveDate {
+    let (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|date| date.pred_opt())
+        .unwrap_or_default()
+}
+
+/// 从当月开始的年份和月份
+fn months_from(date: NaiveDate) -> impl Iterator<Item = (i32, u32)> {
+    let first = date.year() * 12 + date.month0() as i32;
+    (first..first + MAX_SEARCH_MONTHS).map(|index| (index.div_euclid(12), index.rem_euclid(12) as u32 + 1))
+}
+
+/// 每月 / 每季度的执行日，按日期排序
+fn schedule_days(schedule: &PlanSchedule) -> Vec<u32> {
+    let mut days: Vec<u32> = if schedule.days_of_month.is_empty() {
+        schedule.day_of_month.into_iter().map(|day| day as u32).collect()
+    } else {
+        schedule.days_of_month.iter().map(|day| *day as u32).collect()
+    };
+    days.sort_unstable();
+    days.dedup();
+    days
+}
+
+/// 校验执行日程的频率和参数
+pub fn validate_schedule(schedule: &PlanSchedule) -> Result<(), AuthError> {
+    let has_days = schedule.day_of_month.is_some() || !schedule.days_of_month.is_empty();
+    let valid_day_of_week = matches!(schedule.day_of_week, Some(1..=7));
+    let invalid = |message: &str| Err(AuthError::InvalidCredentials(message.to_string()));
+
+    match schedule.frequency.as_str() {
+        "DAILY" | "LAST_TRADING_DAY" => {
+            if schedule.day_of_week.is_some() || has_days {
+                return invalid("每日和每月最后一个交易日定投不需要指定星期几或每月几号");
+            }
+        }
+        "WEEKLY" | "BIWEEKLY" => {
+            if !valid_day_of_week {
+                return invalid("每周和每两周定投需要指定星期几（1-7）");
+            }
+            if has_days {
+                return invalid("每周和每两周定投不需要指定每月几号");
+            }
+        }
+        "MONTHLY" | "QUARTERLY" => {
+            let days = schedule_days(schedule);
+            if days.is_empty() || days.iter().any(|day| !(1..=31).contains(day)) {
+                return invalid("每月和每季度定投需要指定每月几号（1-31）");
+            }
+            if schedule.day_of_week.is_some() {
+                return invalid("每月和每季度定投不需要指定星期几");
+            }
+        }
+        "CRON" => {
+            let expression = schedule
+                .cron_expression
+                .as_deref()
+                .ok_or_else(|| AuthError::InvalidCredentials("CRON 定投需要指定 CRON 表达式".to_string()))?;
+            let cron = CronExpression::parse(expression)?;
+            if cron.next_after(NaiveDate::default().and_hms_opt(0, 0, 0).unwrap_or_default()).is_none() {
+                return invalid("CRON 表达式没有匹配的执行时间");
+            }
+        }
+        _ => {
+            return invalid(
+                "无效的定投频率，支持的频率：DAILY, WEEKLY, BIWEEKLY, MONTHLY, QUARTERLY, LAST_TRADING_DAY, CRON",
+            )
+        }
+    }
+
+    if let (Some(start), Some(end)) = (schedule.start_date, schedule.end_date) {
+        if local_date(end) < local_date(start) {
+            return invalid("结束日期不能早于开始日期");
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * @dev 指定时间之后的下一次执行时间，休市日顺延到下一个交易日
+ * 超过计划结束日期时返回 None
+ */
+pub fn next_execution_after(
+    schedule: &PlanSchedule,
+    calendar: &TradingCalendar,
+    after: i64,
+) -> Result<Option<i64>, AuthError> {
+    let today = local_date(after);
+    // 保留 after 的时刻，平移到目标日期
+    let shift = |date: NaiveDate| after + (date - today).num_days() * SECONDS_PER_DAY;
+    let day_of_week = || {
+        schedule
+            .day_of_week
+            .filter(|day| (1..=7).contains(day))
+            .map(|day| (day - 1) as u32)
+            .ok_or_else(|| AuthError::InvalidCredentials("无效的星期几".to_string()))
+    };
+
+    let next = match schedule.frequency.as_str() {
+        "DAILY" => shift(today + Duration::days(1)),
+        "WEEKLY" | "BIWEEKLY" => {
+            let days_until_target = (7 + day_of_week()? - today.weekday().num_days_from_monday()) % 7;
+
+            // 如果今天是目标日期，则设置为下周（每两周为两周后）
+            let days_to_add = match (schedule.frequency.as_str(), days_until_target) {
+                ("WEEKLY", 0) => 7,
+                ("WEEKLY", days) => days,
+                (_, 0) => 14,
+                (_, days) => days + 7,
+            };
+            shift(today + Duration::days(days_to_add as i64))
+        }
+        "MONTHLY" | "QUARTERLY" => {
+            let quarterly = schedule.frequency == "QUARTERLY";
+            let days = schedule_days(schedule);
+
+            let date = months_from(today)
+                .filter(|(_, month)| !quarterly || (month - 1) % 3 == 0)
+                .find_map(|(year, month)| {
+                    let last_day = last_day_of_month(year, month).day();
+                    days.iter()
+                        .filter_map(|day| NaiveDate::from_ymd_opt(year, month, (*day).min(last_day)))
+                        .find(|date| *date > today)
+                })
+                .ok_or_else(|| AuthError::InvalidCredentials("每月定投需要指定每月几号".to_string()))?;
+            shift(date)
+        }
+        "LAST_TRADING_DAY" => {
+            let date = months_from(today)
+                .map(|(year, month)| calendar.previous_trading_day(last_day_of_month(year, month)))
+                .find(|date| *date > today)
+                .ok_or_else(|| AuthError::InternalError("无法计算每月最后一个交易日".to_string()))?;
+            shift(date)
+        }
+        "CRON" => {
+            let cron = CronExpression::parse(schedule.cron_expression.as_deref().unwrap_or_default())?;
+            let local = DateTime::from_timestamp(after, 0)
+                .unwrap_or_default()
+                .with_timezone(&beijing_offset())
+                .naive_local();
+
+            cron.next_after(local)
+                .and_then(|next| beijing_offset().from_local_datetime(&next).single())
+                .map(|next| next.timestamp())
+                .ok_or_else(|| AuthError::InvalidCredentials("CRON 表达式没有匹配的执行时间".to_string()))?
+        }
+        _ => return Err(AuthError::InvalidCredentials("无效的定投频率".to_string())),
+    };
+
+    let next = calendar.roll_forward(next);
+    match schedule.end_date {
+        Some(end_date) if local_date(next) > local_date(end_date) => Ok(None),
+        _ => Ok(Some(next)),
+    }
+}
+
+/**
+ * @dev 计划的首次执行时间：不早于开始日期，未指定开始日期时从当前时间推算
+ */
+pub fn first_execution(
+    schedule: &PlanSchedule,
+    calendar: &TradingCalendar,
+    now: i64,
+) -> Result<Option<i64>, AuthError> {
+    let after = match schedule.start_date.map(local_date) {
+        // 从开始日期的前一天推算，使开始日期当天可以执行（CRON 从开始日期零点起匹配）
+        Some(start) if start > local_date(now) => {
+            if schedule.frequency == "CRON" {
+                day_start(start) - 1
+            } else {
+                now + ((start - local_date(now)).num_days() - 1) * SECONDS_PER_DAY
+            }
+        }
+        _ => now,
+    };
+
+    next_execution_after(schedule, calendar, after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Exchange;
+    use std::collections::BTreeMap;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn beijing(value: &str, hour: u32, minute: u32) -> i64 {
+        beijing_offset()
+            .from_local_datetime(&date(value).and_hms_opt(hour, minute, 0).unwrap())
+            .unwrap()
+            .timestamp()
+    }
+
+    /// 全年无休的日历，推算结果不受休市日顺延影响
+    fn open_calendar() -> TradingCalendar {
+        TradingCalendar::new(Exchange::Crypto, BTreeMap::new())
+    }
+
+    fn schedule(frequency: &str) -> PlanSchedule {
+        PlanSchedule {
+            frequency: frequency.to_string(),
+            day_of_week: None,
+            day_of_month: None,
+            days_of_month: Vec::new(),
+            cron_expression: None,
+            start_date: None,
+            end_date: None,
+        }
+    }
+
+    fn next(schedule: &PlanSchedule, after: i64) -> Option<i64> {
+        next_execution_after(schedule, &open_calendar(), after).unwrap()
+    }
+
+    #[test]
+    fn cron_fields_parse_lists_ranges_and_steps() {
+        assert_eq!(parse_cron_field("*", 0, 3), Some(0b1111));
+        assert_eq!(parse_cron_field("1,3", 0, 5), Some(0b1010));
+        assert_eq!(parse_cron_field("1-3", 0, 5), Some(0b1110));
+        assert_eq!(parse_cron_field("*/2", 0, 5), Some(0b10101));
+        assert_eq!(parse_cron_field("10-20/5", 0, 59), Some((1 << 10) | (1 << 15) | (1 << 20)));
+        assert_eq!(parse_cron_field("5/20", 0, 59), Some((1 << 5) | (1 << 25) | (1 << 45)));
+        assert_eq!(parse_cron_field("60", 0, 59), None);
+        assert_eq!(parse_cron_field("5-1", 0, 59), None);
+        assert_eq!(parse_cron_field("*/0", 0, 59), None);
+        assert_eq!(parse_cron_field("a", 0, 59), None);
+    }
+
+    #[test]
+    fn cron_expression_requires_five_fields() {
+        assert!(CronExpression::parse("0 10 * *").is_err());
+        assert!(CronExpression::parse("0 24 * * *").is_err());
+
+        // 星期 7 与 0 均为周日
+        let cron = CronExpression::parse("0 10 * * 7").unwrap();
+        assert!(cron.matches_date(date("2024-06-02")));
+        assert!(!cron.matches_date(date("2024-06-03")));
+    }
+
+    #[test]
+    fn cron_stepped_day_is_restricted() {
+        // 日期和星期同时限定时满足其一即可：单数日或周一
+        let cron = CronExpression::parse("0 10 */2 * 1").unwrap();
+        assert!(!cron.any_day);
+        assert!(cron.matches_date(date("2024-06-10"))); // 周一，双数日
+        assert!(cron.matches_date(date("2024-06-11"))); // 周二，单数日
+        assert!(!cron.matches_date(date("2024-06-12"))); // 周三，双数日
+
+        // 日字段为 * 时只按星期匹配
+        let cron = CronExpression::parse("0 10 * * 1").unwrap();
+        assert!(cron.matches_date(date("2024-06-10")));
+        assert!(!cron.matches_date(date("2024-06-11")));
+
+        // 月份限定
+        let cron = CronExpression::parse("0 10 1 3,9 *").unwrap();
+        assert!(cron.matches_date(date("2024-09-01")));
+        assert!(!cron.matches_date(date("2024-10-01")));
+    }
+
+    #[test]
+    fn cron_next_execution() {
+        let mut cron = schedule("CRON");
+        cron.cron_expression = Some("30 9 * * 1-5".to_string());
+
+        // 当天已过触发时刻，顺延到下一个工作日
+        assert_eq!(next(&cron, beijing("2024-06-03", 9, 30)), Some(beijing("2024-06-04", 9, 30)));
+        assert_eq!(next(&cron, beijing("2024-06-03", 9, 0)), Some(beijing("2024-06-03", 9, 30)));
+        assert_eq!(next(&cron, beijing("2024-06-07", 10, 0)), Some(beijing("2024-06-10", 9, 30)));
+    }
+
+    #[test]
+    fn monthly_days_clamp_to_month_end() {
+        let mut monthly = schedule("MONTHLY");
+        monthly.days_of_month = vec![31, 15];
+
+        assert_eq!(next(&monthly, beijing("2024-01-31", 10, 0)), Some(beijing("2024-02-15", 10, 0)));
+        assert_eq!(next(&monthly, beijing("2024-02-15", 10, 0)), Some(beijing("2024-02-29", 10, 0)));
+        assert_eq!(next(&monthly, beijing("2023-02-15", 10, 0)), Some(beijing("2023-02-28", 10, 0)));
+        assert_eq!(next(&monthly, beijing("2024-04-15", 10, 0)), Some(beijing("2024-04-30", 10, 0)));
+    }
+
+    #[test]
+    fn quarterly_runs_in_first_month_of_quarter() {
+        let mut quarterly = schedule("QUARTERLY");
+        quarterly.day_of_month = Some(15);
+
+        assert_eq!(next(&quarterly, beijing("2024-01-10", 10, 0)), Some(beijing("2024-01-15", 10, 0)));
+        assert_eq!(next(&quarterly, beijing("2024-01-15", 10, 0)), Some(beijing("2024-04-15", 10, 0)));
+        assert_eq!(next(&quarterly, beijing("2024-11-20", 10, 0)), Some(beijing("2025-01-15", 10, 0)));
+    }
+
+    #[test]
+    fn biweekly_skips_a_week() {
+        let mut biweekly = schedule("BIWEEKLY");
+        biweekly.day_of_week = Some(1);
+
+        // 当天为执行日时两周后执行
+        assert_eq!(next(&biweekly, beijing("2024-06-03", 10, 0)), Some(beijing("2024-06-17", 10, 0)));
+        // 否则为下一个周一再加一周
+        assert_eq!(next(&biweekly, beijing("2024-06-02", 10, 0)), Some(beijing("2024-06-10", 10, 0)));
+        assert_eq!(next(&biweekly, beijing("2024-06-05", 10, 0)), Some(beijing("2024-06-17", 10, 0)));
+
+        let mut weekly = schedule("WEEKLY");
+        weekly.day_of_week = Some(1);
+        assert_eq!(next(&weekly, beijing("2024-06-03", 10, 0)), Some(beijing("2024-06-10", 10, 0)));
+    }
+
+    #[test]
+    fn holidays_roll_forward_and_end_date_stops() {
+        let mut holidays = BTreeMap::new();
+        holidays.insert(date("2024-10-01"), "国庆节".to_string());
+        let calendar = TradingCalendar::new(Exchange::Sse, holidays);
+
+        let mut monthly = schedule("MONTHLY");
+        monthly.day_of_month = Some(1);
+        let after = beijing("2024-09-15", 10, 0);
+        assert_eq!(
+            next_execution_after(&monthly, &calendar, after).unwrap(),
+            Some(beijing("2024-10-02", 10, 0))
+        );
+
+        monthly.end_date = Some(beijing("2024-09-30", 0, 0));
+        assert_eq!(next_execution_after(&monthly, &calendar, after).unwrap(), None);
+    }
+}
@@ -0,0 +1,192 @@
+/**
+ * 定投成交确认模块
+ *
+ * 定投下单时按资产类型的成交确认规则（`SettlementConfig.rules`）确定成交日：
+ * - 截止时间（北京时间）之前下单按当日收盘价 / 净值成交，之后按下一个交易日成交。
+ * - 成交日之后第 `confirm_days` 个交易日确认（基金 T+1），确认前交易为待确认状态，不参与持仓计算。
+ * - 没有截止时间的资产类型（如数字货币）按当前价格立即成交。
+ *
+ * `settle_pending_plan_executions` 在收盘同步后运行：到达确认日且 `price_history` 中已有成交日价格的
+ * 待确认定投按该价格重新计算份额并确认；超过确认日 `max_pending_days` 个交易日（按资产所在交易所的
+ * 交易日历计算）仍没有价格的标记为失败。
+ *
+ * 成交价取 `price_history` 的收盘价，行情同步只写入不复权的价格；资产类型配置为复权数据源时
+ * 价格历史不会更新，这类待确认定投保持待确认并记录警告，不按复权价格确认。
+ */
+use crate::adapters;
+use crate::config::config::SettlementRule;
+use crate::config::Config;
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{PlanSettlementSummary, TransactionStatus};
+use crate::services::calendar::{exchange_for_asset_type, get_calendar, TradingCalendar};
+use crate::services::scheduler::beijing_offset;
+use crate::services::snapshot::{day_start, local_date};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use log::{error, info, warn};
+use rusqlite::{params, OptionalExtension};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 资产类型的成交确认规则，未配置时使用默认规则
+fn settlement_rule(asset_type: &str) -> SettlementRule {
+    Config::get()
+        .settlement
+        .rules
+        .get(&asset_type.to_uppercase())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// 计算定投的成交日和确认日（北京时间当天 0 点的时间戳），立即成交的资产类型返回 None
+pub fn schedule_settlement(
+    asset_type: &str,
+    calendar: &TradingCalendar,
+    now: i64,
+) -> Option<(i64, i64)> {
+    let rule = settlement_rule(asset_type);
+    let cutoff = rule.cutoff.as_deref().map(|cutoff| {
+        NaiveTime::parse_from_str(cutoff, "%H:%M").unwrap_or_else(|e| {
+            warn!("Invalid settlement cutoff {} for {}: {}", cutoff, asset_type, e);
+            NaiveTime::from_hms_opt(15, 0, 0).unwrap()
+        })
+    })?;
+
+    let today = local_date(now);
+    let local_time = DateTime::from_timestamp(now, 0)
+        .unwrap_or_default()
+        .with_timezone(&beijing_offset())
+        .time();
+
+    let trade_date = if calendar.is_trading_day(today) && local_time < cutoff {
+        today
+    } else {
+        calendar.add_trading_days(today, 1)
+    };
+    let confirm_date = calendar.add_trading_days(trade_date, rule.confirm_days.max(0));
+
+    Some((day_start(trade_date), day_start(confirm_date)))
+}
+
+/// 确认日之后（不含）到今天（含）的交易日数
+fn overdue_trading_days(asset_type: &str, confirm_day: NaiveDate, today: NaiveDate) -> i64 {
+    match confirm_day.succ_opt() {
+        Some(first) if first <= today => get_calendar(exchange_for_asset_type(asset_type))
+            .trading_days(first, today)
+            .len() as i64,
+        _ => 0,
+    }
+}
+
+/// 确认到期的待确认定投
+pub fn settle_pending_plan_executions() -> Result<PlanSettlementSummary, AuthError> {
+    let conn = get_connection_from_pool()?;
+    let today = local_date(Utc::now().timestamp());
+    let max_pending_days = Config::get().settlement.max_pending_days.max(0);
+    let sources = Config::get().market_sync.sources;
+
+    let pending = {
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.asset_id, at.name, t.total_cost, t.settle_date, t.confirm_date
+             FROM transactions t
+             JOIN assets a ON t.asset_id = a.id
+             JOIN asset_types at ON a.asset_type_id = at.id
+             WHERE t.status = ?1 AND t.plan_id IS NOT NULL
+               AND t.settle_date IS NOT NULL AND t.confirm_date IS NOT NULL
+             ORDER BY t.id",
+        )?;
+
+        let result = stmt
+            .query_map(params![TransactionStatus::Pending.to_str()], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, f64>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                error!("Failed to fetch pending plan executions: {}", e);
+                AuthError::DatabaseError(format!("获取待确认定投失败: {}", e))
+            });
+
+        result?
+    };
+
+    let mut summary = PlanSettlementSummary::default();
+
+    for (id, asset_id, asset_type, net_amount, settle_date, confirm_date) in pending {
+        let confirm_day: NaiveDate = local_date(confirm_date);
+        if today < confirm_day {
+            summary.pending += 1;
+            continue;
+        }
+
+        // 复权数据源的价格不是实际成交价，不能用于确认
+        if let Some(source) = sources
+            .get(&asset_type.to_uppercase())
+            .filter(|source| adapters::is_adjusted_source(source))
+        {
+            warn!(
+                "Plan execution {} left pending: {} uses adjusted source {}",
+                id, asset_type, source
+            );
+            summary.pending += 1;
+            continue;
+        }
+
+        // 成交日的收盘价 / 净值
+        let price: Option<f64> = conn
+            .query_row(
+                "SELECT close_price FROM price_history
+                 WHERE asset_id = ?1 AND date >= ?2 AND date < ?3
+                 ORDER BY date DESC LIMIT 1",
+                params![asset_id, settle_date, settle_date + SECONDS_PER_DAY],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match price.filter(|price| *price > 0.0) {
+            Some(price) => {
+                // 下单时间晚于成交日 0 点时保留下单时间，否则记为成交日
+                conn.execute(
+                    "UPDATE transactions
+                     SET status = ?1, amount = ?2, price = ?3, transaction_date = MAX(transaction_date, ?4)
+                     WHERE id = ?5",
+                    params![
+                        TransactionStatus::Confirmed.to_str(),
+                        net_amount / price,
+                        price,
+                        settle_date,
+                        id
+                    ],
+                )?;
+                summary.confirmed += 1;
+                info!("Plan execution {} confirmed at price {}", id, price);
+            }
+            None if overdue_trading_days(&asset_type, confirm_day, today) > max_pending_days => {
+                let reason = format!(
+                    "超过确认日 {} 个交易日仍未获取到 {} 的价格",
+                    max_pending_days,
+                    local_date(settle_date)
+                );
+                conn.execute(
+                    "UPDATE transactions SET status = ?1, failure_reason = ?2 WHERE id = ?3",
+                    params![TransactionStatus::Failed.to_str(), reason, id],
+                )?;
+                summary.failed += 1;
+                warn!("Plan execution {} failed: {}", id, reason);
+            }
+            None => summary.pending += 1,
+        }
+    }
+
+    info!(
+        "Plan settlement finished: {} confirmed, {} failed, {} pending",
+        summary.confirmed, summary.failed, summary.pending
+    );
+    Ok(summary)
+}
@@ -0,0 +1,299 @@
+/**
+ * 持仓计算模块
+ *
+ * 按交易日期顺序回放交易流水，计算资产的持仓批次、持仓成本和已实现收益。
+ * 各交易类型对持仓的影响见 `TransactionType`，计算规则见 `LotLedger::apply`。
+ *
+ * 卖出和转出按用户设置的成本计算方法（先进先出 / 后进先出 / 移动加权平均）结转成本，
+ * 默认使用移动加权平均。
+ *
+ * 主要函数说明：
+ * - `load_asset_ledger(conn, asset_id, method, until, exclude_id)`: 计算单个资产的批次台账。
+ * - `load_asset_position(conn, asset_id, until, exclude_id)`: 计算单个资产的持仓份额（与成本计算方法无关的场景）。
+ * - `load_user_ledgers` / `load_user_positions`: 计算用户所有资产的台账 / 持仓（资产报价币种）。
+ * - `load_user_ledgers_converted`: 计算用户所有资产的台账，金额换算为用户的基准币种。
+ * - `get_position_ledger(user_id, asset_id, method)`: 获取资产的批次、浮动盈亏和卖出明细。
+ * - `get_realized_trades(user_id, asset_id, start, end, method)`: 获取已实现盈亏明细。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    AssetPosition, CostBasisMethod, LotLedger, PositionLedger, RealizedTrade, TransactionType,
+};
+use crate::services::fx::FxConverter;
+use crate::services::settings::load_user_settings;
+use chrono::Utc;
+use log::{error, warn};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+/// 交易流水中参与持仓计算的字段：(交易ID, 资产ID, 交易日期, 交易类型, 数量, 总金额, 费用)
+type TransactionRow = (i64, i64, i64, String, f64, f64, f64);
+
+fn row_to_transaction(row: &rusqlite::Row) -> rusqlite::Result<TransactionRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+    ))
+}
+
+fn replay(rows: Vec<TransactionRow>, method: CostBasisMethod) -> HashMap<i64, LotLedger> {
+    let mut ledgers: HashMap<i64, LotLedger> = HashMap::new();
+
+    for (id, asset_id, date, transaction_type, amount, total_cost, fee) in rows {
+        let kind = match TransactionType::from_str(&transaction_type) {
+            Some(kind) => kind,
+            None => {
+                warn!("Unknown transaction type {} for asset {}", transaction_type, asset_id);
+                continue;
+            }
+        };
+        ledgers
+            .entry(asset_id)
+            .or_insert_with(|| LotLedger::new(asset_id, method))
+            .apply(id, date, kind, amount, total_cost, fee);
+    }
+
+    ledgers
+}
+
+/// 计算单个资产截至某日期（含）的批次台账，可排除指定交易（用于修改交易时的校验）
+pub fn load_asset_ledger(
+    conn: &Connection,
+    asset_id: i64,
+    method: CostBasisMethod,
+    until: Option<i64>,
+    exclude_transaction_id: Option<i64>,
+) -> Result<LotLedger, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, asset_id, transaction_date, transaction_type, amount, total_cost, fee
+         FROM transactions
+         WHERE asset_id = ?1
+           AND (?2 IS NULL OR transaction_date <= ?2)
+           AND (?3 IS NULL OR id != ?3)
+           AND status = 'CONFIRMED'
+         ORDER BY transaction_date, id",
+    )?;
+
+    let rows = stmt
+        .query_map(params![asset_id, until, exclude_transaction_id], row_to_transaction)?
+        .collect::<Result<Vec<TransactionRow>, _>>()?;
+
+    Ok(replay(rows, method)
+        .remove(&asset_id)
+        .unwrap_or_else(|| LotLedger::new(asset_id, method)))
+}
+
+/// 计算单个资产截至某日期（含）的持仓，持仓份额与成本计算方法无关
+pub fn load_asset_position(
+    conn: &Connection,
+    asset_id: i64,
+    until: Option<i64>,
+    exclude_transaction_id: Option<i64>,
+) -> Result<AssetPosition, rusqlite::Error> {
+    load_asset_ledger(
+        conn,
+        asset_id,
+        CostBasisMethod::WeightedAverage,
+        until,
+        exclude_transaction_id,
+    )
+    .map(|ledger| ledger.position)
+}
+
+/// 计算用户所有资产的当前台账，没有交易记录的资产不在结果中
+pub fn load_user_ledgers(
+    conn: &Connection,
+    user_id: i64,
+    method: CostBasisMethod,
+) -> Result<HashMap<i64, LotLedger>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.asset_id, t.transaction_date, t.transaction_type, t.amount, t.total_cost, t.fee
+         FROM transactions t
+         JOIN assets a ON t.asset_id = a.id
+         WHERE a.user_id = ?1 AND t.status = 'CONFIRMED'
+         ORDER BY t.transaction_date, t.id",
+    )?;
+
+    let rows = stmt
+        .query_map(params![user_id], row_to_transaction)?
+        .collect::<Result<Vec<TransactionRow>, _>>()?;
+
+    Ok(replay(rows, method))
+}
+
+/// 计算用户所有资产的当前台账，金额按交易当日汇率换算为基准币种（份额不变）
+pub fn load_user_ledgers_converted(
+    conn: &Connection,
+    user_id: i64,
+    method: CostBasisMethod,
+    fx: &mut FxConverter,
+) -> Result<HashMap<i64, LotLedger>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.asset_id, t.transaction_date, t.transaction_type, t.amount, t.total_cost, t.fee, a.currency
+         FROM transactions t
+         JOIN assets a ON t.asset_id = a.id
+         WHERE a.user_id = ?1 AND t.status = 'CONFIRMED'
+         ORDER BY t.transaction_date, t.id",
+    )?;
+
+    let rows = stmt
+        .query_map(params![user_id], |row| {
+            Ok((row_to_transaction(row)?, row.get::<_, String>(7)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut converted = Vec::with_capacity(rows.len());
+    for ((id, asset_id, date, transaction_type, amount, total_cost, fee), currency) in rows {
+        let rate = fx.rate(&currency, date)?;
+        converted.push((id, asset_id, date, transaction_type, amount, total_cost * rate, fee * rate));
+    }
+
+    Ok(replay(converted, method))
+}
+
+/// 计算用户所有资产的当前持仓，没有交易记录的资产不在结果中
+pub fn load_user_positions(
+    conn: &Connection,
+    user_id: i64,
+    method: CostBasisMethod,
+) -> Result<HashMap<i64, AssetPosition>, rusqlite::Error> {
+    Ok(load_user_ledgers(conn, user_id, method)?
+        .into_iter()
+        .map(|(asset_id, ledger)| (asset_id, ledger.position))
+        .collect())
+}
+
+/// 解析成本计算方法，为空时使用用户设置
+pub fn resolve_cost_basis_method(
+    conn: &Connection,
+    user_id: i64,
+    method: Option<&str>,
+) -> Result<CostBasisMethod, AuthError> {
+    match method {
+        Some(method) => CostBasisMethod::from_str(method).ok_or_else(|| {
+            AuthError::InvalidCredentials(format!("不支持的成本计算方法: {}", method))
+        }),
+        None => Ok(load_user_settings(conn, user_id)?.cost_basis()),
+    }
+}
+
+/// 获取资产的批次台账、浮动盈亏和卖出明细
+pub fn get_position_ledger(
+    user_id: i64,
+    asset_id: i64,
+    method: Option<&str>,
+) -> Result<PositionLedger, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let asset: Option<(String, String, Option<f64>, String)> = conn
+        .query_row(
+            "SELECT name, code, current_price, currency FROM assets WHERE id = ?1 AND user_id = ?2",
+            params![asset_id, user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .ok();
+
+    let (asset_name, asset_code, current_price, currency) = match asset {
+        Some(asset) => asset,
+        None => {
+            return Err(AuthError::InvalidCredentials(
+                "资产不存在或无权限".to_string(),
+            ))
+        }
+    };
+
+    let method = resolve_cost_basis_method(&conn, user_id, method)?;
+
+    let mut ledger = load_asset_ledger(&conn, asset_id, method, None, None).map_err(|e| {
+        error!("Failed to calculate ledger for asset {}: {}", asset_id, e);
+        AuthError::DatabaseError(format!("计算持仓台账失败: {}", e))
+    })?;
+    ledger.mark_holding_days(Utc::now().timestamp());
+
+    let position = &ledger.position;
+    let market_value = current_price.map(|price| price * position.shares);
+    let unrealized_profit = current_price.map(|price| position.unrealized_profit(price));
+    let unrealized_profit_percent = unrealized_profit.map(|profit| {
+        if position.cost > 0.0 {
+            profit / position.cost * 100.0
+        } else {
+            0.0
+        }
+    });
+
+    Ok(PositionLedger {
+        asset_id,
+        asset_name,
+        asset_code,
+        currency,
+        method: method.to_str().to_string(),
+        shares: position.shares,
+        cost: position.cost,
+        average_cost: position.average_cost(),
+        current_price,
+        market_value,
+        unrealized_profit,
+        unrealized_profit_percent,
+        realized_profit: position.realized_profit,
+        dividend_income: position.dividend_income,
+        fee_total: position.fee_total,
+        lots: ledger.lots,
+        realized_trades: ledger.realized_trades,
+    })
+}
+
+/// 获取已实现盈亏明细（按卖出日期倒序），可按资产和卖出日期筛选
+pub fn get_realized_trades(
+    user_id: i64,
+    asset_id: Option<i64>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    method: Option<&str>,
+) -> Result<Vec<RealizedTrade>, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    if let Some(asset_id) = asset_id {
+        let asset_exists: bool = conn
+            .query_row(
+                "SELECT 1 FROM assets WHERE id = ?1 AND user_id = ?2",
+                params![asset_id, user_id],
+                |_| Ok(true),
+            )
+            .unwrap_or(false);
+
+        if !asset_exists {
+            return Err(AuthError::InvalidCredentials(
+                "资产不存在或无权限".to_string(),
+            ));
+        }
+    }
+
+    let method = resolve_cost_basis_method(&conn, user_id, method)?;
+
+    let ledgers = load_user_ledgers(&conn, user_id, method).map_err(|e| {
+        error!("Failed to calculate ledgers for user {}: {}", user_id, e);
+        AuthError::DatabaseError(format!("计算持仓台账失败: {}", e))
+    })?;
+
+    let mut trades: Vec<RealizedTrade> = ledgers
+        .into_values()
+        .filter(|ledger| asset_id.map_or(true, |id| ledger.position.asset_id == id))
+        .flat_map(|ledger| ledger.realized_trades)
+        .filter(|trade| start_date.map_or(true, |start| trade.sell_date >= start))
+        .filter(|trade| end_date.map_or(true, |end| trade.sell_date <= end))
+        .collect();
+
+    trades.sort_by(|a, b| {
+        b.sell_date
+            .cmp(&a.sell_date)
+            .then(b.transaction_id.cmp(&a.transaction_id))
+    });
+
+    Ok(trades)
+}
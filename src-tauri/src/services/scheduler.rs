@@ -0,0 +1,164 @@
+/**
+ * 后台定时任务调度模块
+ *
+ * 在独立线程中运行一个 tokio 运行时，每分钟检查一次各任务是否到期：
+ * - 盘中行情同步：按 `intraday_interval_minutes` 刷新 `intraday_asset_types` 中的资产（如加密货币）。
+ * - 收盘行情同步：每天北京时间 `daily_sync_hour` 之后同步一次其余资产（基金在净值公布后同步）。
+ * - 止盈止损、提醒规则和策略信号：每次行情同步后评估同步成功的资产（盘中同步即盘中评估）。
+ * - 分红/折算检查：收盘同步后检查最近 `history_days` 天的公司行为，并为开启自动处理的资产生成交易。
+ * - 汇率同步：收盘同步后更新最近 `history_days` 天的汇率（`fx.enabled` 关闭时跳过）。
+ * - 定投确认：收盘同步后按成交日净值确认到期的待确认定投。
+ * - 净值快照：每天 `daily_sync_hour` 之后补齐所有用户的组合净值快照（放在收盘同步之后，使用当日收盘价）。
+ * - 摘要报告：每天 `digest.send_hour` 之后为订阅的用户生成日报 / 周报（放在净值快照之后），推送到通知渠道或保存到本地。
+ * - 通知推送：每分钟把新的交易提醒推送到用户的通知渠道，并重试发送失败的通知（`notification.enabled` 关闭时跳过）。
+ *
+ * 任务失败只记录日志，不会中断调度循环。
+ */
+use crate::config::Config;
+use crate::services::{
+    corporate_action, digest, fx, market_sync, notification, plan_settlement, snapshot,
+};
+use chrono::{FixedOffset, NaiveDate, Timelike, Utc};
+use log::{error, info};
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+/// 调度检查间隔(秒)
+const TICK_SECONDS: u64 = 60;
+
+/// 北京时间，用于判断每日任务的执行时点
+pub fn beijing_offset() -> FixedOffset {
+    FixedOffset::east_opt(8 * 3600).unwrap()
+}
+
+/// 记录各任务最近一次执行时间
+#[derive(Default)]
+struct SchedulerState {
+    last_run: HashMap<&'static str, i64>,
+    last_daily_run: HashMap<&'static str, NaiveDate>,
+}
+
+impl SchedulerState {
+    /// 固定间隔任务是否到期，到期时记录本次执行时间
+    fn every(&mut self, job: &'static str, now: i64, interval_seconds: i64) -> bool {
+        let due = self
+            .last_run
+            .get(job)
+            .map_or(true, |last| now - last >= interval_seconds);
+        if due {
+            self.last_run.insert(job, now);
+        }
+        due
+    }
+
+    /// 每日任务是否到期（北京时间超过指定小时且当天未执行）
+    fn daily(&mut self, job: &'static str, hour: u32) -> bool {
+        let local = Utc::now().with_timezone(&beijing_offset());
+        let today = local.date_naive();
+        let due = local.hour() >= hour && self.last_daily_run.get(job) != Some(&today);
+        if due {
+            self.last_daily_run.insert(job, today);
+        }
+        due
+    }
+}
+
+/// 启动后台调度线程
+pub fn start_scheduler() {
+    let spawned = thread::Builder::new()
+        .name("scheduler".to_string())
+        .spawn(|| {
+            let runtime = match tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    error!("Failed to create scheduler runtime: {}", e);
+                    return;
+                }
+            };
+            runtime.block_on(run());
+        });
+
+    match spawned {
+        Ok(_) => info!("Scheduler started"),
+        Err(e) => error!("Failed to start scheduler thread: {}", e),
+    }
+}
+
+async fn run() {
+    let mut state = SchedulerState::default();
+    let mut interval = tokio::time::interval(Duration::from_secs(TICK_SECONDS));
+
+    loop {
+        interval.tick().await;
+        run_due_jobs(&mut state).await;
+    }
+}
+
+async fn run_due_jobs(state: &mut SchedulerState) {
+    let now = Utc::now().timestamp();
+    let sync_config = Config::get().market_sync;
+
+    if sync_config.enabled {
+        let intraday_types = sync_config.intraday_asset_types.clone();
+
+        if !intraday_types.is_empty()
+            && state.every(
+                "market_sync_intraday",
+                now,
+                sync_config.intraday_interval_minutes.max(1) as i64 * 60,
+            )
+        {
+            if let Err(e) = market_sync::sync_assets(None, Some(intraday_types.clone()), true).await
+            {
+                error!("Intraday market sync failed: {}", e);
+            }
+        }
+
+        if state.daily("market_sync_daily", sync_config.daily_sync_hour) {
+            // 收盘同步覆盖全部资产，盘中类型也在此补齐当日日线
+            if let Err(e) = market_sync::sync_assets(None, None, false).await {
+                error!("Daily market sync failed: {}", e);
+            }
+
+            if let Err(e) =
+                corporate_action::sync_corporate_actions(None, sync_config.history_days).await
+            {
+                error!("Corporate action sync failed: {}", e);
+            }
+
+            if Config::get().fx.enabled {
+                if let Err(e) = fx::sync_fx_rates(sync_config.history_days).await {
+                    error!("Fx sync failed: {}", e);
+                }
+            }
+
+            if let Err(e) = plan_settlement::settle_pending_plan_executions() {
+                error!("Plan settlement failed: {}", e);
+            }
+        }
+    }
+
+    if state.daily("portfolio_snapshot", sync_config.daily_sync_hour) {
+        if let Err(e) = snapshot::record_snapshots(None) {
+            error!("Portfolio snapshot failed: {}", e);
+        }
+    }
+
+    let digest_config = Config::get().digest;
+    if digest_config.enabled && state.daily("digest_reports", digest_config.send_hour) {
+        if let Err(e) = digest::send_scheduled_digests() {
+            error!("Digest reports failed: {}", e);
+        }
+    }
+
+    if Config::get().notification.enabled && state.every("notification_dispatch", now, 60) {
+        if let Err(e) = notification::process_notifications().await {
+            error!("Notification dispatch failed: {}", e);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+/**
+ * 用户设置模块
+ *
+ * 主要函数说明：
+ * - `load_user_settings(conn, user_id)`: 读取用户设置，未保存过时返回默认设置。
+ * - `get_user_settings(user_id)`: 获取用户设置。
+ * - `update_user_settings(user_id, cost_basis_method, base_currency)`: 更新用户设置，只修改传入的字段。
+ *   成本计算方法或基准币种变化后重建净值快照。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{CostBasisMethod, UserSettings};
+use crate::services::fx::validate_currency;
+use crate::services::snapshot::rebuild_user_snapshots;
+use chrono::Utc;
+use log::{error, info};
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// 读取用户设置，未保存过时返回默认设置
+pub fn load_user_settings(conn: &Connection, user_id: i64) -> Result<UserSettings, rusqlite::Error> {
+    let settings = conn
+        .query_row(
+            "SELECT user_id, cost_basis_method, base_currency, updated_at FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| {
+                Ok(UserSettings {
+                    user_id: row.get(0)?,
+                    cost_basis_method: row.get(1)?,
+                    base_currency: row.get(2)?,
+                    updated_at: row.get(3)?,
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(settings.unwrap_or_else(|| UserSettings::default_for(user_id)))
+}
+
+/// 获取用户设置
+pub fn get_user_settings(user_id: i64) -> Result<UserSettings, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    load_user_settings(&conn, user_id).map_err(|e| {
+        error!("Failed to get user settings: {}", e);
+        AuthError::DatabaseError(format!("获取用户设置失败: {}", e))
+    })
+}
+
+/// 更新用户设置
+pub fn update_user_settings(
+    user_id: i64,
+    cost_basis_method: Option<&str>,
+    base_currency: Option<&str>,
+) -> Result<UserSettings, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let user_exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM users WHERE id = ?1",
+            params![user_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if !user_exists {
+        return Err(AuthError::InvalidCredentials("用户不存在".to_string()));
+    }
+
+    let previous = load_user_settings(&conn, user_id)?;
+    let mut settings = previous.clone();
+
+    if let Some(method) = cost_basis_method {
+        let method = CostBasisMethod::from_str(method).ok_or_else(|| {
+            AuthError::InvalidCredentials(format!("不支持的成本计算方法: {}", method))
+        })?;
+        settings.cost_basis_method = method.to_str().to_string();
+    }
+    if let Some(currency) = base_currency {
+        settings.base_currency = validate_currency(currency)?;
+    }
+    settings.updated_at = Utc::now().timestamp();
+
+    conn.execute(
+        "INSERT INTO user_settings (user_id, cost_basis_method, base_currency, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(user_id) DO UPDATE SET
+            cost_basis_method = excluded.cost_basis_method,
+            base_currency = excluded.base_currency,
+            updated_at = excluded.updated_at",
+        params![
+            settings.user_id,
+            settings.cost_basis_method,
+            settings.base_currency,
+            settings.updated_at
+        ],
+    )
+    .map_err(|e| {
+        error!("Failed to update user settings: {}", e);
+        AuthError::DatabaseError(format!("更新用户设置失败: {}", e))
+    })?;
+
+    // 快照中的成本和市值依赖成本计算方法和基准币种
+    if settings.cost_basis_method != previous.cost_basis_method
+        || settings.base_currency != previous.base_currency
+    {
+        if let Err(e) = rebuild_user_snapshots(&conn, user_id, None) {
+            error!("Failed to rebuild snapshots for user {}: {}", user_id, e);
+        }
+    }
+
+    info!("User settings updated for user: {}", user_id);
+    Ok(settings)
+}
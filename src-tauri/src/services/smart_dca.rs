@@ -0,0 +1,278 @@
+/**
+ * 智能定投模块
+ *
+ * 执行定投时按计划模式（见 `PlanMode`）计算本期金额，金额和计算依据写入交易备注。
+ * 指标使用 `price_history` 的日线收盘价，执行当日以现价代替当日收盘价。
+ *
+ * 主要函数说明：
+ * - `compute_plan_amount(mode, config, base_amount, closes, state)`: 按收盘价序列计算本期金额（定投执行和回测共用）。
+ * - `window_closes(mode, config, series, as_of, price)`: 按模式截取计算指标使用的日线收盘价。
+ * - `evaluate_plan_amount(conn, plan_id, asset_id, mode, config, base_amount, price, as_of)`: 读取价格历史和计划持仓后计算。
+ * - `validate_smart_config(mode, config)`: 保存计划时校验规则。
+ */
+use crate::error::auth::AuthError;
+use crate::models::{AmountTier, PlanAmount, PlanMode, SmartDcaConfig};
+use crate::services::indicator::{drawdown_from_high, percentile_rank, sma};
+use crate::services::performance::load_price_series;
+use crate::services::snapshot::local_date;
+use rusqlite::{params, Connection};
+
+const DEFAULT_MA_PERIOD: usize = 250;
+const DEFAULT_LOOKBACK_DAYS: i64 = 1825;
+const DEFAULT_MAX_MULTIPLIER: f64 = 3.0;
+/// 回撤和百分位至少需要的收盘价个数
+const MIN_HISTORY: usize = 20;
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// 计划的累计执行情况（价值平均使用）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlanState {
+    pub executions: i64,  // 已执行期数
+    pub plan_shares: f64, // 计划累计买入份额
+}
+
+/// 各模式的默认金额档位
+fn default_tiers(mode: PlanMode) -> Vec<AmountTier> {
+    let tiers: &[(f64, f64)] = match mode {
+        // 低于均线越多买得越多，高于均线减少
+        PlanMode::MaDeviation => &[(-100.0, 2.0), (-20.0, 1.5), (-10.0, 1.2), (0.0, 1.0), (10.0, 0.8), (20.0, 0.5)],
+        // 距最高点回撤越深买得越多
+        PlanMode::Drawdown => &[(0.0, 1.0), (10.0, 1.5), (20.0, 2.0), (30.0, 2.5)],
+        // 百分位越低买得越多，高于 90% 暂停
+        PlanMode::PePercentile => &[(0.0, 2.0), (20.0, 1.5), (40.0, 1.0), (60.0, 0.7), (80.0, 0.5), (90.0, 0.0)],
+        _ => &[],
+    };
+    tiers
+        .iter()
+        .map(|(min_value, multiplier)| AmountTier {
+            min_value: *min_value,
+            multiplier: *multiplier,
+        })
+        .collect()
+}
+
+/// 按指标值匹配档位倍数（取满足条件的最高档，低于所有档位时为 1）
+fn tier_multiplier(tiers: &[AmountTier], value: f64) -> f64 {
+    tiers
+        .iter()
+        .filter(|tier| value >= tier.min_value)
+        .max_by(|a, b| a.min_value.total_cmp(&b.min_value))
+        .map(|tier| tier.multiplier)
+        .unwrap_or(1.0)
+}
+
+/// 校验智能定投规则
+pub fn validate_smart_config(mode: PlanMode, config: &SmartDcaConfig) -> Result<(), AuthError> {
+    if config.tiers.iter().any(|tier| tier.multiplier.is_nan() || tier.multiplier < 0.0 || tier.min_value.is_nan()) {
+        return Err(AuthError::InvalidCredentials("档位倍数不能为负数".to_string()));
+    }
+    if config.ma_period == Some(0) {
+        return Err(AuthError::InvalidCredentials("均线周期必须大于0".to_string()));
+    }
+    if config.lookback_days.map_or(false, |days| days <= 0) {
+        return Err(AuthError::InvalidCredentials("回看天数必须大于0".to_string()));
+    }
+    if config.growth_percent.is_some_and(|g| g.is_nan() || g <= -100.0) {
+        return Err(AuthError::InvalidCredentials("目标增长率必须大于 -100%".to_string()));
+    }
+    let min_amount = config.min_amount.unwrap_or(0.0);
+    if min_amount.is_nan() || min_amount < 0.0 {
+        return Err(AuthError::InvalidCredentials("最低金额不能为负数".to_string()));
+    }
+    if let Some(max_amount) = config.max_amount {
+        if max_amount.is_nan() || max_amount < min_amount {
+            return Err(AuthError::InvalidCredentials("最高金额不能低于最低金额".to_string()));
+        }
+    }
+    if mode == PlanMode::Fixed && !config.tiers.is_empty() {
+        return Err(AuthError::InvalidCredentials("固定金额定投不需要设置档位".to_string()));
+    }
+    Ok(())
+}
+
+/// 按收盘价序列计算本期定投金额，`closes` 的最后一个值为当前价格
+pub fn compute_plan_amount(
+    mode: PlanMode,
+    config: &SmartDcaConfig,
+    base_amount: f64,
+    closes: &[f64],
+    state: PlanState,
+) -> PlanAmount {
+    let fixed = |reason: String| PlanAmount {
+        mode: mode.to_str().to_string(),
+        base_amount,
+        amount: base_amount,
+        indicator: None,
+        multiplier: None,
+        reason,
+    };
+
+    let price = match closes.last() {
+        Some(price) if *price > 0.0 => *price,
+        _ => return fixed("没有价格数据，按基准金额定投".to_string()),
+    };
+
+    let tiers = if config.tiers.is_empty() {
+        default_tiers(mode)
+    } else {
+        config.tiers.clone()
+    };
+
+    // (指标值, 倍数, 未限额金额, 依据)
+    let (indicator, multiplier, raw_amount, basis) = match mode {
+        PlanMode::Fixed => return fixed(format!("固定金额 {:.2}", base_amount)),
+        PlanMode::MaDeviation => {
+            let period = config.ma_period.unwrap_or(DEFAULT_MA_PERIOD);
+            let ma = match sma(closes, period) {
+                Some(ma) if ma > 0.0 => ma,
+                _ => {
+                    return fixed(format!(
+                        "历史数据不足（{}/{}），按基准金额定投",
+                        closes.len(),
+                        period
+                    ))
+                }
+            };
+            let deviation = (price / ma - 1.0) * 100.0;
+            let multiplier = tier_multiplier(&tiers, deviation);
+            (
+                deviation,
+                Some(multiplier),
+                base_amount * multiplier,
+                format!("{}日均线 {:.4}，偏离 {:.2}%，倍数 {:.2}", period, ma, deviation, multiplier),
+            )
+        }
+        PlanMode::Drawdown | PlanMode::PePercentile => {
+            if closes.len() < MIN_HISTORY {
+                return fixed(format!(
+                    "历史数据不足（{}/{}），按基准金额定投",
+                    closes.len(),
+                    MIN_HISTORY
+                ));
+            }
+            let (value, label) = if mode == PlanMode::Drawdown {
+                (drawdown_from_high(closes).unwrap_or(0.0), "距最高点回撤")
+            } else {
+                (percentile_rank(closes).unwrap_or(50.0), "历史百分位")
+            };
+            let multiplier = tier_multiplier(&tiers, value);
+            (
+                value,
+                Some(multiplier),
+                base_amount * multiplier,
+                format!("{} {:.2}%，倍数 {:.2}", label, value, multiplier),
+            )
+        }
+        PlanMode::ValueAveraging => {
+            let growth = config.growth_percent.unwrap_or(0.0) / 100.0;
+            let period = state.executions + 1;
+            let target: f64 = (0..period).map(|k| base_amount * (1.0 + growth).powi(k as i32)).sum();
+            let current = state.plan_shares * price;
+            (
+                target,
+                None,
+                target - current,
+                format!("第{}期目标市值 {:.2}，当前市值 {:.2}", period, target, current),
+            )
+        }
+    };
+
+    let min_amount = config.min_amount.unwrap_or(0.0);
+    let max_amount = config.max_amount.unwrap_or(base_amount * DEFAULT_MAX_MULTIPLIER);
+    let amount = raw_amount.max(min_amount).min(max_amount).max(0.0);
+
+    let reason = if amount > 0.0 {
+        format!("智能定投（{}）：{}，本期金额 {:.2}", mode.label(), basis, amount)
+    } else {
+        format!("智能定投（{}）：{}，本期暂停", mode.label(), basis)
+    };
+
+    PlanAmount {
+        mode: mode.to_str().to_string(),
+        base_amount,
+        amount,
+        indicator: Some(indicator),
+        multiplier,
+        reason,
+    }
+}
+
+/// 取每日最后一个收盘价（执行日之前），并以现价作为最新值
+pub fn daily_closes(series: &[(i64, f64)], as_of: i64, price: f64) -> Vec<f64> {
+    let today = local_date(as_of);
+    let mut closes: Vec<(chrono::NaiveDate, f64)> = Vec::new();
+    for (date, close) in series {
+        let day = local_date(*date);
+        if day >= today {
+            break;
+        }
+        match closes.last_mut() {
+            Some((last_day, last_close)) if *last_day == day => *last_close = *close,
+            _ => closes.push((day, *close)),
+        }
+    }
+
+    let mut closes: Vec<f64> = closes.into_iter().map(|(_, close)| close).collect();
+    closes.push(price);
+    closes
+}
+
+/// 截取计算指标使用的日线收盘价：回撤和百分位只使用回看期内的价格，均线使用全部历史
+pub fn window_closes(
+    mode: PlanMode,
+    config: &SmartDcaConfig,
+    series: &[(i64, f64)],
+    as_of: i64,
+    price: f64,
+) -> Vec<f64> {
+    let lookback = config.lookback_days.unwrap_or(DEFAULT_LOOKBACK_DAYS);
+    let start = as_of - lookback * SECONDS_PER_DAY;
+    let first = if mode == PlanMode::MaDeviation {
+        0
+    } else {
+        series.partition_point(|(date, _)| *date < start)
+    };
+    daily_closes(&series[first..], as_of, price)
+}
+
+/// 读取计划的累计执行情况（包含等待净值确认的定投，份额为按下单时价格预估的值）
+pub fn load_plan_state(conn: &Connection, plan_id: i64) -> Result<PlanState, rusqlite::Error> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(amount), 0) FROM transactions
+         WHERE plan_id = ?1 AND transaction_type = 'BUY' AND status IN ('CONFIRMED', 'PENDING')",
+        params![plan_id],
+        |row| {
+            Ok(PlanState {
+                executions: row.get(0)?,
+                plan_shares: row.get(1)?,
+            })
+        },
+    )
+}
+
+/// 按计划模式计算本期定投金额
+pub fn evaluate_plan_amount(
+    conn: &Connection,
+    plan_id: i64,
+    asset_id: i64,
+    mode: PlanMode,
+    config: &SmartDcaConfig,
+    base_amount: f64,
+    price: f64,
+    as_of: i64,
+) -> Result<PlanAmount, rusqlite::Error> {
+    if mode == PlanMode::Fixed {
+        return Ok(compute_plan_amount(mode, config, base_amount, &[price], PlanState::default()));
+    }
+
+    let series = load_price_series(conn, asset_id)?;
+    let closes = window_closes(mode, config, &series, as_of, price);
+
+    let state = if mode == PlanMode::ValueAveraging {
+        load_plan_state(conn, plan_id)?
+    } else {
+        PlanState::default()
+    };
+
+    Ok(compute_plan_amount(mode, config, base_amount, &closes, state))
+}
@@ -0,0 +1,496 @@
+/**
+ * 组合净值快照模块
+ *
+ * 按交易流水和价格历史逐日回放持仓，记录每天收盘后（北京时间）各资产、分组、资产类型和整个组合的
+ * 市值、成本、累计净投入和单位净值，用于净值曲线、回撤和月度 / 年度收益率统计。
+ *
+ * 主要函数说明：
+ * - `rebuild_user_snapshots(conn, user_id, from)`: 从指定日期重建用户的快照（之前的日期只参与计算，不重写）。
+ * - `record_snapshots(user_id)`: 后台任务调用，从最近一次快照的日期开始补齐到今天，没有快照时完整回填。
+ * - `backfill_snapshots(user_id, start_date)`: 手动回填（修改历史交易或补录价格后使用）。
+ * - `get_snapshots` / `get_drawdowns` / `get_period_returns`: 净值曲线、回撤和区间收益率查询。
+ *
+ * 计算规则：
+ * - 估值价格取当天收盘前最近的价格（价格历史、成交价、当前价格），没有任何价格时按持仓成本估值。
+ * - 单位净值：当日收益率 = (当日市值 - 当日净投入) / 前一日市值 - 1，首次建仓或清仓后重新建仓时沿用原单位净值。
+ * - 分组和资产类型按资产当前的归属统计。
+ * - 金额均为用户的基准币种：交易金额按交易当日汇率、价格按价格日期的汇率换算。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{
+    DrawdownPeriod, DrawdownReport, LotLedger, PeriodReturn, PortfolioSnapshot, ReturnScope,
+    TransactionType,
+};
+use crate::services::fx::FxConverter;
+use crate::services::performance::{load_price_series, price_on, transaction_contribution};
+use crate::services::scheduler::beijing_offset;
+use crate::services::settings::load_user_settings;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: i64 = 86400;
+const VALUE_EPSILON: f64 = 1e-6;
+
+/// 快照统计范围标识：(范围, 对象ID)，整个组合的对象ID为 0
+type ScopeKey = (&'static str, i64);
+
+/// 单个统计范围的当日数据
+#[derive(Default)]
+struct DailyFigures {
+    market_value: f64,
+    cost: f64,
+    realized_profit: f64,
+    contribution: f64,
+}
+
+/// 北京时间某天 0 点的时间戳
+pub fn day_start(date: NaiveDate) -> i64 {
+    beijing_offset()
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .unwrap()
+        .timestamp()
+}
+
+/// 时间戳对应的北京时间日期
+pub fn local_date(timestamp: i64) -> NaiveDate {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&beijing_offset())
+        .date_naive()
+}
+
+fn parse_scope(scope: &str) -> Result<ReturnScope, AuthError> {
+    match ReturnScope::from_str(scope) {
+        Some(ReturnScope::Plan) => Err(AuthError::InvalidCredentials(
+            "定投计划不支持净值快照".to_string(),
+        )),
+        Some(scope) => Ok(scope),
+        None => Err(AuthError::InvalidCredentials(format!("不支持的统计范围: {}", scope))),
+    }
+}
+
+/// 从指定日期（含）重建用户到今天的快照，返回写入的快照条数（缺少汇率且未开启 1:1 兜底时不重写）
+pub fn rebuild_user_snapshots(
+    conn: &Connection,
+    user_id: i64,
+    from: Option<i64>,
+) -> Result<usize, AuthError> {
+    let method = load_user_settings(conn, user_id)?.cost_basis();
+    let mut fx = FxConverter::for_user(conn, user_id)?;
+
+    // 资产归属：资产ID -> (分组ID, 资产类型ID)
+    let mut stmt = conn.prepare("SELECT id, group_id, asset_type_id, currency FROM assets WHERE user_id = ?1")?;
+    let mut assets: HashMap<i64, (Option<i64>, i64)> = HashMap::new();
+    let mut currencies: HashMap<i64, String> = HashMap::new();
+    for row in stmt.query_map(params![user_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, Option<i64>>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })? {
+        let (asset_id, group_id, asset_type_id, currency) = row?;
+        assets.insert(asset_id, (group_id, asset_type_id));
+        currencies.insert(asset_id, currency);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.asset_id, t.transaction_date, t.transaction_type, t.amount, t.price, t.total_cost, t.fee
+         FROM transactions t
+         JOIN assets a ON t.asset_id = a.id
+         WHERE a.user_id = ?1 AND t.status = 'CONFIRMED'
+         ORDER BY t.transaction_date, t.id",
+    )?;
+    let rows = stmt
+        .query_map(params![user_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, f64>(5)?,
+                row.get::<_, f64>(6)?,
+                row.get::<_, f64>(7)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut transactions = Vec::new();
+    let mut prices: HashMap<i64, Vec<(i64, f64)>> = HashMap::new();
+    for (id, asset_id, date, transaction_type, amount, price, total_cost, fee) in rows {
+        let kind = match TransactionType::from_str(&transaction_type) {
+            Some(kind) => kind,
+            None => {
+                warn!("Unknown transaction type {} for asset {}", transaction_type, asset_id);
+                continue;
+            }
+        };
+        let currency = currencies.get(&asset_id).map(String::as_str).unwrap_or_default();
+        let rate = fx.rate(currency, date)?;
+        if kind.has_market_price() && price > 0.0 {
+            prices.entry(asset_id).or_default().push((date, price * rate));
+        }
+        transactions.push((id, asset_id, date, kind, amount, total_cost * rate, fee * rate));
+    }
+
+    let first_day = match transactions.first() {
+        Some(tx) => local_date(tx.2),
+        None => return Ok(0),
+    };
+    let today = Utc::now().with_timezone(&beijing_offset()).date_naive();
+    let from_day = from.map(local_date).unwrap_or(first_day).max(first_day);
+
+    // 没有成交价的资产（如只有转入）同样需要价格历史
+    for tx in &transactions {
+        prices.entry(tx.1).or_default();
+    }
+    for (asset_id, series) in prices.iter_mut() {
+        let currency = currencies.get(asset_id).map(String::as_str).unwrap_or_default();
+        for (date, price) in load_price_series(conn, *asset_id)? {
+            series.push((date, fx.convert(price, currency, date)?));
+        }
+        series.sort_by_key(|(date, _)| *date);
+    }
+    fx.ensure_rates()?;
+
+    let db_tx = conn.unchecked_transaction()?;
+    db_tx.execute(
+        "DELETE FROM portfolio_snapshots WHERE user_id = ?1 AND snapshot_date >= ?2",
+        params![user_id, day_start(from_day)],
+    )?;
+
+    let mut ledgers: HashMap<i64, LotLedger> = HashMap::new();
+    let mut cumulative: HashMap<ScopeKey, f64> = HashMap::new();
+    // 前一日的 (市值, 单位净值)
+    let mut previous: HashMap<ScopeKey, (f64, f64)> = HashMap::new();
+    let mut cursor = 0;
+    let mut written = 0;
+    let created_at = Utc::now().timestamp();
+
+    let mut day = first_day;
+    while day <= today {
+        let start = day_start(day);
+        let end = start + SECONDS_PER_DAY - 1;
+
+        let mut daily_contribution: HashMap<i64, f64> = HashMap::new();
+        while cursor < transactions.len() && transactions[cursor].2 <= end {
+            let (id, asset_id, date, kind, amount, total_cost, fee) = transactions[cursor];
+            ledgers
+                .entry(asset_id)
+                .or_insert_with(|| LotLedger::new(asset_id, method))
+                .apply(id, date, kind, amount, total_cost, fee);
+            *daily_contribution.entry(asset_id).or_insert(0.0) +=
+                transaction_contribution(kind, total_cost, fee);
+            cursor += 1;
+        }
+
+        let mut figures: HashMap<ScopeKey, DailyFigures> = HashMap::new();
+        for (asset_id, ledger) in &ledgers {
+            let position = &ledger.position;
+            let contribution = daily_contribution.get(asset_id).copied();
+            // 已清仓且当日没有交易的资产不再记录
+            if position.shares <= 0.0 && contribution.is_none() {
+                continue;
+            }
+
+            let market_value = if position.shares > 0.0 {
+                prices
+                    .get(asset_id)
+                    .and_then(|series| price_on(series, end))
+                    .map(|price| price * position.shares)
+                    .unwrap_or(position.cost)
+            } else {
+                0.0
+            };
+
+            let (group_id, asset_type_id) = assets.get(asset_id).copied().unwrap_or((None, 0));
+            let mut keys = vec![
+                (ReturnScope::Asset.to_str(), *asset_id),
+                (ReturnScope::AssetType.to_str(), asset_type_id),
+                (ReturnScope::Portfolio.to_str(), 0),
+            ];
+            if let Some(group_id) = group_id {
+                keys.push((ReturnScope::Group.to_str(), group_id));
+            }
+
+            for key in keys {
+                let entry = figures.entry(key).or_default();
+                entry.market_value += market_value;
+                entry.cost += position.cost;
+                entry.realized_profit += position.realized_profit;
+                entry.contribution += contribution.unwrap_or(0.0);
+            }
+        }
+
+        // 当日没有持仓的范围，前一日市值记为 0
+        for (key, state) in previous.iter_mut() {
+            if !figures.contains_key(key) {
+                state.0 = 0.0;
+            }
+        }
+
+        for (key, figure) in figures {
+            let net_contribution = {
+                let total = cumulative.entry(key).or_insert(0.0);
+                *total += figure.contribution;
+                *total
+            };
+            let (previous_value, previous_unit) = previous.get(&key).copied().unwrap_or((0.0, 1.0));
+            let unit_value = if previous_value > VALUE_EPSILON {
+                previous_unit * (figure.market_value - figure.contribution) / previous_value
+            } else {
+                previous_unit
+            };
+            previous.insert(key, (figure.market_value, unit_value));
+
+            if day >= from_day {
+                db_tx.execute(
+                    "INSERT INTO portfolio_snapshots (
+                        user_id, snapshot_date, scope, scope_id, market_value, cost, realized_profit,
+                        net_contribution, daily_contribution, unit_value, created_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        user_id,
+                        start,
+                        key.0,
+                        key.1,
+                        figure.market_value,
+                        figure.cost,
+                        figure.realized_profit,
+                        net_contribution,
+                        figure.contribution,
+                        unit_value,
+                        created_at
+                    ],
+                )?;
+                written += 1;
+            }
+        }
+
+        day = match day.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    db_tx.commit()?;
+    Ok(written)
+}
+
+/// 补齐快照到今天：从最近一次快照的日期开始重算（当天快照可能使用了盘中价格），没有快照时完整回填
+pub fn record_snapshots(user_id: Option<i64>) -> Result<usize, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let users: Vec<(i64, Option<i64>)> = {
+        let mut stmt = conn.prepare(
+            "SELECT u.id, (SELECT MAX(snapshot_date) FROM portfolio_snapshots s WHERE s.user_id = u.id)
+             FROM users u
+             WHERE (?1 IS NULL OR u.id = ?1)
+               AND EXISTS (SELECT 1 FROM assets a JOIN transactions t ON t.asset_id = a.id WHERE a.user_id = u.id)",
+        )?;
+        let result = stmt
+            .query_map(params![user_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        result
+    };
+
+    let mut written = 0;
+    for (user_id, last_snapshot) in users {
+        match rebuild_user_snapshots(&conn, user_id, last_snapshot) {
+            Ok(count) => written += count,
+            Err(e) => error!("Failed to record snapshots for user {}: {}", user_id, e),
+        }
+    }
+
+    info!("Recorded {} portfolio snapshots", written);
+    Ok(written)
+}
+
+/// 手动回填快照
+pub fn backfill_snapshots(user_id: i64, start_date: Option<i64>) -> Result<usize, AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    rebuild_user_snapshots(&conn, user_id, start_date).inspect_err(|e| {
+        error!("Failed to backfill snapshots for user {}: {}", user_id, e);
+    })
+}
+
+/// 获取净值曲线（按日期升序）
+pub fn get_snapshots(
+    user_id: i64,
+    scope: &str,
+    scope_id: Option<i64>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<PortfolioSnapshot>, AuthError> {
+    let scope = parse_scope(scope)?;
+    let scope_id = match scope {
+        ReturnScope::Portfolio => 0,
+        _ => scope_id.ok_or_else(|| {
+            AuthError::InvalidCredentials("统计对象不能为空".to_string())
+        })?,
+    };
+
+    let conn = get_connection_from_pool()?;
+    let mut stmt = conn.prepare(
+        "SELECT snapshot_date, scope, scope_id, market_value, cost, realized_profit,
+                net_contribution, daily_contribution, unit_value
+         FROM portfolio_snapshots
+         WHERE user_id = ?1 AND scope = ?2 AND scope_id = ?3
+           AND (?4 IS NULL OR snapshot_date >= ?4)
+           AND (?5 IS NULL OR snapshot_date <= ?5)
+         ORDER BY snapshot_date",
+    )?;
+
+    let snapshots = stmt
+        .query_map(
+            params![user_id, scope.to_str(), scope_id, start_date, end_date],
+            |row| {
+                let scope_id: i64 = row.get(2)?;
+                Ok(PortfolioSnapshot {
+                    snapshot_date: row.get(0)?,
+                    scope: row.get(1)?,
+                    scope_id: (scope_id != 0).then_some(scope_id),
+                    market_value: row.get(3)?,
+                    cost: row.get(4)?,
+                    realized_profit: row.get(5)?,
+                    net_contribution: row.get(6)?,
+                    daily_contribution: row.get(7)?,
+                    unit_value: row.get(8)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            error!("Failed to fetch portfolio snapshots: {}", e);
+            AuthError::DatabaseError(format!("获取净值快照失败: {}", e))
+        })?;
+
+    Ok(snapshots)
+}
+
+/// 按单位净值计算回撤
+pub fn get_drawdowns(
+    user_id: i64,
+    scope: &str,
+    scope_id: Option<i64>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<DrawdownReport, AuthError> {
+    let snapshots = get_snapshots(user_id, scope, scope_id, start_date, end_date)?;
+
+    let mut periods: Vec<DrawdownPeriod> = Vec::new();
+    let mut current: Option<DrawdownPeriod> = None;
+    let mut peak: Option<(i64, f64)> = None;
+
+    for snapshot in &snapshots {
+        let (peak_date, peak_unit) = match peak {
+            Some(peak) if snapshot.unit_value < peak.1 => peak,
+            _ => {
+                // 创新高，结束当前回撤
+                if let Some(mut period) = current.take() {
+                    period.recovery_date = Some(snapshot.snapshot_date);
+                    period.duration_days = (snapshot.snapshot_date - period.peak_date) / SECONDS_PER_DAY;
+                    periods.push(period);
+                }
+                peak = Some((snapshot.snapshot_date, snapshot.unit_value));
+                continue;
+            }
+        };
+
+        let drawdown = (1.0 - snapshot.unit_value / peak_unit) * 100.0;
+        let period = current.get_or_insert(DrawdownPeriod {
+            peak_date,
+            peak_unit_value: peak_unit,
+            trough_date: snapshot.snapshot_date,
+            trough_unit_value: snapshot.unit_value,
+            recovery_date: None,
+            drawdown_percent: drawdown,
+            duration_days: 0,
+        });
+        if snapshot.unit_value < period.trough_unit_value {
+            period.trough_date = snapshot.snapshot_date;
+            period.trough_unit_value = snapshot.unit_value;
+            period.drawdown_percent = drawdown;
+        }
+    }
+
+    let mut current_drawdown_percent = 0.0;
+    if let (Some(mut period), Some(last)) = (current, snapshots.last()) {
+        current_drawdown_percent = (1.0 - last.unit_value / period.peak_unit_value) * 100.0;
+        period.duration_days = (last.snapshot_date - period.peak_date) / SECONDS_PER_DAY;
+        periods.push(period);
+    }
+
+    periods.sort_by(|a, b| b.drawdown_percent.total_cmp(&a.drawdown_percent));
+
+    Ok(DrawdownReport {
+        scope: scope.to_string(),
+        scope_id,
+        max_drawdown_percent: periods.first().map_or(0.0, |p| p.drawdown_percent),
+        current_drawdown_percent,
+        periods,
+    })
+}
+
+/// 月度（MONTH）或年度（YEAR）收益率
+pub fn get_period_returns(
+    user_id: i64,
+    scope: &str,
+    scope_id: Option<i64>,
+    period: &str,
+) -> Result<Vec<PeriodReturn>, AuthError> {
+    let format = match period {
+        "MONTH" => "%Y-%m",
+        "YEAR" => "%Y",
+        _ => {
+            return Err(AuthError::InvalidCredentials(format!(
+                "不支持的统计周期: {}",
+                period
+            )))
+        }
+    };
+
+    let snapshots = get_snapshots(user_id, scope, scope_id, None, None)?;
+
+    let mut returns: Vec<PeriodReturn> = Vec::new();
+    // 上一周期末的 (市值, 单位净值)
+    let mut base = (0.0, 1.0);
+    let mut index = 0;
+
+    while index < snapshots.len() {
+        let key = local_date(snapshots[index].snapshot_date).format(format).to_string();
+        let group_end = snapshots[index..]
+            .iter()
+            .position(|s| local_date(s.snapshot_date).format(format).to_string() != key)
+            .map_or(snapshots.len(), |offset| index + offset);
+        let group = &snapshots[index..group_end];
+        let last = &group[group.len() - 1];
+
+        let net_contribution: f64 = group.iter().map(|s| s.daily_contribution).sum();
+        returns.push(PeriodReturn {
+            period: key,
+            start_date: group[0].snapshot_date,
+            end_date: last.snapshot_date,
+            start_value: base.0,
+            end_value: last.market_value,
+            net_contribution,
+            profit: last.market_value - base.0 - net_contribution,
+            return_percent: if base.1 > 0.0 {
+                (last.unit_value / base.1 - 1.0) * 100.0
+            } else {
+                0.0
+            },
+        });
+
+        base = (last.market_value, last.unit_value);
+        index = group_end;
+    }
+
+    Ok(returns)
+}
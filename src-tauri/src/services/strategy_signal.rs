@@ -0,0 +1,364 @@
+/**
+ * 策略信号模块
+ *
+ * 对启用的策略应用（`strategy_applications`）按资产最新的日线收盘价运行策略，生成买卖信号并写入交易提醒，
+ * 只作为参考信号，不会自动下单：
+ * - 行情同步（盘中和收盘）和手动更新价格后评估相关资产的策略应用，当天以现价为最新收盘价。
+ * - 信号在指标穿越时产生（比较最新一期和前一期），同一应用同一天的同向信号只提醒一次。
+ * - 提醒的 `alert_type` 为 BUY / SELL，`signal_strategy_id` 为策略，内容包含触发信号的指标值。
+ *
+ * 支持的策略类型和参数（`strategies.parameters`，未指定时使用默认值）：
+ * | 类型 | 参数 | 信号 |
+ * |------|------|------|
+ * | MovingAverageCrossover | fastPeriod=5, slowPeriod=20 | 快线上穿慢线买入，下穿卖出 |
+ * | BollingerBands | period=20, stdDev=2 | 价格跌破下轨买入，突破上轨卖出 |
+ * | RSI | period=14, overbought=70, oversold=30 | RSI 跌破超卖线买入，突破超买线卖出 |
+ * | MACD | fastPeriod=12, slowPeriod=26, signalPeriod=9 | MACD 线上穿信号线买入，下穿卖出 |
+ *
+ * 自定义策略（Custom）不在此评估。
+ */
+use crate::database::get_connection_from_pool;
+use crate::error::auth::AuthError;
+use crate::models::{SignalSide, StrategySignal, StrategyType};
+use crate::services::indicator::{bollinger, macd, rsi, sma};
+use crate::services::performance::load_price_series;
+use crate::services::smart_dca::daily_closes;
+use crate::services::snapshot::local_date;
+use chrono::Utc;
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// 待评估的策略应用
+struct ActiveApplication {
+    id: i64,
+    user_id: i64,
+    strategy_id: i64,
+    strategy_name: String,
+    strategy_type: String,
+    parameters: String,
+    asset_id: i64,
+    asset_name: String,
+    last_signal: Option<String>,
+    last_signal_at: Option<i64>,
+}
+
+/// 策略在最新一期产生的信号
+struct Signal {
+    side: SignalSide,
+    indicators: BTreeMap<String, f64>,
+    description: String,
+}
+
+/// 读取正整数参数，未指定时使用默认值
+fn period_param(parameters: &Value, key: &str, default: usize) -> usize {
+    parameters[key].as_u64().filter(|value| *value > 0).map(|value| value as usize).unwrap_or(default)
+}
+
+fn number_param(parameters: &Value, key: &str, default: f64) -> f64 {
+    parameters[key].as_f64().unwrap_or(default)
+}
+
+/// 最新一期之前的收盘价
+fn previous_closes(closes: &[f64]) -> &[f64] {
+    &closes[..closes.len().saturating_sub(1)]
+}
+
+/// 运行策略，数据不足或没有信号时返回 None
+fn run_strategy(strategy_type: &StrategyType, parameters: &Value, closes: &[f64]) -> Option<Signal> {
+    let previous = previous_closes(closes);
+    let price = *closes.last()?;
+    let previous_price = *previous.last()?;
+
+    match strategy_type {
+        StrategyType::MovingAverageCrossover => {
+            let fast = period_param(parameters, "fastPeriod", 5);
+            let slow = period_param(parameters, "slowPeriod", 20);
+            let (fast_ma, slow_ma) = (sma(closes, fast)?, sma(closes, slow)?);
+            let previous_spread = sma(previous, fast)? - sma(previous, slow)?;
+            let spread = fast_ma - slow_ma;
+
+            let side = if previous_spread <= 0.0 && spread > 0.0 {
+                SignalSide::Buy
+            } else if previous_spread >= 0.0 && spread < 0.0 {
+                SignalSide::Sell
+            } else {
+                return None;
+            };
+            Some(Signal {
+                side,
+                indicators: BTreeMap::from([("fast_ma".to_string(), fast_ma), ("slow_ma".to_string(), slow_ma)]),
+                description: format!(
+                    "MA{} {:.4} {} MA{} {:.4}",
+                    fast,
+                    fast_ma,
+                    if side == SignalSide::Buy { "上穿" } else { "下穿" },
+                    slow,
+                    slow_ma
+                ),
+            })
+        }
+        StrategyType::BollingerBands => {
+            let period = period_param(parameters, "period", 20);
+            let k = number_param(parameters, "stdDev", 2.0);
+            let (middle, upper, lower) = bollinger(closes, period, k)?;
+            let (_, previous_upper, previous_lower) = bollinger(previous, period, k)?;
+
+            let side = if previous_price >= previous_lower && price < lower {
+                SignalSide::Buy
+            } else if previous_price <= previous_upper && price > upper {
+                SignalSide::Sell
+            } else {
+                return None;
+            };
+            Some(Signal {
+                side,
+                indicators: BTreeMap::from([
+                    ("middle".to_string(), middle),
+                    ("upper".to_string(), upper),
+                    ("lower".to_string(), lower),
+                ]),
+                description: match side {
+                    SignalSide::Buy => format!("价格 {:.4} 跌破布林带下轨 {:.4}（中轨 {:.4}）", price, lower, middle),
+                    SignalSide::Sell => format!("价格 {:.4} 突破布林带上轨 {:.4}（中轨 {:.4}）", price, upper, middle),
+                },
+            })
+        }
+        StrategyType::RSI => {
+            let period = period_param(parameters, "period", 14);
+            let overbought = number_param(parameters, "overbought", 70.0);
+            let oversold = number_param(parameters, "oversold", 30.0);
+            let (value, previous_value) = (rsi(closes, period)?, rsi(previous, period)?);
+
+            let side = if previous_value > oversold && value <= oversold {
+                SignalSide::Buy
+            } else if previous_value < overbought && value >= overbought {
+                SignalSide::Sell
+            } else {
+                return None;
+            };
+            Some(Signal {
+                side,
+                indicators: BTreeMap::from([("rsi".to_string(), value)]),
+                description: match side {
+                    SignalSide::Buy => format!("RSI({}) {:.2} 跌破超卖线 {:.2}", period, value, oversold),
+                    SignalSide::Sell => format!("RSI({}) {:.2} 突破超买线 {:.2}", period, value, overbought),
+                },
+            })
+        }
+        StrategyType::MACD => {
+            let fast = period_param(parameters, "fastPeriod", 12);
+            let slow = period_param(parameters, "slowPeriod", 26);
+            let signal = period_param(parameters, "signalPeriod", 9);
+            let (line, signal_line) = macd(closes, fast, slow, signal)?;
+            let (previous_line, previous_signal) = macd(previous, fast, slow, signal)?;
+
+            let side = if previous_line <= previous_signal && line > signal_line {
+                SignalSide::Buy
+            } else if previous_line >= previous_signal && line < signal_line {
+                SignalSide::Sell
+            } else {
+                return None;
+            };
+            Some(Signal {
+                side,
+                indicators: BTreeMap::from([
+                    ("macd".to_string(), line),
+                    ("signal".to_string(), signal_line),
+                    ("histogram".to_string(), line - signal_line),
+                ]),
+                description: format!(
+                    "MACD {:.4} {}信号线 {:.4}",
+                    line,
+                    if side == SignalSide::Buy { "上穿" } else { "下穿" },
+                    signal_line
+                ),
+            })
+        }
+        StrategyType::Custom => None,
+    }
+}
+
+/// 写入交易提醒并记录应用的最近信号
+fn record_signal(
+    conn: &mut Connection,
+    application: &ActiveApplication,
+    signal: Signal,
+    price: f64,
+    now: i64,
+) -> Result<StrategySignal, AuthError> {
+    let message = format!(
+        "策略「{}」{}：{}，价格 {:.4}，建议{}（仅供参考）",
+        application.strategy_name,
+        application.asset_name,
+        signal.description,
+        price,
+        if signal.side == SignalSide::Buy { "买入" } else { "卖出" }
+    );
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO trade_alerts (user_id, asset_id, signal_strategy_id, alert_type, message, is_read, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6)",
+        params![
+            application.user_id,
+            application.asset_id,
+            application.strategy_id,
+            signal.side.to_str(),
+            message,
+            now
+        ],
+    )?;
+    let alert_id = tx.last_insert_rowid();
+    tx.execute(
+        "UPDATE strategy_applications SET last_signal = ?1, last_signal_at = ?2, last_evaluated = ?2 WHERE id = ?3",
+        params![signal.side.to_str(), now, application.id],
+    )?;
+    tx.commit()?;
+
+    Ok(StrategySignal {
+        application_id: application.id,
+        user_id: application.user_id,
+        strategy_id: application.strategy_id,
+        strategy_name: application.strategy_name.clone(),
+        strategy_type: application.strategy_type.clone(),
+        asset_id: application.asset_id,
+        asset_name: application.asset_name.clone(),
+        side: signal.side.to_str().to_string(),
+        price,
+        indicators: signal.indicators,
+        message,
+        alert_id,
+    })
+}
+
+/**
+ * @dev 评估启用的策略应用，生成买卖信号提醒
+ * @param user_id 为 None 时评估所有用户的策略应用
+ * @param asset_ids 为 None 时评估所有资产（价格更新后只评估更新的资产）
+ * @return 本次生成的信号
+ */
+pub fn evaluate_strategy_applications(
+    user_id: Option<i64>,
+    asset_ids: Option<&[i64]>,
+) -> Result<Vec<StrategySignal>, AuthError> {
+    let mut conn = get_connection_from_pool()?;
+    let now = Utc::now().timestamp();
+
+    let applications = {
+        let mut stmt = conn.prepare(
+            "SELECT sa.id, sa.user_id, sa.strategy_id, s.name, s.strategy_type, s.parameters,
+                    sa.asset_id, a.name, sa.last_signal, sa.last_signal_at
+             FROM strategy_applications sa
+             JOIN strategies s ON sa.strategy_id = s.id
+             JOIN assets a ON sa.asset_id = a.id
+             WHERE sa.is_active = 1 AND s.is_active = 1 AND (?1 IS NULL OR sa.user_id = ?1)
+             ORDER BY sa.asset_id, sa.id",
+        )?;
+
+        let result = stmt
+            .query_map(params![user_id], |row| {
+                Ok(ActiveApplication {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    strategy_id: row.get(2)?,
+                    strategy_name: row.get(3)?,
+                    strategy_type: row.get(4)?,
+                    parameters: row.get(5)?,
+                    asset_id: row.get(6)?,
+                    asset_name: row.get(7)?,
+                    last_signal: row.get(8)?,
+                    last_signal_at: row.get(9)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                error!("Failed to fetch strategy applications: {}", e);
+                AuthError::DatabaseError(format!("获取策略应用失败: {}", e))
+            });
+
+        result?
+    };
+
+    // 同一资产的策略应用共用日线收盘价
+    let mut prices: HashMap<i64, Option<(Vec<f64>, f64)>> = HashMap::new();
+    let mut signals = Vec::new();
+
+    for application in applications {
+        if asset_ids.is_some_and(|ids| !ids.contains(&application.asset_id)) {
+            continue;
+        }
+
+        let strategy_type = StrategyType::from_str(&application.strategy_type);
+        if strategy_type == StrategyType::Custom {
+            continue;
+        }
+        let parameters: Value = match serde_json::from_str(&application.parameters) {
+            Ok(parameters) => parameters,
+            Err(e) => {
+                warn!("Invalid parameters for strategy {}: {}", application.strategy_id, e);
+                continue;
+            }
+        };
+
+        if !prices.contains_key(&application.asset_id) {
+            let series = load_price_series(&conn, application.asset_id)?;
+            let loaded = series
+                .last()
+                .map(|(_, price)| *price)
+                .filter(|price| *price > 0.0)
+                .map(|price| (daily_closes(&series, now, price), price));
+            prices.insert(application.asset_id, loaded);
+        }
+        let (closes, price) = match prices.get(&application.asset_id) {
+            Some(Some(loaded)) => loaded,
+            _ => continue,
+        };
+
+        let signal = match run_strategy(&strategy_type, &parameters, closes) {
+            Some(signal) => signal,
+            None => {
+                conn.execute(
+                    "UPDATE strategy_applications SET last_evaluated = ?1 WHERE id = ?2",
+                    params![now, application.id],
+                )?;
+                continue;
+            }
+        };
+
+        // 同一天的同向信号只提醒一次（盘中多次同步时指标可能反复穿越）
+        let repeated = application.last_signal.as_deref() == Some(signal.side.to_str())
+            && application.last_signal_at.is_some_and(|at| local_date(at) == local_date(now));
+        if repeated {
+            continue;
+        }
+
+        match record_signal(&mut conn, &application, signal, *price, now) {
+            Ok(signal) => {
+                info!("Strategy signal generated: {}", signal.message);
+                signals.push(signal);
+            }
+            Err(e) => error!("Failed to record signal for application {}: {}", application.id, e),
+        }
+    }
+
+    Ok(signals)
+}
+
+/**
+ * @dev 价格更新后评估相关资产的策略应用，失败只记录日志，不影响价格更新
+ */
+pub fn evaluate_after_price_update(asset_ids: &[i64]) {
+    if asset_ids.is_empty() {
+        return;
+    }
+
+    match evaluate_strategy_applications(None, Some(asset_ids)) {
+        Ok(signals) if !signals.is_empty() => {
+            info!("{} strategy signals generated after price update", signals.len())
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to evaluate strategy applications after price update: {}", e),
+    }
+}
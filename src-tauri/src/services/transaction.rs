@@ -3,7 +3,9 @@
  */
 use crate::database::get_connection_from_pool;
 use crate::error::auth::AuthError;
-use crate::models::Transaction;
+use crate::models::{CostBasisMethod, LotLedger, Transaction, TransactionStatus, TransactionType};
+use crate::services::fee::calculate_fee;
+use crate::services::snapshot::local_date;
 use chrono::Utc;
 use log::{error, info};
 use rusqlite::params;
@@ -14,16 +16,15 @@ pub fn create_transaction(
     transaction_type: &str,
     amount: f64,
     price: f64,
+    fee: Option<f64>,
     transaction_date: i64,
     notes: Option<&str>,
 ) -> Result<Transaction, AuthError> {
     let conn = get_connection_from_pool()?;
     let now = Utc::now().timestamp();
     
-    // 验证交易类型
-    if transaction_type != "BUY" && transaction_type != "SELL" {
-        return Err(AuthError::InvalidCredentials("交易类型无效，必须为 BUY 或 SELL".to_string()));
-    }
+    // 验证交易类型和数量
+    let kind = parse_transaction_type(transaction_type, amount, price)?;
     
     // 检查资产是否存在且属于该用户
     let asset_exists: bool = conn.query_row(
@@ -36,31 +37,17 @@ pub fn create_transaction(
         return Err(AuthError::InvalidCredentials("资产不存在或无权限".to_string()));
     }
     
-    // 如果是卖出交易，检查持仓是否足够
-    if transaction_type == "SELL" {
-        let current_holdings: f64 = conn.query_row(
-            "SELECT COALESCE(SUM(CASE WHEN transaction_type = 'BUY' THEN amount ELSE -amount END), 0)
-             FROM transactions
-             WHERE asset_id = ?1 AND transaction_date <= ?2",
-            params![asset_id, transaction_date],
-            |row| row.get(0),
-        ).unwrap_or(0.0);
-        
-        if current_holdings < amount {
-            return Err(AuthError::InvalidCredentials(format!(
-                "持仓不足，当前持仓: {}, 卖出数量: {}", 
-                current_holdings, amount
-            )));
-        }
-    }
+    // 检查加入本笔交易后每笔卖出或转出时的持仓是否足够
+    check_holdings(&conn, asset_id, Some((kind, amount, transaction_date)), None)?;
     
-    // 计算总成本
-    let total_cost = amount * price;
+    // 计算总成本和费用
+    let total_cost = kind.total_cost(amount, price);
+    let fee = resolve_fee(&conn, asset_id, kind, amount, price, fee, transaction_date, None)?;
     
     // 创建交易记录
     conn.execute(
-        "INSERT INTO transactions (user_id, asset_id, transaction_type, amount, price, total_cost, transaction_date, notes, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        "INSERT INTO transactions (user_id, asset_id, transaction_type, amount, price, total_cost, fee, transaction_date, notes, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         params![
             user_id,
             asset_id,
@@ -68,6 +55,7 @@ pub fn create_transaction(
             amount,
             price,
             total_cost,
+            fee,
             transaction_date,
             notes,
             now
@@ -93,30 +81,20 @@ pub fn create_transaction(
         amount,
         price,
         total_cost,
+        fee,
         transaction_date,
         notes: notes.map(|s| s.to_string()),
+        status: TransactionStatus::Confirmed.to_str().to_string(),
+        plan_id: None,
+        settle_date: None,
+        confirm_date: None,
+        failure_reason: None,
         created_at: now,
     };
     
-    // 更新资产当前价格
-    conn.execute(
-        "UPDATE assets SET current_price = ?1, last_updated = ?2, updated_at = ?3 WHERE id = ?4",
-        params![price, now, now, asset_id],
-    )?;
-    
-    // 添加历史价格记录
-    let date_exists: bool = conn.query_row(
-        "SELECT 1 FROM price_history WHERE asset_id = ?1 AND date = ?2",
-        params![asset_id, transaction_date],
-        |_| Ok(true),
-    ).unwrap_or(false);
-    
-    if !date_exists {
-        conn.execute(
-            "INSERT INTO price_history (asset_id, date, close_price, created_at)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![asset_id, transaction_date, price, now],
-        )?;
+    // 分红、折算、转入转出和费用的价格不是市场价格，不更新现价和历史价格
+    if kind.has_market_price() {
+        record_market_price(&conn, asset_id, price, transaction_date)?;
     }
     
     info!("Transaction created: {} {} of {} for user: {}", 
@@ -130,61 +108,40 @@ pub fn update_transaction(
     transaction_type: &str,
     amount: f64,
     price: f64,
+    fee: Option<f64>,
     transaction_date: i64,
     notes: Option<&str>,
 ) -> Result<Transaction, AuthError> {
     let conn = get_connection_from_pool()?;
     
-    // 验证交易类型
-    if transaction_type != "BUY" && transaction_type != "SELL" {
-        return Err(AuthError::InvalidCredentials("交易类型无效，必须为 BUY 或 SELL".to_string()));
-    }
+    // 验证交易类型和数量
+    let kind = parse_transaction_type(transaction_type, amount, price)?;
     
     // 检查交易记录是否存在且属于该用户
-    let (asset_id, old_transaction_type, old_amount): (i64, String, f64) = conn.query_row(
-        "SELECT asset_id, transaction_type, amount FROM transactions WHERE id = ?1 AND user_id = ?2",
+    let asset_id: i64 = conn.query_row(
+        "SELECT asset_id FROM transactions WHERE id = ?1 AND user_id = ?2",
         params![id, user_id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        |row| row.get(0),
     ).map_err(|_| AuthError::InvalidCredentials("交易记录不存在或无权限".to_string()))?;
     
-    // 如果是卖出交易，检查持仓是否足够
-    if transaction_type == "SELL" {
-        // 计算当前持仓（不包括当前交易）
-        let mut current_holdings: f64 = conn.query_row(
-            "SELECT COALESCE(SUM(CASE WHEN transaction_type = 'BUY' THEN amount ELSE -amount END), 0)
-             FROM transactions
-             WHERE asset_id = ?1 AND id != ?2 AND transaction_date <= ?3",
-            params![asset_id, id, transaction_date],
-            |row| row.get(0),
-        ).unwrap_or(0.0);
-        
-        // 如果原交易是买入，则不需要加回原交易数量
-        // 如果原交易是卖出，则需要加回原交易数量（因为我们已经排除了当前交易）
-        if old_transaction_type == "SELL" {
-            current_holdings += old_amount;
-        }
-        
-        if current_holdings < amount {
-            return Err(AuthError::InvalidCredentials(format!(
-                "持仓不足，当前持仓: {}, 卖出数量: {}", 
-                current_holdings, amount
-            )));
-        }
-    }
+    // 检查修改后每笔卖出或转出时的持仓是否足够
+    check_holdings(&conn, asset_id, Some((kind, amount, transaction_date)), Some(id))?;
     
-    // 计算总成本
-    let total_cost = amount * price;
+    // 计算总成本和费用
+    let total_cost = kind.total_cost(amount, price);
+    let fee = resolve_fee(&conn, asset_id, kind, amount, price, fee, transaction_date, Some(id))?;
     
     // 更新交易记录
     conn.execute(
         "UPDATE transactions 
-         SET transaction_type = ?1, amount = ?2, price = ?3, total_cost = ?4, transaction_date = ?5, notes = ?6
-         WHERE id = ?7",
+         SET transaction_type = ?1, amount = ?2, price = ?3, total_cost = ?4, fee = ?5, transaction_date = ?6, notes = ?7
+         WHERE id = ?8",
         params![
             transaction_type,
             amount,
             price,
             total_cost,
+            fee,
             transaction_date,
             notes,
             id
@@ -198,10 +155,11 @@ pub fn update_transaction(
         |row| Ok((row.get(0)?, row.get(1)?)),
     )?;
     
-    let created_at: i64 = conn.query_row(
-        "SELECT created_at FROM transactions WHERE id = ?1",
+    let (status, plan_id, settle_date, confirm_date, failure_reason, created_at) = conn.query_row(
+        "SELECT status, plan_id, settle_date, confirm_date, failure_reason, created_at
+         FROM transactions WHERE id = ?1",
         params![id],
-        |row| row.get(0),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
     )?;
     
     let transaction = Transaction {
@@ -214,13 +172,19 @@ pub fn update_transaction(
         amount,
         price,
         total_cost,
+        fee,
         transaction_date,
         notes: notes.map(|s| s.to_string()),
+        status,
+        plan_id,
+        settle_date,
+        confirm_date,
+        failure_reason,
         created_at,
     };
     
     // 添加历史价格记录
-    let date_exists: bool = conn.query_row(
+    let date_exists: bool = !kind.has_market_price() || conn.query_row(
         "SELECT 1 FROM price_history WHERE asset_id = ?1 AND date = ?2",
         params![asset_id, transaction_date],
         |_| Ok(true),
@@ -242,15 +206,14 @@ pub fn delete_transaction(id: i64, user_id: i64) -> Result<(), AuthError> {
     let conn = get_connection_from_pool()?;
     
     // 检查交易记录是否存在且属于该用户
-    let transaction_exists: bool = conn.query_row(
-        "SELECT 1 FROM transactions WHERE id = ?1 AND user_id = ?2",
+    let asset_id: i64 = conn.query_row(
+        "SELECT asset_id FROM transactions WHERE id = ?1 AND user_id = ?2",
         params![id, user_id],
-        |_| Ok(true),
-    ).unwrap_or(false);
+        |row| row.get(0),
+    ).map_err(|_| AuthError::InvalidCredentials("交易记录不存在或无权限".to_string()))?;
     
-    if !transaction_exists {
-        return Err(AuthError::InvalidCredentials("交易记录不存在或无权限".to_string()));
-    }
+    // 删除后之后的卖出或转出不能超过当时的持仓
+    check_holdings(&conn, asset_id, None, Some(id))?;
     
     // 删除交易记录
     conn.execute(
@@ -267,6 +230,7 @@ pub fn get_user_transactions(
     asset_id: Option<i64>,
     start_date: Option<i64>,
     end_date: Option<i64>,
+    status: Option<&str>,
 ) -> Result<Vec<Transaction>, AuthError> {
     let conn = get_connection_from_pool()?;
     
@@ -288,13 +252,21 @@ pub fn get_user_transactions(
         conditions.push("t.transaction_date <= ?".to_string());
         params.push(Box::new(e_date));
     }
+
+    if let Some(status) = status {
+        let status = TransactionStatus::from_str(status)
+            .ok_or_else(|| AuthError::InvalidCredentials(format!("无效的交易状态: {}", status)))?;
+        conditions.push("t.status = ?".to_string());
+        params.push(Box::new(status.to_str()));
+    }
     
     let condition_str = conditions.join(" AND ");
     
     // 构建查询语句
     let query = format!(
         "SELECT t.id, t.user_id, t.asset_id, a.name, a.code, t.transaction_type, 
-                t.amount, t.price, t.total_cost, t.transaction_date, t.notes, t.created_at
+                t.amount, t.price, t.total_cost, t.fee, t.transaction_date, t.notes, t.status, t.created_at,
+                t.plan_id, t.settle_date, t.confirm_date, t.failure_reason
          FROM transactions t
          JOIN assets a ON t.asset_id = a.id
          WHERE {}
@@ -313,9 +285,15 @@ pub fn get_user_transactions(
             amount: row.get(6)?,
             price: row.get(7)?,
             total_cost: row.get(8)?,
-            transaction_date: row.get(9)?,
-            notes: row.get(10)?,
-            created_at: row.get(11)?,
+            fee: row.get(9)?,
+            transaction_date: row.get(10)?,
+            notes: row.get(11)?,
+            status: row.get(12)?,
+            plan_id: row.get(14)?,
+            settle_date: row.get(15)?,
+            confirm_date: row.get(16)?,
+            failure_reason: row.get(17)?,
+            created_at: row.get(13)?,
         })
     })?
     .collect::<Result<Vec<_>, _>>()
@@ -325,4 +303,228 @@ pub fn get_user_transactions(
     })?;
     
     Ok(transactions)
-}
\ No newline at end of file
+}
+
+/// 写入一笔待确认交易，返回交易ID。待确认交易不参与持仓计算，也不校验持仓，确认时再校验
+pub fn insert_pending_transaction(
+    conn: &rusqlite::Connection,
+    user_id: i64,
+    asset_id: i64,
+    kind: TransactionType,
+    amount: f64,
+    price: f64,
+    fee: f64,
+    transaction_date: i64,
+    notes: Option<&str>,
+) -> Result<i64, rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO transactions (user_id, asset_id, transaction_type, amount, price, total_cost, fee, transaction_date, notes, status, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            user_id,
+            asset_id,
+            kind.to_str(),
+            amount,
+            price,
+            kind.total_cost(amount, price),
+            fee,
+            transaction_date,
+            notes,
+            TransactionStatus::Pending.to_str(),
+            Utc::now().timestamp()
+        ],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// 确认待确认交易：校验持仓后生效，并按成交价更新现价和历史价格
+pub fn confirm_transaction(id: i64, user_id: i64) -> Result<(), AuthError> {
+    let conn = get_connection_from_pool()?;
+
+    let pending: Option<(i64, String, f64, f64, i64, String)> = conn
+        .query_row(
+            "SELECT asset_id, transaction_type, amount, price, transaction_date, status
+             FROM transactions WHERE id = ?1 AND user_id = ?2",
+            params![id, user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        )
+        .ok();
+
+    let (asset_id, transaction_type, amount, price, transaction_date, status) = match pending {
+        Some(pending) => pending,
+        None => return Err(AuthError::InvalidCredentials("交易记录不存在或无权限".to_string())),
+    };
+
+    if TransactionStatus::from_str(&status) != Some(TransactionStatus::Pending) {
+        return Err(AuthError::InvalidCredentials("只能确认待确认的交易".to_string()));
+    }
+
+    let kind = parse_transaction_type(&transaction_type, amount, price)?;
+    check_holdings(&conn, asset_id, Some((kind, amount, transaction_date)), Some(id))?;
+
+    conn.execute(
+        "UPDATE transactions SET status = ?1 WHERE id = ?2",
+        params![TransactionStatus::Confirmed.to_str(), id],
+    )?;
+
+    if kind.has_market_price() {
+        record_market_price(&conn, asset_id, price, transaction_date)?;
+    }
+
+    info!("Transaction confirmed: {} for user: {}", id, user_id);
+    Ok(())
+}
+
+/// 按成交价更新资产现价，并在当日没有历史价格时补录
+fn record_market_price(
+    conn: &rusqlite::Connection,
+    asset_id: i64,
+    price: f64,
+    transaction_date: i64,
+) -> Result<(), rusqlite::Error> {
+    let now = Utc::now().timestamp();
+
+    // 更新资产当前价格
+    conn.execute(
+        "UPDATE assets SET current_price = ?1, last_updated = ?2, updated_at = ?3 WHERE id = ?4",
+        params![price, now, now, asset_id],
+    )?;
+
+    // 添加历史价格记录
+    let date_exists: bool = conn.query_row(
+        "SELECT 1 FROM price_history WHERE asset_id = ?1 AND date = ?2",
+        params![asset_id, transaction_date],
+        |_| Ok(true),
+    ).unwrap_or(false);
+
+    if !date_exists {
+        conn.execute(
+            "INSERT INTO price_history (asset_id, date, close_price, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![asset_id, transaction_date, price, now],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 解析交易类型并校验数量和价格
+fn parse_transaction_type(
+    transaction_type: &str,
+    amount: f64,
+    price: f64,
+) -> Result<TransactionType, AuthError> {
+    let kind = TransactionType::from_str(transaction_type).ok_or_else(|| {
+        AuthError::InvalidCredentials(
+            "交易类型无效，必须为 BUY、SELL、DIVIDEND、REINVEST、SPLIT、TRANSFER_IN、TRANSFER_OUT 或 FEE"
+                .to_string(),
+        )
+    })?;
+
+    if amount.is_nan() || amount <= 0.0 {
+        let message = if kind == TransactionType::Split {
+            "折算比例必须大于0"
+        } else {
+            "交易数量必须大于0"
+        };
+        return Err(AuthError::InvalidCredentials(message.to_string()));
+    }
+
+    if price < 0.0 || price.is_nan() {
+        return Err(AuthError::InvalidCredentials("交易价格不能为负数".to_string()));
+    }
+
+    Ok(kind)
+}
+
+/// 校验新增、修改或删除交易后，资产在每笔卖出或转出时的持仓是否足够
+///
+/// 按交易日期回放资产的全部已确认交易（排除 `exclude_transaction_id`，加入 `change`），
+/// 倒填的卖出、转出或折算，以及修改、删除较早的买入，都可能使之后的卖出超过当时的持仓。
+/// `change` 为新增或修改后的交易（交易类型、数量、交易日期），删除交易时为 None。
+fn check_holdings(
+    conn: &rusqlite::Connection,
+    asset_id: i64,
+    change: Option<(TransactionType, f64, i64)>,
+    exclude_transaction_id: Option<i64>,
+) -> Result<(), AuthError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, transaction_date, transaction_type, amount, total_cost, fee
+         FROM transactions
+         WHERE asset_id = ?1 AND (?2 IS NULL OR id != ?2) AND status = 'CONFIRMED'",
+    )?;
+    let mut rows: Vec<(i64, i64, TransactionType, f64, f64, f64)> = stmt
+        .query_map(params![asset_id, exclude_transaction_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get::<_, String>(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AuthError::DatabaseError(format!("计算持仓失败: {}", e)))?
+        .into_iter()
+        .filter_map(|(id, date, kind, amount, total_cost, fee)| {
+            TransactionType::from_str(&kind).map(|kind| (id, date, kind, amount, total_cost, fee))
+        })
+        .collect();
+
+    // 新增的交易排在同一天已有交易之后，修改的交易保持原有顺序
+    if let Some((kind, amount, transaction_date)) = change {
+        let id = exclude_transaction_id.unwrap_or(i64::MAX);
+        rows.push((id, transaction_date, kind, amount, 0.0, 0.0));
+    }
+    rows.sort_by_key(|(id, date, ..)| (*date, *id));
+
+    // 持仓份额与成本计算方法无关，金额不影响份额
+    let mut ledger = LotLedger::new(asset_id, CostBasisMethod::WeightedAverage);
+    for (id, date, kind, amount, total_cost, fee) in rows {
+        // 允许极小的浮点误差，避免全部卖出时误判
+        if kind.reduces_holdings() && ledger.position.shares + 1e-8 < amount {
+            return Err(AuthError::InvalidCredentials(format!(
+                "持仓不足，{} 的持仓: {}, {}数量: {}",
+                local_date(date).format("%Y-%m-%d"),
+                ledger.position.shares,
+                if kind == TransactionType::Sell { "卖出" } else { "转出" },
+                amount
+            )));
+        }
+        ledger.apply(id, date, kind, amount, total_cost, fee);
+    }
+
+    Ok(())
+}
+
+/// 确定交易费用：手动指定时使用指定值，否则按费率方案计算
+fn resolve_fee(
+    conn: &rusqlite::Connection,
+    asset_id: i64,
+    kind: TransactionType,
+    amount: f64,
+    price: f64,
+    fee: Option<f64>,
+    transaction_date: i64,
+    exclude_transaction_id: Option<i64>,
+) -> Result<f64, AuthError> {
+    match fee {
+        Some(fee) if fee < 0.0 || fee.is_nan() => {
+            Err(AuthError::InvalidCredentials("交易费用不能为负数".to_string()))
+        }
+        Some(fee) => Ok(fee),
+        None => calculate_fee(
+            conn,
+            asset_id,
+            kind,
+            amount,
+            price,
+            transaction_date,
+            exclude_transaction_id,
+        )
+        .map(|breakdown| breakdown.total())
+        .map_err(|e| AuthError::DatabaseError(format!("计算交易费用失败: {}", e))),
+    }
+}
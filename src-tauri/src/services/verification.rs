@@ -8,7 +8,7 @@ use log::{error, info};
 use rusqlite::params;
 
 // 生成并发送验证码
-pub fn generate_and_send_verification_code(email: &str, purpose: &str) -> Result<(), AuthError> {
+pub async fn generate_and_send_verification_code(email: &str, purpose: &str) -> Result<(), AuthError> {
     let conn = get_connection_from_pool()?;
     let now = Utc::now().timestamp();
     let config = Config::get();
@@ -52,8 +52,9 @@ pub fn generate_and_send_verification_code(email: &str, purpose: &str) -> Result
         )?;
     }
 
-    // 发送验证码邮件
-    send_verification_code_email(email, &code, purpose)?;
+    // 发送验证码邮件（发送前归还数据库连接）
+    drop(conn);
+    send_verification_code_email(email, &code, purpose).await?;
 
     info!(
         "Verification code sent to: {} code {} for purpose: {}",
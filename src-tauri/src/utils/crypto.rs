@@ -4,6 +4,8 @@ use argon2::{
     Argon2,
 };
 use rand::{distributions::Alphanumeric, Rng};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 
 pub fn hash_password(password: &str) -> Result<String, AuthError> {
     let salt = SaltString::generate(&mut OsRng);
@@ -38,4 +40,11 @@ pub fn generate_verification_code() -> String {
     let mut rng = rand::thread_rng();
     let code: u32 = rng.gen_range(100000..=999999);
     code.to_string()
-}
\ No newline at end of file
+}
+
+// HMAC-SHA256 签名（钉钉、飞书机器人加签）
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 支持任意长度的密钥");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
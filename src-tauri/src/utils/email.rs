@@ -1,131 +1,163 @@
+use crate::config::config::EmailConfig;
 use crate::config::Config;
 use crate::error::auth::AuthError;
-use log::error;
-
-// 发送密码重置邮件
-pub fn send_password_reset_email(email: &str, token: &str) -> Result<(), AuthError> {
-    let config = Config::get();
+use crate::utils::email_template::{
+    alert_digest_email, password_reset_email, verification_code_email, AlertDigestItem, EmailLanguage,
+    RenderedEmail,
+};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::{debug, error, info};
+use std::fs;
+use std::time::Duration;
+
+// 构建邮件（纯文本和 HTML 两种格式）
+fn build_message(config: &EmailConfig, to: &str, email: &RenderedEmail) -> Result<Message, AuthError> {
+    let from = Mailbox::new(
+        Some(config.from_name.clone()).filter(|name| !name.is_empty()),
+        config
+            .from_email
+            .parse()
+            .map_err(|e| AuthError::InternalError(format!("发件人邮箱无效: {}", e)))?,
+    );
+    let to: Mailbox = to
+        .parse()
+        .map_err(|e| AuthError::InvalidCredentials(format!("收件人邮箱无效: {}", e)))?;
+
+    Message::builder()
+        .from(from)
+        .to(to)
+        .subject(email.subject.clone())
+        .multipart(MultiPart::alternative_plain_html(email.text.clone(), email.html.clone()))
+        .map_err(|e| AuthError::InternalError(format!("构建邮件失败: {}", e)))
+}
 
-    // 如果不是开发模式且未启用邮箱验证，则跳过发送
-    if !config.dev_mode && !config.auth.enable_email_verification {
-        return Ok(());
+// 创建异步 SMTP 发送器（在 tokio 运行时中发送，不阻塞调用方所在的异步任务）
+fn smtp_transport(config: &EmailConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>, AuthError> {
+    let builder = match config.tls_mode.as_str() {
+        "TLS" => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+        "STARTTLS" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host),
+        "NONE" => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)),
+        other => {
+            return Err(AuthError::InternalError(format!(
+                "无效的加密方式: {}，支持：NONE, STARTTLS, TLS",
+                other
+            )))
+        }
     }
+    .map_err(|e| AuthError::InternalError(format!("创建 SMTP 连接失败: {}", e)))?;
 
-    // 在开发模式下，只打印邮件内容
-    if config.dev_mode {
-        println!("发送密码重置邮件到 {}", email);
-        println!("重置令牌: {}", token);
-        println!(
-            "重置链接: http://localhost:1420/reset-password?token={}",
-            token
-        );
-        return Ok(());
+    let mut builder = builder
+        .port(config.port)
+        .timeout(Some(Duration::from_secs(config.timeout_seconds.max(1))));
+    if !config.username.is_empty() {
+        builder = builder.credentials(Credentials::new(config.username.clone(), config.password.clone()));
     }
 
-    // 实际应用中，这里应该使用邮件发送库
-    // 例如 lettre 或其他 SMTP 客户端
-    // 以下是一个示例实现，实际应用需要替换
-
-    /*
-    use lettre::{Message, SmtpTransport, Transport};
-    use lettre::transport::smtp::authentication::Credentials;
+    Ok(builder.build())
+}
 
-    let email_body = format!(
-        "您好，\n\n您收到此邮件是因为您请求重置密码。\n\n请点击以下链接重置密码：\n\nhttp://your-app-url/reset-password?token={}\n\n如果您没有请求重置密码，请忽略此邮件。\n\n谢谢！",
-        token
-    );
+// 按配置的发送方式发送邮件
+pub async fn send_email(to: &str, email: &RenderedEmail) -> Result<(), AuthError> {
+    let config = Config::get();
 
-    let email = Message::builder()
-        .from("noreply@your-app.com".parse().unwrap())
-        .to(email.parse().unwrap())
-        .subject("密码重置请求")
-        .body(email_body)
-        .unwrap();
+    // 未启用邮件发送时，开发模式下只记录到 debug 日志（正文可能含验证码等敏感内容，不输出到控制台）
+    if !config.email.enabled {
+        if config.dev_mode {
+            info!("Email sending is disabled, email to {} skipped: {}", to, email.subject);
+            debug!("Email body to {}:\n{}", to, email.text);
+            return Ok(());
+        }
 
-    let creds = Credentials::new("smtp_username".to_string(), "smtp_password".to_string());
+        error!("Email sending is disabled, email to {} not sent", to);
+        return Err(AuthError::InternalError("邮件发送未启用".to_string()));
+    }
 
-    let mailer = SmtpTransport::relay("smtp.your-provider.com")
-        .unwrap()
-        .credentials(creds)
-        .build();
+    let email_config = config.email;
+    let message = build_message(&email_config, to, email)?;
+
+    let result = match email_config.transport.as_str() {
+        "SMTP" => smtp_transport(&email_config)?
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        "FILE" => {
+            fs::create_dir_all(&email_config.outbox_dir)
+                .map_err(|e| AuthError::InternalError(format!("创建邮件目录失败: {}", e)))?;
+            AsyncFileTransport::<Tokio1Executor>::new(&email_config.outbox_dir)
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        other => {
+            return Err(AuthError::InternalError(format!(
+                "无效的邮件发送方式: {}，支持：SMTP, FILE",
+                other
+            )))
+        }
+    };
 
-    match mailer.send(&email) {
-        Ok(_) => Ok(()),
+    match result {
+        Ok(()) => {
+            info!("Email sent to {} via {}: {}", to, email_config.transport, email.subject);
+            Ok(())
+        }
         Err(e) => {
             error!("Failed to send email: {}", e);
             Err(AuthError::InternalError(format!("发送邮件失败: {}", e)))
         }
     }
-    */
+}
+
+// 发送密码重置邮件
+pub async fn send_password_reset_email(email: &str, token: &str) -> Result<(), AuthError> {
+    let config = Config::get();
 
-    // 临时实现，实际应用需要替换
-    Ok(())
+    // 如果不是开发模式且未启用邮箱验证，则跳过发送
+    if !config.dev_mode && !config.auth.enable_email_verification {
+        return Ok(());
+    }
+
+    send_email(email, &password_reset_email(EmailLanguage::current(), token)).await
 }
 
 // 发送验证码邮件
-pub fn send_verification_code_email(
+pub async fn send_verification_code_email(
     email: &str,
     code: &str,
     purpose: &str,
 ) -> Result<(), AuthError> {
     let config = Config::get();
 
-    let purpose_text = match purpose {
-        "register" => "注册账号",
-        "reset_password" => "重置密码",
-        _ => "验证邮箱",
-    };
-
-    // 在开发模式下，只打印邮件内容
-    if config.dev_mode {
-        println!("发送验证码邮件到 {}", email);
-        println!("验证码: {}", code);
-        println!("用途: {}", purpose_text);
-        return Ok(());
-    }
-
-    // 如果未启用邮箱验证，则跳过发送
-    if !config.auth.enable_email_verification {
+    // 如果不是开发模式且未启用邮箱验证，则跳过发送
+    if !config.dev_mode && !config.auth.enable_email_verification {
         return Ok(());
     }
 
-    // 实际应用中，这里应该使用邮件发送库
-    // 例如 lettre 或其他 SMTP 客户端
-    // 以下是一个示例实现，实际应用需要替换
-
-    /*
-    use lettre::{Message, SmtpTransport, Transport};
-    use lettre::transport::smtp::authentication::Credentials;
-
-    let email_body = format!(
-        "您好，\n\n您的验证码是: {}\n\n此验证码用于{}，有效期为10分钟。\n\n如果您没有请求此验证码，请忽略此邮件。\n\n谢谢！",
-        code,
-        purpose_text
-    );
-
-    let email = Message::builder()
-        .from("noreply@your-app.com".parse().unwrap())
-        .to(email.parse().unwrap())
-        .subject(format!("WolfQuant - {}验证码", purpose_text))
-        .body(email_body)
-        .unwrap();
-
-    let creds = Credentials::new("smtp_username".to_string(), "smtp_password".to_string());
-
-    let mailer = SmtpTransport::relay("smtp.your-provider.com")
-        .unwrap()
-        .credentials(creds)
-        .build();
+    send_email(email, &verification_code_email(EmailLanguage::current(), code, purpose)).await
+}
 
-    match mailer.send(&email) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            error!("Failed to send email: {}", e);
-            Err(AuthError::InternalError(format!("发送邮件失败: {}", e)))
-        }
-    }
-    */
+// 发送提醒摘要邮件
+pub async fn send_alert_digest_email(
+    email: &str,
+    title: &str,
+    items: &[AlertDigestItem],
+) -> Result<(), AuthError> {
+    send_email(email, &alert_digest_email(EmailLanguage::current(), title, items)).await
+}
 
-    // 临时实现，实际应用需要替换
-    Ok(())
+// 发送通知邮件（交易提醒推送）
+pub async fn send_notification_email(email: &str, subject: &str, body: &str) -> Result<(), AuthError> {
+    send_alert_digest_email(
+        email,
+        subject,
+        &[AlertDigestItem {
+            title: subject.to_string(),
+            body: body.to_string(),
+        }],
+    )
+    .await
 }
@@ -0,0 +1,214 @@
+/**
+ * 邮件模板
+ *
+ * 模板随应用打包（resources/email/<语言>/ 目录下的 .txt 和 .html），按 `EmailConfig.language` 选择语言，
+ * 未支持的语言使用中文。模板中的 `{{name}}` 替换为对应的值，HTML 模板中的值会转义。
+ */
+use crate::config::Config;
+
+/// 邮件语言
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmailLanguage {
+    ZhCn,
+    EnUs,
+}
+
+impl EmailLanguage {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "zh-CN" => Some(EmailLanguage::ZhCn),
+            "en-US" => Some(EmailLanguage::EnUs),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            EmailLanguage::ZhCn => "zh-CN",
+            EmailLanguage::EnUs => "en-US",
+        }
+    }
+
+    /// 配置中的模板语言
+    pub fn current() -> Self {
+        EmailLanguage::from_str(&Config::get().email.language).unwrap_or(EmailLanguage::ZhCn)
+    }
+}
+
+/// 渲染后的邮件
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// 提醒摘要中的一条提醒
+#[derive(Debug, Clone)]
+pub struct AlertDigestItem {
+    pub title: String,
+    pub body: String,
+}
+
+/// 模板文件：(纯文本, HTML)
+fn template(language: EmailLanguage, name: &str) -> (&'static str, &'static str) {
+    match (language, name) {
+        (EmailLanguage::ZhCn, "verification_code") => (
+            include_str!("../../resources/email/zh-CN/verification_code.txt"),
+            include_str!("../../resources/email/zh-CN/verification_code.html"),
+        ),
+        (EmailLanguage::ZhCn, "password_reset") => (
+            include_str!("../../resources/email/zh-CN/password_reset.txt"),
+            include_str!("../../resources/email/zh-CN/password_reset.html"),
+        ),
+        (EmailLanguage::ZhCn, _) => (
+            include_str!("../../resources/email/zh-CN/alert_digest.txt"),
+            include_str!("../../resources/email/zh-CN/alert_digest.html"),
+        ),
+        (EmailLanguage::EnUs, "verification_code") => (
+            include_str!("../../resources/email/en-US/verification_code.txt"),
+            include_str!("../../resources/email/en-US/verification_code.html"),
+        ),
+        (EmailLanguage::EnUs, "password_reset") => (
+            include_str!("../../resources/email/en-US/password_reset.txt"),
+            include_str!("../../resources/email/en-US/password_reset.html"),
+        ),
+        (EmailLanguage::EnUs, _) => (
+            include_str!("../../resources/email/en-US/alert_digest.txt"),
+            include_str!("../../resources/email/en-US/alert_digest.html"),
+        ),
+    }
+}
+
+/// 转义 HTML 特殊字符
+pub fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 替换模板变量，`raw` 中的值不转义（已是 HTML 片段）
+fn fill(template: &str, values: &[(&str, &str)], raw: &[(&str, &str)], escape: bool) -> String {
+    let mut content = template.to_string();
+    for (name, value) in values {
+        let value = if escape { escape_html(value) } else { value.to_string() };
+        content = content.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    for (name, value) in raw {
+        content = content.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    content
+}
+
+fn render(
+    language: EmailLanguage,
+    name: &str,
+    subject: String,
+    values: &[(&str, &str)],
+    text_raw: &[(&str, &str)],
+    html_raw: &[(&str, &str)],
+) -> RenderedEmail {
+    let (text_template, html_template) = template(language, name);
+    let mut values = values.to_vec();
+    values.push(("subject", &subject));
+
+    RenderedEmail {
+        text: fill(text_template, &values, text_raw, false),
+        html: fill(html_template, &values, html_raw, true),
+        subject,
+    }
+}
+
+/**
+ * @dev 验证码邮件
+ * @param purpose 用途(register / reset_password / 其他为验证邮箱)
+ */
+pub fn verification_code_email(language: EmailLanguage, code: &str, purpose: &str) -> RenderedEmail {
+    let expiry_minutes = Config::get().auth.emial_code_valid_duration.to_string();
+    let purpose_text = match (language, purpose) {
+        (EmailLanguage::ZhCn, "register") => "注册账号",
+        (EmailLanguage::ZhCn, "reset_password") => "重置密码",
+        (EmailLanguage::ZhCn, _) => "验证邮箱",
+        (EmailLanguage::EnUs, "register") => "create your account",
+        (EmailLanguage::EnUs, "reset_password") => "reset your password",
+        (EmailLanguage::EnUs, _) => "verify your email",
+    };
+    let subject = match language {
+        EmailLanguage::ZhCn => format!("WolfQuant - {}验证码", purpose_text),
+        EmailLanguage::EnUs => "WolfQuant - Verification code".to_string(),
+    };
+
+    render(
+        language,
+        "verification_code",
+        subject,
+        &[("code", code), ("purpose", purpose_text), ("expiry_minutes", &expiry_minutes)],
+        &[],
+        &[],
+    )
+}
+
+/// 密码重置邮件
+pub fn password_reset_email(language: EmailLanguage, token: &str) -> RenderedEmail {
+    let config = Config::get();
+    let reset_link = format!(
+        "{}/reset-password?token={}",
+        config.email.app_url.trim_end_matches('/'),
+        token
+    );
+    let expiry_minutes = config.auth.password_reset_token_expiry_minutes.to_string();
+    let subject = match language {
+        EmailLanguage::ZhCn => "WolfQuant - 密码重置请求".to_string(),
+        EmailLanguage::EnUs => "WolfQuant - Password reset request".to_string(),
+    };
+
+    render(
+        language,
+        "password_reset",
+        subject,
+        &[("reset_link", &reset_link), ("expiry_minutes", &expiry_minutes)],
+        &[],
+        &[],
+    )
+}
+
+/**
+ * @dev 提醒摘要邮件
+ * @param title 邮件标题，同时作为正文标题
+ */
+pub fn alert_digest_email(language: EmailLanguage, title: &str, items: &[AlertDigestItem]) -> RenderedEmail {
+    let text_items = items
+        .iter()
+        .map(|item| format!("- {}\n  {}", item.title, item.body.replace('\n', "\n  ")))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let html_items = items
+        .iter()
+        .map(|item| {
+            format!(
+                "<li style=\"margin-bottom:12px;\"><strong>{}</strong><br>{}</li>",
+                escape_html(&item.title),
+                escape_html(&item.body).replace('\n', "<br>")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    render(
+        language,
+        "alert_digest",
+        title.to_string(),
+        &[("title", title)],
+        &[("items", &text_items)],
+        &[("items", &html_items)],
+    )
+}
@@ -1,3 +1,3 @@
 pub mod crypto;
 pub mod logging;
-pub mod email;
\ No newline at end of file
+pub mod email;pub mod email_template;